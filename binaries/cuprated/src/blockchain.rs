@@ -1,14 +1,17 @@
 //! Blockchain
 //!
 //! Contains the blockchain manager, syncer and an interface to mutate the blockchain.
-use std::sync::Arc;
+use std::{fs, path::Path, sync::Arc};
 
 use futures::FutureExt;
 use tokio::sync::{mpsc, Notify};
 use tower::{BoxError, Service, ServiceExt};
 
 use cuprate_blockchain::service::{BlockchainReadHandle, BlockchainWriteHandle};
-use cuprate_consensus::{generate_genesis_block, BlockchainContextService, ContextConfig};
+use cuprate_consensus::{
+    generate_genesis_block, BlockChainContextRequest, BlockChainContextResponse,
+    BlockchainContextService, ContextConfig, ContextSnapshot,
+};
 use cuprate_cryptonight::cryptonight_hash_v0;
 use cuprate_p2p::{block_downloader::BlockDownloaderConfig, NetworkInterface};
 use cuprate_p2p_core::{ClearNet, Network};
@@ -20,15 +23,25 @@ use cuprate_types::{
 use crate::constants::PANIC_CRITICAL_SERVICE_ERROR;
 
 mod chain_service;
+mod checkpoints;
 mod fast_sync;
+mod import;
 pub mod interface;
 mod manager;
+mod optional_index;
+mod revalidate;
 mod syncer;
 mod types;
+mod verify;
 
+pub use checkpoints::set_checkpoints;
 pub use fast_sync::set_fast_sync_hashes;
-pub use manager::init_blockchain_manager;
+pub use import::ImportSource;
+pub use manager::{init_blockchain_manager, IncomingBlockOk};
+pub use optional_index::{backfill_index, OptionalIndex};
+pub use revalidate::revalidate;
 pub use types::ConsensusBlockchainReadHandle;
+pub use verify::verify_blobs;
 
 /// Checks if the genesis block is in the blockchain and adds it if not.
 pub async fn check_add_genesis(
@@ -78,15 +91,54 @@ pub async fn check_add_genesis(
 }
 
 /// Initializes the consensus services.
+///
+/// The context caches are restored from the snapshot in `snapshot_file` if it is not stale.
 pub async fn init_consensus(
     blockchain_read_handle: BlockchainReadHandle,
     context_config: ContextConfig,
+    snapshot_file: &Path,
 ) -> Result<BlockchainContextService, BoxError> {
     let read_handle = ConsensusBlockchainReadHandle::new(blockchain_read_handle, BoxError::from);
 
-    let ctx_service =
-        cuprate_consensus::initialize_blockchain_context(context_config, read_handle.clone())
-            .await?;
+    let snapshot = match fs::read(snapshot_file) {
+        Ok(bytes) => ContextSnapshot::from_bytes(&bytes)
+            .inspect_err(|e| tracing::warn!("Ignoring invalid context snapshot: {e}"))
+            .ok(),
+        Err(_) => None,
+    };
+
+    let ctx_service = cuprate_consensus::initialize_blockchain_context_with_snapshot(
+        context_config,
+        read_handle.clone(),
+        snapshot,
+    )
+    .await?;
 
     Ok(ctx_service)
 }
+
+/// Saves a snapshot of the context caches to `snapshot_file`, so they don't need to be rebuilt on the next start.
+pub async fn save_context_snapshot(
+    mut context_svc: BlockchainContextService,
+    snapshot_file: &Path,
+) -> Result<(), BoxError> {
+    let BlockChainContextResponse::Snapshot(snapshot) = context_svc
+        .ready()
+        .await?
+        .call(BlockChainContextRequest::Snapshot)
+        .await?
+    else {
+        unreachable!();
+    };
+
+    let mut tmp_file = snapshot_file.to_path_buf();
+    tmp_file.set_extension("tmp");
+
+    if let Some(dir) = snapshot_file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&tmp_file, snapshot.to_bytes())?;
+    fs::rename(tmp_file, snapshot_file)?;
+
+    Ok(())
+}
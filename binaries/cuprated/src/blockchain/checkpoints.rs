@@ -0,0 +1,99 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+use cuprate_consensus::checkpoints::Checkpoints;
+use cuprate_helper::network::Network;
+
+/// A checkpoint file, in the same format as `monerod`'s `checkpoints.json`.
+#[derive(Deserialize)]
+struct CheckpointFile {
+    hashlines: Vec<HashLine>,
+}
+
+/// A single checkpoint in a [`CheckpointFile`].
+#[derive(Deserialize)]
+struct HashLine {
+    height: usize,
+    hash: String,
+}
+
+/// Set the checkpoints enforced by the block verifier.
+///
+/// The compiled in checkpoints for `network` are always used, if `file` is not empty the
+/// checkpoints in it are added to them.
+///
+/// # Errors
+///
+/// Returns an error if `file` could not be read, is invalid or conflicts with the compiled in checkpoints.
+pub fn set_checkpoints(network: Network, file: &Path) -> Result<(), anyhow::Error> {
+    let mut checkpoints = Checkpoints::new(network);
+
+    if !file.as_os_str().is_empty() {
+        let bytes = std::fs::read(file)?;
+        add_checkpoints(&mut checkpoints, &bytes)
+            .map_err(|e| anyhow::anyhow!("Invalid checkpoint file {}: {e}", file.display()))?;
+    }
+
+    cuprate_consensus::checkpoints::set_checkpoints(checkpoints);
+
+    Ok(())
+}
+
+/// Add the checkpoints in the JSON checkpoint file `bytes` to `checkpoints`.
+fn add_checkpoints(checkpoints: &mut Checkpoints, bytes: &[u8]) -> Result<(), anyhow::Error> {
+    let file: CheckpointFile = serde_json::from_slice(bytes)?;
+
+    for line in file.hashlines {
+        let mut hash = [0; 32];
+        hex::decode_to_slice(&line.hash, &mut hash)?;
+        checkpoints.add(line.height, hash)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use super::*;
+
+    /// `monerod` checkpoint files are added to the compiled in checkpoints.
+    #[test]
+    fn checkpoint_file() {
+        let mut checkpoints = Checkpoints::new(Network::Mainnet);
+        let len = checkpoints.len();
+
+        add_checkpoints(
+            &mut checkpoints,
+            br#"{"hashlines": [
+                {"height": 1, "hash": "771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148"},
+                {"height": 5000000, "hash": "0101010101010101010101010101010101010101010101010101010101010101"}
+            ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(checkpoints.len(), len + 1);
+        assert_eq!(checkpoints.get(5_000_000), Some(&[1; 32]));
+        assert_eq!(
+            checkpoints.get(1),
+            Some(&hex!(
+                "771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148"
+            ))
+        );
+
+        // A different hash for a compiled in checkpoint is an error.
+        assert!(add_checkpoints(
+            &mut checkpoints,
+            br#"{"hashlines": [{"height": 1, "hash": "0101010101010101010101010101010101010101010101010101010101010101"}]}"#,
+        )
+        .is_err());
+
+        assert!(add_checkpoints(
+            &mut checkpoints,
+            br#"{"hashlines": [{"height": 1, "hash": "01"}]}"#
+        )
+        .is_err());
+    }
+}
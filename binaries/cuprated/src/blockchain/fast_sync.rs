@@ -1,24 +1,76 @@
-use std::slice;
+use std::path::Path;
 
+use cuprate_fast_sync::decode_fast_sync_file;
 use cuprate_helper::network::Network;
 
-/// The hashes of the compiled in fast sync file.
-static FAST_SYNC_HASHES: &[[u8; 32]] = {
-    let bytes = include_bytes!("./fast_sync/fast_sync_hashes.bin");
-
-    if bytes.len() % 32 == 0 {
-        // SAFETY: The file byte length must be perfectly divisible by 32, checked above.
-        unsafe { slice::from_raw_parts(bytes.as_ptr().cast::<[u8; 32]>(), bytes.len() / 32) }
-    } else {
-        panic!();
+/// The compiled in fast sync file of each network.
+///
+/// These are created with `create-fs-file`, see [`cuprate_fast_sync::encode_fast_sync_file`].
+///
+/// Returns [`None`] for networks without a compiled in file, only mainnet has one. Testnet and
+/// stagenet nodes need a file passed with `fast_sync_file` to fast sync.
+const fn embedded_fast_sync_file(network: Network) -> Option<&'static [u8]> {
+    match network {
+        Network::Mainnet => Some(include_bytes!("./fast_sync/mainnet.bin")),
+        Network::Testnet | Network::Stagenet => None,
     }
-};
+}
 
 /// Set the fast-sync hashes according to the provided values.
-pub fn set_fast_sync_hashes(fast_sync: bool, network: Network) {
-    cuprate_fast_sync::set_fast_sync_hashes(if fast_sync && network == Network::Mainnet {
-        FAST_SYNC_HASHES
+///
+/// If `file` is not empty the hashes are loaded from it, otherwise the compiled in hashes
+/// for `network` are used, if `network` has none fast sync is disabled.
+///
+/// # Errors
+///
+/// Returns an error if `file` could not be read or is not a valid fast sync file for `network`.
+pub fn set_fast_sync_hashes(
+    fast_sync: bool,
+    network: Network,
+    file: &Path,
+) -> Result<(), anyhow::Error> {
+    if !fast_sync {
+        cuprate_fast_sync::set_fast_sync_hashes(&[]);
+        return Ok(());
+    }
+
+    let hashes = if file.as_os_str().is_empty() {
+        let Some(embedded_file) = embedded_fast_sync_file(network) else {
+            cuprate_fast_sync::set_fast_sync_hashes(&[]);
+            return Ok(());
+        };
+
+        decode_fast_sync_file(embedded_file, network)
+            .expect("The compiled in fast sync file must be valid")
     } else {
-        &[]
-    });
+        let bytes = std::fs::read(file)?;
+        decode_fast_sync_file(&bytes, network)
+            .map_err(|e| anyhow::anyhow!("Invalid fast sync file {}: {e}", file.display()))?
+    };
+
+    // The hashes are used for the lifetime of `cuprated`.
+    cuprate_fast_sync::set_fast_sync_hashes(hashes.leak());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The compiled in fast sync files are valid for their network and contain at least one batch.
+    #[test]
+    fn embedded_fast_sync_files() {
+        for network in [Network::Mainnet, Network::Testnet, Network::Stagenet] {
+            let Some(file) = embedded_fast_sync_file(network) else {
+                continue;
+            };
+
+            let hashes = decode_fast_sync_file(file, network).unwrap();
+            assert!(
+                !hashes.is_empty(),
+                "{network} fast sync file has no batches"
+            );
+        }
+    }
 }
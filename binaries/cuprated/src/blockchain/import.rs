@@ -0,0 +1,186 @@
+//! Block import
+//!
+//! Imports blocks from a `monerod` raw bootstrap file, see [`cuprate_blockchain::bootstrap`],
+//! or a `monerod` LMDB database, see [`cuprate_blockchain::monerod`].
+use std::{fs::File, io::BufReader, path::PathBuf};
+
+use monero_serai::{block::Block, transaction::Transaction};
+use tower::{Service, ServiceExt};
+use tracing::{error, info};
+
+use cuprate_blockchain::{
+    bootstrap::{BootstrapBlock, BootstrapReader},
+    monerod::MonerodReader,
+    service::BlockchainReadHandle,
+};
+use cuprate_fast_sync::{fast_sync_stop_height, validate_batch_hashes, FAST_SYNC_BATCH_LEN};
+use cuprate_types::blockchain::{BlockchainReadRequest, BlockchainResponse};
+
+use crate::{blockchain::interface, constants::PANIC_CRITICAL_SERVICE_ERROR};
+
+/// The maximum amount of blocks in a batch that will be fully verified.
+const VERIFIED_BATCH_LEN: usize = 100;
+
+/// Where to import blocks from.
+pub enum ImportSource {
+    /// A `monerod` raw bootstrap file.
+    Bootstrap(PathBuf),
+    /// A `monerod` LMDB data directory, the directory containing `data.mdb`.
+    Monerod(PathBuf),
+}
+
+/// Import the blocks in `source`.
+///
+/// Blocks already in the blockchain are skipped, so an interrupted import can be resumed.
+///
+/// If `verify` is `false` all blocks are trusted and added without verification, otherwise blocks
+/// are verified or, under the fast sync height, checked against the fast sync hashes.
+///
+/// Any error is logged, blocks imported before the error are kept.
+pub async fn import_blocks(
+    source: ImportSource,
+    verify: bool,
+    mut blockchain_read_handle: BlockchainReadHandle,
+) {
+    let (ImportSource::Bootstrap(path) | ImportSource::Monerod(path)) = &source;
+    info!("Importing blocks from {}", path.display());
+
+    match import_blocks_inner(source, verify, &mut blockchain_read_handle).await {
+        Ok(height) => info!("Finished importing blocks, chain height: {height}"),
+        Err(e) => error!("Failed to import blocks: {e:#}"),
+    }
+}
+
+/// Inner function for [`import_blocks`], returns the chain height after the import.
+async fn import_blocks_inner(
+    source: ImportSource,
+    verify: bool,
+    blockchain_read_handle: &mut BlockchainReadHandle,
+) -> Result<usize, anyhow::Error> {
+    let BlockchainResponse::ChainHeight(mut height, _) = blockchain_read_handle
+        .ready()
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        .call(BlockchainReadRequest::ChainHeight)
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+    else {
+        unreachable!();
+    };
+
+    let mut reader = tokio::task::spawn_blocking(move || Reader::open(source, height))
+        .await
+        .unwrap()?;
+
+    loop {
+        let batch_len = batch_len(height, verify);
+
+        let (returned_reader, blocks) =
+            tokio::task::spawn_blocking(move || reader.next_blocks(batch_len))
+                .await
+                .unwrap()?;
+        reader = returned_reader;
+
+        if blocks.is_empty() {
+            return Ok(height);
+        }
+
+        // A full fast sync batch can be checked against the fast sync hashes instead of being verified.
+        let trusted = if !verify {
+            true
+        } else if blocks.len() == FAST_SYNC_BATCH_LEN && height < fast_sync_stop_height() {
+            let hashes = blocks
+                .iter()
+                .map(|(block, _)| block.hash())
+                .collect::<Vec<_>>();
+
+            if !validate_batch_hashes(height, &hashes) {
+                anyhow::bail!("Blocks at height {height} do not match the fast sync hashes");
+            }
+
+            true
+        } else {
+            false
+        };
+
+        let len = blocks.len();
+        interface::import_blocks(blocks, trusted).await?;
+        height += len;
+    }
+}
+
+/// The amount of blocks to import in the next batch, starting at `height`.
+///
+/// Under the fast sync height batches are aligned to fast sync batches, so that they
+/// can be checked against the fast sync hashes.
+fn batch_len(height: usize, verify: bool) -> usize {
+    if !verify {
+        return FAST_SYNC_BATCH_LEN;
+    }
+
+    let offset = height % FAST_SYNC_BATCH_LEN;
+
+    if height >= fast_sync_stop_height() {
+        VERIFIED_BATCH_LEN
+    } else if offset == 0 {
+        FAST_SYNC_BATCH_LEN
+    } else {
+        VERIFIED_BATCH_LEN.min(FAST_SYNC_BATCH_LEN - offset)
+    }
+}
+
+/// A reader for an [`ImportSource`].
+enum Reader {
+    /// A raw bootstrap file reader.
+    Bootstrap(BootstrapReader<BufReader<File>>),
+    /// A `monerod` LMDB database reader.
+    Monerod(MonerodReader),
+}
+
+impl Reader {
+    /// Open `source`, skipping the blocks below `height`.
+    fn open(source: ImportSource, height: usize) -> Result<Self, anyhow::Error> {
+        Ok(match source {
+            ImportSource::Bootstrap(path) => {
+                let mut reader = BootstrapReader::new(BufReader::new(File::open(path)?))?;
+
+                for _ in 0..height {
+                    if !reader.skip_chunk()? {
+                        break;
+                    }
+                }
+
+                Self::Bootstrap(reader)
+            }
+            ImportSource::Monerod(path) => Self::Monerod(MonerodReader::open(&path, height)?),
+        })
+    }
+
+    /// Read up to `len` blocks, returning the reader so it can be moved to a blocking thread.
+    ///
+    /// The returned [`Vec`] is empty if there are no more blocks.
+    fn next_blocks(
+        mut self,
+        len: usize,
+    ) -> Result<(Self, Vec<(Block, Vec<Transaction>)>), anyhow::Error> {
+        let blocks = match &mut self {
+            Self::Bootstrap(reader) => {
+                let mut blocks = Vec::with_capacity(len);
+
+                while blocks.len() < len {
+                    let Some(chunk) = reader.next_chunk()? else {
+                        break;
+                    };
+
+                    let BootstrapBlock { block, txs, .. } = BootstrapBlock::read(&chunk)?;
+                    blocks.push((block, txs));
+                }
+
+                blocks
+            }
+            Self::Monerod(reader) => reader.next_blocks(len)?,
+        };
+
+        Ok((self, blocks))
+    }
+}
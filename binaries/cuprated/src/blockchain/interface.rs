@@ -18,9 +18,13 @@ use cuprate_txpool::service::{
     TxpoolReadHandle,
 };
 use cuprate_types::blockchain::{BlockchainReadRequest, BlockchainResponse};
+use cuprate_zmq_types::json_message_types::ReorgAlert;
 
 use crate::{
-    blockchain::manager::{BlockchainManagerCommand, IncomingBlockOk},
+    blockchain::{
+        manager::{BlockchainManagerCommand, IncomingBlockOk},
+        OptionalIndex,
+    },
     constants::PANIC_CRITICAL_SERVICE_ERROR,
 };
 
@@ -170,6 +174,135 @@ pub async fn handle_incoming_block(
         .map_err(IncomingBlockError::InvalidBlock)
 }
 
+/// Add a batch of blocks, imported from a file, to the top of the main chain.
+///
+/// If `trusted` is `true` the blocks will be added without verification.
+///
+/// # Errors
+///
+/// This function will return an error if:
+///  - the blockchain manager has not been initialized
+///  - the blocks do not follow the top of the main chain
+///  - a block was invalid
+///
+/// # Panics
+///
+/// This function will panic if `blocks` is empty.
+pub async fn import_blocks(
+    blocks: Vec<(Block, Vec<Transaction>)>,
+    trusted: bool,
+) -> Result<(), anyhow::Error> {
+    assert!(!blocks.is_empty(), "Block batch should not be empty");
+
+    let Some(command_tx) = COMMAND_TX.get() else {
+        anyhow::bail!("The blockchain manager is not ready");
+    };
+
+    let (response_tx, response_rx) = oneshot::channel();
+
+    command_tx
+        .send(BlockchainManagerCommand::ImportBlocks {
+            blocks,
+            trusted,
+            response_tx,
+        })
+        .await
+        .expect("TODO: don't actually panic here, an err means we are shutting down");
+
+    response_rx
+        .await
+        .expect("The blockchain manager will always respond")
+}
+
+/// Add up to `max_blocks` blocks that were in the database before
+/// `index` was enabled to the index.
+///
+/// Returns the height below which blocks still need to be added,
+/// `0` once all are, or [`None`] if the index is disabled.
+///
+/// # Errors
+///
+/// This function will return an error if the blockchain manager has not been initialized.
+pub async fn backfill_index(
+    index: OptionalIndex,
+    max_blocks: usize,
+) -> Result<Option<usize>, anyhow::Error> {
+    let Some(command_tx) = COMMAND_TX.get() else {
+        anyhow::bail!("The blockchain manager is not ready");
+    };
+
+    let (response_tx, response_rx) = oneshot::channel();
+
+    command_tx
+        .send(BlockchainManagerCommand::BackfillIndex {
+            index,
+            max_blocks,
+            response_tx,
+        })
+        .await
+        .expect("TODO: don't actually panic here, an err means we are shutting down");
+
+    Ok(response_rx
+        .await
+        .expect("The blockchain manager will always respond"))
+}
+
+/// Get the re-org being held for manual approval, if there is one.
+///
+/// Re-orgs removing more main-chain blocks than the maximum automatic re-org depth are held until
+/// approved with [`approve_reorg`].
+///
+/// # Errors
+///
+/// This function will return an error if the blockchain manager has not been initialized.
+pub async fn held_reorg() -> Result<Option<ReorgAlert>, anyhow::Error> {
+    let Some(command_tx) = COMMAND_TX.get() else {
+        anyhow::bail!("The blockchain manager is not ready");
+    };
+
+    let (response_tx, response_rx) = oneshot::channel();
+
+    command_tx
+        .send(BlockchainManagerCommand::HeldReorg { response_tx })
+        .await
+        .expect("TODO: don't actually panic here, an err means we are shutting down");
+
+    Ok(response_rx
+        .await
+        .expect("The blockchain manager will always respond"))
+}
+
+/// Approve the re-org being held for manual approval.
+///
+/// `alt_top_hash` must be the hash of the held re-org's competing chain top block, see [`ReorgAlert::alt_id`].
+///
+/// # Errors
+///
+/// This function will return an error if:
+///  - the blockchain manager has not been initialized
+///  - there is no held re-org with a top block of `alt_top_hash`
+///  - the competing chain no longer has more cumulative difficulty than the main chain
+///  - the re-org failed
+pub async fn approve_reorg(alt_top_hash: [u8; 32]) -> Result<(), anyhow::Error> {
+    let Some(command_tx) = COMMAND_TX.get() else {
+        anyhow::bail!("The blockchain manager is not ready");
+    };
+
+    let (response_tx, response_rx) = oneshot::channel();
+
+    command_tx
+        .send(BlockchainManagerCommand::ApproveReorg {
+            alt_top_hash,
+            response_tx,
+        })
+        .await
+        .expect("TODO: don't actually panic here, an err means we are shutting down");
+
+    response_rx
+        .await
+        .expect("The blockchain manager will always respond")
+}
+
 /// Check if we have a block with the given hash.
 async fn block_exists(
     block_hash: [u8; 32],
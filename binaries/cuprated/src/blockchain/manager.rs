@@ -11,6 +11,7 @@ use cuprate_consensus::{
     BlockChainContextRequest, BlockChainContextResponse, BlockchainContextService,
     ExtendedConsensusError,
 };
+use cuprate_database::ConcreteEnv;
 use cuprate_p2p::{
     block_downloader::{BlockBatch, BlockDownloaderConfig},
     BroadcastSvc, NetworkInterface,
@@ -19,15 +20,20 @@ use cuprate_p2p_core::ClearNet;
 use cuprate_txpool::service::TxpoolWriteHandle;
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
-    Chain, TransactionVerificationData,
+    Chain, ChainId, TransactionVerificationData,
 };
+use cuprate_zmq_types::json_message_types::ReorgAlert;
 
 use crate::{
     blockchain::{
-        chain_service::ChainService, interface::COMMAND_TX, syncer,
+        chain_service::ChainService,
+        import::{import_blocks, ImportSource},
+        interface::COMMAND_TX,
+        syncer,
         types::ConsensusBlockchainReadHandle,
     },
     constants::PANIC_CRITICAL_SERVICE_ERROR,
+    zmq::ZmqPublisher,
 };
 
 mod commands;
@@ -37,34 +43,56 @@ mod handler;
 mod tests;
 
 pub use commands::{BlockchainManagerCommand, IncomingBlockOk};
+pub(super) use handler::new_block_data;
 
 /// Initialize the blockchain manager.
 ///
 /// This function sets up the [`BlockchainManager`] and the [`syncer`] so that the functions in [`interface`](super::interface)
 /// can be called.
+///
+/// If `import` is set, the blocks in the [`ImportSource`] are imported, verified if the [`bool`] is `true`, before the
+/// [`syncer`] is started, as imported blocks must follow the top of the chain.
 pub async fn init_blockchain_manager(
     clearnet_interface: NetworkInterface<ClearNet>,
     blockchain_write_handle: BlockchainWriteHandle,
     blockchain_read_handle: BlockchainReadHandle,
     txpool_write_handle: TxpoolWriteHandle,
+    blockchain_env: Arc<ConcreteEnv>,
+    txpool_env: Arc<ConcreteEnv>,
     mut blockchain_context_service: BlockchainContextService,
     block_downloader_config: BlockDownloaderConfig,
+    max_alt_chain_depth: usize,
+    max_alt_blocks: usize,
+    max_reorg_depth: usize,
+    zmq_publisher: Option<ZmqPublisher>,
+    import: Option<(ImportSource, bool)>,
 ) {
     // TODO: find good values for these size limits
     let (batch_tx, batch_rx) = mpsc::channel(1);
     let stop_current_block_downloader = Arc::new(Notify::new());
+    let synced_notify = Arc::new(Notify::new());
     let (command_tx, command_rx) = mpsc::channel(3);
 
     COMMAND_TX.set(command_tx).unwrap();
 
-    tokio::spawn(syncer::syncer(
+    let syncer = syncer::syncer(
         blockchain_context_service.clone(),
         ChainService(blockchain_read_handle.clone()),
         clearnet_interface.clone(),
         batch_tx,
         Arc::clone(&stop_current_block_downloader),
+        Arc::clone(&synced_notify),
         block_downloader_config,
-    ));
+    );
+
+    let import_read_handle = blockchain_read_handle.clone();
+    tokio::spawn(async move {
+        if let Some((source, verify)) = import {
+            import_blocks(source, verify, import_read_handle).await;
+        }
+
+        syncer.await
+    });
 
     let manager = BlockchainManager {
         blockchain_write_handle,
@@ -73,12 +101,19 @@ pub async fn init_blockchain_manager(
             BoxError::from,
         ),
         txpool_write_handle,
+        blockchain_env,
+        txpool_env,
         blockchain_context_service,
         stop_current_block_downloader,
         broadcast_svc: clearnet_interface.broadcast_svc(),
+        max_alt_chain_depth,
+        max_alt_blocks,
+        max_reorg_depth,
+        held_reorg: None,
+        zmq_publisher,
     };
 
-    tokio::spawn(manager.run(batch_rx, command_rx));
+    tokio::spawn(manager.run(batch_rx, command_rx, synced_notify));
 }
 
 /// The blockchain manager.
@@ -95,6 +130,10 @@ pub struct BlockchainManager {
     blockchain_read_handle: ConsensusBlockchainReadHandle,
     /// A [`TxpoolWriteHandle`].
     txpool_write_handle: TxpoolWriteHandle,
+    /// The blockchain database environment, used to switch its sync mode once synced.
+    blockchain_env: Arc<ConcreteEnv>,
+    /// The tx-pool database environment, used to switch its sync mode once synced.
+    txpool_env: Arc<ConcreteEnv>,
     /// The blockchain context cache, this caches the current state of the blockchain to quickly calculate/retrieve
     /// values without needing to go to a [`BlockchainReadHandle`].
     blockchain_context_service: BlockchainContextService,
@@ -103,6 +142,16 @@ pub struct BlockchainManager {
     stop_current_block_downloader: Arc<Notify>,
     /// The broadcast service, to broadcast new blocks.
     broadcast_svc: BroadcastSvc<ClearNet>,
+    /// Alt-chains with a top block more than this amount of blocks below the main-chain are pruned.
+    max_alt_chain_depth: usize,
+    /// The maximum amount of alt-blocks to keep, the oldest alt-chains are pruned first.
+    max_alt_blocks: usize,
+    /// Re-orgs removing more than this amount of main-chain blocks are held until approved, 0 means no limit.
+    max_reorg_depth: usize,
+    /// The alt-chain and alert of the re-org being held until approved.
+    held_reorg: Option<(ChainId, ReorgAlert)>,
+    /// The ZMQ publisher, [`ReorgAlert`]s are published with this if it is enabled.
+    zmq_publisher: Option<ZmqPublisher>,
 }
 
 impl BlockchainManager {
@@ -111,6 +160,7 @@ impl BlockchainManager {
         mut self,
         mut block_batch_rx: mpsc::Receiver<(BlockBatch, Arc<OwnedSemaphorePermit>)>,
         mut command_rx: mpsc::Receiver<BlockchainManagerCommand>,
+        synced_notify: Arc<Notify>,
     ) {
         loop {
             tokio::select! {
@@ -124,6 +174,9 @@ impl BlockchainManager {
                 Some(incoming_command) = command_rx.recv() => {
                     self.handle_command(incoming_command).await;
                 }
+                () = synced_notify.notified() => {
+                    self.handle_synced().await;
+                }
                 else => {
                     todo!("TODO: exit the BC manager")
                 }
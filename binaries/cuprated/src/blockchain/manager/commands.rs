@@ -1,10 +1,13 @@
 //! This module contains the commands for the blockchain manager.
 use std::collections::HashMap;
 
-use monero_serai::block::Block;
+use monero_serai::{block::Block, transaction::Transaction};
 use tokio::sync::oneshot;
 
 use cuprate_types::TransactionVerificationData;
+use cuprate_zmq_types::json_message_types::ReorgAlert;
+
+use crate::blockchain::OptionalIndex;
 
 /// The blockchain manager commands.
 pub enum BlockchainManagerCommand {
@@ -17,6 +20,38 @@ pub enum BlockchainManagerCommand {
         /// The channel to send the response down.
         response_tx: oneshot::Sender<Result<IncomingBlockOk, anyhow::Error>>,
     },
+    /// Add a batch of blocks, imported from a file, to the top of the main chain.
+    ImportBlocks {
+        /// The blocks to add, with all the transactions defined in [`Block::transactions`].
+        blocks: Vec<(Block, Vec<Transaction>)>,
+        /// If the blocks are trusted, trusted blocks skip verification.
+        trusted: bool,
+        /// The channel to send the response down.
+        response_tx: oneshot::Sender<Result<(), anyhow::Error>>,
+    },
+    /// Add blocks that were in the database before an optional index was enabled to the index.
+    BackfillIndex {
+        /// The index to add blocks to.
+        index: OptionalIndex,
+        /// The maximum amount of blocks to add.
+        max_blocks: usize,
+        /// The channel to send the response down, see
+        /// [`BlockchainResponse::BackfillKeyImageSpends`](cuprate_types::blockchain::BlockchainResponse::BackfillKeyImageSpends)
+        /// and [`BlockchainResponse::BackfillOutputKeys`](cuprate_types::blockchain::BlockchainResponse::BackfillOutputKeys).
+        response_tx: oneshot::Sender<Option<usize>>,
+    },
+    /// Get the re-org being held for manual approval, if there is one.
+    HeldReorg {
+        /// The channel to send the response down.
+        response_tx: oneshot::Sender<Option<ReorgAlert>>,
+    },
+    /// Approve the re-org being held for manual approval, re-orging to the competing chain.
+    ApproveReorg {
+        /// The hash of the competing chain's top block, this must match the held re-org's.
+        alt_top_hash: [u8; 32],
+        /// The channel to send the response down.
+        response_tx: oneshot::Sender<Result<(), anyhow::Error>>,
+    },
 }
 
 /// The [`Ok`] response for an incoming block.
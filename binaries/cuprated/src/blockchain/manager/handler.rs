@@ -9,7 +9,7 @@ use monero_serai::{
 };
 use rayon::prelude::*;
 use tower::{Service, ServiceExt};
-use tracing::{info, instrument, Span};
+use tracing::{info, instrument, warn, Span};
 
 use cuprate_blockchain::service::{BlockchainReadHandle, BlockchainWriteHandle};
 use cuprate_consensus::{
@@ -21,17 +21,26 @@ use cuprate_consensus::{
     BlockChainContextRequest, BlockChainContextResponse, ExtendedConsensusError,
 };
 use cuprate_consensus_context::NewBlockData;
-use cuprate_fast_sync::{block_to_verified_block_information, fast_sync_stop_height};
+use cuprate_database::{config::SyncMode, Env};
+use cuprate_fast_sync::{
+    fast_sync_stop_height, try_block_to_verified_block_information, TrustedBlockError,
+};
 use cuprate_helper::cast::usize_to_u64;
+use cuprate_hex::Hex;
 use cuprate_p2p::{block_downloader::BlockBatch, constants::LONG_BAN, BroadcastRequest};
 use cuprate_txpool::service::interface::TxpoolWriteRequest;
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse, BlockchainWriteRequest},
-    AltBlockInformation, Chain, HardFork, TransactionVerificationData, VerifiedBlockInformation,
+    AltBlockInformation, Chain, ChainId, HardFork, TransactionVerificationData,
+    VerifiedBlockInformation,
 };
+use cuprate_zmq_types::json_message_types::ReorgAlert;
 
 use crate::{
-    blockchain::manager::commands::{BlockchainManagerCommand, IncomingBlockOk},
+    blockchain::{
+        manager::commands::{BlockchainManagerCommand, IncomingBlockOk},
+        OptionalIndex,
+    },
     constants::PANIC_CRITICAL_SERVICE_ERROR,
     signals::REORG_LOCK,
 };
@@ -52,11 +61,72 @@ impl super::BlockchainManager {
             } => {
                 let res = self.handle_incoming_block(block, prepped_txs).await;
 
+                drop(response_tx.send(res));
+            }
+            BlockchainManagerCommand::ImportBlocks {
+                blocks,
+                trusted,
+                response_tx,
+            } => {
+                let res = self.handle_imported_blocks(blocks, trusted).await;
+
+                drop(response_tx.send(res));
+            }
+            BlockchainManagerCommand::BackfillIndex {
+                index,
+                max_blocks,
+                response_tx,
+            } => {
+                let res = self.backfill_index(index, max_blocks).await;
+
+                drop(response_tx.send(res));
+            }
+            BlockchainManagerCommand::HeldReorg { response_tx } => {
+                let held_reorg = self.held_reorg.as_ref().map(|(_, alert)| alert.clone());
+
+                drop(response_tx.send(held_reorg));
+            }
+            BlockchainManagerCommand::ApproveReorg {
+                alt_top_hash,
+                response_tx,
+            } => {
+                let res = self.approve_reorg(alt_top_hash).await;
+
                 drop(response_tx.send(res));
             }
         }
     }
 
+    /// Handle the syncer reporting that we are synced with the network.
+    ///
+    /// This switches databases using [`SyncMode::FastThenSafe`] to safe syncing.
+    pub async fn handle_synced(&mut self) {
+        let envs = [
+            Arc::clone(&self.blockchain_env),
+            Arc::clone(&self.txpool_env),
+        ];
+
+        if envs
+            .iter()
+            .all(|env| env.sync_mode() != SyncMode::FastThenSafe)
+        {
+            return;
+        }
+
+        info!("Synced with the network, switching databases to safe sync mode.");
+
+        // This waits for all database transactions to finish, so don't block the async runtime.
+        let res = tokio::task::spawn_blocking(move || {
+            envs.iter().try_for_each(|env| env.switch_to_safe_sync())
+        })
+        .await
+        .unwrap();
+
+        if let Err(e) = res {
+            tracing::error!("Failed to switch databases to safe sync mode: {e}");
+        }
+    }
+
     /// Broadcast a valid block to the network.
     async fn broadcast_block(&mut self, block_bytes: Bytes, blockchain_height: usize) {
         self.broadcast_svc
@@ -211,22 +281,143 @@ impl super::BlockchainManager {
     /// This function will panic if any internal service returns an unexpected error that we cannot
     /// recover from.
     async fn handle_incoming_block_batch_fast_sync(&mut self, batch: BlockBatch) {
-        let mut valid_blocks = Vec::with_capacity(batch.blocks.len());
-        for (block, txs) in batch.blocks {
-            let block = block_to_verified_block_information(
+        self.add_trusted_blocks_to_main_chain(batch.blocks)
+            .await
+            .expect("fast sync block invalid");
+
+        info!(fast_sync = true, "Successfully added block batch");
+    }
+
+    /// Handles a batch of blocks imported from a file.
+    ///
+    /// Trusted blocks are added like fast sync blocks, without verification, otherwise the blocks
+    /// are fully verified.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the blocks do not follow the top of the main chain and each
+    /// other, or if a block is invalid, any blocks before the invalid block will have been added.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    #[instrument(
+        name = "imported_blocks",
+        skip_all,
+        level = "info",
+        fields(
+            start_height = self.blockchain_context_service.blockchain_context().chain_height,
+            len = blocks.len(),
+            trusted
+        )
+    )]
+    async fn handle_imported_blocks(
+        &mut self,
+        blocks: Vec<(Block, Vec<Transaction>)>,
+        trusted: bool,
+    ) -> Result<(), anyhow::Error> {
+        let mut previous = self
+            .blockchain_context_service
+            .blockchain_context()
+            .top_hash;
+
+        for (i, (block, _)) in blocks.iter().enumerate() {
+            if block.header.previous != previous {
+                if i == 0 {
+                    anyhow::bail!("Imported blocks do not follow the top of the main chain");
+                }
+
+                anyhow::bail!(
+                    "Imported block {i} in the batch does not follow the block before it"
+                );
+            }
+
+            previous = block.hash();
+        }
+
+        if trusted {
+            self.add_trusted_blocks_to_main_chain(blocks).await?;
+            info!("Successfully imported block batch");
+            return Ok(());
+        }
+
+        let (prepped_blocks, mut output_cache) = batch_prepare_main_chain_blocks(
+            blocks,
+            &mut self.blockchain_context_service,
+            self.blockchain_read_handle.clone(),
+        )
+        .await?;
+
+        for (block, txs) in prepped_blocks {
+            let verified_block = verify_prepped_main_chain_block(
+                block,
+                txs,
+                &mut self.blockchain_context_service,
+                self.blockchain_read_handle.clone(),
+                Some(&mut output_cache),
+            )
+            .await?;
+
+            self.add_valid_block_to_main_chain(verified_block).await;
+        }
+
+        info!("Successfully imported block batch");
+        Ok(())
+    }
+
+    /// Adds blocks known to be valid to the main chain, without verifying them.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if a block could not be converted, see [`TrustedBlockError`],
+    /// the blocks before it will have been added.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn add_trusted_blocks_to_main_chain(
+        &mut self,
+        blocks: Vec<(Block, Vec<Transaction>)>,
+    ) -> Result<(), TrustedBlockError> {
+        // The context cache is updated before the blocks are written to the database here, so hold
+        // the lock to stop txs being verified against a chain tip that is not in the database yet.
+        let _guard = REORG_LOCK.write().await;
+
+        let mut valid_blocks = Vec::with_capacity(blocks.len());
+        let mut res = Ok(());
+        for (block, txs) in blocks {
+            let block = match try_block_to_verified_block_information(
                 block,
                 txs,
                 self.blockchain_context_service.blockchain_context(),
-            );
+            ) {
+                Ok(block) => block,
+                Err(e) => {
+                    res = Err(e);
+                    break;
+                }
+            };
             self.add_valid_block_to_blockchain_cache(&block).await;
 
             valid_blocks.push(block);
         }
 
+        // Blocks already added to the context cache must be written to the database.
+        let Some(top_block) = valid_blocks.last() else {
+            return res;
+        };
+        let top_hash = top_block.block_hash;
+        let spent_key_images = spent_key_images(&valid_blocks);
+
         self.batch_add_valid_block_to_blockchain_database(valid_blocks)
             .await;
 
-        info!(fast_sync = true, "Successfully added block batch");
+        self.notify_txpool_of_new_blocks(top_hash, spent_key_images)
+            .await;
+
+        res
     }
 
     /// Handles an incoming [`BlockBatch`] that does not follow the main-chain.
@@ -278,8 +469,8 @@ impl super::BlockchainManager {
                     self.handle_incoming_block_batch_main_chain(batch).await;
                     return;
                 }
-                // continue adding alt blocks.
-                Ok(AddAltBlock::Cached) => (),
+                // continue adding alt blocks, a held re-org's chain is cached.
+                Ok(AddAltBlock::Cached | AddAltBlock::ReorgHeld) => (),
             }
         }
 
@@ -292,6 +483,8 @@ impl super::BlockchainManager {
     /// of the alt chain is higher than the main chain it will attempt a reorg otherwise it will add
     /// the alt block to the alt block cache.
     ///
+    /// Re-orgs deeper than the maximum automatic re-org depth are held, see [`Self::try_do_reorg`].
+    ///
     /// # Errors
     ///
     /// This will return an [`Err`] if:
@@ -326,9 +519,18 @@ impl super::BlockchainManager {
             None => (),
         }
 
-        let alt_block_info =
-            sanity_check_alt_block(block, prepared_txs, self.blockchain_context_service.clone())
-                .await?;
+        let chain_height = self
+            .blockchain_context_service
+            .blockchain_context()
+            .chain_height;
+
+        let alt_block_info = sanity_check_alt_block(
+            block,
+            prepared_txs,
+            chain_height,
+            self.blockchain_context_service.clone(),
+        )
+        .await?;
 
         // If this alt chain has more cumulative difficulty, reorg.
         if alt_block_info.cumulative_difficulty
@@ -337,25 +539,54 @@ impl super::BlockchainManager {
                 .blockchain_context()
                 .cumulative_difficulty
         {
-            self.try_do_reorg(alt_block_info).await?;
-            return Ok(AddAltBlock::Reorged);
+            return self.try_do_reorg(alt_block_info).await;
         }
 
+        self.add_alt_block_to_cache(alt_block_info).await?;
+
+        Ok(AddAltBlock::Cached)
+    }
+
+    /// Adds an [`AltBlockInformation`] to the alt block cache, then prunes old alt-chains.
+    ///
+    /// # Errors
+    ///
+    /// This will return an [`Err`] if the alt block could not be written.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn add_alt_block_to_cache(
+        &mut self,
+        alt_block: AltBlockInformation,
+    ) -> Result<(), anyhow::Error> {
         self.blockchain_write_handle
             .ready()
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR)
-            .call(BlockchainWriteRequest::WriteAltBlock(alt_block_info))
+            .call(BlockchainWriteRequest::WriteAltBlock(alt_block))
             .await?;
 
-        Ok(AddAltBlock::Cached)
+        self.blockchain_write_handle
+            .ready()
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+            .call(BlockchainWriteRequest::PruneAltChains {
+                max_depth: self.max_alt_chain_depth,
+                max_blocks: self.max_alt_blocks,
+            })
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR);
+
+        Ok(())
     }
 
     /// Attempt a re-org with the given top block of the alt-chain.
     ///
-    /// This function will take a write lock on [`REORG_LOCK`] and then set up the blockchain database
-    /// and context cache to verify the alt-chain. It will then attempt to verify and add each block
-    /// in the alt-chain to the main-chain. Releasing the lock on [`REORG_LOCK`] when finished.
+    /// This function will take a write lock on [`REORG_LOCK`], if the re-org would remove more main-chain
+    /// blocks than the maximum automatic re-org depth it is held, see [`Self::hold_reorg`], otherwise
+    /// the re-org is done with [`Self::reorg_to_alt_chain`]. Releasing the lock on [`REORG_LOCK`] when finished.
     ///
     /// # Errors
     ///
@@ -369,25 +600,196 @@ impl super::BlockchainManager {
     async fn try_do_reorg(
         &mut self,
         top_alt_block: AltBlockInformation,
+    ) -> Result<AddAltBlock, anyhow::Error> {
+        let _guard = REORG_LOCK.write().await;
+
+        let mut alt_blocks = self.alt_blocks_in_chain(top_alt_block.chain_id).await?;
+
+        let split_height = alt_blocks.first().unwrap_or(&top_alt_block).height;
+        let depth = self
+            .blockchain_context_service
+            .blockchain_context()
+            .chain_height
+            - split_height;
+
+        if self.max_reorg_depth != 0 && depth > self.max_reorg_depth {
+            self.hold_reorg(top_alt_block, split_height).await?;
+            return Ok(AddAltBlock::ReorgHeld);
+        }
+
+        alt_blocks.push(top_alt_block);
+
+        self.reorg_to_alt_chain(alt_blocks).await?;
+
+        Ok(AddAltBlock::Reorged)
+    }
+
+    /// Holds a re-org deeper than the maximum automatic re-org depth until it is approved with
+    /// [`Self::approve_reorg`].
+    ///
+    /// The top alt block is added to the alt block cache and a [`ReorgAlert`] is logged, published over
+    /// ZMQ if it is enabled and kept, replacing any previously held re-org.
+    ///
+    /// # Errors
+    ///
+    /// This will return an [`Err`] if the alt block could not be written.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn hold_reorg(
+        &mut self,
+        top_alt_block: AltBlockInformation,
+        split_height: usize,
     ) -> Result<(), anyhow::Error> {
+        let fork_height = split_height - 1;
+
+        let BlockchainResponse::BlockHash(fork_hash) = self
+            .blockchain_read_handle
+            .ready()
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+            .call(BlockchainReadRequest::BlockHash(fork_height, Chain::Main))
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        else {
+            unreachable!();
+        };
+
+        let context = self.blockchain_context_service.blockchain_context();
+
+        let alert = ReorgAlert {
+            fork_height: usize_to_u64(fork_height),
+            fork_id: Hex(fork_hash),
+            depth: usize_to_u64(context.chain_height - split_height),
+            main_height: usize_to_u64(context.chain_height - 1),
+            main_id: Hex(context.top_hash),
+            main_cumulative_difficulty: context.cumulative_difficulty,
+            alt_height: usize_to_u64(top_alt_block.height),
+            alt_id: Hex(top_alt_block.block_hash),
+            alt_cumulative_difficulty: top_alt_block.cumulative_difficulty,
+        };
+
+        let chain_id = top_alt_block.chain_id;
+        self.add_alt_block_to_cache(top_alt_block).await?;
+
+        warn!(
+            fork_height = alert.fork_height,
+            fork_hash = %hex::encode(alert.fork_id.0),
+            depth = alert.depth,
+            main_top_hash = %hex::encode(alert.main_id.0),
+            main_cumulative_difficulty = %alert.main_cumulative_difficulty,
+            alt_top_hash = %hex::encode(alert.alt_id.0),
+            alt_cumulative_difficulty = %alert.alt_cumulative_difficulty,
+            "Holding re-org deeper than the maximum automatic re-org depth, it must be approved manually."
+        );
+
+        if let Some(zmq_publisher) = &self.zmq_publisher {
+            zmq_publisher.publish_reorg_alert(&alert);
+        }
+
+        self.held_reorg = Some((chain_id, alert));
+
+        Ok(())
+    }
+
+    /// Approve the held re-org, re-orging to the competing chain if it still has more cumulative
+    /// difficulty than the main-chain.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Err`] if:
+    ///  - There is no held re-org with a top block of `alt_top_hash`.
+    ///  - The competing chain no longer has more cumulative difficulty than the main-chain.
+    ///  - The re-org was unsuccessful.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn approve_reorg(&mut self, alt_top_hash: [u8; 32]) -> Result<(), anyhow::Error> {
+        let chain_id = match &self.held_reorg {
+            Some((chain_id, alert)) if alert.alt_id.0 == alt_top_hash => *chain_id,
+            Some(_) => anyhow::bail!("The held re-org has a different top block"),
+            None => anyhow::bail!("There is no held re-org"),
+        };
+
         let _guard = REORG_LOCK.write().await;
 
-        let BlockchainResponse::AltBlocksInChain(mut alt_blocks) = self
+        let alt_blocks = self.alt_blocks_in_chain(chain_id).await?;
+
+        let alt_cumulative_difficulty = alt_blocks
+            .last()
+            .ok_or_else(|| anyhow::anyhow!("The held re-org's alt-chain is empty"))?
+            .cumulative_difficulty;
+
+        if alt_cumulative_difficulty
+            <= self
+                .blockchain_context_service
+                .blockchain_context()
+                .cumulative_difficulty
+        {
+            self.held_reorg = None;
+            anyhow::bail!("The held re-org's chain no longer has more cumulative difficulty");
+        }
+
+        info!(
+            alt_top_hash = %hex::encode(alt_top_hash),
+            "Approved held re-org"
+        );
+
+        self.reorg_to_alt_chain(alt_blocks).await
+    }
+
+    /// Get all the [`AltBlockInformation`]s in the alt-chain with the given [`ChainId`].
+    ///
+    /// # Errors
+    ///
+    /// This will return an [`Err`] if the alt-chain could not be found.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if the blockchain read service is not ready.
+    async fn alt_blocks_in_chain(
+        &mut self,
+        chain_id: ChainId,
+    ) -> Result<Vec<AltBlockInformation>, anyhow::Error> {
+        let BlockchainResponse::AltBlocksInChain(alt_blocks) = self
             .blockchain_read_handle
             .ready()
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR)
-            .call(BlockchainReadRequest::AltBlocksInChain(
-                top_alt_block.chain_id,
-            ))
+            .call(BlockchainReadRequest::AltBlocksInChain(chain_id))
             .await
             .map_err(|e| anyhow::anyhow!(e))?
         else {
             unreachable!();
         };
 
-        alt_blocks.push(top_alt_block);
+        Ok(alt_blocks)
+    }
 
+    /// Re-org to the alt-chain made up of `alt_blocks`.
+    ///
+    /// This function will set up the blockchain database and context cache to verify the alt-chain. It will
+    /// then attempt to verify and add each block in the alt-chain to the main-chain.
+    ///
+    /// The caller must hold a write lock on [`REORG_LOCK`].
+    ///
+    /// # Errors
+    ///
+    /// This function will return an [`Err`] if the re-org was unsuccessful, if this happens the chain
+    /// will be returned back into its state it was at when then function was called.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn reorg_to_alt_chain(
+        &mut self,
+        alt_blocks: Vec<AltBlockInformation>,
+    ) -> Result<(), anyhow::Error> {
         let split_height = alt_blocks[0].height;
         let current_main_chain_height = self
             .blockchain_context_service
@@ -418,14 +820,80 @@ impl super::BlockchainManager {
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
 
-        let reorg_res = self.verify_add_alt_blocks_to_main_chain(alt_blocks).await;
+        // Popping blocks flushes the alt-chains, so any held re-org is gone.
+        self.held_reorg = None;
 
-        match reorg_res {
-            Ok(()) => Ok(()),
-            Err(e) => {
-                todo!("Reverse reorg")
-            }
+        if let Err(e) = self.verify_add_alt_blocks_to_main_chain(alt_blocks).await {
+            self.reverse_reorg(old_main_chain_id, split_height).await;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    /// Reverse a failed re-org, returning the main-chain to the blocks popped from it.
+    ///
+    /// `old_main_chain_id` is the [`ChainId`] [`BlockchainWriteRequest::PopBlocks`] gave the popped
+    /// main-chain blocks and `split_height` is the height of the first popped block.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn reverse_reorg(&mut self, old_main_chain_id: ChainId, split_height: usize) {
+        warn!("Re-org failed, returning to the old main-chain.");
+
+        let old_main_chain = self
+            .alt_blocks_in_chain(old_main_chain_id)
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR);
+
+        // This pops any alt-blocks that were added and adds the old main-chain blocks back.
+        self.blockchain_write_handle
+            .ready()
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+            .call(BlockchainWriteRequest::ReverseReorg(old_main_chain_id))
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR);
+
+        let numb_blocks = self
+            .blockchain_context_service
+            .blockchain_context()
+            .chain_height
+            - split_height;
+
+        if numb_blocks != 0 {
+            self.blockchain_context_service
+                .ready()
+                .await
+                .expect(PANIC_CRITICAL_SERVICE_ERROR)
+                .call(BlockChainContextRequest::PopBlocks { numb_blocks })
+                .await
+                .expect(PANIC_CRITICAL_SERVICE_ERROR);
+        }
+
+        for alt_block in &old_main_chain {
+            self.update_blockchain_cache(alt_block_new_block_data(alt_block))
+                .await;
         }
+
+        let Some(top_block) = old_main_chain.last() else {
+            return;
+        };
+
+        let spent_key_images = old_main_chain
+            .iter()
+            .flat_map(|block| &block.txs)
+            .flat_map(|tx| &tx.tx.prefix().inputs)
+            .filter_map(|input| match input {
+                Input::ToKey { key_image, .. } => Some(key_image.0),
+                Input::Gen(_) => None,
+            })
+            .collect();
+
+        self.notify_txpool_of_new_blocks(top_block.block_hash, spent_key_images)
+            .await;
     }
 
     /// Verify and add a list of [`AltBlockInformation`]s to the main-chain.
@@ -475,6 +943,9 @@ impl super::BlockchainManager {
     ///
     /// This function will update the blockchain database and the context cache.
     ///
+    /// The blockchain database is updated before the context cache, so a tx verified against the
+    /// new chain tip in the context cache will always see the new block in the database.
+    ///
     /// # Panics
     ///
     /// This function will panic if any internal service returns an unexpected error that we cannot
@@ -483,20 +954,10 @@ impl super::BlockchainManager {
         &mut self,
         verified_block: VerifiedBlockInformation,
     ) {
-        // FIXME: this is pretty inefficient, we should probably return the KI map created in the consensus crate.
-        let spent_key_images = verified_block
-            .txs
-            .iter()
-            .flat_map(|tx| {
-                tx.tx.prefix().inputs.iter().map(|input| match input {
-                    Input::ToKey { key_image, .. } => key_image.0,
-                    Input::Gen(_) => unreachable!(),
-                })
-            })
-            .collect::<Vec<[u8; 32]>>();
+        let spent_key_images = spent_key_images(std::slice::from_ref(&verified_block));
+        let top_hash = verified_block.block_hash;
 
-        self.add_valid_block_to_blockchain_cache(&verified_block)
-            .await;
+        let new_block_data = new_block_data(&verified_block);
 
         self.blockchain_write_handle
             .ready()
@@ -506,11 +967,34 @@ impl super::BlockchainManager {
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
 
+        self.update_blockchain_cache(new_block_data).await;
+
+        self.notify_txpool_of_new_blocks(top_hash, spent_key_images)
+            .await;
+    }
+
+    /// Tells the tx-pool about new blocks added to the main-chain.
+    ///
+    /// This must be called after the blocks have been added to the blockchain database, as the
+    /// tx-pool will reject txs verified against an older chain tip after this call.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn notify_txpool_of_new_blocks(
+        &mut self,
+        top_hash: [u8; 32],
+        spent_key_images: Vec<[u8; 32]>,
+    ) {
         self.txpool_write_handle
             .ready()
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR)
-            .call(TxpoolWriteRequest::NewBlock { spent_key_images })
+            .call(TxpoolWriteRequest::NewBlock {
+                top_hash,
+                spent_key_images,
+            })
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
     }
@@ -525,20 +1009,22 @@ impl super::BlockchainManager {
         &mut self,
         verified_block: &VerifiedBlockInformation,
     ) {
+        self.update_blockchain_cache(new_block_data(verified_block))
+            .await;
+    }
+
+    /// Updates the blockchain context cache with a new block.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn update_blockchain_cache(&mut self, new_block_data: NewBlockData) {
         self.blockchain_context_service
             .ready()
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR)
-            .call(BlockChainContextRequest::Update(NewBlockData {
-                block_hash: verified_block.block_hash,
-                height: verified_block.height,
-                timestamp: verified_block.block.header.timestamp,
-                weight: verified_block.weight,
-                long_term_weight: verified_block.long_term_weight,
-                generated_coins: verified_block.generated_coins,
-                vote: HardFork::from_vote(verified_block.block.header.hardfork_signal),
-                cumulative_difficulty: verified_block.cumulative_difficulty,
-            }))
+            .call(BlockChainContextRequest::Update(new_block_data))
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
     }
@@ -563,6 +1049,102 @@ impl super::BlockchainManager {
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
     }
+
+    /// Adds up to `max_blocks` old blocks to `index`.
+    ///
+    /// Returns the height below which blocks still need to be added, or [`None`] if the
+    /// index is disabled.
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if any internal service returns an unexpected error that we cannot
+    /// recover from.
+    async fn backfill_index(&mut self, index: OptionalIndex, max_blocks: usize) -> Option<usize> {
+        let request = match index {
+            OptionalIndex::KeyImages => BlockchainWriteRequest::BackfillKeyImageSpends(max_blocks),
+            OptionalIndex::OutputKeys => BlockchainWriteRequest::BackfillOutputKeys(max_blocks),
+        };
+
+        match self
+            .blockchain_write_handle
+            .ready()
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+            .call(request)
+            .await
+            .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        {
+            BlockchainResponse::BackfillKeyImageSpends(height)
+            | BlockchainResponse::BackfillOutputKeys(height) => height,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Returns the [`NewBlockData`] to update the blockchain context cache with for a [`VerifiedBlockInformation`].
+pub(super) fn new_block_data(verified_block: &VerifiedBlockInformation) -> NewBlockData {
+    NewBlockData {
+        block_hash: verified_block.block_hash,
+        height: verified_block.height,
+        timestamp: verified_block.block.header.timestamp,
+        weight: verified_block.weight,
+        long_term_weight: verified_block.long_term_weight,
+        generated_coins: verified_block.generated_coins,
+        vote: HardFork::from_vote(verified_block.block.header.hardfork_signal),
+        cumulative_difficulty: verified_block.cumulative_difficulty,
+        tx_fees: verified_block
+            .txs
+            .iter()
+            .map(|tx| (tx.fee, tx.tx_weight))
+            .collect(),
+    }
+}
+
+/// Creates a [`NewBlockData`] for an alt-block from a failed re-org's old main-chain,
+/// see [`super::BlockchainManager::reverse_reorg`].
+fn alt_block_new_block_data(alt_block: &AltBlockInformation) -> NewBlockData {
+    let tx_fees = alt_block
+        .txs
+        .iter()
+        .map(|tx| (tx.fee, tx.tx_weight))
+        .collect::<Vec<_>>();
+
+    let total_fees = tx_fees.iter().map(|(fee, _)| fee).sum::<u64>();
+    let total_miner_output = alt_block
+        .block
+        .miner_transaction
+        .prefix()
+        .outputs
+        .iter()
+        .map(|out| out.amount.unwrap_or(0))
+        .sum::<u64>();
+
+    NewBlockData {
+        block_hash: alt_block.block_hash,
+        height: alt_block.height,
+        timestamp: alt_block.block.header.timestamp,
+        weight: alt_block.weight,
+        long_term_weight: alt_block.long_term_weight,
+        generated_coins: total_miner_output - total_fees,
+        vote: HardFork::from_vote(alt_block.block.header.hardfork_signal),
+        cumulative_difficulty: alt_block.cumulative_difficulty,
+        tx_fees,
+    }
+}
+
+/// Returns all the key images spent in the given blocks.
+fn spent_key_images(blocks: &[VerifiedBlockInformation]) -> Vec<[u8; 32]> {
+    // FIXME: this is pretty inefficient, we should probably return the KI map created in the consensus crate.
+    blocks
+        .iter()
+        .flat_map(|block| &block.txs)
+        .flat_map(|tx| {
+            tx.tx.prefix().inputs.iter().map(|input| match input {
+                Input::ToKey { key_image, .. } => key_image.0,
+                Input::Gen(_) => unreachable!(),
+            })
+        })
+        .collect()
 }
 
 /// The result from successfully adding an alt-block.
@@ -571,4 +1153,6 @@ enum AddAltBlock {
     Cached,
     /// The chain was reorged.
     Reorged,
+    /// The alt-block was cached, the re-org to its chain is held until approved.
+    ReorgHeld,
 }
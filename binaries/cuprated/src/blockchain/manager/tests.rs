@@ -18,7 +18,7 @@ use crate::blockchain::{
     ConsensusBlockchainReadHandle,
 };
 
-async fn mock_manager(data_dir: PathBuf) -> BlockchainManager {
+async fn mock_manager(data_dir: PathBuf, max_reorg_depth: usize) -> BlockchainManager {
     let blockchain_config = cuprate_blockchain::config::ConfigBuilder::new()
         .data_directory(data_dir.clone())
         .build();
@@ -26,9 +26,9 @@ async fn mock_manager(data_dir: PathBuf) -> BlockchainManager {
         .data_directory(data_dir)
         .build();
 
-    let (mut blockchain_read_handle, mut blockchain_write_handle, _) =
+    let (mut blockchain_read_handle, mut blockchain_write_handle, blockchain_env) =
         cuprate_blockchain::service::init(blockchain_config).unwrap();
-    let (txpool_read_handle, txpool_write_handle, _) =
+    let (txpool_read_handle, txpool_write_handle, txpool_env) =
         cuprate_txpool::service::init(txpool_config).unwrap();
 
     check_add_genesis(
@@ -56,9 +56,16 @@ async fn mock_manager(data_dir: PathBuf) -> BlockchainManager {
         blockchain_write_handle,
         blockchain_read_handle,
         txpool_write_handle,
+        blockchain_env,
+        txpool_env,
         blockchain_context_service,
         stop_current_block_downloader: Arc::new(Default::default()),
         broadcast_svc: BroadcastSvc::mock(),
+        max_alt_chain_depth: usize::MAX,
+        max_alt_blocks: usize::MAX,
+        max_reorg_depth,
+        held_reorg: None,
+        zmq_publisher: None,
     }
 }
 
@@ -98,10 +105,10 @@ fn generate_block(context: &BlockchainContext) -> Block {
 async fn simple_reorg() {
     // create 2 managers
     let data_dir_1 = tempfile::tempdir().unwrap();
-    let mut manager_1 = mock_manager(data_dir_1.path().to_path_buf()).await;
+    let mut manager_1 = mock_manager(data_dir_1.path().to_path_buf(), 0).await;
 
     let data_dir_2 = tempfile::tempdir().unwrap();
-    let mut manager_2 = mock_manager(data_dir_2.path().to_path_buf()).await;
+    let mut manager_2 = mock_manager(data_dir_2.path().to_path_buf(), 0).await;
 
     // give both managers the same first non-genesis block
     let block_1 = generate_block(manager_1.blockchain_context_service.blockchain_context());
@@ -213,10 +220,10 @@ async fn simple_reorg_block_batch() {
 
     // create 2 managers
     let data_dir_1 = tempfile::tempdir().unwrap();
-    let mut manager_1 = mock_manager(data_dir_1.path().to_path_buf()).await;
+    let mut manager_1 = mock_manager(data_dir_1.path().to_path_buf(), 0).await;
 
     let data_dir_2 = tempfile::tempdir().unwrap();
-    let mut manager_2 = mock_manager(data_dir_2.path().to_path_buf()).await;
+    let mut manager_2 = mock_manager(data_dir_2.path().to_path_buf(), 0).await;
 
     // give both managers the same first non-genesis block
     let block_1 = generate_block(manager_1.blockchain_context_service.blockchain_context());
@@ -318,3 +325,105 @@ async fn simple_reorg_block_batch() {
         4
     );
 }
+
+async fn add_block(manager: &mut BlockchainManager, block: Block) {
+    manager
+        .handle_command(BlockchainManagerCommand::AddBlock {
+            block,
+            prepped_txs: HashMap::new(),
+            response_tx: oneshot::channel().0,
+        })
+        .await;
+}
+
+/// A re-org deeper than `max_reorg_depth` is held until it is approved.
+#[tokio::test]
+async fn held_reorg() {
+    // manager 1 only re-orgs 1 block automatically
+    let data_dir_1 = tempfile::tempdir().unwrap();
+    let mut manager_1 = mock_manager(data_dir_1.path().to_path_buf(), 1).await;
+
+    let data_dir_2 = tempfile::tempdir().unwrap();
+    let mut manager_2 = mock_manager(data_dir_2.path().to_path_buf(), 0).await;
+
+    // give both managers the same first non-genesis block
+    let block_1 = generate_block(manager_1.blockchain_context_service.blockchain_context());
+    add_block(&mut manager_1, block_1.clone()).await;
+    add_block(&mut manager_2, block_1).await;
+
+    // build 2 blocks on manager 1 and 3 competing blocks on manager 2
+    for _ in 0..2 {
+        let block = generate_block(manager_1.blockchain_context_service.blockchain_context());
+        add_block(&mut manager_1, block).await;
+    }
+
+    let mut competing_blocks = vec![];
+    for _ in 0..3 {
+        let block = generate_block(manager_2.blockchain_context_service.blockchain_context());
+        competing_blocks.push(block.clone());
+        add_block(&mut manager_2, block).await;
+    }
+
+    let manager_1_context = manager_1
+        .blockchain_context_service
+        .blockchain_context()
+        .clone();
+
+    // give manager 1 the competing chain, the last block makes it a depth 2 re-org
+    let alt_top_hash = competing_blocks.last().unwrap().hash();
+    for block in competing_blocks {
+        add_block(&mut manager_1, block).await;
+    }
+
+    // make sure the re-org was held and didn't change the context
+    assert_eq!(
+        &manager_1_context,
+        manager_1.blockchain_context_service.blockchain_context()
+    );
+
+    let (response_tx, response_rx) = oneshot::channel();
+    manager_1
+        .handle_command(BlockchainManagerCommand::HeldReorg { response_tx })
+        .await;
+    let alert = response_rx.await.unwrap().unwrap();
+
+    assert_eq!(alert.depth, 2);
+    assert_eq!(alert.fork_height, 1);
+    assert_eq!(alert.main_id.0, manager_1_context.top_hash);
+    assert_eq!(alert.alt_id.0, alt_top_hash);
+
+    // approving a different chain fails and keeps the re-org held
+    let (response_tx, response_rx) = oneshot::channel();
+    manager_1
+        .handle_command(BlockchainManagerCommand::ApproveReorg {
+            alt_top_hash: [1; 32],
+            response_tx,
+        })
+        .await;
+    assert!(response_rx.await.unwrap().is_err());
+    assert_eq!(
+        &manager_1_context,
+        manager_1.blockchain_context_service.blockchain_context()
+    );
+
+    // approving the held re-org re-orgs to manager 2's chain
+    let (response_tx, response_rx) = oneshot::channel();
+    manager_1
+        .handle_command(BlockchainManagerCommand::ApproveReorg {
+            alt_top_hash,
+            response_tx,
+        })
+        .await;
+    response_rx.await.unwrap().unwrap();
+
+    assert_eq!(
+        manager_1.blockchain_context_service.blockchain_context(),
+        manager_2.blockchain_context_service.blockchain_context()
+    );
+
+    let (response_tx, response_rx) = oneshot::channel();
+    manager_1
+        .handle_command(BlockchainManagerCommand::HeldReorg { response_tx })
+        .await;
+    assert!(response_rx.await.unwrap().is_none());
+}
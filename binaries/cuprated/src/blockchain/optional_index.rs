@@ -0,0 +1,55 @@
+//! Optional indexes
+//!
+//! Backfills the optional database indexes, see [`cuprate_blockchain::ops::key_image`]
+//! and [`cuprate_blockchain::ops::output`].
+use std::{fmt, time::Duration};
+
+use tracing::{debug, error, info};
+
+use crate::blockchain::interface;
+
+/// The maximum amount of blocks to add to an index in a single database write.
+const BACKFILL_BATCH_LEN: usize = 1_000;
+
+/// The time to wait between batches, so new blocks are not held up by the backfill.
+const BACKFILL_BATCH_DELAY: Duration = Duration::from_millis(100);
+
+/// An optional database index.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OptionalIndex {
+    /// The key image index, `key image -> spending transaction`.
+    KeyImages,
+    /// The output key index, `output public key -> output`.
+    OutputKeys,
+}
+
+impl fmt::Display for OptionalIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::KeyImages => "key image index",
+            Self::OutputKeys => "output key index",
+        })
+    }
+}
+
+/// Add all blocks that were in the database before `index` was enabled to the index.
+///
+/// Any error is logged, the backfill continues from where it stopped on the next start.
+pub async fn backfill_index(index: OptionalIndex) {
+    loop {
+        match interface::backfill_index(index, BACKFILL_BATCH_LEN).await {
+            Ok(None) => return,
+            Ok(Some(0)) => {
+                info!("The {index} is complete");
+                return;
+            }
+            Ok(Some(height)) => debug!("Backfilling the {index}, {height} blocks left"),
+            Err(e) => {
+                error!("Failed to backfill the {index}: {e:#}");
+                return;
+            }
+        }
+
+        tokio::time::sleep(BACKFILL_BATCH_DELAY).await;
+    }
+}
@@ -0,0 +1,489 @@
+//! Revalidation
+//!
+//! Re-verifies the blocks in the main chain, e.g. blocks added with fast sync, with full consensus
+//! verification. The database is opened read-only, so invalid blocks are only reported.
+use std::{
+    collections::HashMap,
+    ops::Range,
+    slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use anyhow::bail;
+use futures::{future::BoxFuture, FutureExt, TryFutureExt};
+use indexmap::{IndexMap, IndexSet};
+use monero_serai::{block::Block, transaction::Transaction};
+use serde::Serialize;
+use tower::{BoxError, Service, ServiceExt};
+
+use cuprate_blockchain::{
+    ops::property::get_key_image_index_height,
+    service::BlockchainReadHandle,
+    tables::{OpenTables, Tables},
+};
+use cuprate_consensus::{
+    block::{batch_prepare_main_chain_blocks, verify_prepped_main_chain_block},
+    BlockChainContextRequest, BlockchainContextService, BlockchainReadRequest, BlockchainResponse,
+    ExtendedConsensusError,
+};
+use cuprate_database::{ConcreteEnv, Env, EnvInner};
+use cuprate_database_service::ReaderThreads;
+use cuprate_fast_sync::block_to_verified_block_information;
+use cuprate_helper::cast::usize_to_u64;
+use cuprate_types::{output_cache::OutputCache, Chain, TransactionBlobs, VerifiedBlockInformation};
+
+use crate::{blockchain::manager::new_block_data, config::Config};
+
+/// The maximum amount of blocks verified in a batch.
+const REVALIDATE_BATCH_LEN: usize = 100;
+
+/// The result of [`revalidate`].
+#[derive(Debug, Serialize)]
+struct RevalidateReport {
+    /// The height of the first block verified.
+    start_height: usize,
+    /// The chain height, blocks below this were verified.
+    chain_height: usize,
+    /// The blocks that failed verification.
+    invalid_blocks: Vec<InvalidBlock>,
+}
+
+/// A block that failed verification.
+#[derive(Debug, Serialize)]
+struct InvalidBlock {
+    /// The height of the block.
+    height: usize,
+    /// The hex encoded block hash.
+    hash: String,
+    /// Why the block is invalid.
+    error: String,
+}
+
+/// Re-verify the main chain from `start_height` to the top and print a JSON report.
+///
+/// The context caches are rebuilt at `start_height` and updated with each block, each block
+/// is verified as if it were the top of the chain, see [`HistoricalDatabase`].
+///
+/// The key image index must be fully built, otherwise an error is returned without verifying any blocks.
+///
+/// Returns `true` if all blocks are valid.
+pub fn revalidate(config: &Config, start_height: usize) -> Result<bool, anyhow::Error> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    rt.block_on(async {
+        let env = cuprate_blockchain::open_read_only(config.blockchain_config())?;
+        check_key_image_index(&env)?;
+
+        let mut read_handle = cuprate_blockchain::service::init_read_service(
+            Arc::new(env),
+            ReaderThreads::Number(config.storage.reader_threads),
+        );
+
+        let BlockchainResponse::ChainHeight(chain_height, _) = read_handle
+            .ready()
+            .await?
+            .call(BlockchainReadRequest::ChainHeight)
+            .await?
+        else {
+            unreachable!();
+        };
+
+        // The genesis block is not verified.
+        let start_height = start_height.max(1);
+        if start_height >= chain_height {
+            bail!("Start height {start_height} is not below the chain height {chain_height}");
+        }
+
+        let database = HistoricalDatabase::new(read_handle.clone(), start_height);
+
+        eprintln!("Rebuilding the context caches at height {start_height}");
+        let mut context_svc = cuprate_consensus::initialize_blockchain_context(
+            config.context_config(),
+            database.clone(),
+        )
+        .await?;
+
+        let mut report = RevalidateReport {
+            start_height,
+            chain_height,
+            invalid_blocks: Vec::new(),
+        };
+
+        for batch_start in (start_height..chain_height).step_by(REVALIDATE_BATCH_LEN) {
+            let batch_end = (batch_start + REVALIDATE_BATCH_LEN).min(chain_height);
+            let blocks = read_blocks(&mut read_handle, batch_start..batch_end).await?;
+
+            let (verified, error) = verify_batch(&blocks, &mut context_svc, &database).await;
+
+            // The whole batch failed to prepare or a block is invalid, verify the
+            // remaining blocks one at a time to find the invalid blocks.
+            if error.is_some() {
+                for block in &blocks[verified..] {
+                    let (_, Some(error)) =
+                        verify_batch(slice::from_ref(block), &mut context_svc, &database).await
+                    else {
+                        continue;
+                    };
+
+                    let (block, txs) = block.clone();
+                    let invalid_block = InvalidBlock {
+                        height: context_svc.blockchain_context().chain_height,
+                        hash: hex::encode(block.hash()),
+                        error: error.to_string(),
+                    };
+                    eprintln!(
+                        "\nBlock {} at height {} is invalid: {}",
+                        invalid_block.hash, invalid_block.height, invalid_block.error
+                    );
+                    report.invalid_blocks.push(invalid_block);
+
+                    // Continue from the stored block, like fast sync would.
+                    let block = block_to_verified_block_information(
+                        block,
+                        txs,
+                        context_svc.blockchain_context(),
+                    );
+                    update_context(&mut context_svc, &block).await?;
+                }
+            }
+
+            eprint!("\r{batch_end}/{chain_height} blocks");
+        }
+        eprintln!();
+
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        Ok(report.invalid_blocks.is_empty())
+    })
+}
+
+/// Returns an error if the key image index is not fully built.
+///
+/// Double spends can only be checked with the index, as the database only stores each key image once,
+/// see [`HistoricalDatabase`].
+pub(super) fn check_key_image_index(env: &ConcreteEnv) -> Result<(), anyhow::Error> {
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let tables = env_inner.open_tables(&tx_ro)?;
+
+    match get_key_image_index_height(tables.metadata())? {
+        None => bail!(
+            "Verifying below the chain height needs the key image index to check for double spends, enable `key_image_index` in the `[storage.blockchain]` config"
+        ),
+        Some(0) => Ok(()),
+        Some(height) => bail!(
+            "The key image index is still being built, blocks below height {height} are not in it yet"
+        ),
+    }
+}
+
+/// Fully verify a batch of blocks at the top of the context cache, updating the context with
+/// each valid block.
+///
+/// Returns the amount of blocks verified and the error from the first block that failed, if
+/// the batch failed to prepare no blocks are verified.
+async fn verify_batch(
+    blocks: &[(Block, Vec<Transaction>)],
+    context_svc: &mut BlockchainContextService,
+    database: &HistoricalDatabase,
+) -> (usize, Option<ExtendedConsensusError>) {
+    database.set_chain_height(context_svc.blockchain_context().chain_height);
+
+    let (prepped_blocks, mut cache) =
+        match batch_prepare_main_chain_blocks(blocks.to_vec(), context_svc, database.clone()).await
+        {
+            Ok(prepped) => prepped,
+            Err(e) => return (0, Some(e)),
+        };
+
+    for (i, (block, txs)) in prepped_blocks.into_iter().enumerate() {
+        database.set_chain_height(block.height);
+
+        let result = async {
+            let block = verify_prepped_main_chain_block(
+                block,
+                txs,
+                context_svc,
+                database.clone(),
+                Some(&mut cache),
+            )
+            .await?;
+
+            update_context(context_svc, &block).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            return (i, Some(e));
+        }
+    }
+
+    (blocks.len(), None)
+}
+
+/// Add a block to the top of the context cache.
+async fn update_context(
+    context_svc: &mut BlockchainContextService,
+    block: &VerifiedBlockInformation,
+) -> Result<(), ExtendedConsensusError> {
+    context_svc
+        .ready()
+        .await?
+        .call(BlockChainContextRequest::Update(new_block_data(block)))
+        .await?;
+
+    Ok(())
+}
+
+/// Read the blocks at `heights` and their transactions from the database.
+async fn read_blocks(
+    read_handle: &mut BlockchainReadHandle,
+    heights: Range<usize>,
+) -> Result<Vec<(Block, Vec<Transaction>)>, anyhow::Error> {
+    let BlockchainResponse::BlockCompleteEntriesByHeight(entries) = read_handle
+        .ready()
+        .await?
+        .call(BlockchainReadRequest::BlockCompleteEntriesByHeight(
+            heights.collect(),
+        ))
+        .await?
+    else {
+        unreachable!();
+    };
+
+    entries
+        .into_iter()
+        .map(|entry| {
+            let block = Block::read(&mut entry.block.as_ref())?;
+
+            let txs = match entry.txs {
+                TransactionBlobs::Normal(txs) => txs
+                    .iter()
+                    .map(|tx| Transaction::read(&mut tx.as_ref()))
+                    .collect::<Result<_, _>>()?,
+                TransactionBlobs::None => Vec::new(),
+                TransactionBlobs::Pruned(_) => bail!("Pruned blocks can not be revalidated"),
+            };
+
+            Ok((block, txs))
+        })
+        .collect()
+}
+
+/// A view of the blockchain database at a past chain height.
+///
+/// The requests the context cache and block verifier make are answered as if the blocks at and
+/// above the chain height did not exist, other requests are passed through unchanged.
+#[derive(Clone)]
+pub(super) struct HistoricalDatabase {
+    read_handle: BlockchainReadHandle,
+    chain_height: Arc<AtomicUsize>,
+}
+
+impl HistoricalDatabase {
+    /// Create a view of the database at `chain_height`.
+    ///
+    /// The key image index must be fully built, see [`check_key_image_index`].
+    pub(super) fn new(read_handle: BlockchainReadHandle, chain_height: usize) -> Self {
+        Self {
+            read_handle,
+            chain_height: Arc::new(AtomicUsize::new(chain_height)),
+        }
+    }
+
+    /// Set the chain height of the view, i.e. the height of the block being verified.
+    fn set_chain_height(&self, chain_height: usize) {
+        self.chain_height.store(chain_height, Ordering::Release);
+    }
+}
+
+impl Service<BlockchainReadRequest> for HistoricalDatabase {
+    type Response = BlockchainResponse;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.read_handle.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: BlockchainReadRequest) -> Self::Future {
+        let chain_height = self.chain_height.load(Ordering::Acquire);
+        let mut read_handle = self.read_handle.clone();
+
+        match req {
+            BlockchainReadRequest::ChainHeight => async move {
+                let BlockchainResponse::BlockHash(top_hash) = read_handle
+                    .ready()
+                    .await?
+                    .call(BlockchainReadRequest::BlockHash(
+                        chain_height - 1,
+                        Chain::Main,
+                    ))
+                    .await?
+                else {
+                    unreachable!();
+                };
+
+                Ok(BlockchainResponse::ChainHeight(chain_height, top_hash))
+            }
+            .boxed(),
+            BlockchainReadRequest::NumberOutputsWithAmount(amounts) => async move {
+                let outputs_with_amount =
+                    number_outputs_with_amount(&mut read_handle, amounts, chain_height).await?;
+
+                Ok(BlockchainResponse::NumberOutputsWithAmount(
+                    outputs_with_amount,
+                ))
+            }
+            .boxed(),
+            BlockchainReadRequest::Outputs { outputs, get_txid } => async move {
+                let amounts = outputs.keys().copied().collect();
+                let outputs_with_amount =
+                    number_outputs_with_amount(&mut read_handle, amounts, chain_height).await?;
+
+                let BlockchainResponse::Outputs(cache) = read_handle
+                    .ready()
+                    .await?
+                    .call(BlockchainReadRequest::Outputs {
+                        outputs: outputs.clone(),
+                        get_txid,
+                    })
+                    .await?
+                else {
+                    unreachable!();
+                };
+
+                // Outputs created at or above the chain height are treated as not in the database yet.
+                let mut cached_outputs = IndexMap::<u64, IndexMap<_, _>>::new();
+                let mut wanted_outputs = IndexMap::<u64, IndexSet<_>>::new();
+                for (amount, amount_indexes) in outputs {
+                    for amount_index in amount_indexes {
+                        match cache.get_output(amount, amount_index) {
+                            Some(output) if output.height < chain_height => {
+                                cached_outputs
+                                    .entry(amount)
+                                    .or_default()
+                                    .insert(amount_index, *output);
+                            }
+                            _ => {
+                                wanted_outputs
+                                    .entry(amount)
+                                    .or_default()
+                                    .insert(amount_index);
+                            }
+                        }
+                    }
+                }
+
+                let number_of_outputs = outputs_with_amount
+                    .into_iter()
+                    .map(|(amount, count)| (amount, usize_to_u64(count)))
+                    .collect();
+
+                Ok(BlockchainResponse::Outputs(OutputCache::new(
+                    cached_outputs,
+                    number_of_outputs,
+                    wanted_outputs,
+                )))
+            }
+            .boxed(),
+            BlockchainReadRequest::KeyImagesSpent(key_images) => async move {
+                // Key images in the database are unique, so the key images of the blocks being
+                // verified are only double spends if they were spent in an earlier block. That
+                // can only be checked with the key image index, which `revalidate` checks is built.
+                let BlockchainResponse::KeyImageSpends(Some(spends)) = read_handle
+                    .ready()
+                    .await?
+                    .call(BlockchainReadRequest::KeyImageSpends(
+                        key_images.into_iter().collect(),
+                    ))
+                    .await?
+                else {
+                    return Err("The key image index is disabled".into());
+                };
+
+                let spent = spends
+                    .into_iter()
+                    .flatten()
+                    .any(|spend| spend.block_height < chain_height);
+
+                Ok(BlockchainResponse::KeyImagesSpent(spent))
+            }
+            .boxed(),
+            req => read_handle.oneshot(req).map_err(Into::into).boxed(),
+        }
+    }
+}
+
+/// Returns the amount of outputs with each amount created below `chain_height`.
+///
+/// Output heights only increase with the amount index, so the index of the first output at or
+/// above `chain_height` is binary searched for, for all amounts at once.
+async fn number_outputs_with_amount(
+    read_handle: &mut BlockchainReadHandle,
+    amounts: Vec<u64>,
+    chain_height: usize,
+) -> Result<HashMap<u64, usize>, BoxError> {
+    let BlockchainResponse::NumberOutputsWithAmount(outputs_with_amount) = read_handle
+        .ready()
+        .await?
+        .call(BlockchainReadRequest::NumberOutputsWithAmount(amounts))
+        .await?
+    else {
+        unreachable!();
+    };
+
+    // `amount -> (low, high)`, the first output at or above the chain height is in `low..=high`.
+    let mut bounds = outputs_with_amount
+        .into_iter()
+        .map(|(amount, count)| (amount, (0, count)))
+        .collect::<HashMap<_, _>>();
+
+    loop {
+        let outputs = bounds
+            .iter()
+            .filter(|(_, (low, high))| low < high)
+            .map(|(&amount, &(low, high))| {
+                (amount, IndexSet::from([usize_to_u64((low + high) / 2)]))
+            })
+            .collect::<IndexMap<_, _>>();
+
+        if outputs.is_empty() {
+            break;
+        }
+
+        let BlockchainResponse::Outputs(cache) = read_handle
+            .ready()
+            .await?
+            .call(BlockchainReadRequest::Outputs {
+                outputs,
+                get_txid: false,
+            })
+            .await?
+        else {
+            unreachable!();
+        };
+
+        for (&amount, (low, high)) in &mut bounds {
+            if low >= high {
+                continue;
+            }
+
+            let mid = (*low + *high) / 2;
+            match cache.get_output(amount, usize_to_u64(mid)) {
+                Some(output) if output.height < chain_height => *low = mid + 1,
+                _ => *high = mid,
+            }
+        }
+    }
+
+    Ok(bounds
+        .into_iter()
+        .map(|(amount, (low, _))| (amount, low))
+        .collect())
+}
@@ -37,6 +37,7 @@ pub async fn syncer<CN>(
     mut clearnet_interface: NetworkInterface<ClearNet>,
     incoming_block_batch_tx: mpsc::Sender<(BlockBatch, Arc<OwnedSemaphorePermit>)>,
     stop_current_block_downloader: Arc<Notify>,
+    synced_notify: Arc<Notify>,
     block_downloader_config: BlockDownloaderConfig,
 ) -> Result<(), SyncerError>
 where
@@ -65,7 +66,7 @@ where
 
         let blockchain_context = context_svc.blockchain_context();
 
-        if !check_behind_peers(blockchain_context, &mut clearnet_interface).await? {
+        if !check_behind_peers(blockchain_context, &mut clearnet_interface, &synced_notify).await? {
             continue;
         }
 
@@ -94,7 +95,7 @@ where
 
                         let blockchain_context = context_svc.blockchain_context();
 
-                        if !check_behind_peers(blockchain_context, &mut clearnet_interface).await? {
+                        if !check_behind_peers(blockchain_context, &mut clearnet_interface, &synced_notify).await? {
                             tracing::info!("Synchronised with the network.");
                         }
 
@@ -112,9 +113,12 @@ where
 }
 
 /// Returns `true` if we are behind the current connected network peers.
+///
+/// If we are not behind any connected peers, `synced_notify` is notified.
 async fn check_behind_peers(
     blockchain_context: &BlockchainContext,
     mut clearnet_interface: &mut NetworkInterface<ClearNet>,
+    synced_notify: &Notify,
 ) -> Result<bool, tower::BoxError> {
     let PeerSetResponse::MostPoWSeen {
         cumulative_difficulty,
@@ -130,6 +134,11 @@ async fn check_behind_peers(
     };
 
     if cumulative_difficulty <= blockchain_context.cumulative_difficulty {
+        // A cumulative difficulty of 0 means we have no peers to compare against.
+        if cumulative_difficulty != 0 {
+            synced_notify.notify_one();
+        }
+
         return Ok(false);
     }
 
@@ -0,0 +1,377 @@
+//! Standalone verification
+//!
+//! Verifies a block or transactions against an existing blockchain database without running a node,
+//! the database is opened read-only so nothing is written to it.
+//!
+//! By default the top of the chain is used, a past height can be given to check if a block or
+//! transactions would have been accepted at that height.
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use monero_serai::{block::Block, transaction::Transaction};
+use tower::{BoxError, Service, ServiceExt};
+
+use cuprate_consensus::{
+    __private::Database,
+    batch_verifier::MultiThreadedBatchVerifier,
+    block::{verify_prepped_main_chain_block, PreparedBlock},
+    initialize_blockchain_context,
+    transactions::{new_tx_verification_data, start_tx_verification},
+    BlockChainContextRequest, BlockChainContextResponse, BlockchainContextService,
+    BlockchainReadRequest, BlockchainResponse, ExtendedConsensusError,
+};
+use cuprate_consensus_rules::blocks::randomx_seed_height;
+use cuprate_database_service::ReaderThreads;
+use cuprate_helper::asynch::rayon_spawn_async;
+
+use crate::{
+    blockchain::{
+        init_consensus,
+        revalidate::{check_key_image_index, HistoricalDatabase},
+        ConsensusBlockchainReadHandle,
+    },
+    config::Config,
+};
+
+/// Verify `block` or, if it is [`None`], `txs` against the blockchain database at `height`.
+///
+/// If a block is given `txs` must be its transactions, in any order.
+///
+/// If `height` is [`None`] the top of the chain is used. Otherwise the context caches are rebuilt at
+/// `height` and the blocks at and above it are ignored, see [`HistoricalDatabase`], this needs the
+/// key image index to be fully built.
+///
+/// The result of each verification step is printed along with how long it took.
+///
+/// Returns `true` if the block/transactions were accepted, an error is returned if
+/// the database could not be opened or the blobs could not be deserialized.
+pub fn verify_blobs(
+    config: &Config,
+    block: Option<&[u8]>,
+    txs: &[Vec<u8>],
+    height: Option<usize>,
+) -> Result<bool, anyhow::Error> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+
+    rt.block_on(async {
+        let env = cuprate_blockchain::open_read_only(config.blockchain_config())?;
+        let env = Arc::new(env);
+        let mut read_handle = cuprate_blockchain::service::init_read_service(
+            Arc::clone(&env),
+            ReaderThreads::Number(config.storage.reader_threads),
+        );
+
+        let BlockchainResponse::ChainHeight(chain_height, _) = read_handle
+            .ready()
+            .await?
+            .call(BlockchainReadRequest::ChainHeight)
+            .await?
+        else {
+            unreachable!();
+        };
+
+        let result = match height {
+            Some(height) if height < chain_height => {
+                if height == 0 {
+                    bail!("Can not verify at height 0, the genesis block is not verified");
+                }
+                check_key_image_index(&env)?;
+
+                let database = HistoricalDatabase::new(read_handle, height);
+
+                let (context_svc, elapsed) = timed(initialize_blockchain_context(
+                    config.context_config(),
+                    database.clone(),
+                ))
+                .await;
+                let mut context_svc = context_svc?;
+                print_step("load context", elapsed);
+
+                verify(block, txs, &mut context_svc, database).await?
+            }
+            Some(height) if height > chain_height => {
+                bail!("Height {height} is above the chain height {chain_height}");
+            }
+            _ => {
+                let (context_svc, elapsed) = timed(init_consensus(
+                    read_handle.clone(),
+                    config.context_config(),
+                    &config.context_snapshot_file(),
+                ))
+                .await;
+                let mut context_svc = context_svc.map_err(|e| anyhow!(e))?;
+                print_step("load context", elapsed);
+
+                let database = ConsensusBlockchainReadHandle::new(read_handle, BoxError::from);
+
+                verify(block, txs, &mut context_svc, database).await?
+            }
+        };
+
+        match result {
+            Ok(()) => {
+                println!("Accepted");
+                Ok(true)
+            }
+            Err(e) => {
+                println!("Rejected: {e}");
+                Ok(false)
+            }
+        }
+    })
+}
+
+/// Print the context being verified against, then verify `block` or, if it is [`None`], `txs`.
+///
+/// The outer error is for invalid input, the inner for a rejected block/transactions.
+async fn verify<D>(
+    block: Option<&[u8]>,
+    txs: &[Vec<u8>],
+    context_svc: &mut BlockchainContextService,
+    database: D,
+) -> Result<Result<(), ExtendedConsensusError>, anyhow::Error>
+where
+    D: Database + Clone + Send + 'static,
+{
+    let context = context_svc.blockchain_context();
+    println!(
+        "Verifying at height {}, top block: {}, hard-fork: {:?}",
+        context.chain_height,
+        hex::encode(context.top_hash),
+        context.current_hf
+    );
+
+    match block {
+        Some(block) => verify_block(block, txs, context_svc, database).await,
+        None => verify_txs(txs, context_svc, database).await,
+    }
+}
+
+/// Verify a block and its transactions.
+///
+/// The outer error is for invalid input, the inner for a rejected block.
+async fn verify_block<D>(
+    block: &[u8],
+    txs: &[Vec<u8>],
+    context_svc: &mut BlockchainContextService,
+    database: D,
+) -> Result<Result<(), ExtendedConsensusError>, anyhow::Error>
+where
+    D: Database + Clone + Send + 'static,
+{
+    let start = Instant::now();
+    let block = read_block(block)?;
+    let mut txs = txs
+        .iter()
+        .map(|tx| {
+            let tx = read_tx(tx)?;
+            Ok((tx.hash(), tx))
+        })
+        .collect::<Result<HashMap<_, _>, anyhow::Error>>()?;
+    print_step("deserialize", start.elapsed());
+
+    println!("Block: {}", hex::encode(block.hash()));
+
+    let ordered_txs = block
+        .transactions
+        .iter()
+        .map(|hash| {
+            txs.remove(hash)
+                .ok_or_else(|| anyhow!("missing block transaction {}", hex::encode(hash)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if let Some(hash) = txs.keys().next() {
+        bail!("transaction {} is not in the block", hex::encode(hash));
+    }
+
+    let (txs, elapsed) = timed(rayon_spawn_async(move || {
+        ordered_txs
+            .into_iter()
+            .map(new_tx_verification_data)
+            .collect::<Result<Vec<_>, _>>()
+    }))
+    .await;
+    print_step("prepare transactions", elapsed);
+    let txs = match txs {
+        Ok(txs) => txs,
+        Err(e) => return Ok(Err(e.into())),
+    };
+
+    let height = context_svc.blockchain_context().chain_height;
+
+    // We just use the raw `hardfork_version` here, no need to turn it into a `HardFork`.
+    let start = Instant::now();
+    let rx_vms = if block.header.hardfork_version < 12 {
+        HashMap::new()
+    } else {
+        let BlockChainContextResponse::RxVms(rx_vms) = context_svc
+            .ready()
+            .await
+            .map_err(|e| anyhow!(e))?
+            .call(BlockChainContextRequest::CurrentRxVms)
+            .await
+            .map_err(|e| anyhow!(e))?
+        else {
+            unreachable!();
+        };
+
+        rx_vms
+    };
+    print_step("get RandomX VMs", start.elapsed());
+
+    let (prepped_block, elapsed) = timed(rayon_spawn_async(move || {
+        PreparedBlock::new(
+            block,
+            rx_vms.get(&randomx_seed_height(height)).map(AsRef::as_ref),
+        )
+    }))
+    .await;
+    print_step("prepare block", elapsed);
+    let prepped_block = match prepped_block {
+        Ok(prepped_block) => prepped_block,
+        Err(e) => return Ok(Err(e.into())),
+    };
+
+    let (verified_block, elapsed) = timed(verify_prepped_main_chain_block(
+        prepped_block,
+        txs,
+        context_svc,
+        database,
+        None,
+    ))
+    .await;
+    print_step("verify block", elapsed);
+
+    Ok(verified_block.map(|block| {
+        println!("Height: {}", block.height);
+        println!("Weight: {}", block.weight);
+        println!("Generated coins: {}", block.generated_coins);
+        println!("Fees: {}", block.txs.iter().map(|tx| tx.fee).sum::<u64>());
+    }))
+}
+
+/// Verify transactions as if they were to be added to the tx-pool.
+///
+/// The outer error is for invalid input, the inner for rejected transactions.
+async fn verify_txs<D>(
+    txs: &[Vec<u8>],
+    context_svc: &mut BlockchainContextService,
+    database: D,
+) -> Result<Result<(), ExtendedConsensusError>, anyhow::Error>
+where
+    D: Database + Clone + Send + 'static,
+{
+    if txs.is_empty() {
+        bail!("no block or transactions to verify");
+    }
+
+    let start = Instant::now();
+    let txs = txs
+        .iter()
+        .map(|tx| read_tx(tx))
+        .collect::<Result<Vec<_>, _>>()?;
+    print_step("deserialize", start.elapsed());
+
+    for tx in &txs {
+        println!("Transaction: {}", hex::encode(tx.hash()));
+    }
+
+    let context = context_svc.blockchain_context().clone();
+
+    let (txs, elapsed) = timed(rayon_spawn_async(move || {
+        start_tx_verification().append_txs(txs).prepare()
+    }))
+    .await;
+    print_step("prepare transactions", elapsed);
+    let txs = match txs {
+        Ok(txs) => txs,
+        Err(e) => return Ok(Err(e.into())),
+    };
+
+    let start = Instant::now();
+    let batch_verifier = MultiThreadedBatchVerifier::new(rayon::current_num_threads());
+    let txs = match txs.only_semantic(context.current_hf).queue(&batch_verifier) {
+        Ok(txs) => txs,
+        Err(e) => {
+            print_step("semantic checks", start.elapsed());
+            return Ok(Err(e.into()));
+        }
+    };
+    print_step("semantic checks", start.elapsed());
+
+    let (batch_ok, elapsed) = timed(rayon_spawn_async(move || batch_verifier.verify())).await;
+    print_step("batch verification", elapsed);
+    if !batch_ok {
+        return Ok(Err(
+            ExtendedConsensusError::OneOrMoreBatchVerificationStatementsInvalid,
+        ));
+    }
+
+    let (verified_txs, elapsed) = timed(
+        start_tx_verification()
+            .append_prepped_txs(txs)
+            .prepare()?
+            .full(
+                context.chain_height,
+                context.top_hash,
+                context.current_adjusted_timestamp_for_time_lock(),
+                context.current_hf,
+                database,
+                None,
+            )
+            .verify(),
+    )
+    .await;
+    print_step("contextual checks", elapsed);
+
+    Ok(verified_txs.map(|txs| {
+        for tx in txs {
+            println!(
+                "Transaction {}: weight: {}, fee: {}",
+                hex::encode(tx.tx_hash),
+                tx.tx_weight,
+                tx.fee
+            );
+        }
+    }))
+}
+
+/// Deserialize a block, erroring on trailing bytes.
+fn read_block(mut blob: &[u8]) -> Result<Block, anyhow::Error> {
+    let block = Block::read(&mut blob)?;
+    if !blob.is_empty() {
+        bail!("block blob has {} trailing bytes", blob.len());
+    }
+
+    Ok(block)
+}
+
+/// Deserialize a transaction, erroring on trailing bytes.
+fn read_tx(mut blob: &[u8]) -> Result<Transaction, anyhow::Error> {
+    let tx = Transaction::read(&mut blob)?;
+    if !blob.is_empty() {
+        bail!("transaction blob has {} trailing bytes", blob.len());
+    }
+
+    Ok(tx)
+}
+
+/// Await `fut`, returning its output and how long it took.
+async fn timed<F: Future>(fut: F) -> (F::Output, Duration) {
+    let start = Instant::now();
+    let output = fut.await;
+    (output, start.elapsed())
+}
+
+/// Print how long a verification step took.
+fn print_step(step: &str, elapsed: Duration) {
+    println!("{step:>24}: {elapsed:.2?}");
+}
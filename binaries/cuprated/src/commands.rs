@@ -8,12 +8,19 @@ use tokio::sync::mpsc;
 use tower::{Service, ServiceExt};
 use tracing::level_filters::LevelFilter;
 
+use cuprate_blockchain::service::BlockchainReadHandle;
 use cuprate_consensus_context::{
     BlockChainContextRequest, BlockChainContextResponse, BlockchainContextService,
 };
 use cuprate_helper::time::secs_to_hms;
+use cuprate_types::{
+    blockchain::{BlockchainReadRequest, BlockchainResponse},
+    DatabaseStats,
+};
+use cuprate_zmq_types::json_message_types::ReorgAlert;
 
 use crate::{
+    blockchain,
     constants::PANIC_CRITICAL_SERVICE_ERROR,
     logging::{self, CupratedTracingFilter},
     statics,
@@ -50,6 +57,20 @@ pub enum Command {
 
     /// Print the height of first block not contained in the fast sync hashes.
     FastSyncStopHeight,
+
+    /// Print the size of the blockchain database and each of its tables.
+    DbStats,
+
+    /// Print the re-org being held for manual approval.
+    HeldReorg,
+
+    /// Approve the re-org being held for manual approval.
+    #[command(arg_required_else_help = true)]
+    ApproveReorg {
+        /// The hash of the held re-org's competing chain top block.
+        #[arg(value_parser = parse_hash)]
+        alt_top_hash: [u8; 32],
+    },
 }
 
 /// The log output target.
@@ -91,6 +112,7 @@ pub fn command_listener(incoming_commands: mpsc::Sender<Command>) -> ! {
 pub async fn io_loop(
     mut incoming_commands: mpsc::Receiver<Command>,
     mut context_service: BlockchainContextService,
+    mut blockchain_read_handle: BlockchainReadHandle,
 ) {
     loop {
         let Some(command) = incoming_commands.recv().await else {
@@ -131,6 +153,89 @@ pub async fn io_loop(
 
                 println!("{stop_height}");
             }
+            Command::DbStats => {
+                let BlockchainResponse::DatabaseStats(stats) = blockchain_read_handle
+                    .ready()
+                    .await
+                    .expect(PANIC_CRITICAL_SERVICE_ERROR)
+                    .call(BlockchainReadRequest::DatabaseStats)
+                    .await
+                    .expect(PANIC_CRITICAL_SERVICE_ERROR)
+                else {
+                    unreachable!();
+                };
+
+                print_database_stats(&stats);
+            }
+            Command::HeldReorg => match blockchain::interface::held_reorg().await {
+                Ok(Some(alert)) => print_reorg_alert(&alert),
+                Ok(None) => println!("No held re-org"),
+                Err(e) => eprintln!("Failed to get the held re-org: {e}"),
+            },
+            Command::ApproveReorg { alt_top_hash } => {
+                match blockchain::interface::approve_reorg(alt_top_hash).await {
+                    Ok(()) => println!("Re-orged to {}", hex::encode(alt_top_hash)),
+                    Err(e) => eprintln!("Failed to approve re-org: {e}"),
+                }
+            }
         }
     }
 }
+
+/// Print the [`DatabaseStats`] of the blockchain database, for [`Command::DbStats`].
+fn print_database_stats(stats: &DatabaseStats) {
+    let map_size = stats
+        .map_size
+        .map_or_else(|| "-".to_string(), |map_size| map_size.to_string());
+
+    println!(
+        "DATABASE:\n  disk size: {},\n  map size: {map_size},\n  used: {},\n  free: {}",
+        stats.disk_size, stats.used_bytes, stats.free_bytes
+    );
+    println!(
+        "{:<24} {:>12} {:>6} {:>12} {:>12} {:>12} {:>16}",
+        "TABLE", "ENTRIES", "DEPTH", "BRANCH", "LEAF", "OVERFLOW", "BYTES"
+    );
+
+    for table in &stats.tables {
+        println!(
+            "{:<24} {:>12} {:>6} {:>12} {:>12} {:>12} {:>16}",
+            table.table,
+            table.entries,
+            table.depth,
+            table.branch_pages,
+            table.leaf_pages,
+            table.overflow_pages,
+            table.size_bytes
+        );
+    }
+}
+
+/// Print a held re-org's [`ReorgAlert`], for [`Command::HeldReorg`].
+fn print_reorg_alert(alert: &ReorgAlert) {
+    println!(
+        "HELD RE-ORG:\n  fork height: {},\n  fork hash: {},\n  depth: {}",
+        alert.fork_height,
+        hex::encode(alert.fork_id.0),
+        alert.depth
+    );
+    println!(
+        "  main chain: height: {}, top hash: {}, cumulative difficulty: {}",
+        alert.main_height,
+        hex::encode(alert.main_id.0),
+        alert.main_cumulative_difficulty
+    );
+    println!(
+        "  alt chain: height: {}, top hash: {}, cumulative difficulty: {}",
+        alert.alt_height,
+        hex::encode(alert.alt_id.0),
+        alert.alt_cumulative_difficulty
+    );
+}
+
+/// Parse a hex encoded 32 byte hash.
+fn parse_hash(s: &str) -> Result<[u8; 32], hex::FromHexError> {
+    let mut hash = [0; 32];
+    hex::decode_to_slice(s, &mut hash)?;
+    Ok(hash)
+}
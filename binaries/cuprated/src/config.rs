@@ -2,7 +2,8 @@
 use std::{
     fs::{read_to_string, File},
     io,
-    path::Path,
+    num::NonZero,
+    path::{Path, PathBuf},
     str::FromStr,
     time::Duration,
 };
@@ -12,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use cuprate_consensus::ContextConfig;
 use cuprate_helper::{
-    fs::{CUPRATE_CONFIG_DIR, DEFAULT_CONFIG_FILE_NAME},
+    fs::{context_snapshot_path, CUPRATE_CONFIG_DIR, DEFAULT_CONFIG_FILE_NAME},
     network::Network,
 };
 use cuprate_p2p::block_downloader::BlockDownloaderConfig;
@@ -25,21 +26,30 @@ use crate::{
 
 mod args;
 mod fs;
+mod mining;
 mod p2p;
 mod rayon;
+mod relay_policy;
+mod rpc;
 mod storage;
 mod tokio;
 mod tracing_config;
+mod zmq;
 
 #[macro_use]
 mod macros;
 
+pub use args::Args;
 use fs::FileSystemConfig;
+use mining::MiningConfig;
 use p2p::P2PConfig;
 use rayon::RayonConfig;
+use relay_policy::RelayPolicyConfig;
+use rpc::RpcConfig;
 use storage::StorageConfig;
 use tokio::TokioConfig;
 use tracing_config::TracingConfig;
+use zmq::ZmqConfig;
 
 /// Header to put at the start of the generated config file.
 const HEADER: &str = r"##     ____                      _
@@ -59,8 +69,8 @@ const HEADER: &str = r"##     ____                      _
 
 ";
 
-/// Reads the args & config file, returning a [`Config`].
-pub fn read_config_and_args() -> Config {
+/// Reads the args & config file, returning the [`Config`] and [`Args`].
+pub fn read_config_and_args() -> (Config, Args) {
     let args = args::Args::parse();
     args.do_quick_requests();
 
@@ -95,7 +105,10 @@ pub fn read_config_and_args() -> Config {
             .unwrap_or_default()
     };
 
-    args.apply_args(config)
+    let config = args.apply_args(config);
+    args.do_database_requests(&config);
+
+    (config, args)
 }
 
 config_struct! {
@@ -119,6 +132,42 @@ config_struct! {
         /// Valid values | true, false
         pub fast_sync: bool,
 
+        /// A fast sync hash file to use instead of the built-in one.
+        ///
+        /// The file must be for the configured network, it
+        /// can be created with the `create-fs-file` tool.
+        /// If this is empty the built-in hash file is used, there is
+        /// currently only a built-in file for mainnet.
+        ///
+        /// Type         | String
+        /// Valid values | "", a path to a fast sync hash file
+        /// Examples     | "", "/home/alice/fast_sync_hashes.bin"
+        pub fast_sync_file: PathBuf,
+
+        /// A checkpoint file to add to the built-in checkpoints.
+        ///
+        /// The file uses the same JSON format as monerod's `checkpoints.json`,
+        /// blocks must match these checkpoints and alt blocks at or below
+        /// the highest checkpoint are rejected.
+        /// If this is empty only the built-in checkpoints are used.
+        ///
+        /// Type         | String
+        /// Valid values | "", a path to a checkpoint file
+        /// Examples     | "", "/home/alice/checkpoints.json"
+        pub checkpoints_file: PathBuf,
+
+        /// The maximum depth of an automatic re-org.
+        ///
+        /// Re-orgs removing more than this amount of blocks
+        /// from the main chain are held until approved with
+        /// the `approve_reorg` command, and an alert is logged.
+        /// If this is 0 re-orgs of any depth are done automatically.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 0, 10, 100
+        pub max_reorg_depth: usize,
+
         #[child = true]
         /// Configuration for cuprated's logging system, tracing.
         ///
@@ -148,6 +197,25 @@ config_struct! {
         #[child = true]
         /// Configuration for the file-system.
         pub fs: FileSystemConfig,
+
+        #[child = true]
+        /// Configuration for the built-in miner.
+        pub mining: MiningConfig,
+
+        #[child = true]
+        /// Configuration for the tx relay policy.
+        ///
+        /// The relay policy decides which txs are added to
+        /// the tx-pool and relayed, on top of the consensus rules.
+        pub relay_policy: RelayPolicyConfig,
+
+        #[child = true]
+        /// Configuration for the ZMQ publisher.
+        pub zmq: ZmqConfig,
+
+        #[child = true]
+        /// Configuration for the RPC server.
+        pub rpc: RpcConfig,
     }
 }
 
@@ -156,12 +224,19 @@ impl Default for Config {
         Self {
             network: Default::default(),
             fast_sync: true,
+            fast_sync_file: PathBuf::new(),
+            checkpoints_file: PathBuf::new(),
+            max_reorg_depth: 0,
             tracing: Default::default(),
             tokio: Default::default(),
             rayon: Default::default(),
             p2p: Default::default(),
             storage: Default::default(),
             fs: Default::default(),
+            mining: Default::default(),
+            relay_policy: Default::default(),
+            zmq: Default::default(),
+            rpc: Default::default(),
         }
     }
 }
@@ -223,6 +298,30 @@ impl Config {
         }
     }
 
+    /// The address and threads to start the built-in miner with, [`None`] if mining is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the mining address or thread count is invalid.
+    pub fn mining_config(
+        &self,
+    ) -> Result<Option<(monero_address::MoneroAddress, NonZero<usize>)>, anyhow::Error> {
+        if self.mining.address.is_empty() {
+            return Ok(None);
+        }
+
+        let address = crate::mining::parse_miner_address(self.network, &self.mining.address)?;
+        let threads = NonZero::new(self.mining.threads)
+            .ok_or_else(|| anyhow::anyhow!("Mining threads must be at least 1"))?;
+
+        Ok(Some((address, threads)))
+    }
+
+    /// The [`RelayPolicy`](crate::txpool::RelayPolicy) txs must pass to be relayed.
+    pub fn relay_policy(&self) -> crate::txpool::RelayPolicy {
+        self.relay_policy.relay_policy()
+    }
+
     /// The [`ContextConfig`].
     pub const fn context_config(&self) -> ContextConfig {
         match self.network {
@@ -232,8 +331,18 @@ impl Config {
         }
     }
 
+    /// The file the blockchain context snapshot is stored in.
+    pub fn context_snapshot_file(&self) -> PathBuf {
+        context_snapshot_path(&self.fs.cache_directory, self.network)
+    }
+
     /// The [`cuprate_blockchain`] config.
     pub fn blockchain_config(&self) -> cuprate_blockchain::config::Config {
+        self.blockchain_config_builder().build()
+    }
+
+    /// The [`cuprate_blockchain`] config builder, used for [`Config::blockchain_config`].
+    pub fn blockchain_config_builder(&self) -> cuprate_blockchain::config::ConfigBuilder {
         let blockchain = &self.storage.blockchain;
 
         // We don't set reader threads as we manually make the reader threadpool.
@@ -241,11 +350,18 @@ impl Config {
             .network(self.network)
             .data_directory(self.fs.data_directory.clone())
             .sync_mode(blockchain.shared.sync_mode)
-            .build()
+            .backend(blockchain.shared.backend)
+            .key_image_index(blockchain.key_image_index)
+            .output_key_index(blockchain.output_key_index)
     }
 
     /// The [`cuprate_txpool`] config.
     pub fn txpool_config(&self) -> cuprate_txpool::config::Config {
+        self.txpool_config_builder().build()
+    }
+
+    /// The [`cuprate_txpool`] config builder, used for [`Config::txpool_config`].
+    pub fn txpool_config_builder(&self) -> cuprate_txpool::config::ConfigBuilder {
         let txpool = &self.storage.txpool;
 
         // We don't set reader threads as we manually make the reader threadpool.
@@ -253,7 +369,7 @@ impl Config {
             .network(self.network)
             .data_directory(self.fs.data_directory.clone())
             .sync_mode(txpool.shared.sync_mode)
-            .build()
+            .backend(txpool.shared.backend)
     }
 
     /// The [`BlockDownloaderConfig`].
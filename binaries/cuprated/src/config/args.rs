@@ -3,13 +3,20 @@ use std::{io::Write, path::PathBuf, process::exit};
 use clap::builder::TypedValueParser;
 use serde_json::Value;
 
+use cuprate_database::config::Backend;
 use cuprate_helper::network::Network;
 
-use crate::{config::Config, version::CupratedVersionInfo};
+use crate::{
+    blockchain::{self, ImportSource},
+    config::Config,
+    logging::eprintln_red,
+    storage,
+    version::CupratedVersionInfo,
+};
 
 /// Cuprate Args.
 #[derive(clap::Parser, Debug)]
-#[command(about)]
+#[command(about, group(clap::ArgGroup::new("verify").multiple(true)))]
 pub struct Args {
     /// The network to run on.
     #[arg(
@@ -46,6 +53,85 @@ pub struct Args {
     /// Print misc version information in JSON.
     #[arg(short, long)]
     pub version: bool,
+
+    /// Copy the blockchain and tx-pool databases into new databases using this backend, then exit.
+    ///
+    /// The new databases are created next to the current ones. An interrupted
+    /// conversion is resumed when running this again. Set `backend` in the
+    /// `[storage]` config sections afterwards to use the new databases.
+    #[arg(
+        long,
+        value_parser = clap::builder::PossibleValuesParser::new(["heed", "redb"])
+            .map(|s| if s == "heed" { Backend::Heed } else { Backend::Redb }),
+    )]
+    pub convert_database: Option<Backend>,
+
+    /// Check the blockchain database is consistent and print a JSON report, then exit.
+    ///
+    /// Exits with a non-zero code if problems were found.
+    #[arg(long)]
+    pub check_database: bool,
+
+    /// After `--check-database`, pop blocks down to the first problem found, then exit.
+    #[arg(long, requires = "check_database")]
+    pub repair_database: bool,
+
+    /// Export the blockchain to a `monerod` raw bootstrap file at this PATH, then exit.
+    #[arg(long)]
+    pub export_blocks: Option<PathBuf>,
+
+    /// Import blocks from a `monerod` raw bootstrap file at this PATH.
+    ///
+    /// Blocks already in the blockchain are skipped, so an interrupted import
+    /// can be resumed by running this again. The node keeps running after the import.
+    #[arg(long, group = "import")]
+    pub import_blocks: Option<PathBuf>,
+
+    /// Import blocks from a `monerod` LMDB database, PATH is the directory containing `data.mdb`.
+    ///
+    /// `monerod` must not be running and the database must not be pruned. Like
+    /// `--import-blocks`, an interrupted import is resumed by running this again.
+    #[arg(long, group = "import")]
+    pub import_monerod_db: Option<PathBuf>,
+
+    /// Add imported blocks without verifying them, only use this with trusted files.
+    #[arg(long, requires = "import")]
+    pub dont_verify: bool,
+
+    /// Verify this hex encoded block against the top of the blockchain database, then exit.
+    ///
+    /// The block's transactions must be given with `--verify-tx`. The database is opened
+    /// read-only, the result and timings of each verification step are printed.
+    ///
+    /// Exits with a non-zero code if the block is rejected.
+    #[arg(long, value_name = "HEX", value_parser = parse_hex, group = "verify")]
+    pub verify_block: Option<::std::vec::Vec<u8>>,
+
+    /// Verify this hex encoded transaction against the top of the blockchain database, then exit.
+    ///
+    /// Can be given multiple times, with `--verify-block` these are the block's transactions.
+    ///
+    /// Exits with a non-zero code if a transaction is rejected.
+    #[arg(long, value_name = "HEX", value_parser = parse_hex, group = "verify")]
+    pub verify_tx: Vec<::std::vec::Vec<u8>>,
+
+    /// Verify `--verify-block`/`--verify-tx` as if the chain height was HEIGHT, instead of at the top.
+    ///
+    /// Blocks at and above HEIGHT are ignored, so this checks if a block or transactions would be
+    /// accepted at HEIGHT. The key image index must be enabled and fully built, to check for double spends.
+    #[arg(long, value_name = "HEIGHT", requires = "verify")]
+    pub verify_height: Option<usize>,
+
+    /// Re-verify the main chain from this height with full consensus verification, then exit.
+    ///
+    /// This checks blocks added with fast sync. The database is opened read-only,
+    /// invalid blocks are printed in a JSON report and the chain is not changed.
+    ///
+    /// The key image index must be enabled and fully built, to check for double spends.
+    ///
+    /// Exits with a non-zero code if an invalid block was found.
+    #[arg(long, value_name = "START_HEIGHT")]
+    pub revalidate: Option<usize>,
 }
 
 impl Args {
@@ -66,6 +152,83 @@ impl Args {
         }
     }
 
+    /// Complete any database requests asked for in [`Args`],
+    /// these need the final [`Config`].
+    ///
+    /// May cause the process to [`exit`].
+    pub fn do_database_requests(&self, config: &Config) {
+        if let Some(backend) = self.convert_database {
+            if let Err(e) = storage::convert_databases(config, backend) {
+                eprintln_red(&format!("Failed to convert databases: {e:#}"));
+                exit(1);
+            }
+            exit(0);
+        }
+
+        if self.check_database {
+            match storage::check_database(config, self.repair_database) {
+                Ok(true) => exit(0),
+                Ok(false) => exit(1),
+                Err(e) => {
+                    eprintln_red(&format!("Failed to check the database: {e:#}"));
+                    exit(1);
+                }
+            }
+        }
+
+        if let Some(path) = &self.export_blocks {
+            if let Err(e) = storage::export_blocks(config, path) {
+                eprintln_red(&format!("Failed to export blocks: {e:#}"));
+                exit(1);
+            }
+            exit(0);
+        }
+    }
+
+    /// Complete any verification requests asked for in [`Args`],
+    /// these need the checkpoints to be set.
+    ///
+    /// May cause the process to [`exit`].
+    pub fn do_verify_requests(&self, config: &Config) {
+        if let Some(start_height) = self.revalidate {
+            match blockchain::revalidate(config, start_height) {
+                Ok(true) => exit(0),
+                Ok(false) => exit(1),
+                Err(e) => {
+                    eprintln_red(&format!("Failed to revalidate the blockchain: {e:#}"));
+                    exit(1);
+                }
+            }
+        }
+
+        if self.verify_block.is_none() && self.verify_tx.is_empty() {
+            return;
+        }
+
+        match blockchain::verify_blobs(
+            config,
+            self.verify_block.as_deref(),
+            &self.verify_tx,
+            self.verify_height,
+        ) {
+            Ok(true) => exit(0),
+            Ok(false) => exit(1),
+            Err(e) => {
+                eprintln_red(&format!("Failed to verify: {e:#}"));
+                exit(1);
+            }
+        }
+    }
+
+    /// The [`ImportSource`] to import blocks from, if one was given.
+    pub fn import_source(&self) -> Option<ImportSource> {
+        if let Some(path) = &self.import_blocks {
+            return Some(ImportSource::Bootstrap(path.clone()));
+        }
+
+        self.import_monerod_db.clone().map(ImportSource::Monerod)
+    }
+
     /// Apply the [`Args`] to the given [`Config`].
     ///
     /// This may exit the program if a config value was set that requires an early exit.
@@ -80,3 +243,8 @@ impl Args {
         config
     }
 }
+
+/// Parse a hex encoded argument.
+fn parse_hex(s: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(s)
+}
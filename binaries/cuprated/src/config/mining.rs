@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+use super::macros::config_struct;
+
+config_struct! {
+    /// The built-in miner config.
+    #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+    #[serde(deny_unknown_fields, default)]
+    pub struct MiningConfig {
+        /// The address to mine to, mining is started on startup if this is set.
+        ///
+        /// The built-in miner is meant for testnet/stagenet and local testing.
+        ///
+        /// Type         | String
+        /// Valid values | "", a standard address for the configured network
+        /// Examples     | ""
+        pub address: String,
+
+        /// The amount of threads to mine with.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 1
+        /// Examples     | 1, 2, 4
+        pub threads: usize,
+    }
+}
+
+impl Default for MiningConfig {
+    fn default() -> Self {
+        Self {
+            address: String::new(),
+            threads: 1,
+        }
+    }
+}
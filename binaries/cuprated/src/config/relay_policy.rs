@@ -0,0 +1,180 @@
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use super::macros::config_struct;
+
+use crate::txpool::{
+    ExtraFieldRule, InputOutputRule, MaxWeightRule, MinimumFeeRule, RelayPolicy, UnlockTimeRule,
+    MAX_TX_EXTRA_SIZE,
+};
+
+config_struct! {
+    /// The tx relay policy config.
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    #[serde(deny_unknown_fields, default)]
+    pub struct RelayPolicyConfig {
+        /// Whether to relay txs with a non-zero unlock time.
+        ///
+        /// Type         | boolean
+        /// Valid values | true, false
+        /// Examples     | false
+        pub allow_unlock_time: bool,
+
+        /// The maximum size of a tx's extra field in bytes.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 1060, 2048
+        pub max_extra_size: usize,
+
+        /// The tags allowed in a tx's extra field.
+        ///
+        /// If this is not empty, txs with extra fields that
+        /// cannot be parsed or contain other tags are not relayed.
+        /// If this is empty any extra field is relayed.
+        ///
+        /// Type         | Array of numbers
+        /// Valid values | 0..=255
+        /// Examples     | [], [0, 1, 2, 4]
+        pub allowed_extra_tags: Vec<u8>,
+
+        /// The multiplier applied to the minimum relay fee.
+        ///
+        /// Type         | Floating point number
+        /// Valid values | >= 0.0
+        /// Examples     | 1.0, 1.5, 4.0
+        ##[serde(deserialize_with = "deserialize_fee_multiplier")]
+        pub min_fee_multiplier: f64,
+
+        /// The maximum weight of a relayed tx.
+        ///
+        /// If this is 0 only the consensus limit is used.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 0, 50000, 149400
+        pub max_tx_weight: usize,
+
+        /// The maximum amount of inputs in a relayed tx.
+        ///
+        /// If this is 0 there is no limit.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 0, 16, 128
+        pub max_inputs: usize,
+
+        /// The maximum amount of outputs in a relayed tx.
+        ///
+        /// If this is 0 there is no limit.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 0, 2, 16
+        pub max_outputs: usize,
+    }
+}
+
+impl RelayPolicyConfig {
+    /// Returns the [`RelayPolicy`] for this config.
+    pub fn relay_policy(&self) -> RelayPolicy {
+        let mut policy = RelayPolicy::default();
+
+        if !self.allow_unlock_time {
+            policy = policy.with_rule(UnlockTimeRule);
+        }
+
+        policy = policy
+            .with_rule(ExtraFieldRule {
+                max_size: self.max_extra_size,
+                allowed_tags: self.allowed_extra_tags.clone(),
+            })
+            .with_rule(MinimumFeeRule {
+                multiplier: self.min_fee_multiplier,
+            });
+
+        if self.max_tx_weight != 0 {
+            policy = policy.with_rule(MaxWeightRule {
+                max_weight: self.max_tx_weight,
+            });
+        }
+
+        if self.max_inputs != 0 || self.max_outputs != 0 {
+            policy = policy.with_rule(InputOutputRule {
+                max_inputs: self.max_inputs,
+                max_outputs: self.max_outputs,
+            });
+        }
+
+        policy
+    }
+}
+
+impl Default for RelayPolicyConfig {
+    fn default() -> Self {
+        Self {
+            allow_unlock_time: false,
+            max_extra_size: MAX_TX_EXTRA_SIZE,
+            allowed_extra_tags: Vec::new(),
+            min_fee_multiplier: 1.0,
+            max_tx_weight: 0,
+            max_inputs: 0,
+            max_outputs: 0,
+        }
+    }
+}
+
+/// Deserializes [`RelayPolicyConfig::min_fee_multiplier`], rejecting values that would disable the fee check.
+fn deserialize_fee_multiplier<'de, D>(d: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let multiplier = f64::deserialize(d)?;
+
+    if !multiplier.is_finite() || multiplier < 0.0 {
+        return Err(D::Error::custom(format!(
+            "min_fee_multiplier must be a finite number >= 0.0, got: {multiplier}"
+        )));
+    }
+
+    Ok(multiplier)
+}
+
+#[cfg(test)]
+mod test {
+    use toml::from_str;
+
+    use super::*;
+
+    #[test]
+    fn default_round_trip() {
+        let str = toml::to_string(&RelayPolicyConfig::default()).unwrap();
+        let config: RelayPolicyConfig = from_str(&str).unwrap();
+
+        assert_eq!(config, RelayPolicyConfig::default());
+    }
+
+    #[test]
+    fn min_fee_multiplier() {
+        for valid in [0.0, 2.5] {
+            let config: RelayPolicyConfig =
+                from_str(&format!("min_fee_multiplier = {valid:?}")).unwrap();
+
+            assert_eq!(
+                config,
+                RelayPolicyConfig {
+                    min_fee_multiplier: valid,
+                    ..Default::default()
+                }
+            );
+        }
+
+        for invalid in ["-1.0", "-0.5", "nan", "inf", "-inf"] {
+            from_str::<RelayPolicyConfig>(&format!("min_fee_multiplier = {invalid}")).unwrap_err();
+        }
+    }
+
+    #[test]
+    fn unknown_field() {
+        from_str::<RelayPolicyConfig>("min_fee = 1.0").unwrap_err();
+    }
+}
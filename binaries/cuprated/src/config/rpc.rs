@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use cuprate_rpc_interface::DEFAULT_MAX_BATCH_SIZE;
+
+use super::macros::config_struct;
+
+config_struct! {
+    /// The RPC server config.
+    #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+    #[serde(deny_unknown_fields, default)]
+    pub struct RpcConfig {
+        /// The maximum amount of requests in a JSON-RPC batch.
+        ///
+        /// Batches sent to `/json_rpc` with more requests than
+        /// this are rejected without handling any request.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 1
+        /// Examples     | 10, 100, 1000
+        pub max_batch_size: usize,
+    }
+}
+
+impl Default for RpcConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
+        }
+    }
+}
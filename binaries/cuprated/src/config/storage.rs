@@ -2,7 +2,7 @@ use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 
-use cuprate_database::config::SyncMode;
+use cuprate_database::config::{Backend, SyncMode};
 use cuprate_database_service::ReaderThreads;
 use cuprate_helper::fs::CUPRATE_DATA_DIR;
 
@@ -45,13 +45,82 @@ impl Default for StorageConfig {
 
 config_struct! {
     /// The blockchain config.
-    #[derive(Default, Debug, Deserialize, Serialize, PartialEq, Eq)]
+    #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
     #[serde(deny_unknown_fields, default)]
     pub struct BlockchainConfig {
         #[flatten = true]
         /// Shared config.
         ##[serde(flatten)]
         pub shared: SharedStorageConfig,
+
+        /// Enable/disable the key image index.
+        ///
+        /// The index stores the transaction and block each
+        /// key image was spent in, `is_key_image_spent` will
+        /// also return these when this is enabled.
+        ///
+        /// Blocks already in the database are indexed in the
+        /// background, disabling this deletes the index.
+        ///
+        /// Type         | boolean
+        /// Valid values | true, false
+        pub key_image_index: bool,
+
+        /// Enable/disable the output key index.
+        ///
+        /// The index maps each output's one-time public key
+        /// to the output, `get_outs` can look up outputs by
+        /// key when this is enabled.
+        ///
+        /// Blocks already in the database are indexed in the
+        /// background, disabling this deletes the index.
+        ///
+        /// Type         | boolean
+        /// Valid values | true, false
+        pub output_key_index: bool,
+
+        /// Enable/disable keeping alt blocks across restarts.
+        ///
+        /// Alt blocks are blocks on chains competing with
+        /// the main chain, if this is disabled they are
+        /// deleted when cuprated starts.
+        ///
+        /// Type         | boolean
+        /// Valid values | true, false
+        pub keep_alt_blocks: bool,
+
+        /// The maximum depth of an alt chain.
+        ///
+        /// Alt chains with a top block more than this amount
+        /// of blocks below the top of the main chain are deleted.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 720, 5000
+        pub max_alt_chain_depth: usize,
+
+        /// The maximum amount of alt blocks to store.
+        ///
+        /// If there are more, the oldest alt chains are
+        /// deleted, the newest alt chain is always kept.
+        ///
+        /// Type         | Number
+        /// Valid values | >= 0
+        /// Examples     | 1000, 10000
+        pub max_alt_blocks: usize,
+    }
+}
+
+impl Default for BlockchainConfig {
+    fn default() -> Self {
+        Self {
+            shared: SharedStorageConfig::default(),
+            key_image_index: false,
+            output_key_index: false,
+            keep_alt_blocks: false,
+            max_alt_chain_depth: 720,
+            max_alt_blocks: 1000,
+        }
     }
 }
 
@@ -95,7 +164,20 @@ config_struct! {
         /// if there is an unexpected crash, although it will
         /// make DB writes much slower.
         ///
-        /// Valid values | "Fast", "Safe"
+        /// "FastThenSafe" uses "Fast" until cuprated is
+        /// synced with the network, then switches to "Safe".
+        ///
+        /// Valid values | "Fast", "FastThenSafe", "Safe"
         pub sync_mode: SyncMode,
+
+        #[comment_out = true]
+        /// The database backend.
+        ///
+        /// Databases created with one backend cannot
+        /// be opened with another, changing this requires
+        /// a new database.
+        ///
+        /// Valid values | "Heed", "Redb"
+        pub backend: Backend,
     }
 }
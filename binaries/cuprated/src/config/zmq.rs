@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+use super::macros::config_struct;
+
+config_struct! {
+    /// The ZMQ publisher config.
+    #[derive(Debug, Deserialize, Serialize, Eq, PartialEq)]
+    #[serde(deny_unknown_fields, default)]
+    pub struct ZmqConfig {
+        /// The address to publish ZMQ messages on.
+        ///
+        /// Messages use the same `<topic>:<JSON>` format as
+        /// monerod's `--zmq-pub`, currently only the Cuprate
+        /// `json-full-reorg_alert` topic is published.
+        /// If this is empty the ZMQ publisher is disabled.
+        ///
+        /// Type         | String
+        /// Valid values | "", a ZMQ TCP endpoint
+        /// Examples     | "", "tcp://127.0.0.1:18083"
+        pub pub_address: String,
+    }
+}
+
+impl Default for ZmqConfig {
+    fn default() -> Self {
+        Self {
+            pub_address: String::new(),
+        }
+    }
+}
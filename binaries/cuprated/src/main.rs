@@ -20,7 +20,7 @@ use std::{mem, sync::Arc};
 
 use tokio::sync::mpsc;
 use tower::{Service, ServiceExt};
-use tracing::{error, info, level_filters::LevelFilter};
+use tracing::{error, info, level_filters::LevelFilter, warn};
 use tracing_subscriber::{layer::SubscriberExt, reload::Handle, util::SubscriberInitExt, Registry};
 
 use cuprate_consensus_context::{
@@ -31,7 +31,10 @@ use cuprate_helper::time::secs_to_hms;
 use cuprate_types::blockchain::BlockchainWriteRequest;
 
 use crate::{
-    config::Config, constants::PANIC_CRITICAL_SERVICE_ERROR, logging::CupratedTracingFilter,
+    blockchain::OptionalIndex,
+    config::Config,
+    constants::PANIC_CRITICAL_SERVICE_ERROR,
+    logging::{eprintln_red, CupratedTracingFilter},
 };
 
 mod blockchain;
@@ -40,12 +43,15 @@ mod config;
 mod constants;
 mod killswitch;
 mod logging;
+mod mining;
 mod p2p;
 mod rpc;
 mod signals;
 mod statics;
+mod storage;
 mod txpool;
 mod version;
+mod zmq;
 
 fn main() {
     // Initialize the killswitch.
@@ -54,13 +60,33 @@ fn main() {
     // Initialize global static `LazyLock` data.
     statics::init_lazylock_statics();
 
-    let config = config::read_config_and_args();
+    let (config, args) = config::read_config_and_args();
 
-    blockchain::set_fast_sync_hashes(config.fast_sync, config.network());
+    if let Err(e) =
+        blockchain::set_fast_sync_hashes(config.fast_sync, config.network(), &config.fast_sync_file)
+    {
+        eprintln_red(&format!("Failed to set the fast sync hashes: {e}"));
+        std::process::exit(1);
+    }
+
+    if let Err(e) = blockchain::set_checkpoints(config.network(), &config.checkpoints_file) {
+        eprintln_red(&format!("Failed to set the checkpoints: {e}"));
+        std::process::exit(1);
+    }
+
+    // Verify blocks/txs if requested, after the checkpoints are set as they are enforced.
+    args.do_verify_requests(&config);
 
     // Initialize logging.
     logging::init_logging(&config);
 
+    if config.fast_sync && cuprate_fast_sync::fast_sync_stop_height() == 0 {
+        warn!(
+            "There are no fast sync hashes for {}, all blocks will be fully verified. Set `fast_sync_file` to fast sync.",
+            config.network()
+        );
+    }
+
     // Initialize the thread-pools
 
     init_global_rayon_pool(&config);
@@ -73,7 +99,7 @@ fn main() {
 
     // Start the blockchain & tx-pool databases.
 
-    let (mut blockchain_read_handle, mut blockchain_write_handle, _) =
+    let (mut blockchain_read_handle, mut blockchain_write_handle, blockchain_env) =
         cuprate_blockchain::service::init_with_pool(
             config.blockchain_config(),
             Arc::clone(&db_thread_pool),
@@ -81,7 +107,7 @@ fn main() {
         .inspect_err(|e| error!("Blockchain database error: {e}"))
         .expect(DATABASE_CORRUPT_MSG);
 
-    let (txpool_read_handle, txpool_write_handle, _) =
+    let (txpool_read_handle, txpool_write_handle, txpool_env) =
         cuprate_txpool::service::init_with_pool(config.txpool_config(), db_thread_pool)
             .inspect_err(|e| error!("Txpool database error: {e}"))
             .expect(DATABASE_CORRUPT_MSG);
@@ -89,12 +115,21 @@ fn main() {
     // Initialize async tasks.
 
     rt.block_on(async move {
-        // TODO: we could add an option for people to keep these like monerod?
+        // Keep the alt blocks from the last run if configured to, only removing old alt-chains.
+        let blockchain_config = &config.storage.blockchain;
+        let alt_blocks_request = if blockchain_config.keep_alt_blocks {
+            BlockchainWriteRequest::PruneAltChains {
+                max_depth: blockchain_config.max_alt_chain_depth,
+                max_blocks: blockchain_config.max_alt_blocks,
+            }
+        } else {
+            BlockchainWriteRequest::FlushAltBlocks
+        };
         blockchain_write_handle
             .ready()
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR)
-            .call(BlockchainWriteRequest::FlushAltBlocks)
+            .call(alt_blocks_request)
             .await
             .expect(PANIC_CRITICAL_SERVICE_ERROR);
 
@@ -107,10 +142,13 @@ fn main() {
         .await;
 
         // Start the context service and the block/tx verifier.
-        let context_svc =
-            blockchain::init_consensus(blockchain_read_handle.clone(), config.context_config())
-                .await
-                .unwrap();
+        let context_svc = blockchain::init_consensus(
+            blockchain_read_handle.clone(),
+            config.context_config(),
+            &config.context_snapshot_file(),
+        )
+        .await
+        .unwrap();
 
         // Start clearnet P2P.
         let (clearnet, incoming_tx_handler_tx) = p2p::start_clearnet_p2p(
@@ -126,39 +164,95 @@ fn main() {
         let tx_handler = txpool::IncomingTxHandler::init(
             clearnet.clone(),
             txpool_write_handle.clone(),
-            txpool_read_handle,
+            txpool_read_handle.clone(),
             context_svc.clone(),
             blockchain_read_handle.clone(),
+            config.relay_policy(),
         );
         if incoming_tx_handler_tx.send(tx_handler).is_err() {
             unreachable!()
         }
 
+        // Start the ZMQ publisher if it is configured.
+        let zmq_publisher = if config.zmq.pub_address.is_empty() {
+            None
+        } else {
+            match zmq::init_zmq_publisher(&config.zmq.pub_address).await {
+                Ok(zmq_publisher) => Some(zmq_publisher),
+                Err(e) => {
+                    error!("Failed to start the ZMQ publisher: {e}");
+                    std::process::exit(1);
+                }
+            }
+        };
+
         // Initialize the blockchain manager.
         blockchain::init_blockchain_manager(
             clearnet,
             blockchain_write_handle,
-            blockchain_read_handle,
+            blockchain_read_handle.clone(),
             txpool_write_handle,
+            blockchain_env,
+            txpool_env,
             context_svc.clone(),
             config.block_downloader_config(),
+            config.storage.blockchain.max_alt_chain_depth,
+            config.storage.blockchain.max_alt_blocks,
+            config.max_reorg_depth,
+            zmq_publisher,
+            args.import_source()
+                .map(|source| (source, !args.dont_verify)),
         )
         .await;
 
+        // Add blocks from before the optional indexes were enabled to the indexes.
+        if config.storage.blockchain.key_image_index {
+            tokio::spawn(blockchain::backfill_index(OptionalIndex::KeyImages));
+        }
+        if config.storage.blockchain.output_key_index {
+            tokio::spawn(blockchain::backfill_index(OptionalIndex::OutputKeys));
+        }
+
+        // Start the built-in miner if it is configured.
+        let miner = mining::Miner::new(
+            context_svc.clone(),
+            blockchain_read_handle.clone(),
+            txpool_read_handle,
+        );
+        match config.mining_config() {
+            Ok(Some((address, threads))) => miner.start(address, threads).unwrap(),
+            Ok(None) => (),
+            Err(e) => error!("Invalid mining config, not mining: {e}"),
+        }
+
         // Start the command listener.
         if std::io::IsTerminal::is_terminal(&std::io::stdin()) {
             let (command_tx, command_rx) = mpsc::channel(1);
             std::thread::spawn(|| commands::command_listener(command_tx));
 
             // Wait on the io_loop, spawned on a separate task as this improves performance.
-            tokio::spawn(commands::io_loop(command_rx, context_svc))
-                .await
-                .unwrap();
+            let io_loop = tokio::spawn(commands::io_loop(
+                command_rx,
+                context_svc.clone(),
+                blockchain_read_handle,
+            ));
+
+            tokio::select! {
+                res = io_loop => res.unwrap(),
+                res = tokio::signal::ctrl_c() => res.unwrap(),
+            }
         } else {
             // If no STDIN, await OS exit signal.
             info!("Terminal/TTY not detected, disabling STDIN commands");
             tokio::signal::ctrl_c().await.unwrap();
         }
+
+        // Save the context caches so they don't need to be rebuilt on the next start.
+        if let Err(e) =
+            blockchain::save_context_snapshot(context_svc, &config.context_snapshot_file()).await
+        {
+            error!("Failed to save the blockchain context snapshot: {e}");
+        }
     });
 }
 
@@ -0,0 +1,54 @@
+//! Mining
+//!
+//! Contains the block template builder and merge mining functions used by the mining RPC calls,
+//! and the built-in [`Miner`].
+mod block_template;
+pub mod merge_mining;
+mod miner;
+
+pub use block_template::create_block_template;
+pub use miner::{Miner, MinerStatus};
+
+use anyhow::{bail, Error};
+use monero_address::{AddressType, MoneroAddress};
+
+use cuprate_helper::network::Network;
+
+/// The tx-extra tag for padding.
+const TX_EXTRA_TAG_PADDING: u8 = 0x00;
+
+/// The tx-extra tag for the transaction public key.
+const TX_EXTRA_TAG_PUBKEY: u8 = 0x01;
+
+/// The tx-extra tag for the extra nonce.
+const TX_EXTRA_NONCE: u8 = 0x02;
+
+/// The tx-extra tag for the merge mining tag.
+const TX_EXTRA_MERGE_MINING_TAG: u8 = 0x03;
+
+/// The tx-extra tag for additional transaction public keys.
+const TX_EXTRA_TAG_ADDITIONAL_PUBKEYS: u8 = 0x04;
+
+/// The tx-extra tag `monerod` calls `TX_EXTRA_MYSTERIOUS_MINERGATE_TAG`.
+const TX_EXTRA_MYSTERIOUS_MINERGATE_TAG: u8 = 0xDE;
+
+/// Parses an address that block rewards can be paid to on `network`.
+///
+/// # Errors
+///
+/// Returns an error if the address is invalid for `network` or is not a standard address.
+pub fn parse_miner_address(network: Network, address: &str) -> Result<MoneroAddress, Error> {
+    let network = match network {
+        Network::Mainnet => monero_address::Network::Mainnet,
+        Network::Stagenet => monero_address::Network::Stagenet,
+        Network::Testnet => monero_address::Network::Testnet,
+    };
+
+    let address = MoneroAddress::from_str(network, address)?;
+
+    if *address.kind() != AddressType::Legacy {
+        bail!("Incorrect address type");
+    }
+
+    Ok(address)
+}
@@ -0,0 +1,422 @@
+//! Block Template
+//!
+//! This module builds the block templates handed out to miners over RPC.
+//!
+//! ref: <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/blockchain.cpp#L1563-L1848>
+use anyhow::{anyhow, bail, Error};
+use curve25519_dalek::{EdwardsPoint, Scalar};
+use monero_address::MoneroAddress;
+use monero_serai::{
+    block::{Block, BlockHeader},
+    primitives::{keccak256, keccak256_to_scalar},
+    transaction::{Input, Output, Timelock, Transaction, TransactionPrefix},
+};
+use tower::{Service, ServiceExt};
+
+use cuprate_blockchain::service::BlockchainReadHandle;
+use cuprate_consensus::BlockchainContextService;
+use cuprate_consensus_rules::{
+    blocks::{randomx_seed_height, RX_SEEDHASH_EPOCH_LAG},
+    miner_tx::calculate_block_reward,
+};
+use cuprate_helper::{
+    cast::{u64_to_usize, usize_to_u64},
+    time::current_unix_timestamp,
+};
+use cuprate_txpool::{
+    service::{
+        interface::{TxpoolReadRequest, TxpoolReadResponse},
+        TxpoolReadHandle,
+    },
+    TxEntry,
+};
+use cuprate_types::{
+    blockchain::{BlockchainReadRequest, BlockchainResponse},
+    BlockTemplate, Chain, HardFork,
+};
+
+use crate::{
+    constants::PANIC_CRITICAL_SERVICE_ERROR,
+    mining::{TX_EXTRA_NONCE, TX_EXTRA_TAG_PUBKEY},
+};
+
+/// The weight reserved for the miner transaction when filling a block with transactions.
+///
+/// `CRYPTONOTE_COINBASE_BLOB_RESERVED_SIZE` in `monerod`.
+const COINBASE_BLOB_RESERVED_SIZE: usize = 600;
+
+/// The maximum length of the extra nonce in the miner transaction's extra.
+const MAX_EXTRA_NONCE_SIZE: usize = 255;
+
+/// The amount of blocks the miner transaction's output is locked for.
+const MINER_TX_TIME_LOCKED_BLOCKS: usize = 60;
+
+/// The maximum amount of attempts at finding a stable miner transaction weight, see [`create_miner_tx`].
+const MAX_MINER_TX_WEIGHT_ATTEMPTS: usize = 10;
+
+/// Creates a [`BlockTemplate`] on top of the current chain tip, paying the block reward to `address`.
+///
+/// `prev_block` must either be zeroed or equal to the current top block hash, templates on
+/// alternative chains are not supported.
+///
+/// If `extra_nonce` is not empty it is added to the miner transaction's extra and
+/// [`BlockTemplate::reserved_offset`] will be the offset of it in the block blob, otherwise
+/// [`BlockTemplate::reserved_offset`] will be `0`.
+///
+/// # Errors
+///
+/// This function will return an error if:
+///  - `extra_nonce` is longer than [`MAX_EXTRA_NONCE_SIZE`]
+///  - the current hard-fork is before [`HardFork::V12`]
+///  - `prev_block` is not zeroed or the current top block hash
+pub async fn create_block_template(
+    blockchain_context: &mut BlockchainContextService,
+    blockchain_read: &mut BlockchainReadHandle,
+    txpool_read: &mut TxpoolReadHandle,
+    prev_block: [u8; 32],
+    address: &MoneroAddress,
+    extra_nonce: &[u8],
+) -> Result<BlockTemplate, Error> {
+    if extra_nonce.len() > MAX_EXTRA_NONCE_SIZE {
+        bail!("Too big extra_nonce size");
+    }
+
+    let context = blockchain_context.blockchain_context().clone();
+
+    if context.current_hf < HardFork::V12 {
+        bail!("Block templates are not supported before hard-fork 12");
+    }
+
+    if prev_block != [0; 32] && prev_block != context.top_hash {
+        bail!("Block templates can only be created on top of the current chain tip");
+    }
+
+    let TxpoolReadResponse::Backlog(backlog) = txpool_read
+        .ready()
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        .call(TxpoolReadRequest::Backlog)
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+    else {
+        unreachable!()
+    };
+
+    let height = context.chain_height;
+    let hf = context.current_hf;
+
+    let (txs, fees, txs_weight) = select_txs(
+        backlog,
+        context.median_weight_for_block_reward,
+        context.already_generated_coins,
+        hf,
+    );
+
+    let (miner_transaction, expected_reward) = create_miner_tx(
+        height,
+        address,
+        extra_nonce,
+        txs_weight,
+        fees,
+        context.median_weight_for_block_reward,
+        context.already_generated_coins,
+        hf,
+    )?;
+
+    let block = Block {
+        header: BlockHeader {
+            hardfork_version: hf.as_u8(),
+            hardfork_signal: HardFork::LATEST.as_u8(),
+            timestamp: current_unix_timestamp()
+                .max(context.median_block_timestamp.unwrap_or_default()),
+            previous: context.top_hash,
+            nonce: 0,
+        },
+        miner_transaction,
+        transactions: txs.iter().map(|tx| tx.id).collect(),
+    };
+
+    let reserved_offset = if extra_nonce.is_empty() {
+        0
+    } else {
+        extra_nonce_offset(&block)
+    };
+
+    let seed_height = randomx_seed_height(height);
+    let seed_hash = main_chain_block_hash(blockchain_read, seed_height).await;
+
+    let next_seed_height = randomx_seed_height(height + RX_SEEDHASH_EPOCH_LAG);
+    let next_seed_hash = if next_seed_height == seed_height {
+        [0; 32]
+    } else {
+        main_chain_block_hash(blockchain_read, next_seed_height).await
+    };
+
+    Ok(BlockTemplate {
+        block,
+        reserved_offset,
+        difficulty: context.next_difficulty,
+        height: usize_to_u64(height),
+        expected_reward,
+        seed_height: usize_to_u64(seed_height),
+        seed_hash,
+        next_seed_hash,
+    })
+}
+
+/// Returns the hash of the main-chain block at `height`.
+async fn main_chain_block_hash(
+    blockchain_read: &mut BlockchainReadHandle,
+    height: usize,
+) -> [u8; 32] {
+    let BlockchainResponse::BlockHash(hash) = blockchain_read
+        .ready()
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        .call(BlockchainReadRequest::BlockHash(height, Chain::Main))
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+    else {
+        unreachable!()
+    };
+
+    hash
+}
+
+/// Selects the transactions from the tx-pool `backlog` to put in a block.
+///
+/// Transactions are taken in order of fee per byte, any transaction that would take the block over
+/// the weight limit, or would lower the miner's total reward because of the block weight penalty, is
+/// skipped.
+///
+/// Returns the selected transactions, their total fee and their total weight.
+///
+/// ref: <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/tx_pool.cpp#L1620-L1740>
+fn select_txs(
+    mut backlog: Vec<TxEntry>,
+    median_weight: usize,
+    already_generated_coins: u64,
+    hf: HardFork,
+) -> (Vec<TxEntry>, u64, usize) {
+    let max_total_weight = 2 * median_weight - COINBASE_BLOB_RESERVED_SIZE;
+
+    // Sort by fee per byte, highest first, comparing `fee / weight` without dividing.
+    backlog.sort_unstable_by(|a, b| {
+        (u128::from(b.fee) * u128::from(a.weight)).cmp(&(u128::from(a.fee) * u128::from(b.weight)))
+    });
+
+    let mut txs = Vec::new();
+    let mut total_fees = 0;
+    let mut total_weight = 0;
+    let mut best_coinbase = calculate_block_reward(
+        COINBASE_BLOB_RESERVED_SIZE,
+        median_weight,
+        already_generated_coins,
+        hf,
+    );
+
+    for tx in backlog {
+        let tx_weight = u64_to_usize(tx.weight);
+
+        if total_weight + tx_weight > max_total_weight {
+            continue;
+        }
+
+        let block_reward = calculate_block_reward(
+            total_weight + tx_weight + COINBASE_BLOB_RESERVED_SIZE,
+            median_weight,
+            already_generated_coins,
+            hf,
+        );
+        let coinbase = block_reward + total_fees + tx.fee;
+
+        if coinbase < best_coinbase {
+            continue;
+        }
+
+        best_coinbase = coinbase;
+        total_fees += tx.fee;
+        total_weight += tx_weight;
+        txs.push(tx);
+    }
+
+    (txs, total_fees, total_weight)
+}
+
+/// Creates the miner transaction for a block at `height`, paying to `address`.
+///
+/// `txs_weight` and `fees` are the total weight and fee of the other transactions in the block.
+///
+/// The block reward depends on the block weight, which depends on the miner transaction's weight, which
+/// depends on the reward, so this keeps rebuilding the transaction until its weight stops changing.
+///
+/// Returns the miner transaction and the total amount it pays out.
+#[expect(clippy::too_many_arguments)]
+fn create_miner_tx(
+    height: usize,
+    address: &MoneroAddress,
+    extra_nonce: &[u8],
+    txs_weight: usize,
+    fees: u64,
+    median_weight: usize,
+    already_generated_coins: u64,
+    hf: HardFork,
+) -> Result<(Transaction, u64), Error> {
+    let mut tx_key_bytes = [0; 64];
+    tx_key_bytes[..32].copy_from_slice(&rand::random::<[u8; 32]>());
+    tx_key_bytes[32..].copy_from_slice(&rand::random::<[u8; 32]>());
+    let tx_key = Scalar::from_bytes_mod_order_wide(&tx_key_bytes);
+
+    let tx_pub_key = EdwardsPoint::mul_base(&tx_key);
+    let derivation = (tx_key * address.view()).mul_by_cofactor();
+    let (key, view_tag) = derive_output_key(&derivation, address.spend());
+
+    let mut extra = Vec::with_capacity(1 + 32 + 2 + extra_nonce.len());
+    extra.push(TX_EXTRA_TAG_PUBKEY);
+    extra.extend_from_slice(tx_pub_key.compress().as_bytes());
+    if !extra_nonce.is_empty() {
+        extra.push(TX_EXTRA_NONCE);
+        extra.push(u8::try_from(extra_nonce.len())?);
+        extra.extend_from_slice(extra_nonce);
+    }
+
+    let view_tag = (hf >= HardFork::V15).then_some(view_tag);
+
+    let mut miner_tx_weight = 0;
+
+    for _ in 0..MAX_MINER_TX_WEIGHT_ATTEMPTS {
+        let reward = calculate_block_reward(
+            txs_weight + miner_tx_weight,
+            median_weight,
+            already_generated_coins,
+            hf,
+        ) + fees;
+
+        let miner_tx = Transaction::V2 {
+            prefix: TransactionPrefix {
+                additional_timelock: Timelock::Block(height + MINER_TX_TIME_LOCKED_BLOCKS),
+                inputs: vec![Input::Gen(height)],
+                outputs: vec![Output {
+                    amount: Some(reward),
+                    key: key.compress(),
+                    view_tag,
+                }],
+                extra: extra.clone(),
+            },
+            proofs: None,
+        };
+
+        if miner_tx.weight() == miner_tx_weight {
+            return Ok((miner_tx, reward));
+        }
+
+        miner_tx_weight = miner_tx.weight();
+    }
+
+    Err(anyhow!("Failed to find a stable miner transaction weight"))
+}
+
+/// Derives the one-time output key and view tag of the miner transaction's output.
+///
+/// The miner transaction only has one output so the output index is always `0`, which is
+/// a single zero byte as a varint.
+fn derive_output_key(derivation: &EdwardsPoint, spend_key: EdwardsPoint) -> (EdwardsPoint, u8) {
+    let derivation = derivation.compress();
+
+    let mut buf = Vec::with_capacity(8 + 32 + 1);
+    buf.extend_from_slice(b"view_tag");
+    buf.extend_from_slice(derivation.as_bytes());
+    buf.push(0);
+
+    let view_tag = keccak256(&buf)[0];
+    let key = EdwardsPoint::mul_base(&keccak256_to_scalar(&buf[8..])) + spend_key;
+
+    (key, view_tag)
+}
+
+/// Returns the offset of the extra nonce in the block's blob.
+///
+/// The extra nonce comes straight after the transaction public key, its tag and its length.
+fn extra_nonce_offset(block: &Block) -> u64 {
+    let blob = block.serialize();
+    let tx_pub_key = &block.miner_transaction.prefix().extra[1..33];
+
+    let tx_pub_key_offset = blob
+        .windows(32)
+        .position(|window| window == tx_pub_key)
+        .expect("The miner transaction's public key must be in the block blob");
+
+    usize_to_u64(tx_pub_key_offset + 32 + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn tx_entry(id: u8, weight: u64, fee: u64) -> TxEntry {
+        TxEntry {
+            id: [id; 32],
+            weight,
+            fee,
+            time_in_pool: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn txs_selected_by_fee_per_byte() {
+        let backlog = vec![
+            tx_entry(1, 1_000, 1_000),
+            tx_entry(2, 1_000, 3_000),
+            tx_entry(3, 2_000, 4_000),
+        ];
+
+        let (txs, fees, weight) = select_txs(backlog, 300_000, 0, HardFork::V16);
+
+        let ids = txs.iter().map(|tx| tx.id[0]).collect::<Vec<_>>();
+        assert_eq!(ids, [2, 3, 1]);
+        assert_eq!(fees, 8_000);
+        assert_eq!(weight, 4_000);
+    }
+
+    #[test]
+    fn txs_over_the_weight_limit_are_skipped() {
+        let backlog = vec![tx_entry(1, 600_000, u64::MAX / 4), tx_entry(2, 1_000, 1)];
+
+        let (txs, _, _) = select_txs(backlog, 300_000, 0, HardFork::V16);
+
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].id, [2; 32]);
+    }
+
+    #[test]
+    fn txs_lowering_the_reward_are_skipped() {
+        // This tx is heavy enough to be penalized but its fee does not make up for it.
+        let backlog = vec![tx_entry(1, 400_000, 1)];
+
+        let (txs, fees, weight) = select_txs(backlog, 300_000, 0, HardFork::V16);
+
+        assert!(txs.is_empty());
+        assert_eq!(fees, 0);
+        assert_eq!(weight, 0);
+    }
+
+    #[test]
+    fn output_key_spendable_with_view_key() {
+        let view_key = Scalar::from(7_u64);
+        let spend_key = EdwardsPoint::mul_base(&Scalar::from(11_u64));
+        let tx_key = Scalar::from(13_u64);
+
+        let sender_derivation = (tx_key * EdwardsPoint::mul_base(&view_key)).mul_by_cofactor();
+        let receiver_derivation = (view_key * EdwardsPoint::mul_base(&tx_key)).mul_by_cofactor();
+
+        let (key, view_tag) = derive_output_key(&sender_derivation, spend_key);
+
+        assert_eq!(
+            (key, view_tag),
+            derive_output_key(&receiver_derivation, spend_key)
+        );
+        assert_ne!(key, spend_key);
+    }
+}
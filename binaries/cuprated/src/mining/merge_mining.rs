@@ -0,0 +1,443 @@
+//! Merge Mining
+//!
+//! Functions to merge mine other chains (aux chains) with Monero, as used by RPC's `add_aux_pow`.
+//!
+//! The aux chain hashes are put in a merkle tree, the root of which is committed to in the
+//! miner transaction's extra with a merge mining tag.
+//!
+//! ref: <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/src/cryptonote_basic/merge_mining.cpp>
+use std::mem;
+
+use monero_serai::{
+    io::{read_varint, write_varint},
+    primitives::keccak256,
+};
+use sha2::{Digest, Sha256};
+
+use cuprate_helper::cast::{u32_to_usize, u64_to_usize};
+
+use crate::mining::{
+    TX_EXTRA_MERGE_MINING_TAG, TX_EXTRA_MYSTERIOUS_MINERGATE_TAG, TX_EXTRA_NONCE,
+    TX_EXTRA_TAG_ADDITIONAL_PUBKEYS, TX_EXTRA_TAG_PADDING, TX_EXTRA_TAG_PUBKEY,
+};
+
+/// The maximum amount of aux chains that can be merge mined at once.
+pub const MAX_AUX_CHAINS: usize = 256;
+
+/// The maximum nonce tried when looking for a unique slot for each aux chain.
+const MAX_AUX_NONCE: u32 = 65535;
+
+/// The hash key used when calculating an aux chain's slot.
+///
+/// `config::HASH_KEY_MM_SLOT` in `monerod`.
+const HASH_KEY_MM_SLOT: u8 = b'm';
+
+/// The maximum size of the padding in a tx-extra.
+///
+/// `TX_EXTRA_PADDING_MAX_COUNT` in `monerod`.
+const TX_EXTRA_PADDING_MAX_COUNT: usize = 255;
+
+/// A merge mining tag, committing to the merkle root of the aux chains in the miner transaction's extra.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeMiningTag {
+    /// The amount of aux chains and the nonce used to pick their slots, see [`encode_mm_depth`].
+    pub depth: u64,
+    /// The merkle root of the aux chain hashes.
+    pub merkle_root: [u8; 32],
+}
+
+impl MergeMiningTag {
+    /// Appends this tag to a tx-extra.
+    pub fn write(&self, extra: &mut Vec<u8>) {
+        let mut field = Vec::with_capacity(10 + 32);
+        write_varint(&self.depth, &mut field).unwrap();
+        field.extend_from_slice(&self.merkle_root);
+
+        extra.push(TX_EXTRA_MERGE_MINING_TAG);
+        write_varint(&field.len(), extra).unwrap();
+        extra.extend_from_slice(&field);
+    }
+
+    /// Reads the merge mining tag from a tx-extra.
+    ///
+    /// Returns [`None`] if the tx-extra could not be parsed or there is no merge mining tag.
+    pub fn read(extra: &[u8]) -> Option<Self> {
+        let (_, field) = tx_extra_fields(extra)?
+            .into_iter()
+            .find(|(tag, _)| *tag == TX_EXTRA_MERGE_MINING_TAG)?;
+
+        // Skip the tag and the field length.
+        let mut field = &field[1..];
+        let _: u64 = read_varint(&mut field).ok()?;
+
+        let depth = read_varint(&mut field).ok()?;
+        let merkle_root = field.try_into().ok()?;
+
+        Some(Self { depth, merkle_root })
+    }
+}
+
+/// Removes any merge mining tag from a tx-extra.
+///
+/// Returns [`None`] if the tx-extra could not be parsed.
+pub fn remove_merge_mining_tag(extra: &[u8]) -> Option<Vec<u8>> {
+    Some(
+        tx_extra_fields(extra)?
+            .into_iter()
+            .filter(|(tag, _)| *tag != TX_EXTRA_MERGE_MINING_TAG)
+            .flat_map(|(_, field)| field)
+            .copied()
+            .collect(),
+    )
+}
+
+/// Splits a tx-extra into its fields, returning the tag and the full bytes (including the tag) of each field.
+///
+/// Returns [`None`] if a field is malformed or has an unknown tag.
+///
+/// ref: <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/src/cryptonote_basic/tx_extra.h>
+fn tx_extra_fields(mut extra: &[u8]) -> Option<Vec<(u8, &[u8])>> {
+    let mut fields = Vec::new();
+
+    while let Some(&tag) = extra.first() {
+        let mut body = &extra[1..];
+
+        let field_len = match tag {
+            // Padding is all zeros until the end of the tx-extra.
+            TX_EXTRA_TAG_PADDING => {
+                if extra.len() > TX_EXTRA_PADDING_MAX_COUNT || body.iter().any(|b| *b != 0) {
+                    return None;
+                }
+                extra.len()
+            }
+            TX_EXTRA_TAG_PUBKEY => 1 + 32,
+            TX_EXTRA_NONCE => 1 + 1 + usize::from(*body.first()?),
+            TX_EXTRA_MERGE_MINING_TAG | TX_EXTRA_MYSTERIOUS_MINERGATE_TAG => {
+                let len = u64_to_usize(read_varint(&mut body).ok()?);
+                (extra.len() - body.len()).checked_add(len)?
+            }
+            TX_EXTRA_TAG_ADDITIONAL_PUBKEYS => {
+                let count = u64_to_usize(read_varint(&mut body).ok()?);
+                (extra.len() - body.len()).checked_add(count.checked_mul(32)?)?
+            }
+            _ => return None,
+        };
+
+        if field_len > extra.len() {
+            return None;
+        }
+
+        let (field, rest) = extra.split_at(field_len);
+        fields.push((tag, field));
+        extra = rest;
+    }
+
+    Some(fields)
+}
+
+/// Returns the slot of the aux chain with `id` in the merkle tree.
+///
+/// `n_aux_chains` must not be `0`.
+pub fn aux_slot(id: &[u8; 32], nonce: u32, n_aux_chains: u32) -> u32 {
+    let mut buf = [0; 32 + 4 + 1];
+    buf[..32].copy_from_slice(id);
+    buf[32..36].copy_from_slice(&nonce.to_le_bytes());
+    buf[36] = HASH_KEY_MM_SLOT;
+
+    let hash = Sha256::digest(buf);
+
+    u32::from_le_bytes(hash[..4].try_into().unwrap()) % n_aux_chains
+}
+
+/// Finds a nonce that gives every aux chain in `aux_ids` a unique slot.
+///
+/// Returns the nonce and the slot of each aux chain, in the same order as `aux_ids`, or [`None`]
+/// if `aux_ids` is empty, has more than [`MAX_AUX_CHAINS`] chains or no nonce was found.
+pub fn find_aux_nonce(aux_ids: &[[u8; 32]]) -> Option<(u32, Vec<u32>)> {
+    if aux_ids.is_empty() || aux_ids.len() > MAX_AUX_CHAINS {
+        return None;
+    }
+
+    let n_aux_chains = u32::try_from(aux_ids.len()).unwrap();
+
+    (0..=MAX_AUX_NONCE).find_map(|nonce| {
+        let mut slot_taken = vec![false; aux_ids.len()];
+
+        let slots = aux_ids
+            .iter()
+            .map(|id| {
+                let slot = aux_slot(id, nonce, n_aux_chains);
+
+                if mem::replace(&mut slot_taken[u32_to_usize(slot)], true) {
+                    None
+                } else {
+                    Some(slot)
+                }
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some((nonce, slots))
+    })
+}
+
+/// Encodes the amount of aux chains and the nonce used to find their slots into the merkle tree depth
+/// stored in the [`MergeMiningTag`].
+///
+/// Returns [`None`] if `n_aux_chains` is `0` or more than [`MAX_AUX_CHAINS`].
+pub fn encode_mm_depth(n_aux_chains: usize, nonce: u32) -> Option<u64> {
+    if n_aux_chains == 0 || n_aux_chains > MAX_AUX_CHAINS {
+        return None;
+    }
+
+    let n_aux_chains = u64::try_from(n_aux_chains).unwrap();
+
+    // The amount of bits needed to represent `n_aux_chains - 1`.
+    let mut n_bits = 1;
+    while (1 << n_bits) < n_aux_chains {
+        n_bits += 1;
+    }
+
+    Some((n_bits - 1) | ((n_aux_chains - 1) << 3) | (u64::from(nonce) << (3 + n_bits)))
+}
+
+/// Decodes a merkle tree depth created with [`encode_mm_depth`], returning the amount of aux chains and the nonce.
+///
+/// Returns [`None`] if the nonce does not fit in a [`u32`].
+pub fn decode_mm_depth(depth: u64) -> Option<(u32, u32)> {
+    let n_bits = 1 + (depth & 7);
+    let n_aux_chains = 1 + ((depth >> 3) & ((1 << n_bits) - 1));
+    let nonce = depth >> (3 + n_bits);
+
+    Some((
+        u32::try_from(n_aux_chains).ok()?,
+        u32::try_from(nonce).ok()?,
+    ))
+}
+
+/// Calculates the merkle root of `hashes`, with the same merkle tree used for a block's transactions.
+///
+/// # Panics
+///
+/// This function panics if `hashes` is empty.
+///
+/// ref: <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/src/crypto/tree-hash.c>
+pub fn tree_hash(hashes: &[[u8; 32]]) -> [u8; 32] {
+    fn hash_pair(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+        let mut buf = [0; 64];
+        buf[..32].copy_from_slice(left);
+        buf[32..].copy_from_slice(right);
+        keccak256(buf)
+    }
+
+    match hashes.len() {
+        0 => panic!("Cannot calculate the merkle root of no hashes"),
+        1 => hashes[0],
+        2 => hash_pair(&hashes[0], &hashes[1]),
+        len => {
+            // The largest power of 2 smaller than `len`.
+            let cnt = len.next_power_of_two() / 2;
+
+            // Hash the hashes that don't fit in `cnt` so the next layer is a power of 2.
+            let mut layer = hashes[..2 * cnt - len].to_vec();
+            layer.extend(
+                hashes[2 * cnt - len..]
+                    .chunks_exact(2)
+                    .map(|pair| hash_pair(&pair[0], &pair[1])),
+            );
+
+            while layer.len() > 1 {
+                layer = layer
+                    .chunks_exact(2)
+                    .map(|pair| hash_pair(&pair[0], &pair[1]))
+                    .collect();
+            }
+
+            layer[0]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+    use monero_serai::block::Block;
+
+    use cuprate_test_utils::data::{BLOCK_5ECB7E, BLOCK_F91043};
+
+    use super::*;
+
+    /// The merkle root in a block's hashing blob must match [`tree_hash`] over its transactions.
+    #[test]
+    fn tree_hash_matches_block_merkle_root() {
+        for block in [BLOCK_5ECB7E, BLOCK_F91043] {
+            let block = Block::read(&mut &*block).unwrap();
+
+            let mut hashes = vec![block.miner_transaction.hash()];
+            hashes.extend_from_slice(&block.transactions);
+
+            let header_len = block.header.serialize().len();
+            let hashing_blob = block.serialize_pow_hash();
+
+            assert_eq!(
+                tree_hash(&hashes),
+                hashing_blob[header_len..header_len + 32]
+            );
+        }
+    }
+
+    /// ref: <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/tests/unit_tests/merge_mining.cpp>
+    #[test]
+    fn mm_depth_encode_decode() {
+        for n_aux_chains in 1..=MAX_AUX_CHAINS {
+            for nonce in (0..1024).step_by(1 + n_aux_chains) {
+                let depth = encode_mm_depth(n_aux_chains, nonce).unwrap();
+
+                assert_eq!(
+                    decode_mm_depth(depth),
+                    Some((u32::try_from(n_aux_chains).unwrap(), nonce))
+                );
+            }
+        }
+
+        assert_eq!(encode_mm_depth(0, 0), None);
+        assert_eq!(encode_mm_depth(MAX_AUX_CHAINS + 1, 0), None);
+    }
+
+    /// ref: <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/tests/unit_tests/merge_mining.cpp>
+    #[test]
+    fn aux_slot_in_range() {
+        let id = [0xff; 32];
+
+        assert_eq!(aux_slot(&id, 0, 1), 0);
+
+        for n_aux_chains in 1..256 {
+            assert!(aux_slot(&id, u32::MAX, n_aux_chains) < n_aux_chains);
+        }
+    }
+
+    /// `get_aux_slot` vectors, computed separately from this implementation by hashing
+    /// `id || nonce (LE) || HASH_KEY_MM_SLOT` with SHA-256 as `monerod` does.
+    #[test]
+    fn aux_slot_vectors() {
+        #[rustfmt::skip]
+        let vectors: [([u8; 32], [(u32, u32, u32); 6]); 3] = [
+            ([0x00; 32], [(0, 2, 0), (1, 2, 1), (0, 3, 0), (7, 16, 11), (65535, 256, 253), (u32::MAX, 255, 176)]),
+            ([0x01; 32], [(0, 2, 0), (1, 2, 1), (0, 3, 1), (7, 16, 13), (65535, 256, 78), (u32::MAX, 255, 14)]),
+            ([0xff; 32], [(0, 2, 0), (1, 2, 0), (0, 3, 0), (7, 16, 9), (65535, 256, 108), (u32::MAX, 255, 33)]),
+        ];
+
+        for (id, cases) in vectors {
+            for (nonce, n_aux_chains, slot) in cases {
+                assert_eq!(aux_slot(&id, nonce, n_aux_chains), slot);
+            }
+        }
+    }
+
+    /// `encode_mm_depth` vectors, computed the same way as `aux_slot_vectors`.
+    #[test]
+    fn mm_depth_vectors() {
+        assert_eq!(encode_mm_depth(1, 0), Some(0));
+        assert_eq!(encode_mm_depth(1, 1), Some(16));
+        assert_eq!(encode_mm_depth(2, 0), Some(8));
+        assert_eq!(encode_mm_depth(3, 0), Some(17));
+        assert_eq!(encode_mm_depth(4, 5), Some(185));
+        assert_eq!(encode_mm_depth(256, 0), Some(2047));
+        assert_eq!(encode_mm_depth(256, 65535), Some(134_217_727));
+    }
+
+    #[test]
+    fn aux_nonce_gives_unique_slots() {
+        let aux_ids = (0..8_u8).map(|i| [i; 32]).collect::<Vec<_>>();
+
+        let (nonce, slots) = find_aux_nonce(&aux_ids).unwrap();
+
+        // Computed the same way as `aux_slot_vectors`.
+        assert_eq!(
+            (nonce, slots.as_slice()),
+            (36, [1, 6, 3, 2, 4, 5, 0, 7].as_slice())
+        );
+
+        for (id, slot) in aux_ids.iter().zip(&slots) {
+            assert_eq!(aux_slot(id, nonce, 8), *slot);
+        }
+
+        assert_eq!(find_aux_nonce(&[]), None);
+        assert_eq!(find_aux_nonce(&[[0; 32]; MAX_AUX_CHAINS + 1]), None);
+        // The same ID always gets the same slot.
+        assert_eq!(find_aux_nonce(&[[1; 32], [1; 32]]), None);
+    }
+
+    /// The merkle root and depth `add_aux_pow` commits to for 3 aux chains.
+    ///
+    /// The expected values were computed separately from this implementation, following
+    /// `monerod`'s `get_aux_nonce`, `tree_hash` and `encode_mm_depth`.
+    #[test]
+    fn multiple_aux_chains() {
+        let aux_ids = [[1; 32], [2; 32], [3; 32]];
+        let aux_hashes = [[0x11; 32], [0x12; 32], [0x13; 32]];
+
+        let (nonce, slots) = find_aux_nonce(&aux_ids).unwrap();
+        assert_eq!((nonce, slots.as_slice()), (9, [0, 1, 2].as_slice()));
+
+        let mut ordered = [[0; 32]; 3];
+        for (hash, slot) in aux_hashes.iter().zip(slots) {
+            ordered[u32_to_usize(slot)] = *hash;
+        }
+
+        assert_eq!(
+            tree_hash(&ordered),
+            hex!("373dff9bc4ad16a93c2d8960b65cec40c1008cde8162aac745a31c6a4728a221")
+        );
+        assert_eq!(encode_mm_depth(aux_ids.len(), nonce), Some(305));
+        assert_eq!(decode_mm_depth(305), Some((3, 9)));
+
+        let hashes = (0..5_u8).map(|i| [i; 32]).collect::<Vec<_>>();
+        assert_eq!(
+            tree_hash(&hashes),
+            hex!("60d4c7e49745b945a101686ffa32392f5f081d2683c5fb697ea84498e3e91931")
+        );
+    }
+
+    /// A single aux chain, from `monerod`'s `add_aux_pow` RPC example.
+    #[test]
+    fn single_aux_chain() {
+        let id = hex!("3200b4ea97c3b2081cd4190b58e49572b2319fed00d030ad51809dff06b5d8c8");
+        let hash = hex!("7b35762de164b20885e15dbe656b1138db06bb402fa1796f5765a23933d8859a");
+
+        let (nonce, slots) = find_aux_nonce(&[id]).unwrap();
+
+        assert_eq!((nonce, slots), (0, vec![0]));
+        assert_eq!(tree_hash(&[hash]), hash);
+        assert_eq!(encode_mm_depth(1, nonce), Some(0));
+    }
+
+    #[test]
+    fn merge_mining_tag_replaced() {
+        let mut extra = vec![TX_EXTRA_TAG_PUBKEY];
+        extra.extend_from_slice(&[1; 32]);
+        extra.extend_from_slice(&[TX_EXTRA_NONCE, 4, 1, 2, 3, 4]);
+
+        let old_tag = MergeMiningTag {
+            depth: 0,
+            merkle_root: [2; 32],
+        };
+        let new_tag = MergeMiningTag {
+            depth: encode_mm_depth(MAX_AUX_CHAINS, 1000).unwrap(),
+            merkle_root: [3; 32],
+        };
+
+        let mut extra_with_tag = extra.clone();
+        old_tag.write(&mut extra_with_tag);
+        assert_eq!(MergeMiningTag::read(&extra_with_tag), Some(old_tag));
+
+        let mut extra_without_tag = remove_merge_mining_tag(&extra_with_tag).unwrap();
+        assert_eq!(extra_without_tag, extra);
+        assert_eq!(MergeMiningTag::read(&extra_without_tag), None);
+
+        new_tag.write(&mut extra_without_tag);
+        assert_eq!(MergeMiningTag::read(&extra_without_tag), Some(new_tag));
+
+        // Unknown tags can not be parsed.
+        assert_eq!(remove_merge_mining_tag(&[0x05, 0, 0]), None);
+    }
+}
@@ -0,0 +1,718 @@
+//! Miner
+//!
+//! A simple multithreaded RandomX miner, used by the `start_mining`, `stop_mining` and `mining_status`
+//! RPC calls and the `[mining]` config section.
+//!
+//! This miner is meant for testnet/stagenet experiments and local integration tests, not for profit.
+use std::{
+    collections::HashMap,
+    future::Future,
+    num::NonZero,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex, PoisonError,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Error};
+use monero_address::MoneroAddress;
+use monero_serai::block::Block;
+use tokio::sync::{mpsc::UnboundedSender, watch, Notify};
+use tower::{Service, ServiceExt};
+
+use cuprate_blockchain::service::BlockchainReadHandle;
+use cuprate_consensus::BlockchainContextService;
+use cuprate_consensus_context::{
+    rx_vms::RandomXVm, BlockChainContextRequest, BlockChainContextResponse,
+};
+use cuprate_consensus_rules::blocks::{check_block_pow, RandomX};
+use cuprate_helper::{asynch::rayon_spawn_async, cast::u64_to_usize};
+use cuprate_txpool::service::TxpoolReadHandle;
+use cuprate_types::BlockTemplate;
+
+use crate::{
+    blockchain::interface as blockchain_interface, constants::PANIC_CRITICAL_SERVICE_ERROR,
+    mining::create_block_template,
+};
+
+/// How long to mine on a single block template before creating a new one.
+///
+/// This is how new transactions and new chain tips get picked up by the miner.
+const MINING_ROUND_DURATION: Duration = Duration::from_secs(5);
+
+/// How long to wait before trying again after failing to create a block template or RandomX VM.
+const TEMPLATE_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+/// The slot holding the running miner, shared by all [`Miner`] handles.
+type RunningSlot = Arc<Mutex<Option<RunningMiner>>>;
+
+/// A handle to the built-in miner.
+///
+/// Cloning this handle is cheap, all clones control the same miner.
+#[derive(Clone)]
+pub struct Miner {
+    /// The currently running miner, [`None`] if we are not mining.
+    running: RunningSlot,
+
+    /// Handle to the blockchain context service.
+    blockchain_context: BlockchainContextService,
+
+    /// Read handle to the blockchain database.
+    blockchain_read: BlockchainReadHandle,
+
+    /// Read handle to the transaction pool database.
+    txpool_read: TxpoolReadHandle,
+}
+
+/// A running mining task.
+struct RunningMiner {
+    /// The address the block rewards are paid to.
+    address: MoneroAddress,
+    /// The amount of threads hashing.
+    threads: NonZero<usize>,
+    /// State shared with the mining task.
+    stats: Arc<MinerStats>,
+    /// Closed when the mining task has finished, no value is ever sent.
+    finished: watch::Receiver<()>,
+}
+
+/// State shared between a [`Miner`] and its mining task.
+#[derive(Default)]
+struct MinerStats {
+    /// Set to stop the mining task.
+    stop: AtomicBool,
+    /// Notified when `stop` is set, to wake the mining task if it is waiting.
+    stop_notify: Notify,
+    /// The hash rate of the last mining round, in hashes per second.
+    hash_rate: AtomicU64,
+    /// The block reward of the block template currently being mined.
+    block_reward: AtomicU64,
+}
+
+impl MinerStats {
+    /// Tell the mining task to stop.
+    fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.stop_notify.notify_one();
+    }
+
+    /// Returns `true` if the mining task has been told to stop.
+    fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Sleep for `duration`, waking early if the mining task is told to stop.
+    async fn sleep(&self, duration: Duration) {
+        tokio::select! {
+            () = tokio::time::sleep(duration) => (),
+            () = self.stop_notify.notified() => (),
+        }
+    }
+}
+
+/// The status of a running miner, returned from [`Miner::status`].
+#[derive(Clone)]
+pub struct MinerStatus {
+    /// The address the block rewards are paid to.
+    pub address: MoneroAddress,
+    /// The amount of threads hashing.
+    pub threads: NonZero<usize>,
+    /// The current hash rate, in hashes per second.
+    pub hash_rate: u64,
+    /// The block reward of the block template currently being mined.
+    pub block_reward: u64,
+}
+
+impl Miner {
+    /// Create a new [`Miner`], this does not start mining.
+    pub fn new(
+        blockchain_context: BlockchainContextService,
+        blockchain_read: BlockchainReadHandle,
+        txpool_read: TxpoolReadHandle,
+    ) -> Self {
+        Self {
+            running: Arc::new(Mutex::new(None)),
+            blockchain_context,
+            blockchain_read,
+            txpool_read,
+        }
+    }
+
+    /// Start mining to `address` with `threads` threads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the miner is already running or is still stopping.
+    pub fn start(&self, address: MoneroAddress, threads: NonZero<usize>) -> Result<(), Error> {
+        let blockchain_context = self.blockchain_context.clone();
+        let blockchain_read = self.blockchain_read.clone();
+        let txpool_read = self.txpool_read.clone();
+
+        start_task(&self.running, address, threads, move |stats| {
+            mining_task(
+                blockchain_context,
+                blockchain_read,
+                txpool_read,
+                address,
+                threads,
+                stats,
+            )
+        })
+    }
+
+    /// Stop mining.
+    ///
+    /// This waits for the mining task and its threads to finish, which
+    /// happens after the threads' current hash.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the miner is not running.
+    pub async fn stop(&self) -> Result<(), Error> {
+        stop_task(&self.running).await
+    }
+
+    /// Returns the [`MinerStatus`] if the miner is running, else [`None`].
+    pub fn status(&self) -> Option<MinerStatus> {
+        self.running
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map(|running| MinerStatus {
+                address: running.address,
+                threads: running.threads,
+                hash_rate: running.stats.hash_rate.load(Ordering::Relaxed),
+                block_reward: running.stats.block_reward.load(Ordering::Relaxed),
+            })
+    }
+}
+
+/// Spawn the mining task `task` returns and put it in `running`.
+///
+/// `running` is cleared when the task finishes, even if it panics.
+///
+/// # Errors
+///
+/// Returns an error if `running` already holds a miner.
+fn start_task<F>(
+    running: &RunningSlot,
+    address: MoneroAddress,
+    threads: NonZero<usize>,
+    task: impl FnOnce(Arc<MinerStats>) -> F,
+) -> Result<(), Error>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut slot = running.lock().unwrap();
+
+    if slot.is_some() {
+        bail!("Already mining");
+    }
+
+    let stats = Arc::new(MinerStats::default());
+    let (finished_tx, finished) = watch::channel(());
+
+    let task = task(Arc::clone(&stats));
+    let guard = ClearRunningSlot {
+        running: Arc::clone(running),
+        stats: Arc::clone(&stats),
+        _finished: finished_tx,
+    };
+
+    tokio::spawn(async move {
+        let _guard = guard;
+        task.await;
+    });
+
+    *slot = Some(RunningMiner {
+        address,
+        threads,
+        stats,
+        finished,
+    });
+
+    Ok(())
+}
+
+/// Tell the mining task in `running` to stop and wait for it to finish.
+///
+/// # Errors
+///
+/// Returns an error if `running` does not hold a miner.
+async fn stop_task(running: &RunningSlot) -> Result<(), Error> {
+    let mut finished = {
+        let running = running.lock().unwrap();
+
+        let Some(running) = running.as_ref() else {
+            bail!("Not mining");
+        };
+
+        running.stats.stop();
+        running.finished.clone()
+    };
+
+    // This only returns an error once the task has dropped the sender.
+    while finished.changed().await.is_ok() {}
+
+    Ok(())
+}
+
+/// Clears the [`RunningSlot`] when the mining task finishes.
+struct ClearRunningSlot {
+    /// The slot holding the mining task.
+    running: RunningSlot,
+    /// The stats of the mining task, to check the slot still holds it.
+    stats: Arc<MinerStats>,
+    /// Dropped after the slot is cleared, waking [`stop_task`].
+    _finished: watch::Sender<()>,
+}
+
+impl Drop for ClearRunningSlot {
+    fn drop(&mut self) {
+        let mut running = self.running.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if running
+            .as_ref()
+            .is_some_and(|running| Arc::ptr_eq(&running.stats, &self.stats))
+        {
+            *running = None;
+        }
+    }
+}
+
+/// The mining task, this repeatedly creates block templates and mines on them until stopped.
+async fn mining_task(
+    mut blockchain_context: BlockchainContextService,
+    mut blockchain_read: BlockchainReadHandle,
+    mut txpool_read: TxpoolReadHandle,
+    address: MoneroAddress,
+    threads: NonZero<usize>,
+    stats: Arc<MinerStats>,
+) {
+    tracing::info!("Started mining to {address} with {threads} threads");
+
+    let mut mining_threads = MiningThreads::spawn(threads, &stats);
+
+    // The last used RandomX VM and its seed hash.
+    let mut cached_vm: Option<([u8; 32], Arc<RandomXVm>)> = None;
+
+    while !stats.stopped() {
+        let template = match create_block_template(
+            &mut blockchain_context,
+            &mut blockchain_read,
+            &mut txpool_read,
+            [0; 32],
+            &address,
+            &[],
+        )
+        .await
+        {
+            Ok(template) => template,
+            Err(e) => {
+                tracing::warn!("Failed to create block template for mining: {e}");
+                stats.sleep(TEMPLATE_RETRY_DELAY).await;
+                continue;
+            }
+        };
+
+        stats
+            .block_reward
+            .store(template.expected_reward, Ordering::Relaxed);
+
+        let vm = match &cached_vm {
+            Some((seed_hash, vm)) if *seed_hash == template.seed_hash => Arc::clone(vm),
+            _ => match randomx_vm(&mut blockchain_context, &template).await {
+                Ok(vm) => {
+                    cached_vm = Some((template.seed_hash, Arc::clone(&vm)));
+                    vm
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to get RandomX VM for mining: {e}");
+                    stats.sleep(TEMPLATE_RETRY_DELAY).await;
+                    continue;
+                }
+            },
+        };
+
+        let height = template.height;
+        let start = Instant::now();
+
+        let (block, hashes) = mining_threads.mine(template, vm).await;
+
+        let millis = start.elapsed().as_millis().max(1);
+        let hash_rate = u64::try_from(u128::from(hashes) * 1000 / millis).unwrap_or(u64::MAX);
+        stats.hash_rate.store(hash_rate, Ordering::Relaxed);
+
+        let Some(block) = block else {
+            continue;
+        };
+
+        tracing::info!(
+            "Mined block at height {height}, hash: {}",
+            hex::encode(block.hash())
+        );
+
+        if let Err(e) = blockchain_interface::handle_incoming_block(
+            block,
+            HashMap::new(),
+            &mut blockchain_read,
+            &mut txpool_read,
+        )
+        .await
+        {
+            tracing::warn!("Mined block was not accepted: {e}");
+        }
+    }
+
+    mining_threads.join().await;
+
+    tracing::info!("Stopped mining");
+}
+
+/// Returns a [`RandomXVm`] for the seed of `template`.
+///
+/// The context service's VMs are used if possible, otherwise a new VM is created.
+///
+/// # Errors
+///
+/// Returns an error if a new VM could not be created.
+async fn randomx_vm(
+    blockchain_context: &mut BlockchainContextService,
+    template: &BlockTemplate,
+) -> Result<Arc<RandomXVm>, Error> {
+    let BlockChainContextResponse::RxVms(vms) = blockchain_context
+        .ready()
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+        .call(BlockChainContextRequest::CurrentRxVms)
+        .await
+        .expect(PANIC_CRITICAL_SERVICE_ERROR)
+    else {
+        unreachable!();
+    };
+
+    if let Some(vm) = vms.get(&u64_to_usize(template.seed_height)) {
+        return Ok(Arc::clone(vm));
+    }
+
+    let seed_hash = template.seed_hash;
+    rayon_spawn_async(move || RandomXVm::new(&seed_hash))
+        .await
+        .map(Arc::new)
+        .map_err(|e| anyhow!("Failed to create RandomX VM: {e}"))
+}
+
+/// The mining threads.
+///
+/// The threads are kept across mining rounds, so each thread's
+/// RandomX VM is reused while the seed does not change.
+struct MiningThreads {
+    /// The channels sending each thread its [`MiningRound`]s.
+    rounds: Vec<mpsc::Sender<Arc<MiningRound>>>,
+    /// Receives the amount of hashes each thread calculated in a round.
+    hashes: tokio::sync::mpsc::UnboundedReceiver<u64>,
+    /// The threads.
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl MiningThreads {
+    /// Spawn `threads` mining threads, they stop hashing when `stats` is told to stop.
+    fn spawn(threads: NonZero<usize>, stats: &Arc<MinerStats>) -> Self {
+        let nonce_step = u32::try_from(threads.get()).unwrap_or(u32::MAX);
+        let (hashes_tx, hashes) = tokio::sync::mpsc::unbounded_channel();
+
+        let (rounds, handles) = (0..nonce_step)
+            .map(|start_nonce| {
+                let (rounds_tx, rounds) = mpsc::channel::<Arc<MiningRound>>();
+                let hashes_tx = hashes_tx.clone();
+                let stats = Arc::clone(stats);
+
+                let handle = std::thread::spawn(move || {
+                    mining_thread(start_nonce, nonce_step, &rounds, &hashes_tx, &stats);
+                });
+
+                (rounds_tx, handle)
+            })
+            .unzip();
+
+        Self {
+            rounds,
+            hashes,
+            handles,
+        }
+    }
+
+    /// Mines on `template` with all threads.
+    ///
+    /// Mining stops when a valid nonce is found, the miner is stopped or [`MINING_ROUND_DURATION`] has passed.
+    ///
+    /// Returns the mined [`Block`], if one was found, and the amount of hashes calculated.
+    async fn mine(&mut self, template: BlockTemplate, vm: Arc<RandomXVm>) -> (Option<Block>, u64) {
+        let round = Arc::new(MiningRound::new(template, vm));
+
+        // A thread that has exited can't be sent the round.
+        let mut threads = 0;
+        for rounds in &self.rounds {
+            if rounds.send(Arc::clone(&round)).is_ok() {
+                threads += 1;
+            }
+        }
+
+        let mut hashes = 0;
+        for _ in 0..threads {
+            let Some(thread_hashes) = self.hashes.recv().await else {
+                break;
+            };
+            hashes += thread_hashes;
+        }
+
+        let block = round.found_nonce.lock().unwrap().map(|nonce| {
+            let mut block = round.template.block.clone();
+            block.header.nonce = nonce;
+            block
+        });
+
+        (block, hashes)
+    }
+
+    /// Stop the threads and wait for them to exit.
+    async fn join(self) {
+        drop(self.rounds);
+
+        let handles = self.handles;
+        tokio::task::spawn_blocking(move || {
+            for handle in handles {
+                if handle.join().is_err() {
+                    tracing::error!("Mining thread panicked");
+                }
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
+
+/// A mining thread, this mines on each [`MiningRound`] it receives until `rounds` is closed.
+fn mining_thread(
+    start_nonce: u32,
+    nonce_step: u32,
+    rounds: &mpsc::Receiver<Arc<MiningRound>>,
+    hashes: &UnboundedSender<u64>,
+    stats: &MinerStats,
+) {
+    for round in rounds {
+        let round_hashes = round.mine(start_nonce, nonce_step, &stats.stop);
+
+        if hashes.send(round_hashes).is_err() {
+            return;
+        }
+    }
+}
+
+/// A mining round on a single block template, shared by all mining threads.
+struct MiningRound {
+    /// The block template being mined.
+    template: BlockTemplate,
+    /// The hashing blob of the template.
+    hashing_blob: Vec<u8>,
+    /// The offset of the nonce in `hashing_blob`.
+    nonce_offset: usize,
+    /// The RandomX VM for the template's seed.
+    vm: Arc<RandomXVm>,
+    /// When the round ends if no block is found.
+    deadline: Instant,
+    /// Set when a thread found a block or the deadline passed.
+    round_over: AtomicBool,
+    /// The nonce of the found block.
+    found_nonce: Mutex<Option<u32>>,
+}
+
+impl MiningRound {
+    /// Create a new [`MiningRound`] for `template`, ending after [`MINING_ROUND_DURATION`].
+    fn new(template: BlockTemplate, vm: Arc<RandomXVm>) -> Self {
+        let hashing_blob = template.block.serialize_pow_hash();
+        // The nonce is the last field of the block header.
+        let nonce_offset = template.block.header.serialize().len() - 4;
+
+        Self {
+            template,
+            hashing_blob,
+            nonce_offset,
+            vm,
+            deadline: Instant::now() + MINING_ROUND_DURATION,
+            round_over: AtomicBool::new(false),
+            found_nonce: Mutex::new(None),
+        }
+    }
+
+    /// Mine the nonces `start_nonce`, `start_nonce + nonce_step`, ... until the round is over or `stop` is set.
+    ///
+    /// Returns the amount of hashes calculated.
+    fn mine(&self, start_nonce: u32, nonce_step: u32, stop: &AtomicBool) -> u64 {
+        let mut blob = self.hashing_blob.clone();
+        let nonce_range = self.nonce_offset..self.nonce_offset + 4;
+        let mut nonce = start_nonce;
+        let mut hashes = 0;
+
+        while !self.round_over.load(Ordering::Relaxed) && !stop.load(Ordering::Relaxed) {
+            blob[nonce_range.clone()].copy_from_slice(&nonce.to_le_bytes());
+
+            let Ok(hash) = self.vm.calculate_hash(&blob) else {
+                tracing::error!("Failed to calculate RandomX hash");
+                break;
+            };
+            hashes += 1;
+
+            if check_block_pow(&hash, self.template.difficulty).is_ok() {
+                *self.found_nonce.lock().unwrap() = Some(nonce);
+                self.round_over.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            if Instant::now() >= self.deadline {
+                self.round_over.store(true, Ordering::Relaxed);
+                break;
+            }
+
+            let Some(next_nonce) = nonce.checked_add(nonce_step) else {
+                break;
+            };
+            nonce = next_nonce;
+        }
+
+        hashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cuprate_helper::network::Network;
+    use cuprate_test_utils::data::BLOCK_V16_TX0;
+
+    use crate::mining::parse_miner_address;
+
+    use super::*;
+
+    /// An address from `monerod`'s RPC docs.
+    const ADDRESS: &str = "44AFFq5kSiGBoZ4NMDwYtN18obc8AemS33DBLWs3H7otXft3XjrpDtQGv7SqSsaBYBb98uNbr2VBBEt7f2wfn3RVGQBEP3A";
+
+    fn address() -> MoneroAddress {
+        parse_miner_address(Network::Mainnet, ADDRESS).unwrap()
+    }
+
+    fn template(difficulty: u128) -> BlockTemplate {
+        BlockTemplate {
+            block: BLOCK_V16_TX0.block.clone(),
+            reserved_offset: 0,
+            difficulty,
+            height: 0,
+            expected_reward: 0,
+            seed_height: 0,
+            seed_hash: [0; 32],
+            next_seed_hash: [0; 32],
+        }
+    }
+
+    /// A mining task that takes a while to finish after being stopped, like
+    /// the mining threads finishing their hash, then sets `finished`.
+    async fn slow_task(stats: Arc<MinerStats>, finished: Arc<AtomicBool>) {
+        while !stats.stopped() {
+            stats.sleep(Duration::from_secs(60)).await;
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        finished.store(true, Ordering::Relaxed);
+    }
+
+    #[tokio::test]
+    async fn stop_waits_for_task() {
+        let running = RunningSlot::default();
+        let threads = NonZero::new(1).unwrap();
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let task = |finished: &Arc<AtomicBool>| {
+            let finished = Arc::clone(finished);
+            move |stats: Arc<MinerStats>| slow_task(stats, finished)
+        };
+
+        start_task(&running, address(), threads, task(&finished)).unwrap();
+        start_task(&running, address(), threads, task(&finished)).unwrap_err();
+
+        stop_task(&running).await.unwrap();
+        assert!(finished.load(Ordering::Relaxed));
+        assert!(running.lock().unwrap().is_none());
+        stop_task(&running).await.unwrap_err();
+
+        // The miner can be started again straight away, all stop calls wait.
+        finished.store(false, Ordering::Relaxed);
+        start_task(&running, address(), threads, task(&finished)).unwrap();
+
+        let (stop_1, stop_2) = tokio::join!(stop_task(&running), stop_task(&running));
+        stop_1.unwrap();
+        stop_2.unwrap();
+        assert!(finished.load(Ordering::Relaxed));
+        assert!(running.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn panicking_task_clears_slot() {
+        let running = RunningSlot::default();
+
+        start_task(&running, address(), NonZero::new(1).unwrap(), |_| async {
+            panic!("mining task panicked");
+        })
+        .unwrap();
+
+        stop_task(&running).await.unwrap();
+        assert!(running.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn mining_threads_find_block() {
+        let stats = Arc::new(MinerStats::default());
+        let mut threads = MiningThreads::spawn(NonZero::new(2).unwrap(), &stats);
+        let vm = Arc::new(RandomXVm::new(&[0; 32]).unwrap());
+        let template = template(4);
+
+        // The same threads are used for every round.
+        for _ in 0..2 {
+            let (block, hashes) = threads.mine(template.clone(), Arc::clone(&vm)).await;
+
+            let hash = vm
+                .calculate_hash(&block.unwrap().serialize_pow_hash())
+                .unwrap();
+            check_block_pow(&hash, template.difficulty).unwrap();
+            assert!(hashes >= 1);
+            assert!(threads.handles.iter().all(|handle| !handle.is_finished()));
+        }
+
+        threads.join().await;
+    }
+
+    #[tokio::test]
+    async fn stop_ends_mining_round() {
+        let stats = Arc::new(MinerStats::default());
+        let mut threads = MiningThreads::spawn(NonZero::new(2).unwrap(), &stats);
+        let vm = Arc::new(RandomXVm::new(&[0; 32]).unwrap());
+
+        let start = Instant::now();
+        let stop = tokio::spawn({
+            let stats = Arc::clone(&stats);
+            async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                stats.stop();
+            }
+        });
+
+        // No hash meets this difficulty.
+        let (block, _) = threads.mine(template(u128::MAX), vm).await;
+
+        assert!(block.is_none());
+        assert!(start.elapsed() < MINING_ROUND_DURATION);
+
+        stop.await.unwrap();
+        threads.join().await;
+    }
+}
@@ -5,15 +5,16 @@
 //! <https://github.com/Cuprate/cuprate/pull/355>
 
 use std::{
+    collections::HashMap,
     net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4},
-    num::NonZero,
     time::{Duration, Instant},
 };
 
 use anyhow::{anyhow, Error};
-use monero_serai::block::Block;
+use monero_serai::{block::Block, transaction::Transaction};
 use strum::{EnumCount, VariantArray};
 
+use cuprate_consensus_rules::blocks::randomx_seed_height;
 use cuprate_constants::{
     build::RELEASE,
     rpc::{RESTRICTED_BLOCK_COUNT, RESTRICTED_BLOCK_HEADER_RANGE},
@@ -29,36 +30,42 @@ use cuprate_rpc_interface::RpcHandler;
 use cuprate_rpc_types::{
     base::{AccessResponseBase, ResponseBase},
     json::{
-        AddAuxPowRequest, AddAuxPowResponse, BannedRequest, BannedResponse, CalcPowRequest,
-        CalcPowResponse, FlushCacheRequest, FlushCacheResponse, FlushTransactionPoolRequest,
-        FlushTransactionPoolResponse, GenerateBlocksRequest, GenerateBlocksResponse,
-        GetAlternateChainsRequest, GetAlternateChainsResponse, GetBansRequest, GetBansResponse,
-        GetBlockCountRequest, GetBlockCountResponse, GetBlockHeaderByHashRequest,
-        GetBlockHeaderByHashResponse, GetBlockHeaderByHeightRequest,
-        GetBlockHeaderByHeightResponse, GetBlockHeadersRangeRequest, GetBlockHeadersRangeResponse,
-        GetBlockRequest, GetBlockResponse, GetBlockTemplateRequest, GetBlockTemplateResponse,
-        GetCoinbaseTxSumRequest, GetCoinbaseTxSumResponse, GetConnectionsRequest,
-        GetConnectionsResponse, GetFeeEstimateRequest, GetFeeEstimateResponse, GetInfoRequest,
-        GetInfoResponse, GetLastBlockHeaderRequest, GetLastBlockHeaderResponse,
-        GetMinerDataRequest, GetMinerDataResponse, GetOutputDistributionRequest,
-        GetOutputDistributionResponse, GetOutputHistogramRequest, GetOutputHistogramResponse,
-        GetTransactionPoolBacklogRequest, GetTransactionPoolBacklogResponse, GetTxIdsLooseRequest,
-        GetTxIdsLooseResponse, GetVersionRequest, GetVersionResponse, HardForkInfoRequest,
-        HardForkInfoResponse, JsonRpcRequest, JsonRpcResponse, OnGetBlockHashRequest,
-        OnGetBlockHashResponse, PruneBlockchainRequest, PruneBlockchainResponse, RelayTxRequest,
-        RelayTxResponse, SetBansRequest, SetBansResponse, SubmitBlockRequest, SubmitBlockResponse,
-        SyncInfoRequest, SyncInfoResponse,
+        AddAuxPowRequest, AddAuxPowResponse, ApproveReorgRequest, ApproveReorgResponse,
+        BannedRequest, BannedResponse, CalcPowRequest, CalcPowResponse, FlushCacheRequest,
+        FlushCacheResponse, FlushTransactionPoolRequest, FlushTransactionPoolResponse,
+        GenerateBlocksRequest, GenerateBlocksResponse, GetAlternateChainsRequest,
+        GetAlternateChainsResponse, GetBansRequest, GetBansResponse, GetBlockCountRequest,
+        GetBlockCountResponse, GetBlockHeaderByHashRequest, GetBlockHeaderByHashResponse,
+        GetBlockHeaderByHeightRequest, GetBlockHeaderByHeightResponse, GetBlockHeadersRangeRequest,
+        GetBlockHeadersRangeResponse, GetBlockRequest, GetBlockResponse, GetBlockTemplateRequest,
+        GetBlockTemplateResponse, GetCoinbaseTxSumRequest, GetCoinbaseTxSumResponse,
+        GetConnectionsRequest, GetConnectionsResponse, GetFeeEstimateRequest,
+        GetFeeEstimateResponse, GetInfoRequest, GetInfoResponse, GetLastBlockHeaderRequest,
+        GetLastBlockHeaderResponse, GetMinerDataRequest, GetMinerDataResponse,
+        GetOutputDistributionRequest, GetOutputDistributionResponse, GetOutputHistogramRequest,
+        GetOutputHistogramResponse, GetTransactionPoolBacklogRequest,
+        GetTransactionPoolBacklogResponse, GetTxIdsLooseRequest, GetTxIdsLooseResponse,
+        GetVersionRequest, GetVersionResponse, HardForkInfoRequest, HardForkInfoResponse,
+        JsonRpcRequest, JsonRpcResponse, OnGetBlockHashRequest, OnGetBlockHashResponse,
+        PruneBlockchainRequest, PruneBlockchainResponse, RelayTxRequest, RelayTxResponse,
+        SetBansRequest, SetBansResponse, SubmitBlockRequest, SubmitBlockResponse, SyncInfoRequest,
+        SyncInfoResponse,
+    },
+    misc::{
+        BlockHeader, ChainInfo, DatabaseTableInfo, Distribution, GetBan, HistogramEntry, Status,
+        SyncInfoPeer,
     },
-    misc::{BlockHeader, ChainInfo, Distribution, GetBan, HistogramEntry, Status, SyncInfoPeer},
     CORE_RPC_VERSION,
 };
 use cuprate_types::{
-    rpc::{AuxPow, CoinbaseTxSum, GetMinerDataTxBacklogEntry, HardForkEntry, TxBacklogEntry},
+    rpc::{CoinbaseTxSum, GetMinerDataTxBacklogEntry, HardForkEntry, TxBacklogEntry},
     BlockTemplate, Chain, HardFork,
 };
 
 use crate::{
+    blockchain::{interface as blockchain_interface, IncomingBlockOk},
     constants::VERSION_BUILD,
+    mining::{self, merge_mining, merge_mining::MergeMiningTag},
     rpc::{
         constants::{FIELD_NOT_SUPPORTED, UNSUPPORTED_RPC_CALL},
         handlers::{helper, shared},
@@ -122,6 +129,7 @@ pub async fn map_request(
         Req::PruneBlockchain(r) => Resp::PruneBlockchain(prune_blockchain(state, r).await?),
         Req::CalcPow(r) => Resp::CalcPow(calc_pow(state, r).await?),
         Req::AddAuxPow(r) => Resp::AddAuxPow(add_aux_pow(state, r).await?),
+        Req::ApproveReorg(r) => Resp::ApproveReorg(approve_reorg(state, r).await?),
 
         // Unsupported RPC calls.
         Req::GetTxIdsLoose(_) | Req::FlushCache(_) => return Err(anyhow!(UNSUPPORTED_RPC_CALL)),
@@ -147,21 +155,16 @@ async fn get_block_template(
         return Err(anyhow!("Too big extra_nonce size"));
     }
 
-    // TODO: This should be `cuprated`'s active network.
-    let network = match Network::Mainnet {
-        Network::Mainnet => monero_address::Network::Mainnet,
-        Network::Stagenet => monero_address::Network::Stagenet,
-        Network::Testnet => monero_address::Network::Testnet,
-    };
-
-    let address = monero_address::MoneroAddress::from_str(network, &request.wallet_address)?;
-
-    if *address.kind() != monero_address::AddressType::Legacy {
-        return Err(anyhow!("Incorrect address type"));
-    }
+    let address = mining::parse_miner_address(state.network(), &request.wallet_address)?;
 
     let prev_block = request.prev_block.try_into().unwrap_or([0; 32]);
 
+    let extra_nonce = if request.reserve_size == 0 {
+        request.extra_nonce.0
+    } else {
+        vec![0; u64_to_usize(request.reserve_size)]
+    };
+
     let BlockTemplate {
         block,
         reserved_offset,
@@ -171,14 +174,23 @@ async fn get_block_template(
         seed_height,
         seed_hash,
         next_seed_hash,
-    } = *blockchain_manager::create_block_template(
-        &mut state.blockchain_manager,
+    } = mining::create_block_template(
+        &mut state.blockchain_context,
+        &mut state.blockchain_read,
+        &mut state.txpool_read,
         prev_block,
-        request.wallet_address,
-        request.extra_nonce.0,
+        &address,
+        &extra_nonce,
     )
     .await?;
 
+    // `monerod` only returns the offset to space reserved with `reserve_size`.
+    let reserved_offset = if request.reserve_size == 0 {
+        0
+    } else {
+        reserved_offset
+    };
+
     let blockhashing_blob = HexVec(block.serialize_pow_hash());
     let blocktemplate_blob = HexVec(block.serialize());
     let (difficulty, difficulty_top64) = split_u128_into_low_high_bits(difficulty);
@@ -241,8 +253,19 @@ async fn submit_block(
     let block = Block::read(&mut blob.as_slice())?;
     let block_id = Hex(block.hash());
 
-    // Attempt to relay the block.
-    blockchain_manager::relay_block(&mut state.blockchain_manager, Box::new(block)).await?;
+    // Attempt to add the block to the blockchain, the blockchain manager will relay it if it is valid.
+    let res = blockchain_interface::handle_incoming_block(
+        block,
+        HashMap::new(),
+        &mut state.blockchain_read,
+        &mut state.txpool_read,
+    )
+    .await
+    .map_err(|e| anyhow!("Block not accepted: {e}"))?;
+
+    if matches!(res, IncomingBlockOk::NotReady) {
+        return Err(anyhow!("Core is busy"));
+    }
 
     Ok(SubmitBlockResponse {
         base: helper::response_base(false),
@@ -493,6 +516,27 @@ async fn get_info(
         (database_size, free_space)
     };
 
+    let (database_free_space, database_tables) = if restricted {
+        (0, Vec::new())
+    } else {
+        let stats = blockchain::database_stats(&mut state.blockchain_read).await?;
+        let tables = stats
+            .tables
+            .into_iter()
+            .map(|table| DatabaseTableInfo {
+                name: table.table.to_string(),
+                entries: table.entries,
+                depth: table.depth,
+                branch_pages: table.branch_pages,
+                leaf_pages: table.leaf_pages,
+                overflow_pages: table.overflow_pages,
+                size_bytes: table.size_bytes,
+            })
+            .collect();
+
+        (stats.free_bytes, tables)
+    };
+
     let (difficulty, difficulty_top64) = split_u128_into_low_high_bits(c.next_difficulty);
 
     let height = usize_to_u64(c.chain_height);
@@ -598,6 +642,8 @@ async fn get_info(
         white_peerlist_size,
         wide_cumulative_difficulty,
         wide_difficulty,
+        database_free_space,
+        database_tables,
     })
 }
 
@@ -844,11 +890,30 @@ async fn get_fee_estimate(
         blockchain_context::fee_estimate(&mut state.blockchain_context, request.grace_blocks)
             .await?;
 
+    // Cuprate extension, estimate the fee to be included within `target_blocks` blocks.
+    let target_fee = if request.target_blocks == 0 {
+        0
+    } else {
+        let pool = txpool::backlog(&mut state.txpool_read)
+            .await?
+            .into_iter()
+            .map(|entry| (entry.fee, u64_to_usize(entry.weight)))
+            .collect();
+
+        blockchain_context::target_fee_estimate(
+            &mut state.blockchain_context,
+            u64_to_usize(request.target_blocks),
+            pool,
+        )
+        .await?
+    };
+
     Ok(GetFeeEstimateResponse {
         base: helper::access_response_base(false),
         fee: estimate.fee,
         fees: estimate.fees,
         quantization_mask: estimate.quantization_mask,
+        target_fee,
     })
 }
 
@@ -857,10 +922,20 @@ async fn get_alternate_chains(
     mut state: CupratedRpcHandler,
     _: GetAlternateChainsRequest,
 ) -> Result<GetAlternateChainsResponse, Error> {
+    // Cuprate extension, mark the chain of a re-org held until it is manually approved.
+    let held_reorg_top_hash = blockchain_interface::held_reorg()
+        .await
+        .ok()
+        .flatten()
+        .map(|alert| alert.alt_id.0);
+
     let chains = blockchain::alt_chains(&mut state.blockchain_read)
         .await?
         .into_iter()
-        .map(Into::into)
+        .map(|chain| ChainInfo {
+            reorg_held: held_reorg_top_hash == Some(chain.block_hash),
+            ..chain.into()
+        })
         .collect();
 
     Ok(GetAlternateChainsResponse {
@@ -960,8 +1035,9 @@ async fn get_miner_data(
     let major_version = c.current_hf.as_u8();
     let height = usize_to_u64(c.chain_height);
     let prev_id = Hex(c.top_hash);
-    // TODO: RX seed hash <https://github.com/Cuprate/cuprate/pull/355#discussion_r1911814320>.
-    let seed_hash = Hex([0; 32]);
+    let seed_height = usize_to_u64(randomx_seed_height(c.chain_height));
+    let seed_hash =
+        Hex(blockchain::block_hash(&mut state.blockchain_read, seed_height, Chain::Main).await?);
     let difficulty = c.next_difficulty.hex_prefix();
     // TODO: <https://github.com/Cuprate/cuprate/pull/355#discussion_r1911821515>
     let median_weight = usize_to_u64(c.effective_median_weight);
@@ -1015,22 +1091,6 @@ async fn calc_pow(
     let block = Block::read(&mut request.block_blob.as_slice())?;
     let seed_hash = request.seed_hash.0;
 
-    // let block_weight = todo!();
-
-    // let median_for_block_reward = blockchain_context::context(&mut state.blockchain_context)
-    //     .await?
-    //     .unchecked_blockchain_context()
-    //     .context_to_verify_block
-    //     .median_weight_for_block_reward;
-
-    // if cuprate_consensus_rules::blocks::check_block_weight(block_weight, median_for_block_reward)
-    //     .is_err()
-    // {
-    //     return Err(anyhow!("Block blob size is too big, rejecting block"));
-    // }
-
-    // TODO: will `CalculatePow` do the above checks?
-
     let pow_hash = blockchain_context::calculate_pow(
         &mut state.blockchain_context,
         hardfork,
@@ -1066,182 +1126,55 @@ fn add_aux_pow_inner(
     state: CupratedRpcHandler,
     request: AddAuxPowRequest,
 ) -> Result<AddAuxPowResponse, Error> {
-    let Some(non_zero_len) = NonZero::<usize>::new(request.aux_pow.len()) else {
+    if request.aux_pow.is_empty() {
         return Err(anyhow!("Empty `aux_pow` vector"));
-    };
-
-    // Some of the code below requires that the
-    // `.len()` of certain containers are the same.
-    // Boxed slices are used over `Vec` to slightly
-    // safe-guard against accidently pushing to it.
-    let aux_pow = request.aux_pow.into_boxed_slice();
-
-    // TODO: why is this here? it does nothing:
-    // <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L2110-L2112>
-    // let mut path_domain = 1_usize;
-    // while 1 << path_domain < len {
-    //     path_domain += 1;
-    // }
-
-    fn find_nonce(
-        aux_pow: &[AuxPow],
-        non_zero_len: NonZero<usize>,
-        aux_pow_len: usize,
-    ) -> Result<(u32, Box<[u32]>), Error> {
-        /// <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/src/cryptonote_basic/merge_mining.cpp#L48>
-        fn get_aux_slot(id: &[u8; 32], nonce: u32, n_aux_chains: NonZero<u32>) -> u32 {
-            const HASH_SIZE: usize = 32;
-
-            let mut buf = [0; HASH_SIZE + size_of::<u32>() + 1];
-            buf[..HASH_SIZE].copy_from_slice(id);
-
-            let v: [u8; 4] = nonce.to_le_bytes();
-            buf[HASH_SIZE..HASH_SIZE + size_of::<u32>()].copy_from_slice(&v);
-
-            const HASH_KEY_MM_SLOT: u8 = b'm';
-            buf[HASH_SIZE + size_of::<u32>()] = HASH_KEY_MM_SLOT;
-
-            fn sha256sum(buf: &[u8]) -> [u8; 32] {
-                todo!()
-            }
-            let res = sha256sum(&buf);
-
-            let v = u32::from_le_bytes(res[..4].try_into().unwrap());
-
-            v % n_aux_chains.get()
-        }
-
-        // INVARIANT: this must be the same `.len()` as `aux_pow`
-        let mut slots: Box<[u32]> = vec![u32::MAX; aux_pow_len].into_boxed_slice();
-        let mut slot_seen: Box<[bool]> = vec![false; aux_pow_len].into_boxed_slice();
-
-        const MAX_NONCE: u32 = 65535;
-
-        for nonce in 0..=MAX_NONCE {
-            for i in &mut slots {
-                let slot_u32 = get_aux_slot(
-                    &aux_pow[u32_to_usize(*i)].id.0,
-                    nonce,
-                    non_zero_len.try_into().unwrap(),
-                );
-
-                let slot = u32_to_usize(slot_u32);
-
-                if slot >= aux_pow_len {
-                    return Err(anyhow!("Computed slot is out of range"));
-                }
-
-                if slot_seen[slot] {
-                    return Ok((nonce, slots));
-                }
-
-                slot_seen[slot] = true;
-                *i = slot_u32;
-            }
-
-            slots.fill(u32::MAX);
-        }
-
-        Err(anyhow!("Failed to find a suitable nonce"))
     }
 
-    let len = non_zero_len.get();
-    let (nonce, slots) = find_nonce(&aux_pow, non_zero_len, len)?;
-
-    // FIXME: use iterator version.
-    let (aux_pow_id_raw, aux_pow_raw) = {
-        let mut aux_pow_id_raw = Vec::<[u8; 32]>::with_capacity(len);
-        let mut aux_pow_raw = Vec::<[u8; 32]>::with_capacity(len);
-
-        assert_eq!(
-            aux_pow.len(),
-            slots.len(),
-            "these need to be the same or else the below .zip() doesn't make sense"
-        );
-
-        for (aux_pow, slot) in aux_pow.iter().zip(&slots) {
-            if u32_to_usize(*slot) >= len {
-                return Err(anyhow!("Slot value out of range"));
-            }
-
-            aux_pow_id_raw.push(aux_pow.id.0);
-            aux_pow_raw.push(aux_pow.hash.0);
-        }
+    if request.aux_pow.len() > merge_mining::MAX_AUX_CHAINS {
+        return Err(anyhow!("Too many aux chains"));
+    }
 
-        assert_eq!(
-            slots.len(),
-            aux_pow_raw.len(),
-            "these need to be the same or else the below .zip() doesn't make sense"
-        );
-        assert_eq!(
-            aux_pow_raw.len(),
-            aux_pow_id_raw.len(),
-            "these need to be the same or else the below .zip() doesn't make sense"
-        );
-
-        for (slot, aux_pow) in slots.iter().zip(&aux_pow) {
-            let slot = u32_to_usize(*slot);
-
-            if slot >= len {
-                return Err(anyhow!("Slot value out of range"));
-            }
-
-            aux_pow_raw[slot] = aux_pow.hash.0;
-            aux_pow_id_raw[slot] = aux_pow.id.0;
-        }
+    let aux_pow_ids = request
+        .aux_pow
+        .iter()
+        .map(|aux_pow| aux_pow.id.0)
+        .collect::<Vec<_>>();
 
-        (
-            aux_pow_id_raw.into_boxed_slice(),
-            aux_pow_raw.into_boxed_slice(),
-        )
+    let Some((nonce, slots)) = merge_mining::find_aux_nonce(&aux_pow_ids) else {
+        return Err(anyhow!("Failed to find a suitable nonce"));
     };
 
-    fn tree_hash(aux_pow_raw: &[[u8; 32]]) -> [u8; 32] {
-        todo!("https://github.com/serai-dex/serai/pull/629")
+    // Order the aux chain hashes by their slot in the merkle tree.
+    let mut aux_pow_raw = vec![[0; 32]; request.aux_pow.len()];
+    for (aux_pow, slot) in request.aux_pow.iter().zip(slots) {
+        aux_pow_raw[u32_to_usize(slot)] = aux_pow.hash.0;
     }
 
-    fn encode_mm_depth(aux_pow_len: usize, nonce: u32) -> u64 {
-        todo!("https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/src/cryptonote_basic/merge_mining.cpp#L74")
-    }
+    let merkle_root = merge_mining::tree_hash(&aux_pow_raw);
+    let merkle_tree_depth = merge_mining::encode_mm_depth(request.aux_pow.len(), nonce)
+        .expect("the amount of aux chains was checked above");
 
-    let merkle_root = tree_hash(aux_pow_raw.as_ref());
-    let merkle_tree_depth = encode_mm_depth(len, nonce);
+    let mut block_template = Block::read(&mut request.blocktemplate_blob.as_slice())?;
 
-    let block_template = Block::read(&mut request.blocktemplate_blob.as_slice())?;
-
-    fn remove_field_from_tx_extra() -> Result<(), ()> {
-        todo!("https://github.com/monero-project/monero/blob/master/src/cryptonote_basic/cryptonote_format_utils.cpp#L767")
-    }
+    let (Transaction::V1 { prefix, .. } | Transaction::V2 { prefix, .. }) =
+        &mut block_template.miner_transaction;
 
-    if remove_field_from_tx_extra().is_err() {
+    let Some(mut extra) = merge_mining::remove_merge_mining_tag(&prefix.extra) else {
         return Err(anyhow!("Error removing existing merkle root"));
-    }
-
-    fn add_mm_merkle_root_to_tx_extra() -> Result<(), ()> {
-        todo!("https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L2189
-        ")
-    }
-
-    if add_mm_merkle_root_to_tx_extra().is_err() {
-        return Err(anyhow!("Error adding merkle root"));
-    }
+    };
 
-    fn invalidate_hashes() {
-        // block_template.invalidate_hashes();
-        // block_template.miner_tx.invalidate_hashes();
-        // <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L2195-L2196>
-        todo!();
+    MergeMiningTag {
+        depth: merkle_tree_depth,
+        merkle_root,
     }
+    .write(&mut extra);
 
-    invalidate_hashes();
-
-    let blocktemplate_blob = block_template.serialize();
-    let blockhashing_blob = block_template.serialize_pow_hash();
+    prefix.extra = extra;
 
-    let blocktemplate_blob = HexVec(blocktemplate_blob);
-    let blockhashing_blob = HexVec(blockhashing_blob);
+    let blocktemplate_blob = HexVec(block_template.serialize());
+    let blockhashing_blob = HexVec(block_template.serialize_pow_hash());
     let merkle_root = Hex(merkle_root);
-    let aux_pow = aux_pow.into_vec();
+    let aux_pow = request.aux_pow;
 
     Ok(AddAuxPowResponse {
         base: helper::response_base(false),
@@ -1253,6 +1186,20 @@ fn add_aux_pow_inner(
     })
 }
 
+//---------------------------------------------------------------------------------------------------- Cuprate extensions
+
+/// Approve the re-org held back by `--max-reorg-depth`, see [`blockchain_interface::approve_reorg`].
+async fn approve_reorg(
+    _: CupratedRpcHandler,
+    request: ApproveReorgRequest,
+) -> Result<ApproveReorgResponse, Error> {
+    blockchain_interface::approve_reorg(request.alt_top_hash.0).await?;
+
+    Ok(ApproveReorgResponse {
+        base: helper::response_base(false),
+    })
+}
+
 //---------------------------------------------------------------------------------------------------- Unsupported RPC calls (for now)
 
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L3553-L3627>
@@ -7,22 +7,26 @@
 use std::{
     borrow::Cow,
     collections::{BTreeSet, HashMap, HashSet},
+    num::NonZero,
 };
 
 use anyhow::{anyhow, Error};
+use indexmap::IndexSet;
 use monero_serai::transaction::{Input, Timelock, Transaction};
 
+use cuprate_consensus::transactions::new_tx_verification_data;
+use cuprate_consensus_rules::{transactions::TransactionError, ConsensusError};
 use cuprate_constants::rpc::{
     MAX_RESTRICTED_GLOBAL_FAKE_OUTS_COUNT, RESTRICTED_SPENT_KEY_IMAGES_COUNT,
     RESTRICTED_TRANSACTIONS_COUNT,
 };
-use cuprate_helper::cast::usize_to_u64;
+use cuprate_helper::{cast::usize_to_u64, fmt::HexPrefix, map::split_u128_into_low_high_bits};
 use cuprate_hex::{Hex, HexVec};
 use cuprate_p2p_core::{client::handshaker::builder::DummyAddressBook, ClearNet};
 use cuprate_rpc_interface::RpcHandler;
 use cuprate_rpc_types::{
     base::{AccessResponseBase, ResponseBase},
-    misc::{Status, TxEntry, TxEntryType},
+    misc::{GetOutputsOut, Status, TxEntry, TxEntryType},
     other::{
         GetAltBlocksHashesRequest, GetAltBlocksHashesResponse, GetHeightRequest, GetHeightResponse,
         GetLimitRequest, GetLimitResponse, GetNetStatsRequest, GetNetStatsResponse, GetOutsRequest,
@@ -43,10 +47,11 @@ use cuprate_rpc_types::{
 };
 use cuprate_types::{
     rpc::{KeyImageSpentStatus, PoolInfo, PoolTxInfo, PublicNode},
-    TxInPool, TxRelayChecks,
+    HardFork, TxInPool, TxRelayChecks,
 };
 
 use crate::{
+    mining,
     rpc::{
         constants::UNSUPPORTED_RPC_CALL,
         handlers::{helper, shared},
@@ -96,14 +101,14 @@ pub async fn map_request(
             Resp::GetTransactionPoolHashes(get_transaction_pool_hashes(state, r).await?)
         }
         Req::GetPublicNodes(r) => Resp::GetPublicNodes(get_public_nodes(state, r).await?),
+        Req::StartMining(r) => Resp::StartMining(start_mining(state, r).await?),
+        Req::StopMining(r) => Resp::StopMining(stop_mining(state, r).await?),
+        Req::MiningStatus(r) => Resp::MiningStatus(mining_status(state, r).await?),
 
         // Unsupported requests.
-        Req::SetBootstrapDaemon(_)
-        | Req::Update(_)
-        | Req::StartMining(_)
-        | Req::StopMining(_)
-        | Req::MiningStatus(_)
-        | Req::SetLogHashRate(_) => return Err(anyhow!(UNSUPPORTED_RPC_CALL)),
+        Req::SetBootstrapDaemon(_) | Req::Update(_) | Req::SetLogHashRate(_) => {
+            return Err(anyhow!(UNSUPPORTED_RPC_CALL))
+        }
     })
 }
 
@@ -274,10 +279,14 @@ async fn get_alt_blocks_hashes(
     mut state: CupratedRpcHandler,
     _: GetAltBlocksHashesRequest,
 ) -> Result<GetAltBlocksHashesResponse, Error> {
+    // Chains that split from another alt-chain share its blocks.
     let blks_hashes = blockchain::alt_chains(&mut state.blockchain_read)
         .await?
         .into_iter()
-        .map(|info| Hex(info.block_hash))
+        .flat_map(|info| info.block_hashes)
+        .collect::<IndexSet<_>>()
+        .into_iter()
+        .map(Hex)
         .collect();
 
     Ok(GetAltBlocksHashesResponse {
@@ -305,6 +314,20 @@ async fn is_key_image_spent(
 
     let mut spent_status = Vec::with_capacity(key_images.len());
 
+    // Find where the key images were spent, if the key image index is enabled.
+    let (spent_tx_hashes, spent_heights) =
+        match blockchain::key_image_spends(&mut state.blockchain_read, key_images.clone()).await? {
+            Some(spends) => spends
+                .into_iter()
+                .map(|spend| {
+                    spend.map_or((Hex([0; 32]), 0), |spend| {
+                        (Hex(spend.tx_hash), usize_to_u64(spend.block_height))
+                    })
+                })
+                .unzip(),
+            None => (vec![], vec![]),
+        };
+
     // Check the blockchain for key image spend status.
     blockchain::key_images_spent_vec(&mut state.blockchain_read, key_images.clone())
         .await?
@@ -352,6 +375,8 @@ async fn is_key_image_spent(
     Ok(IsKeyImageSpentResponse {
         base: helper::access_response_base(false),
         spent_status,
+        spent_tx_hashes,
+        spent_heights,
     })
 }
 
@@ -375,6 +400,9 @@ async fn send_raw_transaction(
         too_big: false,
         too_few_outputs: false,
         tx_extra_too_big: false,
+        tx_extra_not_allowed: false,
+        too_many_inputs: false,
+        too_many_outputs: false,
     };
 
     let tx = Transaction::read(&mut request.tx_as_hex.as_slice())?;
@@ -452,6 +480,34 @@ async fn send_raw_transaction(
         }
     }
 
+    let tx_data = match new_tx_verification_data(tx.clone()) {
+        Ok(tx_data) => tx_data,
+        Err(e) => {
+            let tx_relay_checks = match e {
+                ConsensusError::Transaction(TransactionError::InputsOverflow) => {
+                    TxRelayChecks::INVALID_INPUT
+                }
+                ConsensusError::Transaction(TransactionError::OutputsTooHigh) => {
+                    TxRelayChecks::OVERSPEND
+                }
+                _ => TxRelayChecks::empty(),
+            };
+
+            set_relay_check_fields(&mut resp, tx_relay_checks);
+            resp.base.response_base.status = Status::Failed;
+            resp.reason = e.to_string();
+            return Ok(resp);
+        }
+    };
+    let context = state.blockchain_context.blockchain_context();
+
+    if let Err(e) = state.relay_policy.check(&tx_data, context) {
+        set_relay_check_fields(&mut resp, e.relay_check());
+        resp.base.response_base.status = Status::Failed;
+        resp.reason = e.to_string();
+        return Ok(resp);
+    }
+
     let tx_relay_checks =
         txpool::check_maybe_relay_local(&mut state.txpool_manager, tx, !request.do_not_relay)
             .await?;
@@ -460,6 +516,17 @@ async fn send_raw_transaction(
         return Ok(resp);
     }
 
+    resp.base.response_base.status = Status::Failed;
+    resp.reason = set_relay_check_fields(&mut resp, tx_relay_checks);
+
+    Ok(resp)
+}
+
+/// Sets the fields of `resp` for the failed checks in `tx_relay_checks`, returning the reasons.
+fn set_relay_check_fields(
+    resp: &mut SendRawTransactionResponse,
+    tx_relay_checks: TxRelayChecks,
+) -> String {
     // <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L124>
     fn add_reason(reasons: &mut String, reason: &'static str) {
         if !reasons.is_empty() {
@@ -482,6 +549,9 @@ async fn send_raw_transaction(
         (&mut resp.too_big, TxRelayChecks::TOO_BIG, "too big"),
         (&mut resp.too_few_outputs, TxRelayChecks::TOO_FEW_OUTPUTS, "too few outputs"),
         (&mut resp.tx_extra_too_big, TxRelayChecks::TX_EXTRA_TOO_BIG, "tx-extra too big"),
+        (&mut resp.tx_extra_not_allowed, TxRelayChecks::TX_EXTRA_NOT_ALLOWED, "tx-extra not allowed"),
+        (&mut resp.too_many_inputs, TxRelayChecks::TOO_MANY_INPUTS, "too many inputs"),
+        (&mut resp.too_many_outputs, TxRelayChecks::TOO_MANY_OUTPUTS, "too many outputs"),
     ];
 
     for (field, flag, reason) in array {
@@ -491,7 +561,7 @@ async fn send_raw_transaction(
         }
     }
 
-    Ok(resp)
+    reasons
 }
 
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L1525-L1535>
@@ -631,13 +701,36 @@ async fn get_net_stats(
 
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L912-L957>
 async fn get_outs(
-    state: CupratedRpcHandler,
+    mut state: CupratedRpcHandler,
     request: GetOutsRequest,
 ) -> Result<GetOutsResponse, Error> {
+    // Check the limit before looking up any output keys.
+    if state.is_restricted()
+        && request.outputs.len() + request.output_keys.len() > MAX_RESTRICTED_GLOBAL_FAKE_OUTS_COUNT
+    {
+        return Err(anyhow!("Too many outs requested"));
+    }
+
+    // Cuprate extension, look up outputs by their one-time public key.
+    let mut output_indices = Vec::with_capacity(request.output_keys.len());
+    let mut missed_output_keys = vec![];
+    for output_key in request.output_keys {
+        match blockchain::output_by_key(&mut state.blockchain_read, output_key.0).await? {
+            Some((amount, index)) => output_indices.push(GetOutputsOut { amount, index }),
+            None => missed_output_keys.push(output_key),
+        }
+    }
+
+    let outputs = request
+        .outputs
+        .into_iter()
+        .chain(output_indices.iter().copied())
+        .collect();
+
     let outs = shared::get_outs(
         state,
         cuprate_rpc_types::bin::GetOutsRequest {
-            outputs: request.outputs,
+            outputs,
             get_txid: request.get_txid,
         },
     )
@@ -650,6 +743,8 @@ async fn get_outs(
     Ok(GetOutsResponse {
         base: helper::response_base(false),
         outs,
+        output_indices,
+        missed_output_keys,
     })
 }
 
@@ -755,32 +850,94 @@ async fn set_log_categories(
     todo!()
 }
 
-//---------------------------------------------------------------------------------------------------- Unsupported RPC calls (forever)
-
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L1413-L1462>
 async fn start_mining(
     state: CupratedRpcHandler,
     request: StartMiningRequest,
 ) -> Result<StartMiningResponse, Error> {
-    unreachable!()
+    if request.do_background_mining {
+        return Err(anyhow!("Background mining is not supported"));
+    }
+
+    let address = mining::parse_miner_address(state.network(), &request.miner_address)?;
+
+    let threads = usize::try_from(request.threads_count)
+        .ok()
+        .and_then(NonZero::new)
+        .ok_or_else(|| anyhow!("Invalid threads_count"))?;
+
+    state.miner.start(address, threads)?;
+
+    Ok(StartMiningResponse {
+        base: ResponseBase::OK,
+    })
 }
 
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L1464-L1482>
 async fn stop_mining(
     state: CupratedRpcHandler,
-    request: StopMiningRequest,
+    _: StopMiningRequest,
 ) -> Result<StopMiningResponse, Error> {
-    unreachable!();
+    state.miner.stop().await?;
+
+    Ok(StopMiningResponse {
+        base: ResponseBase::OK,
+    })
 }
 
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L1484-L1523>
 async fn mining_status(
     state: CupratedRpcHandler,
-    request: MiningStatusRequest,
+    _: MiningStatusRequest,
 ) -> Result<MiningStatusResponse, Error> {
-    unreachable!();
+    let c = state.blockchain_context.blockchain_context();
+
+    let (difficulty, difficulty_top64) = split_u128_into_low_high_bits(c.next_difficulty);
+    let wide_difficulty = (difficulty, difficulty_top64).hex_prefix();
+    let block_target = u32::try_from(c.current_hf.block_time().as_secs()).unwrap();
+
+    let pow_algorithm = if c.current_hf >= HardFork::V12 {
+        "RandomX"
+    } else {
+        "Cryptonight"
+    }
+    .to_string();
+
+    let status = state.miner.status();
+    let active = status.is_some();
+
+    let (address, threads_count, speed, block_reward) =
+        status.map_or((String::new(), 0, 0, 0), |s| {
+            (
+                s.address.to_string(),
+                u32::try_from(s.threads.get()).unwrap_or(u32::MAX),
+                s.hash_rate,
+                s.block_reward,
+            )
+        });
+
+    Ok(MiningStatusResponse {
+        base: ResponseBase::OK,
+        active,
+        address,
+        bg_idle_threshold: 0,
+        bg_ignore_battery: false,
+        bg_min_idle_seconds: 0,
+        bg_target: 0,
+        block_reward,
+        block_target,
+        difficulty,
+        difficulty_top64,
+        is_background_mining_enabled: false,
+        pow_algorithm,
+        speed,
+        threads_count,
+        wide_difficulty,
+    })
 }
 
+//---------------------------------------------------------------------------------------------------- Unsupported RPC calls (forever)
+
 /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.cpp#L1626-L1639>
 async fn set_log_hash_rate(
     state: CupratedRpcHandler,
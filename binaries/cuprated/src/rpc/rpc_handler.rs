@@ -9,6 +9,7 @@ use tower::Service;
 
 use cuprate_blockchain::service::{BlockchainReadHandle, BlockchainWriteHandle};
 use cuprate_consensus::BlockchainContextService;
+use cuprate_p2p_core::Network;
 use cuprate_pruning::PruningSeed;
 use cuprate_rpc_interface::RpcHandler;
 use cuprate_rpc_types::{
@@ -19,7 +20,7 @@ use cuprate_rpc_types::{
 use cuprate_txpool::service::TxpoolReadHandle;
 use cuprate_types::BlockTemplate;
 
-use crate::rpc::handlers;
+use crate::{mining::Miner, rpc::handlers, txpool::RelayPolicy};
 
 /// TODO: use real type when public.
 #[derive(Clone)]
@@ -161,6 +162,16 @@ pub struct CupratedRpcHandler {
     /// This is not `pub` on purpose, as it should not be mutated after [`Self::new`].
     restricted: bool,
 
+    /// The network `cuprated` is running on.
+    ///
+    /// This is not `pub` on purpose, as it should not be mutated after [`Self::new`].
+    network: Network,
+
+    /// The [maximum JSON-RPC batch size](RpcHandler::max_batch_size).
+    ///
+    /// This is not `pub` on purpose, as it should not be mutated after [`Self::new`].
+    max_batch_size: usize,
+
     /// Read handle to the blockchain database.
     pub blockchain_read: BlockchainReadHandle,
 
@@ -175,33 +186,56 @@ pub struct CupratedRpcHandler {
 
     /// TODO: handle to txpool service.
     pub txpool_manager: std::convert::Infallible,
+
+    /// The relay policy txs sent to this node must pass.
+    pub relay_policy: RelayPolicy,
+
+    /// Handle to the built-in miner.
+    pub miner: Miner,
 }
 
 impl CupratedRpcHandler {
     /// Create a new [`Self`].
     pub const fn new(
         restricted: bool,
+        network: Network,
+        max_batch_size: usize,
         blockchain_read: BlockchainReadHandle,
         blockchain_context: BlockchainContextService,
         blockchain_manager: BlockchainManagerHandle,
         txpool_read: TxpoolReadHandle,
         txpool_manager: std::convert::Infallible,
+        relay_policy: RelayPolicy,
+        miner: Miner,
     ) -> Self {
         Self {
             restricted,
+            network,
+            max_batch_size,
             blockchain_read,
             blockchain_context,
             blockchain_manager,
             txpool_read,
             txpool_manager,
+            relay_policy,
+            miner,
         }
     }
+
+    /// The network `cuprated` is running on.
+    pub const fn network(&self) -> Network {
+        self.network
+    }
 }
 
 impl RpcHandler for CupratedRpcHandler {
     fn is_restricted(&self) -> bool {
         self.restricted
     }
+
+    fn max_batch_size(&self) -> usize {
+        self.max_batch_size
+    }
 }
 
 impl Service<JsonRpcRequest> for CupratedRpcHandler {
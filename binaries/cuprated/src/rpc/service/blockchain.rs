@@ -20,8 +20,8 @@ use cuprate_types::{
         ChainInfo, CoinbaseTxSum, KeyImageSpentStatus, OutputDistributionData,
         OutputHistogramEntry, OutputHistogramInput,
     },
-    BlockCompleteEntry, Chain, ExtendedBlockHeader, OutputDistributionInput, OutputOnChain,
-    TxInBlockchain,
+    BlockCompleteEntry, Chain, DatabaseStats, ExtendedBlockHeader, KeyImageSpend,
+    OutputDistributionInput, OutputOnChain, TxInBlockchain,
 };
 
 /// [`BlockchainReadRequest::Block`].
@@ -314,6 +314,40 @@ pub async fn key_images_spent_vec(
     Ok(status)
 }
 
+/// [`BlockchainReadRequest::KeyImageSpends`]
+pub async fn key_image_spends(
+    blockchain_read: &mut BlockchainReadHandle,
+    key_images: Vec<[u8; 32]>,
+) -> Result<Option<Vec<Option<KeyImageSpend>>>, Error> {
+    let BlockchainResponse::KeyImageSpends(spends) = blockchain_read
+        .ready()
+        .await?
+        .call(BlockchainReadRequest::KeyImageSpends(key_images))
+        .await?
+    else {
+        unreachable!();
+    };
+
+    Ok(spends)
+}
+
+/// [`BlockchainReadRequest::OutputByKey`]
+pub async fn output_by_key(
+    blockchain_read: &mut BlockchainReadHandle,
+    output_key: [u8; 32],
+) -> Result<Option<(u64, u64)>, Error> {
+    let BlockchainResponse::OutputByKey(output) = blockchain_read
+        .ready()
+        .await?
+        .call(BlockchainReadRequest::OutputByKey(output_key))
+        .await?
+    else {
+        unreachable!();
+    };
+
+    Ok(output)
+}
+
 /// [`BlockchainReadRequest::CompactChainHistory`]
 pub async fn compact_chain_history(
     blockchain_read: &mut BlockchainReadHandle,
@@ -383,6 +417,22 @@ pub async fn database_size(
     Ok((database_size, free_space))
 }
 
+/// [`BlockchainReadRequest::DatabaseStats`]
+pub async fn database_stats(
+    blockchain_read: &mut BlockchainReadHandle,
+) -> Result<DatabaseStats, Error> {
+    let BlockchainResponse::DatabaseStats(stats) = blockchain_read
+        .ready()
+        .await?
+        .call(BlockchainReadRequest::DatabaseStats)
+        .await?
+    else {
+        unreachable!();
+    };
+
+    Ok(stats)
+}
+
 /// [`BlockchainReadRequest::OutputDistribution`]
 pub async fn output_distribution(
     blockchain_read: &mut BlockchainReadHandle,
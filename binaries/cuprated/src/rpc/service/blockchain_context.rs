@@ -1,13 +1,16 @@
 //! Functions to send [`BlockChainContextRequest`]s.
 
+use std::sync::Arc;
+
 use anyhow::{anyhow, Error};
 use monero_serai::block::Block;
 use tower::{Service, ServiceExt};
 
 use cuprate_consensus_context::{
-    BlockChainContextRequest, BlockChainContextResponse, BlockchainContext,
+    rx_vms::RandomXVm, BlockChainContextRequest, BlockChainContextResponse, BlockchainContext,
     BlockchainContextService,
 };
+use cuprate_consensus_rules::blocks::calculate_pow_hash;
 use cuprate_types::{
     rpc::{FeeEstimate, HardForkInfo},
     HardFork,
@@ -53,7 +56,33 @@ pub(crate) async fn fee_estimate(
     Ok(fee)
 }
 
-/// [`BlockChainContextRequest::CalculatePow`]
+/// [`BlockChainContextRequest::TargetFeeEstimate`]
+pub(crate) async fn target_fee_estimate(
+    blockchain_context: &mut BlockchainContextService,
+    target_blocks: usize,
+    pool: Vec<(u64, usize)>,
+) -> Result<u64, Error> {
+    let BlockChainContextResponse::TargetFeeEstimate(fee) = blockchain_context
+        .ready()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .call(BlockChainContextRequest::TargetFeeEstimate {
+            target_blocks,
+            pool,
+        })
+        .await
+        .map_err(|e| anyhow!(e))?
+    else {
+        unreachable!();
+    };
+
+    Ok(fee)
+}
+
+/// Calculates the proof-of-work hash of a block.
+///
+/// This uses [`BlockChainContextRequest::CachedRxVm`] and creates the RandomX VM
+/// outside the context task if it is not cached, as that takes seconds.
 pub(crate) async fn calculate_pow(
     blockchain_context: &mut BlockchainContextService,
     hardfork: HardFork,
@@ -64,24 +93,40 @@ pub(crate) async fn calculate_pow(
         return Err(anyhow!("Block is missing height"));
     };
 
-    let block = Box::new(block);
+    let vm = if hardfork >= HardFork::V12 {
+        let BlockChainContextResponse::CachedRxVm(vm) = blockchain_context
+            .ready()
+            .await
+            .map_err(|e| anyhow!(e))?
+            .call(BlockChainContextRequest::CachedRxVm { seed_hash })
+            .await
+            .map_err(|e| anyhow!(e))?
+        else {
+            unreachable!();
+        };
 
-    let BlockChainContextResponse::CalculatePow(hash) = blockchain_context
-        .ready()
-        .await
-        .map_err(|e| anyhow!(e))?
-        .call(BlockChainContextRequest::CalculatePow {
-            hardfork,
-            height,
-            block,
-            seed_hash,
+        Some(match vm {
+            Some(vm) => vm,
+            None => Arc::new(
+                tokio::task::spawn_blocking(move || RandomXVm::new(&seed_hash))
+                    .await?
+                    .map_err(|e| anyhow!("Failed to create RandomX VM: {e}"))?,
+            ),
         })
-        .await
-        .map_err(|e| anyhow!(e))?
-    else {
-        unreachable!();
+    } else {
+        None
     };
 
+    let hash = tokio::task::spawn_blocking(move || {
+        calculate_pow_hash(
+            vm.as_deref(),
+            &block.serialize_pow_hash(),
+            height,
+            &hardfork,
+        )
+    })
+    .await??;
+
     Ok(hash)
 }
 
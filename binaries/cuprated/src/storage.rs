@@ -0,0 +1,179 @@
+//! Storage
+//!
+//! Offline tools for the blockchain and tx-pool databases, these run instead of the node.
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    num::NonZeroUsize,
+    path::Path,
+};
+
+use anyhow::Error;
+
+use cuprate_blockchain::{
+    bootstrap::{block_chunk, BootstrapWriter},
+    integrity::{check_integrity, pop_blocks, IntegrityReport},
+    ops::blockchain::chain_height,
+    tables::{OpenTables, Tables},
+};
+use cuprate_database::{config::Backend, ConcreteEnv, Env, EnvInner};
+
+use crate::config::Config;
+
+/// The amount of rows copied per write transaction when converting databases.
+const CONVERT_BATCH_SIZE: usize = 10_000;
+
+/// The amount of blocks exported between progress updates.
+const EXPORT_PROGRESS_INTERVAL: usize = 1_000;
+
+/// Copy the blockchain and tx-pool databases into new databases using `backend`.
+///
+/// Backends use different file names, so the new databases are created in the same
+/// directories as the current ones. Databases already using `backend` are skipped.
+///
+/// The optional indexes' state is copied too, so a running index backfill
+/// continues in the new database.
+///
+/// If this is interrupted, calling this again will resume where it stopped.
+pub fn convert_databases(config: &Config, backend: Backend) -> Result<(), Error> {
+    let batch_size = NonZeroUsize::new(CONVERT_BATCH_SIZE).unwrap();
+
+    let blockchain = config.blockchain_config();
+    if blockchain.db_config.backend() == backend {
+        println!("The blockchain database already uses {}", backend.name());
+    } else {
+        println!(
+            "Converting the blockchain database from {} to {}",
+            blockchain.db_config.backend().name(),
+            backend.name()
+        );
+
+        let src = cuprate_blockchain::open(blockchain)?;
+        // The schema and index state are copied from `src`, so don't write them to `dst` first.
+        let dst = cuprate_blockchain::open_copy_destination(
+            config.blockchain_config_builder().backend(backend).build(),
+        )?;
+
+        cuprate_blockchain::tables::copy_tables(&src, &dst, batch_size, print_progress)?;
+        println!("Verifying the converted blockchain database");
+        cuprate_blockchain::tables::verify_tables(&src, &dst)?;
+        finish(&dst)?;
+    }
+
+    let txpool = config.txpool_config();
+    if txpool.db_config.backend() == backend {
+        println!("The tx-pool database already uses {}", backend.name());
+    } else {
+        println!(
+            "Converting the tx-pool database from {} to {}",
+            txpool.db_config.backend().name(),
+            backend.name()
+        );
+
+        let src = cuprate_txpool::open(txpool)?;
+        let dst = cuprate_txpool::open_copy_destination(
+            config.txpool_config_builder().backend(backend).build(),
+        )?;
+
+        cuprate_txpool::tables::copy_tables(&src, &dst, batch_size, print_progress)?;
+        println!("Verifying the converted tx-pool database");
+        cuprate_txpool::tables::verify_tables(&src, &dst)?;
+        finish(&dst)?;
+    }
+
+    println!(
+        "Databases converted, set `backend = \"{backend:?}\"` in the `[storage.blockchain]` \
+        and `[storage.txpool]` config sections to use them"
+    );
+
+    Ok(())
+}
+
+/// Check the blockchain database is consistent, printing the report as JSON to stdout.
+///
+/// The database is opened read-only for the check, so it is not written to unless `repair` is `true`.
+///
+/// If `repair` is `true` and problems were found in blocks, the database is opened writable,
+/// those blocks and all blocks above them are popped and the database is checked again.
+///
+/// Returns `true` if the database is consistent.
+pub fn check_database(config: &Config, repair: bool) -> Result<bool, Error> {
+    let env = cuprate_blockchain::open_read_only(config.blockchain_config())?;
+
+    eprintln!("Checking the blockchain database, this may take a while");
+    let mut report = check_blockchain(&env)?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    drop(env);
+
+    if repair {
+        if let Some(height) = report.first_bad_height() {
+            // The schema was checked by the read-only open, so this won't run any migrations.
+            let env = cuprate_blockchain::open_keep_indexes(config.blockchain_config())?;
+
+            eprintln!("Popping blocks down to height {height}");
+            let popped = pop_blocks(&env, height)?;
+            eprintln!("Popped {popped} blocks, checking the blockchain database again");
+
+            report = check_blockchain(&env)?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+
+            env.sync()?;
+        }
+    }
+
+    Ok(report.is_ok())
+}
+
+/// Run [`check_integrity`] on the blockchain database.
+fn check_blockchain(env: &ConcreteEnv) -> Result<IntegrityReport, Error> {
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let tables = env_inner.open_tables(&tx_ro)?;
+
+    Ok(check_integrity(&tables)?)
+}
+
+/// Export the blockchain to a `monerod` raw bootstrap file at `path`.
+///
+/// The file can be imported by `cuprated` or `monero-blockchain-import`.
+pub fn export_blocks(config: &Config, path: &Path) -> Result<(), Error> {
+    let env = cuprate_blockchain::open(config.blockchain_config())?;
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let tables = env_inner.open_tables(&tx_ro)?;
+
+    let height = chain_height(tables.block_heights())?;
+    println!("Exporting {height} blocks to {}", path.display());
+
+    let mut writer = BootstrapWriter::new(BufWriter::new(File::create(path)?))?;
+    for block_height in 0..height {
+        writer.write_chunk(&block_chunk(&block_height, &tables)?)?;
+
+        if (block_height + 1) % EXPORT_PROGRESS_INTERVAL == 0 || block_height + 1 == height {
+            print!("\r{}/{height} blocks", block_height + 1);
+            std::io::stdout().flush().unwrap();
+        }
+    }
+    println!();
+
+    writer.finish()?.into_inner()?.sync_all()?;
+    println!("Done: {}", path.display());
+
+    Ok(())
+}
+
+/// Print the progress of copying a table.
+fn print_progress(table: &'static str, copied: u64, rows: u64) {
+    print!("\r{table}: {copied}/{rows} rows");
+    if copied == rows {
+        println!();
+    }
+    std::io::stdout().flush().unwrap();
+}
+
+/// Sync a converted database to disk.
+fn finish(env: &ConcreteEnv) -> Result<(), Error> {
+    env.sync()?;
+    println!("Done: {}", env.config().db_file().display());
+    Ok(())
+}
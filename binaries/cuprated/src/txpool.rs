@@ -12,3 +12,7 @@ mod relay_rules;
 mod txs_being_handled;
 
 pub use incoming_tx::{IncomingTxError, IncomingTxHandler, IncomingTxs};
+pub use relay_rules::{
+    ExtraFieldRule, InputOutputRule, MaxWeightRule, MinimumFeeRule, RelayPolicy, RelayRule,
+    RelayRuleError, UnlockTimeRule, MAX_TX_EXTRA_SIZE,
+};
@@ -31,7 +31,7 @@ use cuprate_txpool::{
     },
     transaction_blob_hash,
 };
-use cuprate_types::TransactionVerificationData;
+use cuprate_types::{CachedVerificationState, TransactionVerificationData};
 
 use crate::{
     blockchain::ConsensusBlockchainReadHandle,
@@ -40,7 +40,7 @@ use crate::{
     signals::REORG_LOCK,
     txpool::{
         dandelion,
-        relay_rules::check_tx_relay_rules,
+        relay_rules::RelayPolicy,
         txs_being_handled::{TxsBeingHandled, TxsBeingHandledLocally},
     },
 };
@@ -89,6 +89,8 @@ pub struct IncomingTxHandler {
     pub(super) txpool_read_handle: TxpoolReadHandle,
     /// The blockchain read handle.
     pub(super) blockchain_read_handle: ConsensusBlockchainReadHandle,
+    /// The relay policy txs must pass to be added to the pool.
+    pub(super) relay_policy: RelayPolicy,
 }
 
 impl IncomingTxHandler {
@@ -100,6 +102,7 @@ impl IncomingTxHandler {
         txpool_read_handle: TxpoolReadHandle,
         blockchain_context_cache: BlockchainContextService,
         blockchain_read_handle: BlockchainReadHandle,
+        relay_policy: RelayPolicy,
     ) -> Self {
         let dandelion_router = dandelion::dandelion_router(clear_net);
 
@@ -119,6 +122,7 @@ impl IncomingTxHandler {
                 blockchain_read_handle,
                 BoxError::from,
             ),
+            relay_policy,
         }
     }
 }
@@ -141,6 +145,7 @@ impl Service<IncomingTxs> for IncomingTxHandler {
             self.txpool_write_handle.clone(),
             self.txpool_read_handle.clone(),
             self.dandelion_pool_manager.clone(),
+            self.relay_policy.clone(),
         )
         .boxed()
     }
@@ -155,46 +160,65 @@ async fn handle_incoming_txs(
     mut txpool_write_handle: TxpoolWriteHandle,
     mut txpool_read_handle: TxpoolReadHandle,
     mut dandelion_pool_manager: DandelionPoolService<DandelionTx, TxId, CrossNetworkInternalPeerId>,
+    relay_policy: RelayPolicy,
 ) -> Result<(), IncomingTxError> {
     let _reorg_guard = REORG_LOCK.read().await;
 
     let (txs, stem_pool_txs, txs_being_handled_guard) =
         prepare_incoming_txs(txs, txs_being_handled, &mut txpool_read_handle).await?;
 
-    let context = blockchain_context_cache.blockchain_context();
-
-    let txs = start_tx_verification()
-        .append_prepped_txs(txs)
-        .prepare()
-        .map_err(|e| IncomingTxError::Consensus(e.into()))?
-        .full(
-            context.chain_height,
-            context.top_hash,
-            context.current_adjusted_timestamp_for_time_lock(),
-            context.current_hf,
-            blockchain_read_handle,
-            None,
-        )
-        .verify()
-        .await
-        .map_err(IncomingTxError::Consensus)?;
-
-    for tx in txs {
-        // TODO: this could be a DoS, if someone spams us with txs that violate these rules?
-        // Maybe we should remember these invalid txs for some time to prevent them getting repeatedly sent.
-        if let Err(e) = check_tx_relay_rules(&tx, context) {
-            tracing::debug!(err = %e, tx = hex::encode(tx.tx_hash), "Tx failed relay check, skipping.");
+    let mut txs = txs;
+
+    // Txs are verified against the current chain tip, if the chain tip changes before a tx is added
+    // to the pool the tx-pool will reject it and it will be verified again against the new chain tip.
+    while !txs.is_empty() {
+        let context = blockchain_context_cache.blockchain_context();
+
+        let verified_txs = start_tx_verification()
+            .append_prepped_txs(txs)
+            .prepare()
+            .map_err(|e| IncomingTxError::Consensus(e.into()))?
+            .full(
+                context.chain_height,
+                context.top_hash,
+                context.current_adjusted_timestamp_for_time_lock(),
+                context.current_hf,
+                blockchain_read_handle.clone(),
+                None,
+            )
+            .verify()
+            .await
+            .map_err(IncomingTxError::Consensus)?;
+
+        let mut stale_txs = Vec::new();
+
+        for tx in verified_txs {
+            // TODO: this could be a DoS, if someone spams us with txs that violate these rules?
+            // Maybe we should remember these invalid txs for some time to prevent them getting repeatedly sent.
+            //
+            // Local txs are checked by the submitter, which can return the reason to the user.
+            if !matches!(state, TxState::Local) {
+                if let Err(e) = relay_policy.check(&tx, context) {
+                    tracing::debug!(err = %e, tx = hex::encode(tx.tx_hash), "Tx failed relay check, skipping.");
+
+                    continue;
+                }
+            }
 
-            continue;
+            if let Some(tx) = handle_valid_tx(
+                tx,
+                state.clone(),
+                context.top_hash,
+                &mut txpool_write_handle,
+                &mut dandelion_pool_manager,
+            )
+            .await
+            {
+                stale_txs.push(tx);
+            }
         }
 
-        handle_valid_tx(
-            tx,
-            state.clone(),
-            &mut txpool_write_handle,
-            &mut dandelion_pool_manager,
-        )
-        .await;
+        txs = stale_txs;
     }
 
     // Re-relay any txs we got in the block that were already in our stem pool.
@@ -299,40 +323,53 @@ async fn prepare_incoming_txs(
 /// Handle a verified tx.
 ///
 /// This will add the tx to the txpool and route it to the network.
+///
+/// If the chain tip changed since the tx was verified, the tx will not be added and will be returned
+/// so it can be verified again.
 async fn handle_valid_tx(
     tx: TransactionVerificationData,
     state: TxState<CrossNetworkInternalPeerId>,
+    chain_top_hash: [u8; 32],
     txpool_write_handle: &mut TxpoolWriteHandle,
     dandelion_pool_manager: &mut DandelionPoolService<
         DandelionTx,
         TxId,
         CrossNetworkInternalPeerId,
     >,
-) {
+) -> Option<TransactionVerificationData> {
     let incoming_tx =
         IncomingTxBuilder::new(DandelionTx(Bytes::copy_from_slice(&tx.tx_blob)), tx.tx_hash);
 
-    let TxpoolWriteResponse::AddTransaction(double_spend) = txpool_write_handle
+    // Keep the tx around in case we need to verify it again, this is cheap compared to verification.
+    let tx = Box::new(tx);
+
+    let double_spend = match txpool_write_handle
         .ready()
         .await
         .expect(PANIC_CRITICAL_SERVICE_ERROR)
         .call(TxpoolWriteRequest::AddTransaction {
-            tx: Box::new(tx),
+            tx: tx.clone(),
             state_stem: state.is_stem_stage(),
+            chain_top_hash,
         })
         .await
         .expect("TODO")
-    else {
-        unreachable!()
+    {
+        TxpoolWriteResponse::AddTransaction(double_spend) => double_spend,
+        // A block was added after we verified this tx, it needs to be checked against the new chain.
+        TxpoolWriteResponse::ChainTipChanged => {
+            let mut tx = *tx;
+            reset_contextual_verification(&mut tx);
+            return Some(tx);
+        }
+        TxpoolWriteResponse::Ok => unreachable!(),
     };
 
     // TODO: track double spends to quickly ignore them from their blob hash.
     if let Some(tx_hash) = double_spend {
-        return;
+        return None;
     };
 
-    // TODO: There is a race condition possible if a tx and block come in at the same time: <https://github.com/Cuprate/cuprate/issues/314>.
-
     let incoming_tx = incoming_tx
         .with_routing_state(state)
         .with_state_in_db(None)
@@ -346,6 +383,22 @@ async fn handle_valid_tx(
         .call(incoming_tx)
         .await
         .expect(PANIC_CRITICAL_SERVICE_ERROR);
+
+    None
+}
+
+/// Drops the contextual part of a tx's cached verification state, keeping the semantic part.
+///
+/// The block a tx was verified at stays in the main chain when a new block is added on top, so without
+/// this the tx would be treated as valid without being checked against the new block.
+fn reset_contextual_verification(tx: &mut TransactionVerificationData) {
+    match tx.cached_verification_state {
+        CachedVerificationState::ValidAtHashAndHF { hf, .. }
+        | CachedVerificationState::ValidAtHashAndHFWithTimeBasedLock { hf, .. } => {
+            tx.cached_verification_state = CachedVerificationState::OnlySemantic(hf);
+        }
+        CachedVerificationState::NotVerified | CachedVerificationState::OnlySemantic(_) => (),
+    }
 }
 
 /// Re-relay a tx that was already in our stem pool.
@@ -387,3 +440,121 @@ async fn rerelay_stem_tx(
         .await
         .expect(PANIC_CRITICAL_SERVICE_ERROR);
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, future::ready};
+
+    use monero_serai::transaction::Input;
+    use tower::service_fn;
+
+    use cuprate_consensus::transactions::new_tx_verification_data;
+    use cuprate_test_utils::data::TX_E2D393;
+    use cuprate_types::{
+        blockchain::{BlockchainReadRequest, BlockchainResponse},
+        HardFork,
+    };
+
+    use super::*;
+
+    /// A tx returned with [`TxpoolWriteResponse::ChainTipChanged`] must be checked against the block
+    /// that was added, even though the block it was verified at is still in the main chain.
+    ///
+    /// <https://github.com/Cuprate/cuprate/issues/314>
+    #[tokio::test]
+    async fn stale_tx_spent_in_new_block_stays_out_of_pool() {
+        let tip_a = [1; 32];
+        let tip_b = [2; 32];
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = cuprate_txpool::config::ConfigBuilder::new()
+            .data_directory(tempdir.path().into())
+            .build();
+        let (mut txpool_read_handle, mut txpool_write_handle, _) =
+            cuprate_txpool::service::init(config).unwrap();
+
+        let mut tx = new_tx_verification_data(Transaction::read(&mut TX_E2D393).unwrap()).unwrap();
+        // The tx was fully verified against tip `A`.
+        tx.cached_verification_state = CachedVerificationState::ValidAtHashAndHF {
+            block_hash: tip_a,
+            hf: HardFork::V9,
+        };
+
+        let key_images = tx
+            .tx
+            .prefix()
+            .inputs
+            .iter()
+            .map(|input| match input {
+                Input::ToKey { key_image, .. } => key_image.0,
+                Input::Gen(_) => unreachable!(),
+            })
+            .collect::<HashSet<_>>();
+
+        for (top_hash, spent_key_images) in [
+            (tip_a, vec![]),
+            (tip_b, key_images.iter().copied().collect()),
+        ] {
+            txpool_write_handle
+                .ready()
+                .await
+                .unwrap()
+                .call(TxpoolWriteRequest::NewBlock {
+                    top_hash,
+                    spent_key_images,
+                })
+                .await
+                .unwrap();
+        }
+
+        let response = txpool_write_handle
+            .ready()
+            .await
+            .unwrap()
+            .call(TxpoolWriteRequest::AddTransaction {
+                tx: Box::new(tx.clone()),
+                state_stem: false,
+                chain_top_hash: tip_a,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response, TxpoolWriteResponse::ChainTipChanged);
+
+        reset_contextual_verification(&mut tx);
+        assert_eq!(
+            tx.cached_verification_state,
+            CachedVerificationState::OnlySemantic(HardFork::V9)
+        );
+
+        // Block `B` spent the tx's key images, block `A` is still in the main chain.
+        let database = service_fn(move |req: BlockchainReadRequest| {
+            ready(Ok::<_, BoxError>(match req {
+                BlockchainReadRequest::FilterUnknownHashes(hashes) => {
+                    BlockchainResponse::FilterUnknownHashes(hashes)
+                }
+                BlockchainReadRequest::KeyImagesSpent(kis) => {
+                    BlockchainResponse::KeyImagesSpent(!kis.is_disjoint(&key_images))
+                }
+                _ => panic!("Database request not needed for this test"),
+            }))
+        });
+
+        let res = start_tx_verification()
+            .append_prepped_txs(vec![tx.clone()])
+            .prepare()
+            .unwrap()
+            .full(11, tip_b, u64::MAX, HardFork::V9, database, None)
+            .verify()
+            .await;
+        assert!(res.is_err());
+
+        let in_pool = txpool_read_handle
+            .ready()
+            .await
+            .unwrap()
+            .call(TxpoolReadRequest::TxBlob(tx.tx_hash))
+            .await
+            .is_ok();
+        assert!(!in_pool);
+    }
+}
@@ -1,57 +1,303 @@
-use std::cmp::max;
+//! Relay rules
+//!
+//! Relay rules govern the txs we accept to our tx-pool and propagate around the network, unlike consensus
+//! rules these are set by the operator, through a [`RelayPolicy`].
+use std::{cmp::max, sync::Arc};
 
 use monero_serai::transaction::Timelock;
 use thiserror::Error;
 
-use cuprate_consensus_context::BlockchainContext;
-use cuprate_consensus_rules::miner_tx::calculate_block_reward;
+use cuprate_consensus_context::fee::{
+    DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT, FEE_QUANTIZATION_MASK,
+};
+use cuprate_consensus_rules::{blocks::ContextToVerifyBlock, miner_tx::calculate_block_reward};
 use cuprate_helper::cast::usize_to_u64;
-use cuprate_types::TransactionVerificationData;
+use cuprate_types::{TransactionVerificationData, TxRelayChecks};
 
 /// The maximum size of the tx extra field.
 ///
 /// <https://github.com/monero-project/monero/blob/3b01c490953fe92f3c6628fa31d280a4f0490d28/src/cryptonote_config.h#L217>
-const MAX_TX_EXTRA_SIZE: usize = 1060;
+pub const MAX_TX_EXTRA_SIZE: usize = 1060;
 
-/// <https://github.com/monero-project/monero/blob/3b01c490953fe92f3c6628fa31d280a4f0490d28/src/cryptonote_config.h#L75>
-const DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT: u128 = 3_000;
+/// The tx extra field tags.
+///
+/// <https://github.com/monero-project/monero/blob/3b01c490953fe92f3c6628fa31d280a4f0490d28/src/cryptonote_basic/tx_extra.h#L36-L43>
+const TX_EXTRA_TAG_PADDING: u8 = 0x00;
+const TX_EXTRA_TAG_PUBKEY: u8 = 0x01;
+const TX_EXTRA_NONCE: u8 = 0x02;
+const TX_EXTRA_MERGE_MINING_TAG: u8 = 0x03;
+const TX_EXTRA_TAG_ADDITIONAL_PUBKEYS: u8 = 0x04;
+const TX_EXTRA_MYSTERIOUS_MINERGATE_TAG: u8 = 0xDE;
 
-/// <https://github.com/monero-project/monero/blob/3b01c490953fe92f3c6628fa31d280a4f0490d28/src/cryptonote_core/blockchain.h#L646>
-const FEE_MASK: u64 = 10_u64.pow(4);
+/// The maximum amount of padding bytes in the tx extra field.
+const TX_EXTRA_PADDING_MAX_COUNT: usize = 255;
 
-#[derive(Debug, Error)]
+/// The reason a tx was rejected by a [`RelayRule`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
 pub enum RelayRuleError {
     #[error("Tx has non-zero timelock.")]
     NonZeroTimelock,
-    #[error("Tx extra field is too large.")]
-    ExtraFieldTooLarge,
-    #[error("Tx fee too low.")]
-    FeeBelowMinimum,
+    #[error("Tx extra field is too large, size: {size}, maximum: {max}.")]
+    ExtraFieldTooLarge { size: usize, max: usize },
+    #[error("Tx extra field is not standard.")]
+    NonStandardExtraField,
+    #[error("Tx extra field tag is not allowed: {0:#04x}.")]
+    ExtraFieldTagNotAllowed(u8),
+    #[error("Tx fee too low, fee: {fee}, minimum: {minimum}.")]
+    FeeBelowMinimum { fee: u64, minimum: u64 },
+    #[error("Tx weight is too large, weight: {weight}, maximum: {max}.")]
+    TooBig { weight: usize, max: usize },
+    #[error("Tx has too many inputs, inputs: {inputs}, maximum: {max}.")]
+    TooManyInputs { inputs: usize, max: usize },
+    #[error("Tx has too many outputs, outputs: {outputs}, maximum: {max}.")]
+    TooManyOutputs { outputs: usize, max: usize },
+}
+
+impl RelayRuleError {
+    /// Returns the [`TxRelayChecks`] flag for this error, as returned in `send_raw_transaction`.
+    pub const fn relay_check(&self) -> TxRelayChecks {
+        match self {
+            Self::NonZeroTimelock => TxRelayChecks::NONZERO_UNLOCK_TIME,
+            Self::ExtraFieldTooLarge { .. } => TxRelayChecks::TX_EXTRA_TOO_BIG,
+            Self::NonStandardExtraField | Self::ExtraFieldTagNotAllowed(_) => {
+                TxRelayChecks::TX_EXTRA_NOT_ALLOWED
+            }
+            Self::FeeBelowMinimum { .. } => TxRelayChecks::FEE_TOO_LOW,
+            Self::TooBig { .. } => TxRelayChecks::TOO_BIG,
+            Self::TooManyInputs { .. } => TxRelayChecks::TOO_MANY_INPUTS,
+            Self::TooManyOutputs { .. } => TxRelayChecks::TOO_MANY_OUTPUTS,
+        }
+    }
+}
+
+/// A relay rule, a tx must pass all the rules in the [`RelayPolicy`] to be relayed.
+pub trait RelayRule: Send + Sync {
+    /// Checks the transaction passes this rule.
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        context: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError>;
+}
+
+/// The relay policy, the [`RelayRule`]s txs must pass to be accepted to our tx-pool and relayed.
+#[derive(Clone, Default)]
+pub struct RelayPolicy {
+    /// The rules, checked in order.
+    rules: Vec<Arc<dyn RelayRule>>,
+}
+
+impl RelayPolicy {
+    /// Returns a [`RelayPolicy`] with `rule` added, it is checked after the current rules.
+    #[must_use]
+    pub fn with_rule(mut self, rule: impl RelayRule + 'static) -> Self {
+        self.rules.push(Arc::new(rule));
+        self
+    }
+
+    /// Checks the transaction passes the relay rules.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first rule the tx fails.
+    pub fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        context: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        self.rules
+            .iter()
+            .try_for_each(|rule| rule.check(tx, context))
+    }
 }
 
-/// Checks the transaction passes the relay rules.
+/// Rejects txs with a non-zero timelock.
+pub struct UnlockTimeRule;
+
+impl RelayRule for UnlockTimeRule {
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        _: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        if tx.tx.prefix().additional_timelock != Timelock::None {
+            return Err(RelayRuleError::NonZeroTimelock);
+        }
+
+        Ok(())
+    }
+}
+
+/// Limits the size of the tx extra field and, if `allowed_tags` is not empty, the tags in it.
+pub struct ExtraFieldRule {
+    /// The maximum size of the tx extra field.
+    pub max_size: usize,
+    /// The allowed tx extra field tags, any tag is allowed if this is empty.
+    pub allowed_tags: Vec<u8>,
+}
+
+impl RelayRule for ExtraFieldRule {
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        _: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        let extra = &tx.tx.prefix().extra;
+
+        if extra.len() > self.max_size {
+            return Err(RelayRuleError::ExtraFieldTooLarge {
+                size: extra.len(),
+                max: self.max_size,
+            });
+        }
+
+        if self.allowed_tags.is_empty() {
+            return Ok(());
+        }
+
+        let tags = extra_field_tags(extra).ok_or(RelayRuleError::NonStandardExtraField)?;
+
+        if let Some(tag) = tags
+            .into_iter()
+            .find(|tag| !self.allowed_tags.contains(tag))
+        {
+            return Err(RelayRuleError::ExtraFieldTagNotAllowed(tag));
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects txs with a fee below the minimum fee, multiplied by `multiplier`.
+pub struct MinimumFeeRule {
+    /// The multiplier for the minimum fee.
+    pub multiplier: f64,
+}
+
+impl RelayRule for MinimumFeeRule {
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        context: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        check_fee(tx.tx_weight, tx.fee, self.multiplier, context)
+    }
+}
+
+/// Rejects txs with a weight above `max_weight`.
+pub struct MaxWeightRule {
+    /// The maximum tx weight.
+    pub max_weight: usize,
+}
+
+impl RelayRule for MaxWeightRule {
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        _: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        if tx.tx_weight > self.max_weight {
+            return Err(RelayRuleError::TooBig {
+                weight: tx.tx_weight,
+                max: self.max_weight,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Limits the amount of inputs and outputs in a tx, a limit of `0` means no limit.
+pub struct InputOutputRule {
+    /// The maximum amount of inputs.
+    pub max_inputs: usize,
+    /// The maximum amount of outputs.
+    pub max_outputs: usize,
+}
+
+impl RelayRule for InputOutputRule {
+    fn check(
+        &self,
+        tx: &TransactionVerificationData,
+        _: &ContextToVerifyBlock,
+    ) -> Result<(), RelayRuleError> {
+        let inputs = tx.tx.prefix().inputs.len();
+        if self.max_inputs != 0 && inputs > self.max_inputs {
+            return Err(RelayRuleError::TooManyInputs {
+                inputs,
+                max: self.max_inputs,
+            });
+        }
+
+        let outputs = tx.tx.prefix().outputs.len();
+        if self.max_outputs != 0 && outputs > self.max_outputs {
+            return Err(RelayRuleError::TooManyOutputs {
+                outputs,
+                max: self.max_outputs,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the tags in the tx extra field, or [`None`] if the field is not standard.
 ///
-/// Relay rules are rules that govern the txs we accept to our tx-pool and propagate around the network.
-pub fn check_tx_relay_rules(
-    tx: &TransactionVerificationData,
-    context: &BlockchainContext,
-) -> Result<(), RelayRuleError> {
-    if tx.tx.prefix().additional_timelock != Timelock::None {
-        return Err(RelayRuleError::NonZeroTimelock);
+/// <https://github.com/monero-project/monero/blob/3b01c490953fe92f3c6628fa31d280a4f0490d28/src/cryptonote_basic/tx_extra.h>
+fn extra_field_tags(mut extra: &[u8]) -> Option<Vec<u8>> {
+    let mut tags = Vec::new();
+
+    while let Some((&tag, rest)) = extra.split_first() {
+        tags.push(tag);
+        extra = rest;
+
+        let len = match tag {
+            // Padding takes up the rest of the field and must be all zeros.
+            TX_EXTRA_TAG_PADDING => {
+                if extra.len() >= TX_EXTRA_PADDING_MAX_COUNT || extra.iter().any(|b| *b != 0) {
+                    return None;
+                }
+
+                extra.len()
+            }
+            TX_EXTRA_TAG_PUBKEY => 32,
+            TX_EXTRA_NONCE | TX_EXTRA_MERGE_MINING_TAG | TX_EXTRA_MYSTERIOUS_MINERGATE_TAG => {
+                read_varint(&mut extra)?
+            }
+            TX_EXTRA_TAG_ADDITIONAL_PUBKEYS => read_varint(&mut extra)?.checked_mul(32)?,
+            _ => return None,
+        };
+
+        extra = extra.get(len..)?;
     }
 
-    if tx.tx.prefix().extra.len() > MAX_TX_EXTRA_SIZE {
-        return Err(RelayRuleError::ExtraFieldTooLarge);
+    Some(tags)
+}
+
+/// Reads a Monero varint from the start of `buf`.
+fn read_varint(buf: &mut &[u8]) -> Option<usize> {
+    let mut value = 0_u64;
+
+    for i in 0..10 {
+        let (&byte, rest) = buf.split_first()?;
+        *buf = rest;
+
+        value |= u64::from(byte & 0x7F).checked_shl(7 * i)?;
+
+        if byte & 0x80 == 0 {
+            return value.try_into().ok();
+        }
     }
 
-    check_fee(tx.tx_weight, tx.fee, context)
+    None
 }
 
 /// Checks the fee is enough for the tx weight and current blockchain state.
 fn check_fee(
     tx_weight: usize,
     fee: u64,
-    context: &BlockchainContext,
+    multiplier: f64,
+    context: &ContextToVerifyBlock,
 ) -> Result<(), RelayRuleError> {
     let base_reward = calculate_block_reward(
         1,
@@ -63,11 +309,19 @@ fn check_fee(
     let fee_per_byte = dynamic_base_fee(base_reward, context.effective_median_weight);
     let needed_fee = usize_to_u64(tx_weight) * fee_per_byte;
 
-    let needed_fee = needed_fee.div_ceil(FEE_MASK) * FEE_MASK;
+    let needed_fee = needed_fee.div_ceil(FEE_QUANTIZATION_MASK) * FEE_QUANTIZATION_MASK;
 
-    if fee < (needed_fee - needed_fee / 50) {
-        tracing::debug!(fee, needed_fee, "Tx fee is below minimum.");
-        return Err(RelayRuleError::FeeBelowMinimum);
+    #[expect(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss,
+        reason = "the multiplier is a config value, the minimum fee does not need to be exact"
+    )]
+    let minimum = ((needed_fee - needed_fee / 50) as f64 * multiplier) as u64;
+
+    if fee < minimum {
+        tracing::debug!(fee, needed_fee, minimum, "Tx fee is below minimum.");
+        return Err(RelayRuleError::FeeBelowMinimum { fee, minimum });
     }
 
     Ok(())
@@ -77,7 +331,8 @@ fn check_fee(
 fn dynamic_base_fee(base_reward: u64, effective_media_block_weight: usize) -> u64 {
     let median_block_weight = effective_media_block_weight as u128;
 
-    let fee_per_byte_100 = u128::from(base_reward) * DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT
+    let fee_per_byte_100 = u128::from(base_reward)
+        * u128::from(DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT)
         / median_block_weight
         / median_block_weight;
     let fee_per_byte = fee_per_byte_100 - fee_per_byte_100 / 20;
@@ -85,3 +340,245 @@ fn dynamic_base_fee(base_reward: u64, effective_media_block_weight: usize) -> u6
     #[expect(clippy::cast_possible_truncation)]
     max(fee_per_byte as u64, 1)
 }
+
+#[cfg(test)]
+mod tests {
+    use monero_serai::transaction::Transaction;
+
+    use cuprate_consensus::transactions::new_tx_verification_data;
+    use cuprate_test_utils::data::{TX_2180A8, TX_3BC7FF, TX_E2D393};
+    use cuprate_types::HardFork;
+
+    use super::*;
+
+    #[test]
+    fn extra_field_tags_standard() {
+        let mut extra = vec![TX_EXTRA_TAG_PUBKEY];
+        extra.extend([1; 32]);
+        extra.extend([TX_EXTRA_NONCE, 3, 0, 0, 0]);
+        extra.extend([TX_EXTRA_TAG_ADDITIONAL_PUBKEYS, 1]);
+        extra.extend([2; 32]);
+        extra.extend([TX_EXTRA_TAG_PADDING, 0, 0]);
+
+        assert_eq!(
+            extra_field_tags(&extra),
+            Some(vec![
+                TX_EXTRA_TAG_PUBKEY,
+                TX_EXTRA_NONCE,
+                TX_EXTRA_TAG_ADDITIONAL_PUBKEYS,
+                TX_EXTRA_TAG_PADDING
+            ])
+        );
+        assert_eq!(extra_field_tags(&[]), Some(vec![]));
+    }
+
+    #[test]
+    fn extra_field_tags_non_standard() {
+        // Unknown tag.
+        assert_eq!(extra_field_tags(&[0x05, 0]), None);
+        // Truncated pubkey.
+        assert_eq!(extra_field_tags(&[TX_EXTRA_TAG_PUBKEY, 1, 2]), None);
+        // Non-zero padding.
+        assert_eq!(extra_field_tags(&[TX_EXTRA_TAG_PADDING, 0, 1]), None);
+        // Nonce longer than the field.
+        assert_eq!(extra_field_tags(&[TX_EXTRA_NONCE, 0x80, 0x01, 0]), None);
+    }
+
+    #[test]
+    fn relay_check_flags() {
+        assert_eq!(
+            RelayRuleError::FeeBelowMinimum { fee: 0, minimum: 1 }.relay_check(),
+            TxRelayChecks::FEE_TOO_LOW
+        );
+        assert_eq!(
+            RelayRuleError::NonStandardExtraField.relay_check(),
+            TxRelayChecks::TX_EXTRA_NOT_ALLOWED
+        );
+        assert_eq!(
+            RelayRuleError::ExtraFieldTagNotAllowed(TX_EXTRA_NONCE).relay_check(),
+            TxRelayChecks::TX_EXTRA_NOT_ALLOWED
+        );
+        assert_eq!(
+            RelayRuleError::TooManyInputs { inputs: 2, max: 1 }.relay_check(),
+            TxRelayChecks::TOO_MANY_INPUTS
+        );
+        assert_eq!(
+            RelayRuleError::TooManyOutputs { outputs: 2, max: 1 }.relay_check(),
+            TxRelayChecks::TOO_MANY_OUTPUTS
+        );
+    }
+
+    fn context() -> ContextToVerifyBlock {
+        ContextToVerifyBlock {
+            median_weight_for_block_reward: 300_000,
+            effective_median_weight: 300_000,
+            top_hash: [0; 32],
+            median_block_timestamp: None,
+            chain_height: 3_000_000,
+            current_hf: HardFork::V16,
+            next_difficulty: 1,
+            already_generated_coins: 18_000_000_000_000_000_000,
+        }
+    }
+
+    fn tx(blob: &[u8]) -> TransactionVerificationData {
+        new_tx_verification_data(Transaction::read(&mut &*blob).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn unlock_time_rule() {
+        assert_eq!(
+            UnlockTimeRule.check(&tx(TX_3BC7FF), &context()),
+            Err(RelayRuleError::NonZeroTimelock)
+        );
+        assert_eq!(UnlockTimeRule.check(&tx(TX_E2D393), &context()), Ok(()));
+    }
+
+    #[test]
+    fn extra_field_rule() {
+        let mut tx = tx(TX_E2D393);
+
+        let mut extra = vec![TX_EXTRA_TAG_PUBKEY];
+        extra.extend([1; 32]);
+        extra.extend([TX_EXTRA_NONCE, 1, 0]);
+        tx.tx.prefix_mut().extra = extra;
+
+        let rule = |max_size, allowed_tags: &[u8]| ExtraFieldRule {
+            max_size,
+            allowed_tags: allowed_tags.to_vec(),
+        };
+
+        assert_eq!(rule(MAX_TX_EXTRA_SIZE, &[]).check(&tx, &context()), Ok(()));
+        assert_eq!(
+            rule(34, &[]).check(&tx, &context()),
+            Err(RelayRuleError::ExtraFieldTooLarge { size: 35, max: 34 })
+        );
+        assert_eq!(
+            rule(MAX_TX_EXTRA_SIZE, &[TX_EXTRA_TAG_PUBKEY, TX_EXTRA_NONCE]).check(&tx, &context()),
+            Ok(())
+        );
+        assert_eq!(
+            rule(MAX_TX_EXTRA_SIZE, &[TX_EXTRA_TAG_PUBKEY]).check(&tx, &context()),
+            Err(RelayRuleError::ExtraFieldTagNotAllowed(TX_EXTRA_NONCE))
+        );
+
+        // Any extra field is allowed if no tags are set.
+        tx.tx.prefix_mut().extra = vec![0x05, 0];
+        assert_eq!(rule(MAX_TX_EXTRA_SIZE, &[]).check(&tx, &context()), Ok(()));
+        assert_eq!(
+            rule(MAX_TX_EXTRA_SIZE, &[TX_EXTRA_TAG_PUBKEY]).check(&tx, &context()),
+            Err(RelayRuleError::NonStandardExtraField)
+        );
+    }
+
+    #[test]
+    fn minimum_fee_rule() {
+        let context = context();
+        let mut tx = tx(TX_E2D393);
+        tx.tx_weight = 1_500;
+
+        let base_reward = calculate_block_reward(
+            1,
+            context.effective_median_weight,
+            context.already_generated_coins,
+            context.current_hf,
+        );
+        let needed_fee = usize_to_u64(tx.tx_weight)
+            * dynamic_base_fee(base_reward, context.effective_median_weight);
+        let needed_fee = needed_fee.div_ceil(FEE_QUANTIZATION_MASK) * FEE_QUANTIZATION_MASK;
+        let minimum = needed_fee - needed_fee / 50;
+
+        let rule = |multiplier| MinimumFeeRule { multiplier };
+
+        tx.fee = minimum;
+        assert_eq!(rule(1.0).check(&tx, &context), Ok(()));
+        assert_eq!(
+            rule(2.0).check(&tx, &context),
+            Err(RelayRuleError::FeeBelowMinimum {
+                fee: minimum,
+                minimum: minimum * 2
+            })
+        );
+
+        tx.fee = minimum - 1;
+        assert_eq!(
+            rule(1.0).check(&tx, &context),
+            Err(RelayRuleError::FeeBelowMinimum {
+                fee: minimum - 1,
+                minimum
+            })
+        );
+
+        tx.fee = 0;
+        assert_eq!(rule(0.0).check(&tx, &context), Ok(()));
+    }
+
+    #[test]
+    fn max_weight_rule() {
+        let tx = tx(TX_E2D393);
+
+        assert_eq!(
+            MaxWeightRule {
+                max_weight: tx.tx_weight
+            }
+            .check(&tx, &context()),
+            Ok(())
+        );
+        assert_eq!(
+            MaxWeightRule {
+                max_weight: tx.tx_weight - 1
+            }
+            .check(&tx, &context()),
+            Err(RelayRuleError::TooBig {
+                weight: tx.tx_weight,
+                max: tx.tx_weight - 1
+            })
+        );
+    }
+
+    #[test]
+    fn input_output_rule() {
+        // 19 inputs, 61 outputs.
+        let tx = tx(TX_2180A8);
+
+        let rule = |max_inputs, max_outputs| InputOutputRule {
+            max_inputs,
+            max_outputs,
+        };
+
+        assert_eq!(rule(0, 0).check(&tx, &context()), Ok(()));
+        assert_eq!(rule(19, 61).check(&tx, &context()), Ok(()));
+        assert_eq!(
+            rule(18, 0).check(&tx, &context()),
+            Err(RelayRuleError::TooManyInputs {
+                inputs: 19,
+                max: 18
+            })
+        );
+        assert_eq!(
+            rule(0, 60).check(&tx, &context()),
+            Err(RelayRuleError::TooManyOutputs {
+                outputs: 61,
+                max: 60
+            })
+        );
+    }
+
+    #[test]
+    fn relay_policy_first_error() {
+        let tx = tx(TX_3BC7FF);
+
+        let policy = RelayPolicy::default()
+            .with_rule(InputOutputRule {
+                max_inputs: 0,
+                max_outputs: 1,
+            })
+            .with_rule(UnlockTimeRule);
+
+        assert_eq!(
+            policy.check(&tx, &context()),
+            Err(RelayRuleError::TooManyOutputs { outputs: 5, max: 1 })
+        );
+        assert_eq!(RelayPolicy::default().check(&tx, &context()), Ok(()));
+    }
+}
@@ -0,0 +1,95 @@
+//! ZMQ
+//!
+//! Contains the ZMQ publisher, which sends messages to subscribers in the same
+//! `<topic>:<JSON>` format as `monerod`'s `--zmq-pub`.
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+use zeromq::{PubSocket, Socket, SocketSend, ZmqMessage};
+
+use cuprate_zmq_types::json_message_types::ReorgAlert;
+
+/// The amount of messages that can be waiting to be published, new messages are dropped when this is reached.
+const MAX_QUEUED_MESSAGES: usize = 64;
+
+/// The topic [`ReorgAlert`]s are published on.
+const REORG_ALERT_TOPIC: &str = "json-full-reorg_alert";
+
+/// A handle to the ZMQ publisher.
+#[derive(Clone)]
+pub struct ZmqPublisher {
+    /// The channel to send messages to the publisher task down.
+    message_tx: mpsc::Sender<String>,
+}
+
+impl ZmqPublisher {
+    /// Publish a [`ReorgAlert`] on the `json-full-reorg_alert` topic.
+    pub fn publish_reorg_alert(&self, alert: &ReorgAlert) {
+        self.publish(REORG_ALERT_TOPIC, alert);
+    }
+
+    /// Publish a message on a topic, the message is dropped if the publisher can't keep up.
+    fn publish(&self, topic: &str, message: &impl Serialize) {
+        let message = format!(
+            "{topic}:{}",
+            serde_json::to_string(message).expect("ZMQ messages can always be serialized")
+        );
+
+        if self.message_tx.try_send(message).is_err() {
+            warn!(
+                topic,
+                "Failed to publish ZMQ message, the publisher is not keeping up."
+            );
+        }
+    }
+}
+
+/// Start the ZMQ publisher, listening for subscribers on `address`.
+///
+/// # Errors
+///
+/// This function will return an error if the publisher could not bind to `address`.
+pub async fn init_zmq_publisher(address: &str) -> Result<ZmqPublisher, anyhow::Error> {
+    let mut socket = PubSocket::new();
+    let endpoint = socket
+        .bind(address)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to bind the ZMQ publisher to {address}: {e}"))?;
+
+    info!(%endpoint, "ZMQ publisher listening");
+
+    let (message_tx, mut message_rx) = mpsc::channel::<String>(MAX_QUEUED_MESSAGES);
+
+    tokio::spawn(async move {
+        while let Some(message) = message_rx.recv().await {
+            if let Err(e) = socket.send(ZmqMessage::from(message)).await {
+                warn!("Failed to publish ZMQ message: {e}");
+            }
+        }
+    });
+
+    Ok(ZmqPublisher { message_tx })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Messages are published as `<topic>:<JSON>`.
+    #[test]
+    fn reorg_alert_message() {
+        let (message_tx, mut message_rx) = mpsc::channel(1);
+        let publisher = ZmqPublisher { message_tx };
+
+        let alert = ReorgAlert {
+            fork_height: 10,
+            depth: 2,
+            ..Default::default()
+        };
+        publisher.publish_reorg_alert(&alert);
+
+        let message = message_rx.try_recv().unwrap();
+        let json = message.strip_prefix("json-full-reorg_alert:").unwrap();
+        assert_eq!(serde_json::from_str::<ReorgAlert>(json).unwrap(), alert);
+    }
+}
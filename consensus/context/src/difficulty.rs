@@ -11,13 +11,16 @@ use std::{collections::VecDeque, ops::Range};
 use tower::ServiceExt;
 use tracing::instrument;
 
-use cuprate_helper::num::median;
+use cuprate_helper::{
+    cast::{u64_to_usize, usize_to_u64},
+    num::median,
+};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
     Chain,
 };
 
-use crate::{ContextCacheError, Database, HardFork};
+use crate::{snapshot::DifficultySnapshot, ContextCacheError, Database, HardFork};
 
 /// The amount of blocks we account for to calculate difficulty
 const DIFFICULTY_WINDOW: usize = 720;
@@ -116,6 +119,48 @@ impl DifficultyCache {
         Ok(diff)
     }
 
+    /// Restore the difficulty cache from a [`DifficultySnapshot`].
+    ///
+    /// Returns [`None`] if the snapshot was taken with a different config.
+    pub(crate) fn from_snapshot(
+        snapshot: DifficultySnapshot,
+        config: DifficultyCacheConfig,
+    ) -> Option<Self> {
+        let snapshot_config = DifficultyCacheConfig {
+            window: u64_to_usize(snapshot.window),
+            cut: u64_to_usize(snapshot.cut),
+            lag: u64_to_usize(snapshot.lag),
+            fixed_difficulty: snapshot.fixed_difficulty,
+        };
+
+        if snapshot_config != config
+            || snapshot.timestamps.len() != snapshot.cumulative_difficulties.len()
+            || snapshot.timestamps.len() > config.total_block_count()
+        {
+            return None;
+        }
+
+        Some(Self {
+            timestamps: snapshot.timestamps.into(),
+            cumulative_difficulties: snapshot.cumulative_difficulties.into(),
+            last_accounted_height: u64_to_usize(snapshot.last_accounted_height),
+            config,
+        })
+    }
+
+    /// Returns a [`DifficultySnapshot`] of the cache.
+    pub(crate) fn snapshot(&self) -> DifficultySnapshot {
+        DifficultySnapshot {
+            window: usize_to_u64(self.config.window),
+            cut: usize_to_u64(self.config.cut),
+            lag: usize_to_u64(self.config.lag),
+            fixed_difficulty: self.config.fixed_difficulty,
+            timestamps: self.timestamps.iter().copied().collect(),
+            cumulative_difficulties: self.cumulative_difficulties.iter().copied().collect(),
+            last_accounted_height: usize_to_u64(self.last_accounted_height),
+        }
+    }
+
     /// Pop some blocks from the top of the cache.
     ///
     /// The cache will be returned to the state it would have been in `numb_blocks` ago.
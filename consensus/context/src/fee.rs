@@ -0,0 +1,247 @@
+//! # Fee Estimation
+//!
+//! This module contains the dynamic fee tiers, as calculated by `monerod`, and an estimate of the fee
+//! needed for a transaction to be included within a target amount of blocks, using the fees of
+//! transactions in the tx-pool and in recent blocks.
+//!
+//! All fees in this module are per byte of transaction weight.
+//!
+use std::collections::VecDeque;
+
+use cuprate_consensus_rules::{
+    blocks::{penalty_free_zone, PENALTY_FREE_ZONE_5},
+    miner_tx::calculate_block_reward,
+    HardFork,
+};
+use cuprate_helper::cast::usize_to_u64;
+
+use crate::{snapshot::RecentFeesSnapshot, weight::BlockWeightsCache};
+
+/// The mask fee estimates are rounded up to a multiple of.
+pub const FEE_QUANTIZATION_MASK: u64 = 10_000;
+
+/// The amount of recent blocks with their fees kept for fee estimation.
+pub const FEE_BLOCKS_WINDOW: usize = 30;
+
+/// The weight of the reference transaction used to scale the fee tiers.
+pub const DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT: u64 = 3_000;
+
+/// Calculates the 4 dynamic fee tiers for the next block, see [`dynamic_fee_tiers`].
+///
+/// The medians are calculated as if the next `grace_blocks` blocks were empty.
+///
+/// ref: `get_dynamic_base_fee_estimate_2021_scaling` in
+/// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/blockchain.cpp>
+pub fn fee_tiers(
+    weight_cache: &BlockWeightsCache,
+    hf: HardFork,
+    already_generated_coins: u64,
+    grace_blocks: usize,
+) -> [u64; 4] {
+    let long_term_median = weight_cache
+        .median_long_term_weight_with_grace(grace_blocks)
+        .max(penalty_free_zone(hf));
+    let short_term_median = weight_cache
+        .median_short_term_weight_with_grace(grace_blocks)
+        .clamp(long_term_median, 50 * long_term_median);
+
+    let base_reward = calculate_block_reward(
+        0,
+        weight_cache.median_for_block_reward(hf),
+        already_generated_coins,
+        hf,
+    );
+
+    dynamic_fee_tiers(base_reward, short_term_median, long_term_median)
+}
+
+/// Calculates the 4 dynamic fee tiers: low, normal, elevated and priority.
+///
+/// `short_term_median` and `long_term_median` are the median block weights, they must not be below
+/// the penalty free zone and `short_term_median` must not be below `long_term_median`.
+///
+/// ref: `get_dynamic_base_fee_estimate_2021_scaling` in
+/// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/blockchain.cpp>
+pub fn dynamic_fee_tiers(
+    base_reward: u64,
+    short_term_median: usize,
+    long_term_median: usize,
+) -> [u64; 4] {
+    let base_reward = u128::from(base_reward);
+    let reference_weight = u128::from(DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT);
+    let penalty_free_zone = u128::from(usize_to_u64(PENALTY_FREE_ZONE_5));
+
+    let short_term_median = u128::from(usize_to_u64(short_term_median));
+    let long_term_median = u128::from(usize_to_u64(long_term_median));
+    let fee_median = short_term_median.min(long_term_median);
+
+    let low = base_reward * reference_weight / fee_median / fee_median;
+    let normal = 4 * low;
+    let elevated = 16 * base_reward * reference_weight / penalty_free_zone / fee_median;
+    // `monerod` truncates `32 * 3000 * Mnw / Mlw` before dividing by it, keep the same operation order.
+    let priority = (4 * elevated).max(
+        4 * elevated * fee_median / (32 * reference_weight * short_term_median / long_term_median),
+    );
+
+    [low, normal, elevated, priority]
+        .map(|fee| quantize_fee(u64::try_from(fee).unwrap_or(u64::MAX)))
+}
+
+/// Rounds a fee up to a multiple of [`FEE_QUANTIZATION_MASK`].
+pub const fn quantize_fee(fee: u64) -> u64 {
+    fee.div_ceil(FEE_QUANTIZATION_MASK)
+        .saturating_mul(FEE_QUANTIZATION_MASK)
+}
+
+/// Returns the fee per byte of a transaction.
+const fn fee_per_byte(fee: u64, weight: usize) -> u64 {
+    if weight == 0 {
+        return fee;
+    }
+
+    fee / usize_to_u64(weight)
+}
+
+/// The fees paid by transactions in recent blocks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecentBlockFees {
+    /// The fee per byte deciles of the last [`FEE_BLOCKS_WINDOW`] blocks.
+    ///
+    /// This is [`None`] for blocks which had space left for another transaction.
+    blocks: VecDeque<Option<[u64; 10]>>,
+}
+
+impl RecentBlockFees {
+    /// Restore the fees from a [`RecentFeesSnapshot`].
+    ///
+    /// Returns [`None`] if the snapshot has more than [`FEE_BLOCKS_WINDOW`] blocks.
+    pub(crate) fn from_snapshot(snapshot: RecentFeesSnapshot) -> Option<Self> {
+        if snapshot.blocks.len() > FEE_BLOCKS_WINDOW {
+            return None;
+        }
+
+        Some(Self {
+            blocks: snapshot.blocks.into(),
+        })
+    }
+
+    /// Returns a [`RecentFeesSnapshot`] of the fees.
+    pub(crate) fn snapshot(&self) -> RecentFeesSnapshot {
+        RecentFeesSnapshot {
+            blocks: self.blocks.iter().copied().collect(),
+        }
+    }
+
+    /// Add a new block's fees.
+    ///
+    /// `tx_fees` are the fee and weight of each of the block's transactions, `effective_median` is the
+    /// effective median weight the block was added with.
+    pub fn new_block(
+        &mut self,
+        tx_fees: &[(u64, usize)],
+        block_weight: usize,
+        effective_median: usize,
+    ) {
+        if self.blocks.len() >= FEE_BLOCKS_WINDOW {
+            self.blocks.pop_front();
+        }
+
+        let full = usize_to_u64(block_weight) + DYNAMIC_FEE_REFERENCE_TRANSACTION_WEIGHT
+            > usize_to_u64(effective_median);
+
+        if !full || tx_fees.is_empty() {
+            self.blocks.push_back(None);
+            return;
+        }
+
+        let mut fees = tx_fees
+            .iter()
+            .map(|&(fee, weight)| fee_per_byte(fee, weight))
+            .collect::<Vec<_>>();
+        fees.sort_unstable();
+
+        let deciles = std::array::from_fn(|i| fees[i * fees.len() / 10]);
+        self.blocks.push_back(Some(deciles));
+    }
+
+    /// Remove the fees of the top `numb_blocks` blocks.
+    pub fn pop_blocks(&mut self, numb_blocks: usize) {
+        let len = self.blocks.len().saturating_sub(numb_blocks);
+        self.blocks.truncate(len);
+    }
+
+    /// Returns the fee per byte that would have been needed to be included within `target_blocks` blocks
+    /// in recent blocks.
+    ///
+    /// This is `0` if most recent blocks had space left for another transaction.
+    pub fn estimate(&self, target_blocks: usize) -> u64 {
+        // A target of 1 block needs to beat half the transactions in a full block, a target of
+        // 10 or more blocks only the cheapest.
+        let decile = 10 / (target_blocks.max(1) + 1);
+
+        let mut fees = self
+            .blocks
+            .iter()
+            .flatten()
+            .map(|deciles| deciles[decile])
+            .collect::<Vec<_>>();
+
+        if fees.len() * 2 <= self.blocks.len() {
+            return 0;
+        }
+
+        fees.sort_unstable();
+        fees[fees.len() / 2]
+    }
+}
+
+/// Returns the fee per byte needed to be included within `target_blocks` blocks of `block_capacity`
+/// weight, ahead of the transactions in the tx-pool.
+///
+/// `pool` is the fee and weight of each transaction in the tx-pool.
+///
+/// This is `0` if all the transactions in the tx-pool fit in the blocks.
+pub fn pool_fee_estimate(
+    target_blocks: usize,
+    block_capacity: usize,
+    pool: &[(u64, usize)],
+) -> u64 {
+    let mut fees = pool
+        .iter()
+        .map(|&(fee, weight)| (fee_per_byte(fee, weight), weight))
+        .collect::<Vec<_>>();
+    fees.sort_unstable_by(|a, b| b.0.cmp(&a.0));
+
+    let capacity = target_blocks.saturating_mul(block_capacity);
+    let mut total_weight = 0_usize;
+
+    for (fee, weight) in fees {
+        total_weight = total_weight.saturating_add(weight);
+
+        if total_weight > capacity {
+            return fee.saturating_add(1);
+        }
+    }
+
+    0
+}
+
+/// Returns the fee per byte needed for a transaction to be included within `target_blocks` blocks.
+///
+/// This is the highest of the low fee tier, [`pool_fee_estimate`] and [`RecentBlockFees::estimate`],
+/// rounded up with [`quantize_fee`].
+pub fn target_fee_estimate(
+    target_blocks: usize,
+    block_capacity: usize,
+    pool: &[(u64, usize)],
+    recent_fees: &RecentBlockFees,
+    fee_tiers: &[u64; 4],
+) -> u64 {
+    let target_blocks = target_blocks.max(1);
+
+    let fee = fee_tiers[0]
+        .max(pool_fee_estimate(target_blocks, block_capacity, pool))
+        .max(recent_fees.estimate(target_blocks));
+
+    quantize_fee(fee)
+}
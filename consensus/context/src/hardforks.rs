@@ -4,12 +4,13 @@ use tower::ServiceExt;
 use tracing::instrument;
 
 use cuprate_consensus_rules::{HFVotes, HFsInfo, HardFork};
+use cuprate_helper::cast::{u64_to_usize, usize_to_u64};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
     Chain,
 };
 
-use crate::{ContextCacheError, Database};
+use crate::{snapshot::HardForkSnapshot, ContextCacheError, Database};
 
 /// The default amount of hard-fork votes to track to decide on activation of a hard-fork.
 ///
@@ -115,6 +116,46 @@ impl HardForkState {
         Ok(hfs)
     }
 
+    /// Restore the hard-fork state from a [`HardForkSnapshot`].
+    ///
+    /// Returns [`None`] if the snapshot was taken with a different config or is invalid.
+    pub(crate) fn from_snapshot(
+        snapshot: HardForkSnapshot,
+        config: HardForkConfig,
+    ) -> Option<Self> {
+        if u64_to_usize(snapshot.window) != config.window || snapshot.votes.len() > config.window {
+            return None;
+        }
+
+        let mut votes = HFVotes::new(config.window);
+        for vote in snapshot.votes {
+            votes.add_vote_for_hf(&HardFork::from_version(vote).ok()?);
+        }
+
+        Some(Self {
+            current_hardfork: HardFork::from_version(snapshot.current_hardfork).ok()?,
+            config,
+            votes,
+            last_height: u64_to_usize(snapshot.last_height),
+        })
+    }
+
+    /// Returns a [`HardForkSnapshot`] of the state.
+    pub(crate) fn snapshot(&self) -> HardForkSnapshot {
+        HardForkSnapshot {
+            window: usize_to_u64(self.config.window),
+            current_hardfork: self.current_hardfork.as_u8(),
+            votes: self
+                .votes
+                .vote_list()
+                .iter()
+                .copied()
+                .map(HardFork::as_u8)
+                .collect(),
+            last_height: usize_to_u64(self.last_height),
+        }
+    }
+
     /// Pop some blocks from the top of the cache.
     ///
     /// The cache will be returned to the state it would have been in `numb_blocks` ago.
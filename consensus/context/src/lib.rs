@@ -29,8 +29,10 @@ use cuprate_consensus_rules::{
 };
 
 pub mod difficulty;
+pub mod fee;
 pub mod hardforks;
 pub mod rx_vms;
+pub mod snapshot;
 pub mod weight;
 
 mod alt_chains;
@@ -42,6 +44,7 @@ use cuprate_types::{
 };
 use difficulty::DifficultyCache;
 use rx_vms::RandomXVm;
+use snapshot::ContextSnapshot;
 use weight::BlockWeightsCache;
 
 pub use alt_chains::{sealed::AltChainRequestToken, AltChainContextCache};
@@ -103,7 +106,23 @@ where
     D: Database + Clone + Send + Sync + 'static,
     D::Future: Send + 'static,
 {
-    let (context_task, context_cache) = task::ContextTask::init_context(cfg, database).await?;
+    initialize_blockchain_context_with_snapshot(cfg, database, None).await
+}
+
+/// Initialize the blockchain context service, restoring the caches from `snapshot`.
+///
+/// If `snapshot` is [`None`] or stale, see [`snapshot`], this is the same as [`initialize_blockchain_context`].
+pub async fn initialize_blockchain_context_with_snapshot<D>(
+    cfg: ContextConfig,
+    database: D,
+    snapshot: Option<ContextSnapshot>,
+) -> Result<BlockchainContextService, ContextCacheError>
+where
+    D: Database + Clone + Send + Sync + 'static,
+    D::Future: Send + 'static,
+{
+    let (context_task, context_cache) =
+        task::ContextTask::init_context(cfg, database, snapshot).await?;
 
     // TODO: make buffer size configurable.
     let (tx, rx) = mpsc::channel(15);
@@ -190,6 +209,8 @@ pub struct NewBlockData {
     pub vote: HardFork,
     /// The cumulative difficulty of the chain.
     pub cumulative_difficulty: u128,
+    /// The fee and weight of each of the blocks transactions.
+    pub tx_fees: Vec<(u64, usize)>,
 }
 
 /// A request to the blockchain context cache.
@@ -231,11 +252,27 @@ pub enum BlockChainContextRequest {
 
     /// Get the current fee estimate.
     FeeEstimate {
-        /// TODO
+        /// The amount of blocks the estimate should stay valid for.
+        ///
+        /// This many of the oldest blocks in the short term weight window are replaced
+        /// with blocks at the penalty free zone weight.
         grace_blocks: u64,
     },
 
+    /// Get the fee per byte needed for a transaction to be included within a target amount of blocks.
+    ///
+    /// See [`fee::target_fee_estimate`].
+    TargetFeeEstimate {
+        /// The amount of blocks the transaction should be included within.
+        target_blocks: usize,
+        /// The fee and weight of each transaction in the tx-pool.
+        pool: Vec<(u64, usize)>,
+    },
+
     /// Calculate proof-of-work for this block.
+    ///
+    /// After hard-fork 12 this will return an error if there is no cached RandomX VM for
+    /// `seed_hash`, see [`BlockChainContextRequest::CachedRxVm`].
     CalculatePow {
         /// The hardfork of the protocol at this block height.
         hardfork: HardFork,
@@ -250,12 +287,24 @@ pub enum BlockChainContextRequest {
         seed_hash: [u8; 32],
     },
 
+    /// Get a cached RandomX VM for this seed hash.
+    ///
+    /// VMs are never created by this request, as that would block the context task for seconds,
+    /// the caller must create a VM itself if none is cached.
+    CachedRxVm {
+        /// The seed hash of the VM.
+        seed_hash: [u8; 32],
+    },
+
     /// Clear the alt chain context caches.
     ClearAltCache,
 
     /// Get information on all the current alternate chains.
     AltChains,
 
+    /// Take a snapshot of the context caches.
+    Snapshot,
+
     //----------------------------------------------------------------------------------------------------------- AltChainRequests
     /// A request for an alt chain context cache.
     ///
@@ -342,14 +391,27 @@ pub enum BlockChainContextResponse {
     /// Response to [`BlockChainContextRequest::FeeEstimate`]
     FeeEstimate(FeeEstimate),
 
+    /// Response to [`BlockChainContextRequest::TargetFeeEstimate`]
+    ///
+    /// The inner value is the fee per byte.
+    TargetFeeEstimate(u64),
+
     /// Response to [`BlockChainContextRequest::CalculatePow`]
     CalculatePow([u8; 32]),
 
+    /// Response to [`BlockChainContextRequest::CachedRxVm`]
+    ///
+    /// This will be [`None`] if no VM is cached for the seed hash.
+    CachedRxVm(Option<Arc<RandomXVm>>),
+
     /// Response to [`BlockChainContextRequest::AltChains`]
     ///
     /// If the inner [`Vec::is_empty`], there were no alternate chains.
     AltChains(Vec<ChainInfo>),
 
+    /// Response to [`BlockChainContextRequest::Snapshot`]
+    Snapshot(Box<ContextSnapshot>),
+
     /// An alt chain context cache.
     AltChainContextCache(Box<AltChainContextCache>),
 
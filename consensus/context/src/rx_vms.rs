@@ -129,6 +129,22 @@ impl RandomXVmCache {
         self.cached_vm.replace(vm);
     }
 
+    /// Returns a cached RandomX VM created with `seed_hash`, if we have one.
+    pub fn get_vm_for_seed(&self, seed_hash: &[u8; 32]) -> Option<Arc<RandomXVm>> {
+        for (vm_main_chain_height, vm_seed_hash) in &self.seeds {
+            if vm_seed_hash == seed_hash {
+                if let Some(vm) = self.vms.get(vm_main_chain_height) {
+                    return Some(Arc::clone(vm));
+                }
+            }
+        }
+
+        self.cached_vm
+            .as_ref()
+            .filter(|(cached_hash, _)| cached_hash == seed_hash)
+            .map(|(_, vm)| Arc::clone(vm))
+    }
+
     /// Creates a RX VM for an alt chain, looking at the main chain RX VMs to see if we can use one
     /// of them first.
     pub async fn get_alt_vm<D: Database>(
@@ -0,0 +1,184 @@
+//! Context Cache Snapshots
+//!
+//! Initializing the context caches reads large windows of blocks from the database, a [`ContextSnapshot`]
+//! holds the data in the difficulty, weight and hard-fork caches and the recent block fees so they can be
+//! restored without the database, e.g. after a restart.
+//!
+//! A snapshot is taken with [`BlockChainContextRequest::Snapshot`](crate::BlockChainContextRequest::Snapshot)
+//! and used with [`initialize_blockchain_context_with_snapshot`](crate::initialize_blockchain_context_with_snapshot).
+//! It is only used if it was taken at the current top block with the same config, otherwise the caches
+//! are rebuilt from the database.
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use cuprate_helper::cast::{u64_to_usize, usize_to_u64};
+
+use crate::{
+    difficulty::DifficultyCache, fee::RecentBlockFees, hardforks::HardForkState,
+    weight::BlockWeightsCache, BlockWeightsCacheConfig, DifficultyCacheConfig, HardForkConfig,
+};
+
+/// The version of the [`ContextSnapshot`] format, snapshots with a different version are not used.
+pub const CONTEXT_SNAPSHOT_VERSION: u8 = 2;
+
+/// A snapshot of the main-chain context caches.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ContextSnapshot {
+    /// The hash of the top block when the snapshot was taken.
+    top_block_hash: [u8; 32],
+    /// The chain height when the snapshot was taken.
+    chain_height: u64,
+
+    /// The difficulty cache.
+    difficulty: DifficultySnapshot,
+    /// The block weights cache.
+    weights: WeightsSnapshot,
+    /// The hard-fork state.
+    hard_forks: HardForkSnapshot,
+    /// The fees paid in recent blocks.
+    recent_fees: RecentFeesSnapshot,
+}
+
+/// The data in a [`DifficultyCache`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct DifficultySnapshot {
+    /// [`DifficultyCacheConfig::window`].
+    pub(crate) window: u64,
+    /// [`DifficultyCacheConfig::cut`].
+    pub(crate) cut: u64,
+    /// [`DifficultyCacheConfig::lag`].
+    pub(crate) lag: u64,
+    /// [`DifficultyCacheConfig::fixed_difficulty`].
+    pub(crate) fixed_difficulty: Option<u128>,
+
+    /// [`DifficultyCache::timestamps`].
+    pub(crate) timestamps: Vec<u64>,
+    /// [`DifficultyCache::cumulative_difficulties`].
+    pub(crate) cumulative_difficulties: Vec<u128>,
+    /// [`DifficultyCache::last_accounted_height`].
+    pub(crate) last_accounted_height: u64,
+}
+
+/// The data in a [`BlockWeightsCache`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct WeightsSnapshot {
+    /// The short term window length.
+    pub(crate) short_term_window: u64,
+    /// The long term window length.
+    pub(crate) long_term_window: u64,
+
+    /// The short term block weights, oldest first.
+    pub(crate) short_term_block_weights: Vec<u64>,
+    /// The long term block weights, oldest first.
+    pub(crate) long_term_weights: Vec<u64>,
+    /// The height of the top block.
+    pub(crate) tip_height: u64,
+}
+
+/// The data in a [`HardForkState`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct HardForkSnapshot {
+    /// [`HardForkConfig::window`].
+    pub(crate) window: u64,
+
+    /// [`HardForkState::current_hardfork`].
+    pub(crate) current_hardfork: u8,
+    /// The votes in the window, oldest first.
+    pub(crate) votes: Vec<u8>,
+    /// [`HardForkState::last_height`].
+    pub(crate) last_height: u64,
+}
+
+/// The data in a [`RecentBlockFees`].
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub(crate) struct RecentFeesSnapshot {
+    /// The fee per byte deciles of each block, oldest first.
+    pub(crate) blocks: Vec<Option<[u64; 10]>>,
+}
+
+impl ContextSnapshot {
+    /// Take a snapshot of the main-chain context caches.
+    pub(crate) fn new(
+        top_block_hash: [u8; 32],
+        chain_height: usize,
+        difficulty_cache: &DifficultyCache,
+        weight_cache: &BlockWeightsCache,
+        hardfork_state: &HardForkState,
+        recent_fees: &RecentBlockFees,
+    ) -> Self {
+        Self {
+            top_block_hash,
+            chain_height: usize_to_u64(chain_height),
+            difficulty: difficulty_cache.snapshot(),
+            weights: weight_cache.snapshot(),
+            hard_forks: hardfork_state.snapshot(),
+            recent_fees: recent_fees.snapshot(),
+        }
+    }
+
+    /// The hash of the top block when this snapshot was taken.
+    pub const fn top_block_hash(&self) -> [u8; 32] {
+        self.top_block_hash
+    }
+
+    /// The chain height when this snapshot was taken.
+    pub fn chain_height(&self) -> usize {
+        u64_to_usize(self.chain_height)
+    }
+
+    /// Serialize the snapshot, the bytes start with [`CONTEXT_SNAPSHOT_VERSION`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![CONTEXT_SNAPSHOT_VERSION];
+        borsh::to_writer(&mut bytes, self).unwrap();
+        bytes
+    }
+
+    /// Deserialize a snapshot serialized with [`ContextSnapshot::to_bytes`].
+    ///
+    /// # Errors
+    /// This returns an error if the snapshot has a different version or is invalid.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        match bytes.split_first() {
+            Some((&CONTEXT_SNAPSHOT_VERSION, snapshot)) => borsh::from_slice(snapshot),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unsupported context snapshot version",
+            )),
+        }
+    }
+
+    /// Restore the caches from this snapshot.
+    ///
+    /// Returns [`None`] if the snapshot is stale, i.e. it was not taken at `top_block_hash`
+    /// or with the same config.
+    pub(crate) fn restore(
+        self,
+        chain_height: usize,
+        top_block_hash: [u8; 32],
+        difficulty_cfg: DifficultyCacheConfig,
+        weights_config: BlockWeightsCacheConfig,
+        hard_fork_cfg: HardForkConfig,
+    ) -> Option<(
+        DifficultyCache,
+        BlockWeightsCache,
+        HardForkState,
+        RecentBlockFees,
+    )> {
+        if self.chain_height() != chain_height || self.top_block_hash != top_block_hash {
+            return None;
+        }
+
+        let top_height = chain_height - 1;
+
+        let difficulty_cache = DifficultyCache::from_snapshot(self.difficulty, difficulty_cfg)
+            .filter(|cache| cache.last_accounted_height == top_height)?;
+        let weight_cache = BlockWeightsCache::from_snapshot(self.weights, weights_config)
+            .filter(|cache| cache.tip_height == top_height)?;
+        let hardfork_state = HardForkState::from_snapshot(self.hard_forks, hard_fork_cfg)
+            .filter(|state| state.last_height == top_height)?;
+        let recent_fees = RecentBlockFees::from_snapshot(self.recent_fees)?;
+
+        Some((difficulty_cache, weight_cache, hardfork_state, recent_fees))
+    }
+}
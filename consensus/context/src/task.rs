@@ -11,18 +11,21 @@ use tokio::sync::mpsc;
 use tower::ServiceExt;
 use tracing::Instrument;
 
-use cuprate_consensus_rules::blocks::ContextToVerifyBlock;
-use cuprate_helper::cast::u64_to_usize;
+use cuprate_consensus_rules::blocks::{calculate_pow_hash, ContextToVerifyBlock};
+use cuprate_helper::{asynch::rayon_spawn_async, cast::u64_to_usize};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
+    rpc::FeeEstimate,
     Chain, HardFork,
 };
 
 use crate::{
     alt_chains::{get_alt_chain_difficulty_cache, get_alt_chain_weight_cache, AltChainMap},
     difficulty::DifficultyCache,
+    fee::{self, RecentBlockFees, FEE_QUANTIZATION_MASK},
     hardforks::HardForkState,
     rx_vms,
+    snapshot::ContextSnapshot,
     weight::BlockWeightsCache,
     BlockChainContextRequest, BlockChainContextResponse, BlockchainContext, ContextCacheError,
     ContextConfig, Database, BLOCKCHAIN_TIMESTAMP_CHECK_WINDOW,
@@ -50,6 +53,9 @@ pub(crate) struct ContextTask<D: Database> {
     rx_vm_cache: rx_vms::RandomXVmCache,
     /// The hard-fork state cache.
     hardfork_state: HardForkState,
+    /// The fees paid in recent blocks, this starts empty, unless restored from a snapshot, and is
+    /// filled as new blocks are added.
+    recent_fees: RecentBlockFees,
 
     alt_chain_cache_map: AltChainMap,
 
@@ -66,9 +72,13 @@ pub(crate) struct ContextTask<D: Database> {
 impl<D: Database + Clone + Send + 'static> ContextTask<D> {
     /// Initialize the [`ContextTask`], this will need to pull a lot of data from the database so may take a
     /// while to complete.
+    ///
+    /// If `snapshot` is not stale the difficulty, weight and hard-fork caches and the recent block fees are
+    /// restored from it instead.
     pub(crate) async fn init_context(
         cfg: ContextConfig,
         mut database: D,
+        snapshot: Option<ContextSnapshot>,
     ) -> Result<(Self, Arc<ArcSwap<BlockchainContext>>), ContextCacheError> {
         let ContextConfig {
             difficulty_cfg,
@@ -96,34 +106,90 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
             panic!("Database sent incorrect response!");
         };
 
-        let db = database.clone();
-        let hardfork_state_handle = tokio::spawn(async move {
-            HardForkState::init_from_chain_height(chain_height, hard_fork_cfg, db).await
-        });
-
-        let db = database.clone();
-        let difficulty_cache_handle = tokio::spawn(async move {
-            DifficultyCache::init_from_chain_height(chain_height, difficulty_cfg, db, Chain::Main)
-                .await
-        });
+        let restored = snapshot.and_then(|snapshot| {
+            let restored = snapshot.restore(
+                chain_height,
+                top_block_hash,
+                difficulty_cfg,
+                weights_config,
+                hard_fork_cfg,
+            );
+
+            if restored.is_none() {
+                tracing::info!("Context cache snapshot is stale, rebuilding caches.");
+            }
 
-        let db = database.clone();
-        let weight_cache_handle = tokio::spawn(async move {
-            BlockWeightsCache::init_from_chain_height(chain_height, weights_config, db, Chain::Main)
-                .await
+            restored
         });
 
-        // Wait for the hardfork state to finish first as we need it to start the randomX VM cache.
-        let hardfork_state = hardfork_state_handle.await.unwrap()?;
-        let current_hf = hardfork_state.current_hardfork();
+        let (difficulty_cache, weight_cache, hardfork_state, recent_fees, rx_vm_cache) =
+            if let Some((difficulty_cache, weight_cache, hardfork_state, recent_fees)) = restored {
+                tracing::info!(
+                    "Restored context caches from snapshot, chain-height: {chain_height}"
+                );
 
-        let db = database.clone();
-        let rx_seed_handle = tokio::spawn(async move {
-            rx_vms::RandomXVmCache::init_from_chain_height(chain_height, &current_hf, db).await
-        });
+                let rx_vm_cache = rx_vms::RandomXVmCache::init_from_chain_height(
+                    chain_height,
+                    &hardfork_state.current_hardfork(),
+                    database.clone(),
+                )
+                .await?;
+
+                (
+                    difficulty_cache,
+                    weight_cache,
+                    hardfork_state,
+                    recent_fees,
+                    rx_vm_cache,
+                )
+            } else {
+                let db = database.clone();
+                let hardfork_state_handle = tokio::spawn(async move {
+                    HardForkState::init_from_chain_height(chain_height, hard_fork_cfg, db).await
+                });
+
+                let db = database.clone();
+                let difficulty_cache_handle = tokio::spawn(async move {
+                    DifficultyCache::init_from_chain_height(
+                        chain_height,
+                        difficulty_cfg,
+                        db,
+                        Chain::Main,
+                    )
+                    .await
+                });
+
+                let db = database.clone();
+                let weight_cache_handle = tokio::spawn(async move {
+                    BlockWeightsCache::init_from_chain_height(
+                        chain_height,
+                        weights_config,
+                        db,
+                        Chain::Main,
+                    )
+                    .await
+                });
+
+                // Wait for the hardfork state to finish first as we need it to start the randomX VM cache.
+                let hardfork_state = hardfork_state_handle.await.unwrap()?;
+                let current_hf = hardfork_state.current_hardfork();
+
+                let db = database.clone();
+                let rx_seed_handle = tokio::spawn(async move {
+                    rx_vms::RandomXVmCache::init_from_chain_height(chain_height, &current_hf, db)
+                        .await
+                });
+
+                (
+                    difficulty_cache_handle.await.unwrap()?,
+                    weight_cache_handle.await.unwrap()?,
+                    hardfork_state,
+                    RecentBlockFees::default(),
+                    rx_seed_handle.await.unwrap()?,
+                )
+            };
 
-        let difficulty_cache = difficulty_cache_handle.await.unwrap()?;
-        let weight_cache = weight_cache_handle.await.unwrap()?;
+        let current_hf = hardfork_state.current_hardfork();
 
         let blockchain_context = blockchain_context(
             &weight_cache,
@@ -140,8 +206,9 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
             context_cache: Arc::clone(&context_cache),
             difficulty_cache,
             weight_cache,
-            rx_vm_cache: rx_seed_handle.await.unwrap()?,
+            rx_vm_cache,
             hardfork_state,
+            recent_fees,
             alt_chain_cache_map: AltChainMap::new(),
             chain_height,
             already_generated_coins,
@@ -165,6 +232,16 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
         self.context_cache.store(Arc::new(context));
     }
 
+    /// Returns the dynamic fee tiers, see [`fee::fee_tiers`].
+    fn fee_tiers(&self, grace_blocks: usize) -> [u64; 4] {
+        fee::fee_tiers(
+            &self.weight_cache,
+            self.hardfork_state.current_hardfork(),
+            self.already_generated_coins,
+            grace_blocks,
+        )
+    }
+
     /// Handles a [`BlockChainContextRequest`] and returns a [`BlockChainContextResponse`].
     pub(crate) async fn handle_req(
         &mut self,
@@ -200,6 +277,13 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
                     new.cumulative_difficulty,
                 );
 
+                self.recent_fees.new_block(
+                    &new.tx_fees,
+                    new.weight,
+                    self.weight_cache
+                        .effective_median_block_weight(self.hardfork_state.current_hardfork()),
+                );
+
                 self.weight_cache
                     .new_block(new.height, new.weight, new.long_term_weight);
 
@@ -226,6 +310,7 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
                 self.weight_cache
                     .pop_blocks_main_chain(numb_blocks, self.database.clone())
                     .await?;
+                self.recent_fees.pop_blocks(numb_blocks);
                 self.rx_vm_cache
                     .pop_blocks_main_chain(self.chain_height - numb_blocks - 1);
                 self.hardfork_state
@@ -307,10 +392,74 @@ impl<D: Database + Clone + Send + 'static> ContextTask<D> {
                 self.alt_chain_cache_map.add_alt_cache(cache);
                 BlockChainContextResponse::Ok
             }
-            BlockChainContextRequest::HardForkInfo(_)
-            | BlockChainContextRequest::FeeEstimate { .. }
-            | BlockChainContextRequest::AltChains
-            | BlockChainContextRequest::CalculatePow { .. } => {
+            BlockChainContextRequest::CalculatePow {
+                hardfork,
+                height,
+                block,
+                seed_hash,
+            } => {
+                // Creating a VM takes seconds, so only cached VMs are used to not block this task.
+                let vm = if hardfork >= HardFork::V12 {
+                    Some(
+                        self.rx_vm_cache
+                            .get_vm_for_seed(&seed_hash)
+                            .ok_or("No cached RandomX VM for seed hash")?,
+                    )
+                } else {
+                    None
+                };
+
+                let pow_hash = rayon_spawn_async(move || {
+                    calculate_pow_hash(
+                        vm.as_deref(),
+                        &block.serialize_pow_hash(),
+                        height,
+                        &hardfork,
+                    )
+                })
+                .await?;
+
+                BlockChainContextResponse::CalculatePow(pow_hash)
+            }
+            BlockChainContextRequest::CachedRxVm { seed_hash } => {
+                BlockChainContextResponse::CachedRxVm(self.rx_vm_cache.get_vm_for_seed(&seed_hash))
+            }
+            BlockChainContextRequest::Snapshot => {
+                BlockChainContextResponse::Snapshot(Box::new(ContextSnapshot::new(
+                    self.top_block_hash,
+                    self.chain_height,
+                    &self.difficulty_cache,
+                    &self.weight_cache,
+                    &self.hardfork_state,
+                    &self.recent_fees,
+                )))
+            }
+            BlockChainContextRequest::FeeEstimate { grace_blocks } => {
+                let fees = self.fee_tiers(u64_to_usize(grace_blocks));
+
+                BlockChainContextResponse::FeeEstimate(FeeEstimate {
+                    fee: fees[0],
+                    fees: fees.to_vec(),
+                    quantization_mask: FEE_QUANTIZATION_MASK,
+                })
+            }
+            BlockChainContextRequest::TargetFeeEstimate {
+                target_blocks,
+                pool,
+            } => {
+                let block_capacity = self
+                    .weight_cache
+                    .effective_median_block_weight(self.hardfork_state.current_hardfork());
+
+                BlockChainContextResponse::TargetFeeEstimate(fee::target_fee_estimate(
+                    target_blocks,
+                    block_capacity,
+                    &pool,
+                    &self.recent_fees,
+                    &self.fee_tiers(0),
+                ))
+            }
+            BlockChainContextRequest::HardForkInfo(_) | BlockChainContextRequest::AltChains => {
                 todo!("finish https://github.com/Cuprate/cuprate/pull/297")
             }
         })
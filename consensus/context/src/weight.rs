@@ -15,13 +15,17 @@ use tower::ServiceExt;
 use tracing::instrument;
 
 use cuprate_consensus_rules::blocks::{penalty_free_zone, PENALTY_FREE_ZONE_5};
-use cuprate_helper::{asynch::rayon_spawn_async, num::RollingMedian};
+use cuprate_helper::{
+    asynch::rayon_spawn_async,
+    cast::{u64_to_usize, usize_to_u64},
+    num::{median, RollingMedian},
+};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
     Chain,
 };
 
-use crate::{ContextCacheError, Database, HardFork};
+use crate::{snapshot::WeightsSnapshot, ContextCacheError, Database, HardFork};
 
 /// The short term block weight window.
 pub const SHORT_TERM_WINDOW: usize = 100;
@@ -113,6 +117,56 @@ impl BlockWeightsCache {
         })
     }
 
+    /// Restore the weight cache from a [`WeightsSnapshot`].
+    ///
+    /// Returns [`None`] if the snapshot was taken with a different config.
+    pub(crate) fn from_snapshot(
+        snapshot: WeightsSnapshot,
+        config: BlockWeightsCacheConfig,
+    ) -> Option<Self> {
+        let snapshot_config = BlockWeightsCacheConfig::new(
+            u64_to_usize(snapshot.short_term_window),
+            u64_to_usize(snapshot.long_term_window),
+        );
+
+        if snapshot_config != config
+            || snapshot.short_term_block_weights.len() > config.short_term_window
+            || snapshot.long_term_weights.len() > config.long_term_window
+        {
+            return None;
+        }
+
+        let into_usize = |weights: Vec<u64>| weights.into_iter().map(u64_to_usize).collect();
+
+        Some(Self {
+            short_term_block_weights: RollingMedian::from_vec(
+                into_usize(snapshot.short_term_block_weights),
+                config.short_term_window,
+            ),
+            long_term_weights: RollingMedian::from_vec(
+                into_usize(snapshot.long_term_weights),
+                config.long_term_window,
+            ),
+            tip_height: u64_to_usize(snapshot.tip_height),
+            config,
+        })
+    }
+
+    /// Returns a [`WeightsSnapshot`] of the cache.
+    pub(crate) fn snapshot(&self) -> WeightsSnapshot {
+        let into_u64 = |weights: &RollingMedian<usize>| {
+            weights.window().iter().copied().map(usize_to_u64).collect()
+        };
+
+        WeightsSnapshot {
+            short_term_window: usize_to_u64(self.config.short_term_window),
+            long_term_window: usize_to_u64(self.config.long_term_window),
+            short_term_block_weights: into_u64(&self.short_term_block_weights),
+            long_term_weights: into_u64(&self.long_term_weights),
+            tip_height: usize_to_u64(self.tip_height),
+        }
+    }
+
     /// Pop some blocks from the top of the cache.
     ///
     /// The cache will be returned to the state it would have been in `numb_blocks` ago.
@@ -205,6 +259,44 @@ impl BlockWeightsCache {
         self.short_term_block_weights.median()
     }
 
+    /// Returns the median long term weight over the newest [`LONG_TERM_WINDOW`] `- grace_blocks` blocks,
+    /// padded with `grace_blocks` blocks with a weight of `0`.
+    ///
+    /// `grace_blocks` is clamped to the short term window, as in `monerod`.
+    ///
+    /// ref: `get_dynamic_base_fee_estimate_2021_scaling` in
+    /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/blockchain.cpp>
+    pub fn median_long_term_weight_with_grace(&self, grace_blocks: usize) -> usize {
+        let grace_blocks = grace_blocks.min(self.config.short_term_window);
+
+        let mut weights = newest_weights(
+            &self.long_term_weights,
+            self.config.long_term_window - grace_blocks,
+        );
+        weights.resize(weights.len() + grace_blocks, 0);
+
+        weights.sort_unstable();
+        median(weights)
+    }
+
+    /// Returns the median weight over the newest [`SHORT_TERM_WINDOW`] `- grace_blocks` blocks, with
+    /// the window filled up to [`SHORT_TERM_WINDOW`] with blocks with a weight of `0`.
+    ///
+    /// ref: `get_dynamic_base_fee_estimate_2021_scaling` in
+    /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/cryptonote_core/blockchain.cpp>
+    pub fn median_short_term_weight_with_grace(&self, grace_blocks: usize) -> usize {
+        let grace_blocks = grace_blocks.min(self.config.short_term_window);
+
+        let mut weights = newest_weights(
+            &self.short_term_block_weights,
+            self.config.short_term_window - grace_blocks,
+        );
+        weights.resize(self.config.short_term_window, 0);
+
+        weights.sort_unstable();
+        median(weights)
+    }
+
     /// Returns the effective median weight, used for block reward calculations and to calculate
     /// the block weight limit.
     ///
@@ -230,6 +322,17 @@ impl BlockWeightsCache {
     }
 }
 
+/// Returns the newest `count` weights in `weights`.
+fn newest_weights(weights: &RollingMedian<usize>, count: usize) -> Vec<usize> {
+    let window = weights.window();
+
+    window
+        .iter()
+        .skip(window.len().saturating_sub(count))
+        .copied()
+        .collect()
+}
+
 /// Calculates the effective median with the long term and short term median.
 fn calculate_effective_median_block_weight(
     hf: HardFork,
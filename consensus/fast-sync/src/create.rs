@@ -3,20 +3,21 @@
     reason = "binary shares same Cargo.toml as library"
 )]
 
-use std::fs::write;
+use std::{fs::write, path::PathBuf};
 
-use clap::Parser;
+use clap::{builder::TypedValueParser, Parser};
 use tower::{Service, ServiceExt};
 
 use cuprate_blockchain::{
     config::ConfigBuilder, cuprate_database::DbResult, service::BlockchainReadHandle,
 };
+use cuprate_helper::network::Network;
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
     Chain,
 };
 
-use cuprate_fast_sync::FAST_SYNC_BATCH_LEN;
+use cuprate_fast_sync::{encode_fast_sync_file, FAST_SYNC_BATCH_LEN};
 
 async fn read_batch(
     handle: &mut BlockchainReadHandle,
@@ -41,6 +42,19 @@ async fn read_batch(
 struct Args {
     #[arg(long)]
     height: usize,
+
+    /// The network of the blockchain database to read.
+    #[arg(
+        long,
+        default_value_t = Network::Mainnet,
+        value_parser = clap::builder::PossibleValuesParser::new(["mainnet", "testnet", "stagenet"])
+            .map(|s| s.parse::<Network>().unwrap()),
+    )]
+    network: Network,
+
+    /// The file to write the hashes to.
+    #[arg(long, default_value = "fast_sync_hashes.bin")]
+    output: PathBuf,
 }
 
 #[tokio::main]
@@ -48,7 +62,7 @@ async fn main() {
     let args = Args::parse();
     let height_target = args.height;
 
-    let config = ConfigBuilder::new().build();
+    let config = ConfigBuilder::new().network(args.network).build();
 
     let (mut read_handle, _, _) = cuprate_blockchain::service::init(config).unwrap();
 
@@ -71,10 +85,17 @@ async fn main() {
 
     drop(read_handle);
 
-    write("fast_sync_hashes.bin", hashes_of_hashes.concat().as_slice())
-        .expect("Could not write file");
+    write(
+        &args.output,
+        encode_fast_sync_file(args.network, &hashes_of_hashes),
+    )
+    .expect("Could not write file");
 
-    println!("Generated hashes up to block height {height}");
+    println!(
+        "Generated {} hashes up to block height {height}: {}",
+        args.network,
+        args.output.display()
+    );
 }
 
 pub fn hash_of_hashes(hashes: &[[u8; 32]]) -> [u8; 32] {
@@ -14,6 +14,7 @@ use tower::{Service, ServiceExt};
 use cuprate_blockchain::service::BlockchainReadHandle;
 use cuprate_consensus::transactions::new_tx_verification_data;
 use cuprate_consensus_context::BlockchainContext;
+use cuprate_consensus_rules::ConsensusError;
 use cuprate_p2p::block_downloader::ChainEntry;
 use cuprate_p2p_core::NetworkZone;
 use cuprate_types::{
@@ -173,11 +174,59 @@ pub async fn validate_entries<N: NetworkZone>(
     Ok((entries, unknown))
 }
 
+/// Validates that `hashes` are the block hashes of a full fast-sync batch.
+///
+/// `start_height` should be the height of the first block in `hashes`.
+///
+/// Returns `false` if `start_height` is not the start of a batch, `hashes` is not
+/// [`FAST_SYNC_BATCH_LEN`] long, the batch is past the fast sync blocks or the hashes do not match.
+///
+/// # Panics
+///
+/// This will panic if [`set_fast_sync_hashes`] has not been called.
+pub fn validate_batch_hashes(start_height: usize, hashes: &[[u8; 32]]) -> bool {
+    if start_height % FAST_SYNC_BATCH_LEN != 0
+        || hashes.len() != FAST_SYNC_BATCH_LEN
+        || start_height + FAST_SYNC_BATCH_LEN > fast_sync_stop_height()
+    {
+        return false;
+    }
+
+    let mut hasher = Hasher::default();
+    for hash in hashes {
+        hasher.update(hash);
+    }
+
+    hasher.finalize() == FAST_SYNC_HASHES.get().unwrap()[get_hash_index_for_height(start_height)]
+}
+
 /// Get the index of the hash that contains this block in the fast sync hashes.
 const fn get_hash_index_for_height(height: usize) -> usize {
     height / FAST_SYNC_BATCH_LEN
 }
 
+/// An error converting a trusted block with [`try_block_to_verified_block_information`].
+///
+/// These are only the errors that stop the block being converted, the block is not verified.
+#[derive(Debug, thiserror::Error)]
+pub enum TrustedBlockError {
+    /// The miner transaction does not have a single [`Input::Gen`] input.
+    #[error("The miner transaction's input is not a gen input")]
+    MinerInputNotGen,
+    /// The block is not at the height of the top of the chain.
+    #[error("The block has height {found}, expected {expected}")]
+    WrongHeight { expected: usize, found: usize },
+    /// A transaction could not be parsed.
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(#[from] ConsensusError),
+    /// A transaction in the block was not given.
+    #[error("Missing transaction: {}", hex::encode(.0))]
+    MissingTransaction([u8; 32]),
+    /// The transactions pay more in fees than the miner transaction outputs.
+    #[error("The miner transaction outputs are less than the fees")]
+    OutputsBelowFees,
+}
+
 /// Creates a [`VerifiedBlockInformation`] from a block known to be valid.
 ///
 /// # Panics
@@ -188,31 +237,52 @@ pub fn block_to_verified_block_information(
     txs: Vec<Transaction>,
     blockchin_ctx: &BlockchainContext,
 ) -> VerifiedBlockInformation {
+    try_block_to_verified_block_information(block, txs, blockchin_ctx)
+        .expect("fast sync block invalid")
+}
+
+/// Creates a [`VerifiedBlockInformation`] from a trusted block, without verifying it.
+///
+/// Unlike [`block_to_verified_block_information`] this returns an error, instead of panicking, if the block
+/// can not be converted, e.g. a block read from a corrupt file.
+///
+/// # Errors
+///
+/// See [`TrustedBlockError`].
+pub fn try_block_to_verified_block_information(
+    block: Block,
+    txs: Vec<Transaction>,
+    blockchin_ctx: &BlockchainContext,
+) -> Result<VerifiedBlockInformation, TrustedBlockError> {
     let block_hash = block.hash();
 
     let block_blob = block.serialize();
 
-    let Some(Input::Gen(height)) = block.miner_transaction.prefix().inputs.first() else {
-        panic!("fast sync block invalid");
+    let [Input::Gen(height)] = &block.miner_transaction.prefix().inputs[..] else {
+        return Err(TrustedBlockError::MinerInputNotGen);
     };
 
-    assert_eq!(
-        *height, blockchin_ctx.chain_height,
-        "fast sync block invalid"
-    );
+    if *height != blockchin_ctx.chain_height {
+        return Err(TrustedBlockError::WrongHeight {
+            expected: blockchin_ctx.chain_height,
+            found: *height,
+        });
+    }
 
     let mut txs = txs
         .into_iter()
         .map(|tx| {
-            let data = new_tx_verification_data(tx).expect("fast sync block invalid");
+            let data = new_tx_verification_data(tx)?;
 
-            (data.tx_hash, data)
+            Ok((data.tx_hash, data))
         })
-        .collect::<HashMap<_, _>>();
+        .collect::<Result<HashMap<_, _>, ConsensusError>>()?;
 
     let mut verified_txs = Vec::with_capacity(txs.len());
     for tx in &block.transactions {
-        let data = txs.remove(tx).expect("fast sync block invalid");
+        let data = txs
+            .remove(tx)
+            .ok_or(TrustedBlockError::MissingTransaction(*tx))?;
 
         verified_txs.push(VerifiedTransactionInformation {
             tx_blob: data.tx_blob,
@@ -232,12 +302,14 @@ pub fn block_to_verified_block_information(
         .map(|output| output.amount.unwrap_or(0))
         .sum::<u64>();
 
-    let generated_coins = total_outputs - total_fees;
+    let generated_coins = total_outputs
+        .checked_sub(total_fees)
+        .ok_or(TrustedBlockError::OutputsBelowFees)?;
 
     let weight = block.miner_transaction.weight()
         + verified_txs.iter().map(|tx| tx.tx_weight).sum::<usize>();
 
-    VerifiedBlockInformation {
+    Ok(VerifiedBlockInformation {
         block_blob,
         txs: verified_txs,
         block_hash,
@@ -248,7 +320,7 @@ pub fn block_to_verified_block_information(
         long_term_weight: blockchin_ctx.next_block_long_term_weight(weight),
         cumulative_difficulty: blockchin_ctx.cumulative_difficulty + blockchin_ctx.next_difficulty,
         block,
-    }
+    })
 }
 
 #[cfg(test)]
@@ -0,0 +1,187 @@
+//! Fast sync hash files.
+//!
+//! A fast sync hash file contains the fast sync hashes of one network, preceded by a header:
+//!
+//! | Field      | Size     | Description                                                  |
+//! |------------|----------|--------------------------------------------------------------|
+//! | Magic      | 8        | [`FAST_SYNC_FILE_MAGIC`]                                     |
+//! | Network    | 1        | `0` for mainnet, `1` for testnet, `2` for stagenet           |
+//! | Batch len  | 4        | [`FAST_SYNC_BATCH_LEN`] as a little endian `u32`             |
+//! | Checksum   | 32       | The `blake3` hash of the hashes following the header         |
+//! | Hashes     | 32 * `n` | The fast sync hashes, see [`crate::validate_batch_hashes`]   |
+//!
+//! Files are created with [`encode_fast_sync_file`] (the `create-fs-file` tool)
+//! and must be checked with [`decode_fast_sync_file`] before they are used.
+
+use cuprate_helper::network::Network;
+
+use crate::FAST_SYNC_BATCH_LEN;
+
+/// The magic bytes at the start of a fast sync hash file.
+pub const FAST_SYNC_FILE_MAGIC: [u8; 8] = *b"CUPFSYNC";
+
+/// The length of the header before the hashes.
+const HEADER_LEN: usize = FAST_SYNC_FILE_MAGIC.len() + 1 + 4 + 32;
+
+/// An error decoding a fast sync hash file.
+#[derive(Debug, thiserror::Error)]
+pub enum FastSyncFileError {
+    /// The file does not start with [`FAST_SYNC_FILE_MAGIC`].
+    #[error("the file is not a fast sync hash file")]
+    InvalidMagic,
+
+    /// The network byte is unknown.
+    #[error("the file has an unknown network: {0}")]
+    UnknownNetwork(u8),
+
+    /// The file is for a different network.
+    #[error("the file is for {found}, expected {expected}")]
+    WrongNetwork {
+        /// The network the file was expected to be for.
+        expected: Network,
+        /// The network the file is for.
+        found: Network,
+    },
+
+    /// The batch length is not [`FAST_SYNC_BATCH_LEN`].
+    #[error("the file uses a batch length of {0}, expected {FAST_SYNC_BATCH_LEN}")]
+    BatchLen(u32),
+
+    /// The file is too short or the hashes are not a multiple of 32 bytes.
+    #[error("the file length is invalid")]
+    Length,
+
+    /// The checksum does not match the hashes.
+    #[error("the file checksum does not match its hashes")]
+    Checksum,
+}
+
+/// Encode `hashes` into a fast sync hash file for `network`.
+pub fn encode_fast_sync_file(network: Network, hashes: &[[u8; 32]]) -> Vec<u8> {
+    let hashes = hashes.concat();
+
+    let mut file = Vec::with_capacity(HEADER_LEN + hashes.len());
+    file.extend_from_slice(&FAST_SYNC_FILE_MAGIC);
+    file.push(network_to_u8(network));
+    file.extend_from_slice(&u32::try_from(FAST_SYNC_BATCH_LEN).unwrap().to_le_bytes());
+    file.extend_from_slice(blake3::hash(&hashes).as_bytes());
+    file.extend_from_slice(&hashes);
+
+    file
+}
+
+/// Decode and check the fast sync hash file `bytes`, returning the hashes.
+///
+/// # Errors
+/// This returns an error if the header is invalid, the file is not for `network`
+/// or the checksum does not match.
+pub fn decode_fast_sync_file(
+    bytes: &[u8],
+    network: Network,
+) -> Result<Vec<[u8; 32]>, FastSyncFileError> {
+    if bytes.len() < HEADER_LEN {
+        return Err(FastSyncFileError::Length);
+    }
+
+    let (magic, rest) = bytes.split_at(FAST_SYNC_FILE_MAGIC.len());
+    if magic != FAST_SYNC_FILE_MAGIC {
+        return Err(FastSyncFileError::InvalidMagic);
+    }
+
+    let (&network_byte, rest) = rest.split_first().unwrap();
+    let found = network_from_u8(network_byte)?;
+    if found != network {
+        return Err(FastSyncFileError::WrongNetwork {
+            expected: network,
+            found,
+        });
+    }
+
+    let (batch_len, rest) = rest.split_at(4);
+    let batch_len = u32::from_le_bytes(batch_len.try_into().unwrap());
+    if usize::try_from(batch_len) != Ok(FAST_SYNC_BATCH_LEN) {
+        return Err(FastSyncFileError::BatchLen(batch_len));
+    }
+
+    let (checksum, hashes) = rest.split_at(32);
+    if hashes.len() % 32 != 0 {
+        return Err(FastSyncFileError::Length);
+    }
+    if blake3::hash(hashes).as_bytes() != checksum {
+        return Err(FastSyncFileError::Checksum);
+    }
+
+    Ok(hashes
+        .chunks_exact(32)
+        .map(|hash| hash.try_into().unwrap())
+        .collect())
+}
+
+/// The header byte for `network`.
+const fn network_to_u8(network: Network) -> u8 {
+    match network {
+        Network::Mainnet => 0,
+        Network::Testnet => 1,
+        Network::Stagenet => 2,
+    }
+}
+
+/// The [`Network`] for a header byte.
+const fn network_from_u8(byte: u8) -> Result<Network, FastSyncFileError> {
+    Ok(match byte {
+        0 => Network::Mainnet,
+        1 => Network::Testnet,
+        2 => Network::Stagenet,
+        _ => return Err(FastSyncFileError::UnknownNetwork(byte)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Files round trip and invalid files are rejected.
+    #[test]
+    fn encode_decode() {
+        let hashes = [[1; 32], [2; 32], [3; 32]];
+        let file = encode_fast_sync_file(Network::Stagenet, &hashes);
+
+        assert_eq!(
+            decode_fast_sync_file(&file, Network::Stagenet).unwrap(),
+            hashes
+        );
+        assert!(matches!(
+            decode_fast_sync_file(&file, Network::Mainnet),
+            Err(FastSyncFileError::WrongNetwork { .. })
+        ));
+
+        let mut bad_magic = file.clone();
+        bad_magic[0] ^= 1;
+        assert!(matches!(
+            decode_fast_sync_file(&bad_magic, Network::Stagenet),
+            Err(FastSyncFileError::InvalidMagic)
+        ));
+
+        let mut bad_hash = file.clone();
+        *bad_hash.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            decode_fast_sync_file(&bad_hash, Network::Stagenet),
+            Err(FastSyncFileError::Checksum)
+        ));
+
+        assert!(matches!(
+            decode_fast_sync_file(&file[..file.len() - 1], Network::Stagenet),
+            Err(FastSyncFileError::Length)
+        ));
+    }
+
+    /// An empty file is valid, fast sync is then disabled.
+    #[test]
+    fn empty() {
+        let file = encode_fast_sync_file(Network::Testnet, &[]);
+        assert_eq!(file.len(), HEADER_LEN);
+        assert!(decode_fast_sync_file(&file, Network::Testnet)
+            .unwrap()
+            .is_empty());
+    }
+}
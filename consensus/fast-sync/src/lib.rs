@@ -5,8 +5,13 @@ use hex as _;
 use tokio as _;
 
 mod fast_sync;
+mod file;
 
 pub use fast_sync::{
     block_to_verified_block_information, fast_sync_stop_height, set_fast_sync_hashes,
-    validate_entries, FAST_SYNC_BATCH_LEN,
+    try_block_to_verified_block_information, validate_batch_hashes, validate_entries,
+    TrustedBlockError, FAST_SYNC_BATCH_LEN,
+};
+pub use file::{
+    decode_fast_sync_file, encode_fast_sync_file, FastSyncFileError, FAST_SYNC_FILE_MAGIC,
 };
@@ -183,6 +183,11 @@ impl HFVotes {
         }
     }
 
+    /// Returns the votes in the window, in order of the blocks they are from.
+    pub const fn vote_list(&self) -> &VecDeque<HardFork> {
+        &self.vote_list
+    }
+
     /// Pop a number of blocks from the top of the cache and push some values into the front of the cache,
     /// i.e. the oldest blocks.
     ///
@@ -29,7 +29,9 @@ use cuprate_consensus_rules::{
     ConsensusError, HardFork,
 };
 
-use crate::{transactions::start_tx_verification, Database, ExtendedConsensusError};
+use crate::{
+    checkpoints::checkpoints, transactions::start_tx_verification, Database, ExtendedConsensusError,
+};
 
 mod alt_block;
 mod batch_prepare;
@@ -261,6 +263,11 @@ where
 
     tracing::debug!("verifying block: {}", hex::encode(prepped_block.block_hash));
 
+    let height = context.chain_height;
+    if checkpoints().is_some_and(|c| !c.check_block(height, &prepped_block.block_hash)) {
+        return Err(ExtendedConsensusError::CheckpointMismatch(height));
+    }
+
     check_block_pow(&prepped_block.pow_hash, context.next_difficulty)
         .map_err(ConsensusError::Block)?;
 
@@ -28,6 +28,7 @@ use cuprate_types::{
 
 use crate::{
     block::{free::pull_ordered_transactions, PreparedBlock},
+    checkpoints::checkpoints,
     BlockChainContextRequest, BlockChainContextResponse, ExtendedConsensusError,
 };
 
@@ -35,10 +36,12 @@ use crate::{
 ///
 /// Returns [`AltBlockInformation`], which contains the cumulative difficulty of the alt chain.
 ///
-/// This function only checks the block's proof-of-work and its weight.
+/// This function only checks the block's proof-of-work and its weight, alt-blocks at or below the
+/// highest [checkpoint](crate::checkpoints) at or below `chain_height`, the main chain's height, are rejected.
 pub async fn sanity_check_alt_block<C>(
     block: Block,
     txs: HashMap<[u8; 32], TransactionVerificationData>,
+    chain_height: usize,
     mut context_svc: C,
 ) -> Result<AltBlockInformation, ExtendedConsensusError>
 where
@@ -50,6 +53,19 @@ where
         + 'static,
     C::Future: Send + 'static,
 {
+    // Check if the block's miner input is formed correctly.
+    let [Input::Gen(height)] = &block.miner_transaction.prefix().inputs[..] else {
+        return Err(ConsensusError::Block(BlockError::MinerTxError(
+            MinerTxError::InputNotOfTypeGen,
+        ))
+        .into());
+    };
+
+    // Alt-blocks can't re-org past a checkpoint, reject them before doing any work.
+    if checkpoints().is_some_and(|c| !c.alt_block_allowed(chain_height, *height)) {
+        return Err(ExtendedConsensusError::AltBlockBelowCheckpoint(*height));
+    }
+
     // Fetch the alt-chains context cache.
     let BlockChainContextResponse::AltChainContextCache(mut alt_context_cache) = context_svc
         .ready()
@@ -63,14 +79,6 @@ where
         panic!("Context service returned wrong response!");
     };
 
-    // Check if the block's miner input is formed correctly.
-    let [Input::Gen(height)] = &block.miner_transaction.prefix().inputs[..] else {
-        return Err(ConsensusError::Block(BlockError::MinerTxError(
-            MinerTxError::InputNotOfTypeGen,
-        ))
-        .into());
-    };
-
     if *height != alt_context_cache.chain_height {
         return Err(ConsensusError::Block(BlockError::MinerTxError(
             MinerTxError::InputsHeightIncorrect,
@@ -0,0 +1,206 @@
+//! Checkpoints
+//!
+//! Checkpoints are `(height, block hash)` pairs the chain must contain, they are
+//! set once with [`set_checkpoints`] and then enforced by the block verifier:
+//!
+//! - main-chain blocks at a checkpoint height must have the checkpoint's hash.
+//! - alt-blocks at or below the highest checkpoint under the current chain height are rejected,
+//!   before their proof-of-work is checked.
+//!
+//! If no checkpoints are set nothing is enforced.
+use std::{collections::BTreeMap, sync::OnceLock};
+
+use hex_literal::hex;
+
+use cuprate_helper::network::Network;
+
+/// A [`OnceLock`] holding the checkpoints used by the verifier.
+static CHECKPOINTS: OnceLock<Checkpoints> = OnceLock::new();
+
+/// The compiled in mainnet checkpoints.
+///
+/// These are taken from `monerod`, more can be added with a checkpoints file:
+/// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/checkpoints/checkpoints.cpp>
+const MAINNET_CHECKPOINTS: &[(usize, [u8; 32])] = &[
+    (
+        0,
+        hex!("418015bb9ae982a1975da7d79277c2705727a56894ba0fb246adaabb1f4632e3"),
+    ),
+    (
+        1,
+        hex!("771fbcd656ec1464d3a02ead5e18644030007a0fc664c0a964d30922821a8148"),
+    ),
+    (
+        202_612,
+        hex!("bbd604d2ba11ba27935e006ed39c9bfdd99b76bf4a50654bc1e1e61217962698"),
+    ),
+];
+
+/// The compiled in testnet checkpoints.
+const TESTNET_CHECKPOINTS: &[(usize, [u8; 32])] = &[(
+    0,
+    hex!("48ca7cd3c8de5b6a4d53d2861fbdaedca141553559f9be9520068053cda8430b"),
+)];
+
+/// The compiled in stagenet checkpoints.
+const STAGENET_CHECKPOINTS: &[(usize, [u8; 32])] = &[(
+    0,
+    hex!("76ee3cc98646292206cd3e86f74d88b4dcc1d937088645e9b0cbca84b7ce74eb"),
+)];
+
+/// An error adding a checkpoint.
+#[derive(Debug, thiserror::Error)]
+#[error("conflicting checkpoints at height {0}")]
+pub struct ConflictingCheckpoint(pub usize);
+
+/// A set of checkpoints for a single network.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Checkpoints {
+    /// `height -> block hash`
+    points: BTreeMap<usize, [u8; 32]>,
+}
+
+impl Checkpoints {
+    /// Returns the compiled in checkpoints for `network`.
+    pub fn new(network: Network) -> Self {
+        let points = match network {
+            Network::Mainnet => MAINNET_CHECKPOINTS,
+            Network::Testnet => TESTNET_CHECKPOINTS,
+            Network::Stagenet => STAGENET_CHECKPOINTS,
+        };
+
+        Self {
+            points: points.iter().copied().collect(),
+        }
+    }
+
+    /// Add a checkpoint.
+    ///
+    /// Adding a checkpoint that already exists is a no-op.
+    ///
+    /// # Errors
+    /// This returns an error if a different hash is already set for `height`.
+    pub fn add(&mut self, height: usize, hash: [u8; 32]) -> Result<(), ConflictingCheckpoint> {
+        match self.points.get(&height) {
+            Some(existing) if existing != &hash => Err(ConflictingCheckpoint(height)),
+            _ => {
+                self.points.insert(height, hash);
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns the checkpoint hash at `height`, if there is one.
+    pub fn get(&self, height: usize) -> Option<&[u8; 32]> {
+        self.points.get(&height)
+    }
+
+    /// Returns the height of the highest checkpoint.
+    pub fn highest_height(&self) -> Option<usize> {
+        self.points.last_key_value().map(|(height, _)| *height)
+    }
+
+    /// Returns `true` if there are no checkpoints.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns the amount of checkpoints.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if a block with `hash` at `height` passes the checkpoints.
+    pub fn check_block(&self, height: usize, hash: &[u8; 32]) -> bool {
+        self.get(height).is_none_or(|checkpoint| checkpoint == hash)
+    }
+
+    /// Returns `true` if an alt-block at `block_height` is allowed when our chain is `chain_height` blocks long.
+    ///
+    /// The alt-block must be above the highest checkpoint at or below `chain_height`, checkpoints above our chain
+    /// are ignored so a node on the wrong fork can still switch to the chain with the checkpoints.
+    ///
+    /// ref: `is_alternative_block_allowed` in
+    /// <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/checkpoints/checkpoints.cpp>
+    pub fn alt_block_allowed(&self, chain_height: usize, block_height: usize) -> bool {
+        if block_height == 0 {
+            return false;
+        }
+
+        self.points
+            .range(..=chain_height)
+            .next_back()
+            .is_none_or(|(checkpoint_height, _)| block_height > *checkpoint_height)
+    }
+}
+
+/// Sets the checkpoints enforced by the verifier.
+///
+/// # Panics
+///
+/// This function will panic if the checkpoints have already been set.
+pub fn set_checkpoints(checkpoints: Checkpoints) {
+    CHECKPOINTS.set(checkpoints).unwrap();
+}
+
+/// Returns the checkpoints set with [`set_checkpoints`].
+pub fn checkpoints() -> Option<&'static Checkpoints> {
+    CHECKPOINTS.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use cuprate_consensus_rules::genesis::generate_genesis_block;
+
+    use super::*;
+
+    /// The compiled in checkpoints start at each network's genesis block.
+    #[test]
+    fn genesis_checkpoints() {
+        for network in [Network::Mainnet, Network::Testnet, Network::Stagenet] {
+            let checkpoints = Checkpoints::new(network);
+            assert!(checkpoints.check_block(0, &generate_genesis_block(network).hash()));
+        }
+    }
+
+    /// Blocks and alt-blocks are checked against the checkpoints.
+    #[test]
+    fn check_blocks() {
+        let mut checkpoints = Checkpoints::default();
+        assert!(checkpoints.check_block(10, &[1; 32]));
+        assert!(checkpoints.alt_block_allowed(5, 1));
+        assert!(!checkpoints.alt_block_allowed(5, 0));
+
+        checkpoints.add(10, [1; 32]).unwrap();
+        checkpoints.add(10, [1; 32]).unwrap();
+        assert!(checkpoints.add(10, [2; 32]).is_err());
+        assert_eq!(checkpoints.len(), 1);
+
+        assert!(checkpoints.check_block(10, &[1; 32]));
+        assert!(!checkpoints.check_block(10, &[2; 32]));
+        assert!(checkpoints.check_block(11, &[2; 32]));
+
+        assert!(!checkpoints.alt_block_allowed(20, 5));
+        assert!(!checkpoints.alt_block_allowed(20, 10));
+        assert!(checkpoints.alt_block_allowed(20, 11));
+    }
+
+    /// Checkpoints above our chain height don't stop us switching to the chain with them.
+    #[test]
+    fn alt_blocks_below_chain_height() {
+        let mut checkpoints = Checkpoints::default();
+        checkpoints.add(10, [1; 32]).unwrap();
+        checkpoints.add(100, [1; 32]).unwrap();
+
+        // Our chain is below the first checkpoint.
+        assert!(checkpoints.alt_block_allowed(9, 5));
+        assert!(checkpoints.alt_block_allowed(9, 10));
+
+        // Only the checkpoint at 10 applies.
+        assert!(!checkpoints.alt_block_allowed(50, 10));
+        assert!(checkpoints.alt_block_allowed(50, 11));
+        assert!(checkpoints.alt_block_allowed(50, 100));
+
+        assert!(!checkpoints.alt_block_allowed(100, 100));
+    }
+}
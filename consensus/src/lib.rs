@@ -12,8 +12,9 @@ cfg_if::cfg_if! {
     if #[cfg(test)] {
         use cuprate_test_utils as _;
         use curve25519_dalek as _;
-        use hex_literal as _;
         use futures as _;
+        use serde as _;
+        use serde_json as _;
     }
 }
 
@@ -21,12 +22,14 @@ use cuprate_consensus_rules::ConsensusError;
 
 pub mod batch_verifier;
 pub mod block;
+pub mod checkpoints;
 #[cfg(test)]
 mod tests;
 pub mod transactions;
 
 pub use cuprate_consensus_context::{
-    initialize_blockchain_context, BlockChainContextRequest, BlockChainContextResponse,
+    initialize_blockchain_context, initialize_blockchain_context_with_snapshot,
+    snapshot::ContextSnapshot, BlockChainContextRequest, BlockChainContextResponse,
     BlockchainContext, BlockchainContextService, ContextConfig,
 };
 
@@ -56,6 +59,12 @@ pub enum ExtendedConsensusError {
     /// A request to verify a batch of blocks had no blocks in the batch.
     #[error("A request to verify a batch of blocks had no blocks in the batch.")]
     NoBlocksToVerify,
+    /// The block at this height does not match the checkpoint.
+    #[error("The block at height {0} does not match the checkpoint.")]
+    CheckpointMismatch(usize),
+    /// An alt-block at this height is at or below the highest checkpoint under our chain height.
+    #[error("The alt-block at height {0} is at or below the highest checkpoint.")]
+    AltBlockBelowCheckpoint(usize),
 }
 
 use __private::Database;
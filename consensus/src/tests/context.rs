@@ -3,13 +3,15 @@ use proptest::{strategy::Strategy, test_runner::TestRunner};
 use tower::ServiceExt;
 
 use cuprate_consensus_context::{
-    initialize_blockchain_context, BlockChainContextRequest, ContextConfig, NewBlockData,
+    initialize_blockchain_context, initialize_blockchain_context_with_snapshot,
+    BlockChainContextRequest, BlockChainContextResponse, ContextConfig, NewBlockData,
 };
 
 use crate::{tests::mock_db::*, HardFork};
 
 pub(crate) mod data;
 mod difficulty;
+mod fee;
 mod hardforks;
 mod rx_vms;
 mod weight;
@@ -49,6 +51,7 @@ async fn context_invalidated_on_new_block() -> Result<(), tower::BoxError> {
             generated_coins: 0,
             vote: HardFork::V1,
             cumulative_difficulty: 0,
+            tx_fees: vec![],
         }))
         .await?;
 
@@ -75,3 +78,117 @@ async fn context_height_correct() -> Result<(), tower::BoxError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn context_restored_from_snapshot() -> Result<(), tower::BoxError> {
+    const BLOCKCHAIN_HEIGHT: usize = 6000;
+
+    let mut runner = TestRunner::default();
+    let db = arb_dummy_database(BLOCKCHAIN_HEIGHT)
+        .new_tree(&mut runner)
+        .unwrap()
+        .current();
+
+    let mut ctx_svc = initialize_blockchain_context(TEST_CONTEXT_CONFIG, db.clone()).await?;
+
+    let BlockChainContextResponse::Snapshot(snapshot) = ctx_svc
+        .clone()
+        .oneshot(BlockChainContextRequest::Snapshot)
+        .await?
+    else {
+        panic!("Context service returned wrong response!");
+    };
+
+    // A database without any blocks, the caches can only be restored from the snapshot.
+    let empty_db = DummyDatabaseBuilder::default().finish(Some(BLOCKCHAIN_HEIGHT));
+
+    let mut restored_svc = initialize_blockchain_context_with_snapshot(
+        TEST_CONTEXT_CONFIG,
+        empty_db,
+        Some((*snapshot).clone()),
+    )
+    .await?;
+
+    assert_eq!(
+        ctx_svc.blockchain_context(),
+        restored_svc.blockchain_context()
+    );
+
+    // A snapshot from a different top block is stale and the caches are rebuilt.
+    let mut db = db;
+    db.add_block(DummyBlockExtendedHeader::default());
+
+    let mut rebuilt_svc =
+        initialize_blockchain_context_with_snapshot(TEST_CONTEXT_CONFIG, db, Some(*snapshot))
+            .await?;
+
+    assert_eq!(
+        rebuilt_svc.blockchain_context().chain_height,
+        BLOCKCHAIN_HEIGHT + 1
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn recent_fees_restored_from_snapshot() -> Result<(), tower::BoxError> {
+    const BLOCKCHAIN_HEIGHT: usize = 6000;
+
+    let mut runner = TestRunner::default();
+    let db = arb_dummy_database(BLOCKCHAIN_HEIGHT)
+        .new_tree(&mut runner)
+        .unwrap()
+        .current();
+
+    let ctx_svc = initialize_blockchain_context(TEST_CONTEXT_CONFIG, db).await?;
+
+    // The dummy database's top hash is its height.
+    let mut block_hash = [0; 32];
+    block_hash[0..8].copy_from_slice(&(BLOCKCHAIN_HEIGHT + 1).to_le_bytes());
+
+    // A full block, the arbitrary block weights are below 100,000,000, with a fee per byte above
+    // the fee tiers.
+    ctx_svc
+        .clone()
+        .oneshot(BlockChainContextRequest::Update(NewBlockData {
+            block_hash,
+            height: BLOCKCHAIN_HEIGHT,
+            timestamp: 0,
+            weight: 200_000_000,
+            long_term_weight: 0,
+            generated_coins: 0,
+            vote: HardFork::V1,
+            cumulative_difficulty: 0,
+            tx_fees: vec![(10_000_000_000_000, 1_000)],
+        }))
+        .await?;
+
+    let BlockChainContextResponse::Snapshot(snapshot) = ctx_svc
+        .clone()
+        .oneshot(BlockChainContextRequest::Snapshot)
+        .await?
+    else {
+        panic!("Context service returned wrong response!");
+    };
+
+    let empty_db = DummyDatabaseBuilder::default().finish(Some(BLOCKCHAIN_HEIGHT + 1));
+    let restored_svc =
+        initialize_blockchain_context_with_snapshot(TEST_CONTEXT_CONFIG, empty_db, Some(*snapshot))
+            .await?;
+
+    for svc in [ctx_svc, restored_svc] {
+        let BlockChainContextResponse::TargetFeeEstimate(fee) = svc
+            .oneshot(BlockChainContextRequest::TargetFeeEstimate {
+                target_blocks: 1,
+                pool: vec![],
+            })
+            .await?
+        else {
+            panic!("Context service returned wrong response!");
+        };
+
+        assert_eq!(fee, 10_000_000_000);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use cuprate_consensus_context::{
+    fee::{
+        dynamic_fee_tiers, fee_tiers, pool_fee_estimate, quantize_fee, target_fee_estimate,
+        RecentBlockFees, FEE_BLOCKS_WINDOW,
+    },
+    weight::BlockWeightsCache,
+};
+use cuprate_types::Chain;
+
+use crate::{
+    tests::{context::weight::TEST_WEIGHT_CONFIG, mock_db::*},
+    HardFork,
+};
+
+/// A base reward of 0.6 XMR.
+const BASE_REWARD: u64 = 600_000_000_000;
+
+const PENALTY_FREE_ZONE: usize = 300_000;
+
+#[test]
+fn fee_tiers_at_penalty_free_zone() {
+    // These match the values returned by monerod with these medians, see the `get_fee_estimate` RPC test data.
+    assert_eq!(
+        dynamic_fee_tiers(BASE_REWARD, PENALTY_FREE_ZONE, PENALTY_FREE_ZONE),
+        [20000, 80000, 320000, 4000000]
+    );
+}
+
+#[test]
+fn fee_tiers_short_term_median_above_long_term_median() {
+    // Calculated with `monerod`'s operation order, which truncates `32 * 3000 * Mnw / Mlw` to 96240
+    // before the priority tier's division, without the truncation the priority tier would be 3990000.
+    assert_eq!(
+        dynamic_fee_tiers(BASE_REWARD, 300_752, PENALTY_FREE_ZONE),
+        [20000, 80000, 320000, 4000000]
+    );
+}
+
+/// Returns a [`BlockWeightsCache`] of 200 blocks with these weights.
+async fn weight_cache(weight: usize, long_term_weight: usize) -> BlockWeightsCache {
+    let mut db_builder = DummyDatabaseBuilder::default();
+    for _ in 0..200 {
+        let block = DummyBlockExtendedHeader::default().with_weight_into(weight, long_term_weight);
+        db_builder.add_block(block);
+    }
+
+    BlockWeightsCache::init_from_chain_height(
+        200,
+        TEST_WEIGHT_CONFIG,
+        db_builder.finish(None),
+        Chain::Main,
+    )
+    .await
+    .unwrap()
+}
+
+/// The fee tiers `monerod` returned in the `get_fee_estimate` RPC test data, with the default 10 grace
+/// blocks, blocks below the penalty free zone and the `already_generated_coins` from the
+/// `get_miner_data` RPC test data.
+#[tokio::test]
+async fn fee_tiers_monerod_vector() {
+    let weight_cache = weight_cache(100_000, 100_000).await;
+
+    assert_eq!(
+        fee_tiers(&weight_cache, HardFork::V16, 18_186_022_843_595_960_691, 10),
+        [20000, 80000, 320000, 4000000]
+    );
+}
+
+/// The short term median is floored at the long term median, not the penalty free zone.
+#[tokio::test]
+async fn fee_tiers_short_term_median_floored_at_long_term_median() {
+    let weight_cache = weight_cache(400_000, 600_000).await;
+
+    assert_eq!(
+        fee_tiers(&weight_cache, HardFork::V16, u64::MAX, 0),
+        dynamic_fee_tiers(BASE_REWARD, 600_000, 600_000)
+    );
+}
+
+#[test]
+fn fee_tiers_fall_with_larger_medians() {
+    let tiers = dynamic_fee_tiers(BASE_REWARD, PENALTY_FREE_ZONE * 2, PENALTY_FREE_ZONE * 2);
+
+    assert!(tiers[0] < 20000);
+    assert!(tiers.windows(2).all(|w| w[0] <= w[1]));
+}
+
+#[test]
+fn quantize_rounds_up() {
+    assert_eq!(quantize_fee(0), 0);
+    assert_eq!(quantize_fee(1), 10000);
+    assert_eq!(quantize_fee(10000), 10000);
+    assert_eq!(quantize_fee(10001), 20000);
+}
+
+#[test]
+fn pool_estimate_zero_when_pool_fits() {
+    let pool = [(3_000_000, 1_000); 10];
+
+    assert_eq!(pool_fee_estimate(1, 100_000, &pool), 0);
+}
+
+#[test]
+fn pool_estimate_beats_pushed_out_tx() {
+    // Fee per byte of 1000, 500 and 100.
+    let pool = [(100_000, 100), (50_000, 100), (10_000, 100)];
+
+    assert_eq!(pool_fee_estimate(1, 150, &pool), 501);
+    assert_eq!(pool_fee_estimate(1, 250, &pool), 101);
+    assert_eq!(pool_fee_estimate(2, 150, &pool), 0);
+}
+
+#[test]
+fn recent_fees_ignore_blocks_with_space() {
+    let mut recent_fees = RecentBlockFees::default();
+
+    for _ in 0..FEE_BLOCKS_WINDOW {
+        recent_fees.new_block(&[(1_000_000, 1_000)], 1_000, PENALTY_FREE_ZONE);
+    }
+
+    assert_eq!(recent_fees.estimate(1), 0);
+}
+
+#[test]
+fn recent_fees_use_full_blocks() {
+    let mut recent_fees = RecentBlockFees::default();
+
+    // Fees per byte of 1 to 10.
+    let tx_fees = (1..=10).map(|fee| (fee * 1_000, 1_000)).collect::<Vec<_>>();
+
+    for _ in 0..FEE_BLOCKS_WINDOW {
+        recent_fees.new_block(&tx_fees, PENALTY_FREE_ZONE, PENALTY_FREE_ZONE);
+    }
+
+    assert_eq!(recent_fees.estimate(1), 6);
+    assert_eq!(recent_fees.estimate(10), 1);
+
+    recent_fees.pop_blocks(FEE_BLOCKS_WINDOW);
+    assert_eq!(recent_fees.estimate(1), 0);
+}
+
+#[test]
+fn target_estimate_at_least_low_tier() {
+    let tiers = dynamic_fee_tiers(BASE_REWARD, PENALTY_FREE_ZONE, PENALTY_FREE_ZONE);
+
+    assert_eq!(
+        target_fee_estimate(
+            1,
+            PENALTY_FREE_ZONE,
+            &[],
+            &RecentBlockFees::default(),
+            &tiers
+        ),
+        tiers[0]
+    );
+
+    // A pool with more than a block of transactions at a fee per byte of 50,000.
+    let pool = [(50_000 * 1_000, 1_000); 400];
+
+    assert_eq!(
+        target_fee_estimate(
+            1,
+            PENALTY_FREE_ZONE,
+            &pool,
+            &RecentBlockFees::default(),
+            &tiers
+        ),
+        60000
+    );
+}
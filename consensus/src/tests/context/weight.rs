@@ -168,3 +168,33 @@ async fn calc_bw_ltw_2850000_3050000() {
 }
 
 // TODO: protests
+
+#[tokio::test]
+async fn medians_with_grace_pad_with_empty_blocks() -> Result<(), tower::BoxError> {
+    let mut db_builder = DummyDatabaseBuilder::default();
+    for _ in 0..200 {
+        let block = DummyBlockExtendedHeader::default().with_weight_into(1_000, 1_000);
+        db_builder.add_block(block);
+    }
+
+    let weight_cache = BlockWeightsCache::init_from_chain_height(
+        200,
+        TEST_WEIGHT_CONFIG,
+        db_builder.finish(None),
+        Chain::Main,
+    )
+    .await?;
+
+    assert_eq!(weight_cache.median_short_term_weight_with_grace(0), 1_000);
+    assert_eq!(weight_cache.median_long_term_weight_with_grace(0), 1_000);
+
+    // The short term window is filled with empty blocks: 40 blocks of 1,000 and 60 empty blocks.
+    assert_eq!(weight_cache.median_short_term_weight_with_grace(60), 0);
+    // Only the empty blocks are added to the long term weights: 200 blocks of 1,000 and 60 empty blocks.
+    assert_eq!(weight_cache.median_long_term_weight_with_grace(60), 1_000);
+
+    // The grace blocks are limited to the short term window.
+    assert_eq!(weight_cache.median_long_term_weight_with_grace(250), 1_000);
+
+    Ok(())
+}
@@ -0,0 +1,430 @@
+//! Consensus rule conformance tests.
+//!
+//! Runs the test vectors in `tests/vectors` through [`check_block`], [`check_transaction_semantic`]
+//! and [`check_transaction_contextual`] and checks they are accepted or rejected with the expected error.
+//!
+//! Extra vectors, e.g. ones generated from `monerod`, can be run by setting the `CUPRATE_CONSENSUS_VECTORS`
+//! environment variable to a directory of vector files.
+//!
+//! # Format
+//!
+//! Each `.json` file in a vector directory holds a list of vectors:
+//!
+//! ```json
+//! { "vectors": [ { "name": "...", "description": "...", "check": "block", ... } ] }
+//! ```
+//!
+//! Every vector has:
+//! - `name`: a name to identify the vector in failures.
+//! - `blob` or `blob_file`: the block or transaction blob, in hex or as a path to a binary file relative to the vector file.
+//! - `check`: the check to run, `block`, `tx_semantic` or `tx_contextual`.
+//! - `error`: the [`Debug`] representation of the expected [`ConsensusError`], or `null` if the vector should be accepted.
+//!   A failed batch verification is `OneOrMoreBatchVerificationStatementsInvalid`.
+//!
+//! `block` vectors have:
+//! - `context`: the fields of [`ContextToVerifyBlock`], with the hard-fork as a number and the hash in hex.
+//!   `next_difficulty` is optional, [`check_block`] does not check the `PoW`.
+//! - `total_fees` and `block_weight`.
+//! - optionally the expected `vote` and `generated_coins` if accepted.
+//!
+//! `tx_semantic` vectors have:
+//! - `hf`: the hard-fork.
+//! - optionally `weight`, defaulting to the weight of the transaction, and the expected `fee` if accepted.
+//!
+//! `tx_contextual` vectors have:
+//! - `hf` and `chain_height`.
+//! - optionally `time_lock_timestamp` and `youngest_used_out_height`, defaulting to 0.
+//! - optionally `outputs_with_amount`, a list of `[amount, number of outputs]`, amounts not in the list have 0 outputs.
+//! - optionally `rings`, a list of rings of output keys, or `[output key, commitment]` for `RingCT` transactions.
+//! - optionally `time_locked_outs`, a list of `{"block": height}` or `{"time": timestamp}` time-locks used by the rings.
+#![expect(unused_crate_dependencies, reason = "external test module")]
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use monero_serai::{
+    block::Block,
+    transaction::{Timelock, Transaction},
+};
+use serde::Deserialize;
+
+use cuprate_consensus::{batch_verifier::MultiThreadedBatchVerifier, ExtendedConsensusError};
+use cuprate_consensus_rules::{
+    blocks::{check_block, ContextToVerifyBlock},
+    transactions::{
+        check_transaction_contextual, check_transaction_semantic, DecoyInfo, Rings,
+        TxRingMembersInfo,
+    },
+    ConsensusError, HardFork,
+};
+
+/// The environment variable holding an extra directory of vectors to run.
+const VECTORS_DIR_ENV: &str = "CUPRATE_CONSENSUS_VECTORS";
+
+/// A file of test vectors.
+#[derive(Deserialize)]
+struct VectorFile {
+    vectors: Vec<Vector>,
+}
+
+/// A single test vector.
+#[derive(Deserialize)]
+struct Vector {
+    name: String,
+    blob: Option<String>,
+    blob_file: Option<PathBuf>,
+    #[serde(flatten)]
+    check: Check,
+    error: Option<String>,
+}
+
+/// The check a [`Vector`] runs and its inputs.
+#[derive(Deserialize)]
+#[serde(tag = "check", rename_all = "snake_case")]
+enum Check {
+    Block {
+        context: BlockContext,
+        total_fees: u64,
+        block_weight: usize,
+        vote: Option<u8>,
+        generated_coins: Option<u64>,
+    },
+    TxSemantic {
+        hf: u8,
+        weight: Option<usize>,
+        fee: Option<u64>,
+    },
+    TxContextual {
+        hf: u8,
+        chain_height: usize,
+        #[serde(default)]
+        time_lock_timestamp: u64,
+        #[serde(default)]
+        youngest_used_out_height: usize,
+        #[serde(default)]
+        outputs_with_amount: Vec<(u64, usize)>,
+        #[serde(default)]
+        rings: Vec<Vec<RingMember>>,
+        #[serde(default)]
+        time_locked_outs: Vec<TimeLock>,
+    },
+}
+
+/// The JSON form of [`ContextToVerifyBlock`].
+#[derive(Deserialize)]
+struct BlockContext {
+    median_weight_for_block_reward: usize,
+    effective_median_weight: usize,
+    top_hash: String,
+    median_block_timestamp: Option<u64>,
+    chain_height: usize,
+    current_hf: u8,
+    #[serde(default)]
+    next_difficulty: u64,
+    already_generated_coins: u64,
+}
+
+/// A ring member, either an output key or an output key and commitment.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RingMember {
+    Legacy(String),
+    RingCT([String; 2]),
+}
+
+/// The JSON form of [`Timelock`].
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum TimeLock {
+    Block(usize),
+    Time(u64),
+}
+
+/// A value returned by an accepted vector: `(name, expected value, returned value)`.
+type Returned = (&'static str, Option<u64>, u64);
+
+/// Returns the [`HardFork`] with the version `hf`.
+fn hard_fork(hf: u8) -> HardFork {
+    HardFork::from_version(hf).unwrap()
+}
+
+/// Decodes a 32 byte hash, or point, from hex.
+fn hash(hex: &str) -> [u8; 32] {
+    let mut hash = [0; 32];
+    hex::decode_to_slice(hex, &mut hash).unwrap();
+    hash
+}
+
+/// Runs a `block` vector with [`check_block`].
+fn run_block(
+    blob: &[u8],
+    context: &BlockContext,
+    total_fees: u64,
+    block_weight: usize,
+    vote: Option<u8>,
+    generated_coins: Option<u64>,
+) -> Result<Vec<Returned>, ExtendedConsensusError> {
+    let block = Block::read(&mut &*blob).unwrap();
+
+    let context = ContextToVerifyBlock {
+        median_weight_for_block_reward: context.median_weight_for_block_reward,
+        effective_median_weight: context.effective_median_weight,
+        top_hash: hash(&context.top_hash),
+        median_block_timestamp: context.median_block_timestamp,
+        chain_height: context.chain_height,
+        current_hf: hard_fork(context.current_hf),
+        next_difficulty: u128::from(context.next_difficulty),
+        already_generated_coins: context.already_generated_coins,
+    };
+
+    let (got_vote, got_generated_coins) =
+        check_block(&block, total_fees, block_weight, blob.len(), &context)
+            .map_err(ConsensusError::Block)?;
+
+    Ok(vec![
+        ("vote", vote.map(u64::from), u64::from(got_vote.as_u8())),
+        ("generated_coins", generated_coins, got_generated_coins),
+    ])
+}
+
+/// Runs a `tx_semantic` vector with [`check_transaction_semantic`].
+fn run_tx_semantic(
+    blob: &[u8],
+    hf: u8,
+    weight: Option<usize>,
+    fee: Option<u64>,
+) -> Result<Vec<Returned>, ExtendedConsensusError> {
+    let tx = Transaction::read(&mut &*blob).unwrap();
+    let batch_verifier = MultiThreadedBatchVerifier::new(1);
+
+    let got_fee = check_transaction_semantic(
+        &tx,
+        blob.len(),
+        weight.unwrap_or_else(|| tx.weight()),
+        &tx.hash(),
+        hard_fork(hf),
+        &batch_verifier,
+    )
+    .map_err(ConsensusError::Transaction)?;
+
+    if !batch_verifier.verify() {
+        return Err(ExtendedConsensusError::OneOrMoreBatchVerificationStatementsInvalid);
+    }
+
+    Ok(vec![("fee", fee, got_fee)])
+}
+
+/// Runs a `tx_contextual` vector with [`check_transaction_contextual`].
+#[expect(clippy::too_many_arguments)]
+fn run_tx_contextual(
+    blob: &[u8],
+    hf: u8,
+    chain_height: usize,
+    time_lock_timestamp: u64,
+    youngest_used_out_height: usize,
+    outputs_with_amount: &[(u64, usize)],
+    rings: &[Vec<RingMember>],
+    time_locked_outs: &[TimeLock],
+) -> Result<Vec<Returned>, ExtendedConsensusError> {
+    let tx = Transaction::read(&mut &*blob).unwrap();
+    let hf = hard_fork(hf);
+
+    let point = |hex: &str| CompressedEdwardsY(hash(hex));
+    let rings = match tx {
+        Transaction::V1 { .. } => Rings::Legacy(
+            rings
+                .iter()
+                .map(|ring| {
+                    ring.iter()
+                        .map(|member| match member {
+                            RingMember::Legacy(key) => point(key),
+                            RingMember::RingCT(_) => panic!("RingCT ring member in a v1 ring"),
+                        })
+                        .collect()
+                })
+                .collect(),
+        ),
+        Transaction::V2 { .. } => Rings::RingCT(
+            rings
+                .iter()
+                .map(|ring| {
+                    ring.iter()
+                        .map(|member| match member {
+                            RingMember::RingCT([key, commitment]) => {
+                                [point(key), point(commitment)]
+                            }
+                            RingMember::Legacy(_) => panic!("v1 ring member in a RingCT ring"),
+                        })
+                        .collect()
+                })
+                .collect(),
+        ),
+    };
+
+    let decoy_info = if hf == HardFork::V1 {
+        None
+    } else {
+        let outputs_with_amount = outputs_with_amount
+            .iter()
+            .copied()
+            .collect::<HashMap<_, _>>();
+
+        Some(
+            DecoyInfo::new(
+                &tx.prefix().inputs,
+                |amount| outputs_with_amount.get(&amount).copied().unwrap_or(0),
+                hf,
+            )
+            .map_err(ConsensusError::Transaction)?,
+        )
+    };
+
+    let tx_ring_members_info = TxRingMembersInfo {
+        rings,
+        decoy_info,
+        youngest_used_out_height,
+        time_locked_outs: time_locked_outs
+            .iter()
+            .map(|time_lock| match time_lock {
+                TimeLock::Block(height) => Timelock::Block(*height),
+                TimeLock::Time(timestamp) => Timelock::Time(*timestamp),
+            })
+            .collect(),
+    };
+
+    check_transaction_contextual(
+        &tx,
+        &tx_ring_members_info,
+        chain_height,
+        time_lock_timestamp,
+        hf,
+    )
+    .map_err(ConsensusError::Transaction)?;
+
+    Ok(vec![])
+}
+
+/// Runs a vector, returning a description of the difference if the result was not the expected one.
+fn run_vector(vector: &Vector, dir: &Path) -> Result<(), String> {
+    let blob = match (&vector.blob, &vector.blob_file) {
+        (Some(blob), None) => hex::decode(blob).unwrap(),
+        (None, Some(file)) => fs::read(dir.join(file)).unwrap(),
+        _ => panic!("vector must have exactly one of `blob` or `blob_file`"),
+    };
+
+    let res = match &vector.check {
+        Check::Block {
+            context,
+            total_fees,
+            block_weight,
+            vote,
+            generated_coins,
+        } => run_block(
+            &blob,
+            context,
+            *total_fees,
+            *block_weight,
+            *vote,
+            *generated_coins,
+        ),
+        Check::TxSemantic { hf, weight, fee } => run_tx_semantic(&blob, *hf, *weight, *fee),
+        Check::TxContextual {
+            hf,
+            chain_height,
+            time_lock_timestamp,
+            youngest_used_out_height,
+            outputs_with_amount,
+            rings,
+            time_locked_outs,
+        } => run_tx_contextual(
+            &blob,
+            *hf,
+            *chain_height,
+            *time_lock_timestamp,
+            *youngest_used_out_height,
+            outputs_with_amount,
+            rings,
+            time_locked_outs,
+        ),
+    };
+
+    match (res, &vector.error) {
+        (Ok(returned), None) => {
+            for (name, expected, got) in returned {
+                if let Some(expected) = expected.filter(|expected| *expected != got) {
+                    return Err(format!("returned {name} {got}, expected {expected}"));
+                }
+            }
+            Ok(())
+        }
+        (Ok(_), Some(expected)) => Err(format!("accepted, expected {expected}")),
+        (Err(e), expected) => {
+            let got = match e {
+                ExtendedConsensusError::ConErr(e) => format!("{e:?}"),
+                e => format!("{e:?}"),
+            };
+
+            match expected {
+                Some(expected) if expected == &got => Ok(()),
+                Some(expected) => Err(format!("rejected with {got}, expected {expected}")),
+                None => Err(format!("rejected with {got}, expected acceptance")),
+            }
+        }
+    }
+}
+
+/// Returns the vectors in all the vector files in `dir`.
+fn load_vectors(dir: &Path) -> Vec<(PathBuf, Vector)> {
+    let mut files = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<_>>();
+    files.sort();
+
+    files
+        .into_iter()
+        .flat_map(|file| {
+            let vectors: VectorFile = serde_json::from_slice(&fs::read(&file).unwrap())
+                .unwrap_or_else(|e| panic!("invalid vector file {}: {e}", file.display()));
+
+            vectors
+                .vectors
+                .into_iter()
+                .map(move |vector| (file.clone(), vector))
+        })
+        .collect()
+}
+
+#[test]
+fn conformance_vectors() {
+    let mut dirs = vec![Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/vectors")];
+    if let Some(dir) = std::env::var_os(VECTORS_DIR_ENV) {
+        dirs.push(dir.into());
+    }
+
+    let mut ran = 0;
+    let mut failures = Vec::new();
+
+    for dir in dirs {
+        for (file, vector) in load_vectors(&dir) {
+            ran += 1;
+
+            if let Err(e) = run_vector(&vector, &dir) {
+                failures.push(format!("{} - {}: {e}", file.display(), vector.name));
+            }
+        }
+    }
+
+    assert_ne!(ran, 0, "no vectors ran");
+    assert!(
+        failures.is_empty(),
+        "{} of {ran} vectors failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}
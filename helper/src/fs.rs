@@ -29,6 +29,7 @@
 
 //---------------------------------------------------------------------------------------------------- Use
 use std::{
+    io,
     path::{Path, PathBuf},
     sync::LazyLock,
 };
@@ -230,6 +231,82 @@ pub fn address_book_path(cache_dir: &Path, network: Network) -> PathBuf {
     path_with_network(cache_dir, network).join("addressbook")
 }
 
+/// Cuprate's blockchain context snapshot file.
+///
+/// This is the PATH used for the snapshot of the blockchain context caches.
+///
+/// ```rust
+/// use cuprate_helper::{network::Network, fs::{CUPRATE_CACHE_DIR, context_snapshot_path}};
+///
+/// assert_eq!(context_snapshot_path(&**CUPRATE_CACHE_DIR, Network::Mainnet).as_path(), CUPRATE_CACHE_DIR.join("context_snapshot"));
+/// assert_eq!(context_snapshot_path(&**CUPRATE_CACHE_DIR, Network::Stagenet).as_path(), CUPRATE_CACHE_DIR.join(Network::Stagenet.to_string()).join("context_snapshot"));
+/// assert_eq!(context_snapshot_path(&**CUPRATE_CACHE_DIR, Network::Testnet).as_path(), CUPRATE_CACHE_DIR.join(Network::Testnet.to_string()).join("context_snapshot"));
+/// ```
+pub fn context_snapshot_path(cache_dir: &Path, network: Network) -> PathBuf {
+    path_with_network(cache_dir, network).join("context_snapshot")
+}
+
+//---------------------------------------------------------------------------------------------------- Disk space
+/// Returns the amount of free bytes on the disk `path` is on, that are available to this process.
+///
+/// ```rust
+/// use cuprate_helper::fs::free_disk_space;
+///
+/// let dir = std::env::temp_dir();
+/// assert!(free_disk_space(&dir).unwrap() > 0);
+/// ```
+///
+/// # Errors
+/// This returns an error if `path` does not exist or the OS fails to get the free space.
+pub fn free_disk_space(path: &Path) -> io::Result<u64> {
+    #[cfg(target_os = "windows")]
+    {
+        use target_os_lib as windows;
+        use windows::{core::HSTRING, Win32::Storage::FileSystem::GetDiskFreeSpaceExW};
+
+        let mut free_bytes = 0_u64;
+
+        // SAFETY: calling C, `free_bytes` is valid to write to.
+        unsafe {
+            GetDiskFreeSpaceExW(
+                &HSTRING::from(path),
+                Some(std::ptr::addr_of_mut!(free_bytes)),
+                None,
+                None,
+            )
+        }
+        .map_err(io::Error::other)?;
+
+        Ok(free_bytes)
+    }
+
+    #[cfg(target_family = "unix")]
+    {
+        use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+        use target_os_lib as libc;
+
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+        // SAFETY: calling C, `path` is a valid C string and `stat` is valid to write to.
+        if unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // SAFETY: `statvfs` succeeded, so `stat` is initialized.
+        let stat = unsafe { stat.assume_init() };
+
+        // The integer types differ between platforms.
+        #[allow(
+            clippy::useless_conversion,
+            reason = "these are `u64` on some platforms"
+        )]
+        let free_bytes = u64::from(stat.f_bavail).saturating_mul(u64::from(stat.f_frsize));
+
+        Ok(free_bytes)
+    }
+}
+
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {
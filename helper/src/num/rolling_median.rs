@@ -138,6 +138,11 @@ where
         }
     }
 
+    /// Returns the items currently in the [`RollingMedian`], in order of insertion.
+    pub const fn window(&self) -> &VecDeque<T> {
+        &self.window
+    }
+
     /// Returns the number of items currently in the [`RollingMedian`].
     pub fn window_len(&self) -> usize {
         self.window.len()
@@ -9,7 +9,7 @@ mod rpc_handler_dummy;
 mod rpc_service;
 
 pub use router_builder::RouterBuilder;
-pub use rpc_handler::RpcHandler;
+pub use rpc_handler::{RpcHandler, DEFAULT_MAX_BATCH_SIZE};
 #[cfg(feature = "dummy")]
 pub use rpc_handler_dummy::RpcHandlerDummy;
 pub use rpc_service::RpcService;
@@ -19,7 +19,6 @@ pub use rpc_service::RpcService;
 mod test {
     extern crate axum;
     extern crate cuprate_test_utils;
-    extern crate serde_json;
     extern crate tokio;
     extern crate ureq;
 }
@@ -2,9 +2,11 @@
 
 //---------------------------------------------------------------------------------------------------- Import
 use axum::{extract::State, http::StatusCode, Json};
+use futures::future::join_all;
+use serde_json::Value;
 use tower::ServiceExt;
 
-use cuprate_json_rpc::{Id, Response};
+use cuprate_json_rpc::{error::ErrorObject, Batch, Id, Request, Response};
 use cuprate_rpc_types::{
     json::{JsonRpcRequest, JsonRpcResponse},
     RpcCallValue,
@@ -14,10 +16,75 @@ use crate::rpc_handler::RpcHandler;
 
 //---------------------------------------------------------------------------------------------------- Routes
 /// The `/json_rpc` route function used in [`crate::RouterBuilder`].
+///
+/// This accepts both single requests and
+/// [batches](https://www.jsonrpc.org/specification#batch) of requests,
+/// requests within a batch are handled concurrently.
 pub(crate) async fn json_rpc<H: RpcHandler>(
     State(handler): State<H>,
-    Json(request): Json<cuprate_json_rpc::Request<JsonRpcRequest>>,
-) -> Result<Json<Response<JsonRpcResponse>>, StatusCode> {
+    Json(request): Json<Batch<Value>>,
+) -> Result<Json<Batch<Response<JsonRpcResponse>>>, StatusCode> {
+    let requests = match request {
+        Batch::Single(request) => {
+            let Ok(request) = serde_json::from_value::<Request<JsonRpcRequest>>(request) else {
+                return Err(StatusCode::UNPROCESSABLE_ENTITY);
+            };
+
+            let Some(response) = handle_request(handler, request).await else {
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            };
+
+            return Ok(Json(Batch::Single(response)));
+        }
+        Batch::Batch(requests) => requests,
+    };
+
+    // JSON-RPC 2.0 rule:
+    // An empty batch must be responded to with a single `Invalid Request` error.
+    if requests.is_empty() {
+        return Ok(Json(Batch::Single(Response::invalid_request(Id::Null))));
+    }
+
+    if requests.len() > handler.max_batch_size() {
+        let mut error = ErrorObject::invalid_request();
+        error.message = "Batch size exceeds the maximum".into();
+        return Ok(Json(Batch::Single(Response::err(Id::Null, error))));
+    }
+
+    let responses = requests.into_iter().map(|request| {
+        let handler = handler.clone();
+
+        async move {
+            // JSON-RPC 2.0 rule:
+            // If there was an error in detecting the `Request`'s ID,
+            // the `Response` must contain an `Id::Null`
+            let id = request
+                .get("id")
+                .and_then(|id| serde_json::from_value::<Id>(id.clone()).ok())
+                .unwrap_or(Id::Null);
+
+            // Unlike single requests, an invalid request or a handler
+            // error can only fail its own element in the batch.
+            let Ok(request) = serde_json::from_value::<Request<JsonRpcRequest>>(request) else {
+                return Response::invalid_request(id);
+            };
+
+            handle_request(handler, request)
+                .await
+                .unwrap_or_else(|| Response::internal_error(id))
+        }
+    });
+
+    Ok(Json(Batch::Batch(join_all(responses).await)))
+}
+
+/// Handle a single JSON-RPC [`Request`].
+///
+/// Returns [`None`] if the [`RpcHandler`] returned an error.
+async fn handle_request<H: RpcHandler>(
+    handler: H,
+    request: Request<JsonRpcRequest>,
+) -> Option<Response<JsonRpcResponse>> {
     // TODO: <https://www.jsonrpc.org/specification#notification>
     //
     // JSON-RPC notifications (requests without `id`)
@@ -38,24 +105,137 @@ pub(crate) async fn json_rpc<H: RpcHandler>(
     // the functions themselves do not check if they are being called
     // from an (un)restricted context. This line must be here or all
     // methods will be allowed to be called freely.
+    //
+    // This is checked for every request, including each request within a batch.
     if request.body.is_restricted() && handler.is_restricted() {
         // The error when a restricted JSON-RPC method is called as per:
         //
         // - <https://github.com/monero-project/monero/blob/893916ad091a92e765ce3241b94e706ad012b62a/contrib/epee/include/net/http_server_handlers_map2.h#L244-L252>
         // - <https://github.com/monero-project/monero/blob/cc73fe71162d564ffda8e549b79a350bca53c454/src/rpc/core_rpc_server.h#L188>
-        return Ok(Json(Response::method_not_found(id)));
+        return Some(Response::method_not_found(id));
     }
 
     // Send request.
-    let Ok(response) = handler.oneshot(request.body).await else {
-        return Err(StatusCode::INTERNAL_SERVER_ERROR);
-    };
+    let response = handler.oneshot(request.body).await.ok()?;
 
-    Ok(Json(Response::ok(id, response)))
+    Some(Response::ok(id, response))
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {
-    // use super::*;
+    use serde_json::json;
+
+    use cuprate_json_rpc::error::ErrorCode;
+
+    use super::*;
+    use crate::{RpcHandlerDummy, DEFAULT_MAX_BATCH_SIZE};
+
+    /// Send `request` to the `/json_rpc` route of a [`RpcHandlerDummy`].
+    async fn send(
+        restricted: bool,
+        request: Value,
+    ) -> Result<Batch<Response<JsonRpcResponse>>, StatusCode> {
+        let handler = RpcHandlerDummy { restricted };
+        let request = serde_json::from_value(request).unwrap();
+
+        json_rpc(State(handler), Json(request))
+            .await
+            .map(|Json(response)| response)
+    }
+
+    /// Returns the error code of `response`, [`None`] if it succeeded.
+    fn error_code(response: &Response<JsonRpcResponse>) -> Option<ErrorCode> {
+        response.payload.as_ref().err().map(|e| e.code)
+    }
+
+    /// A `get_block_count` request with `id`.
+    fn get_block_count(id: u64) -> Value {
+        json!({"jsonrpc": "2.0", "id": id, "method": "get_block_count", "params": {}})
+    }
+
+    /// Batches over the limit are rejected as a whole.
+    #[tokio::test]
+    async fn batch_over_limit() {
+        let batch = (0..=DEFAULT_MAX_BATCH_SIZE)
+            .map(|id| get_block_count(id.try_into().unwrap()))
+            .collect();
+
+        let Batch::Single(response) = send(false, Value::Array(batch)).await.unwrap() else {
+            panic!("expected a single error response");
+        };
+        assert_eq!(response.id, Id::Null);
+        assert_eq!(error_code(&response), Some(ErrorCode::InvalidRequest));
+
+        // A batch at the limit is handled.
+        let batch = (0..DEFAULT_MAX_BATCH_SIZE)
+            .map(|id| get_block_count(id.try_into().unwrap()))
+            .collect();
+
+        let Batch::Batch(responses) = send(false, Value::Array(batch)).await.unwrap() else {
+            panic!("expected a batch response");
+        };
+        assert_eq!(responses.len(), DEFAULT_MAX_BATCH_SIZE);
+        assert!(responses.iter().all(|r| r.payload.is_ok()));
+    }
+
+    /// An empty batch is a single `Invalid Request` error.
+    #[tokio::test]
+    async fn empty_batch() {
+        let Batch::Single(response) = send(false, json!([])).await.unwrap() else {
+            panic!("expected a single error response");
+        };
+        assert_eq!(response.id, Id::Null);
+        assert_eq!(error_code(&response), Some(ErrorCode::InvalidRequest));
+    }
+
+    /// Invalid elements only fail themselves and keep their ID if it could be read.
+    #[tokio::test]
+    async fn batch_mixed_valid_invalid() {
+        let batch = json!([
+            get_block_count(1),
+            {"jsonrpc": "2.0", "id": 2, "method": "not_a_method"},
+            1,
+            {"jsonrpc": "2.0", "id": "four", "method": "get_block_count", "params": {}},
+        ]);
+
+        let Batch::Batch(responses) = send(false, batch).await.unwrap() else {
+            panic!("expected a batch response");
+        };
+        assert_eq!(responses.len(), 4);
+
+        assert_eq!(responses[0].id, Id::Num(1));
+        assert_eq!(error_code(&responses[0]), None);
+
+        assert_eq!(responses[1].id, Id::Num(2));
+        assert_eq!(error_code(&responses[1]), Some(ErrorCode::InvalidRequest));
+
+        assert_eq!(responses[2].id, Id::Null);
+        assert_eq!(error_code(&responses[2]), Some(ErrorCode::InvalidRequest));
+
+        assert_eq!(responses[3].id, Id::Str("four".into()));
+        assert_eq!(error_code(&responses[3]), None);
+    }
+
+    /// Restricted methods are checked for each request in a batch.
+    #[tokio::test]
+    async fn batch_restricted_methods() {
+        let batch = json!([
+            get_block_count(1),
+            {"jsonrpc": "2.0", "id": 2, "method": "relay_tx", "params": {"txids": []}},
+        ]);
+
+        let Batch::Batch(responses) = send(true, batch.clone()).await.unwrap() else {
+            panic!("expected a batch response");
+        };
+        assert_eq!(error_code(&responses[0]), None);
+        assert_eq!(responses[1].id, Id::Num(2));
+        assert_eq!(error_code(&responses[1]), Some(ErrorCode::MethodNotFound));
+
+        // Unrestricted servers allow them.
+        let Batch::Batch(responses) = send(false, batch).await.unwrap() else {
+            panic!("expected a batch response");
+        };
+        assert!(responses.iter().all(|r| r.payload.is_ok()));
+    }
 }
@@ -9,6 +9,10 @@ use cuprate_rpc_types::{
 
 use crate::RpcService;
 
+//---------------------------------------------------------------------------------------------------- Constants
+/// The default value of [`RpcHandler::max_batch_size`].
+pub const DEFAULT_MAX_BATCH_SIZE: usize = 100;
+
 //---------------------------------------------------------------------------------------------------- RpcHandler
 /// An RPC handler.
 ///
@@ -47,4 +51,15 @@ pub trait RpcHandler:
     /// will automatically be denied access when using the
     /// [`axum::Router`] provided by [`RouterBuilder`](crate::RouterBuilder).
     fn is_restricted(&self) -> bool;
+
+    /// The maximum amount of requests allowed in a
+    /// [JSON-RPC 2.0 batch](https://www.jsonrpc.org/specification#batch).
+    ///
+    /// Batches larger than this will be rejected as a whole
+    /// by the `/json_rpc` route, without any request being handled.
+    ///
+    /// Defaults to [`DEFAULT_MAX_BATCH_SIZE`].
+    fn max_batch_size(&self) -> usize {
+        DEFAULT_MAX_BATCH_SIZE
+    }
 }
@@ -92,6 +92,7 @@ impl Service<JsonRpcRequest> for RpcHandlerDummy {
             Req::FlushCache(_) => Resp::FlushCache(Default::default()),
             Req::AddAuxPow(_) => Resp::AddAuxPow(Default::default()),
             Req::GetTxIdsLoose(_) => Resp::GetTxIdsLoose(Default::default()),
+            Req::ApproveReorg(_) => Resp::ApproveReorg(Default::default()),
         };
 
         let (tx, rx) = channel();
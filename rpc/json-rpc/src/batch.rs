@@ -0,0 +1,231 @@
+//! JSON-RPC 2.0 batch object.
+
+//---------------------------------------------------------------------------------------------------- Use
+use serde::{Deserialize, Serialize};
+
+//---------------------------------------------------------------------------------------------------- Batch
+/// Either a single object, or [a batch](https://www.jsonrpc.org/specification#batch) of objects.
+///
+/// The generic `T` is the object type, i.e. a [`Request`](crate::Request)
+/// when (de)serializing requests and a [`Response`](crate::Response) when
+/// (de)serializing responses.
+///
+/// A batch is a JSON array of objects, a single object is not wrapped in an array:
+///
+/// ```rust
+/// use cuprate_json_rpc::{Batch, Id, Response};
+///
+/// let single = Batch::Single(Response::ok(Id::Num(0), "OK"));
+/// assert_eq!(
+///     serde_json::to_string(&single).unwrap(),
+///     r#"{"jsonrpc":"2.0","id":0,"result":"OK"}"#,
+/// );
+///
+/// let batch = Batch::Batch(vec![
+///     Response::ok(Id::Num(0), "OK"),
+///     Response::ok(Id::Num(1), "OK"),
+/// ]);
+/// assert_eq!(
+///     serde_json::to_string(&batch).unwrap(),
+///     r#"[{"jsonrpc":"2.0","id":0,"result":"OK"},{"jsonrpc":"2.0","id":1,"result":"OK"}]"#,
+/// );
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(untagged)]
+pub enum Batch<T> {
+    /// A batch of objects, i.e. `[{...}, {...}]`.
+    ///
+    /// Note that the specification does not allow empty batches,
+    /// this is not enforced during deserialization and must be checked by the caller.
+    //
+    // INVARIANT: this must be the first variant, a JSON
+    // array must not be attempted to be parsed as a `T`.
+    Batch(Vec<T>),
+
+    /// A single object, i.e. `{...}`.
+    Single(T),
+}
+
+impl<T> Batch<T> {
+    /// Returns `true` if this is [`Batch::Batch`].
+    ///
+    /// ```rust
+    /// use cuprate_json_rpc::Batch;
+    ///
+    /// assert!(Batch::<()>::Batch(vec![]).is_batch());
+    /// assert!(!Batch::Single(()).is_batch());
+    /// ```
+    pub const fn is_batch(&self) -> bool {
+        matches!(self, Self::Batch(_))
+    }
+
+    /// Returns the amount of objects.
+    ///
+    /// ```rust
+    /// use cuprate_json_rpc::Batch;
+    ///
+    /// assert_eq!(Batch::Single(()).len(), 1);
+    /// assert_eq!(Batch::Batch(vec![(), ()]).len(), 2);
+    /// assert_eq!(Batch::<()>::Batch(vec![]).len(), 0);
+    /// ```
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Batch(vec) => vec.len(),
+            Self::Single(_) => 1,
+        }
+    }
+
+    /// Returns `true` if this is an empty [`Batch::Batch`].
+    ///
+    /// ```rust
+    /// use cuprate_json_rpc::Batch;
+    ///
+    /// assert!(Batch::<()>::Batch(vec![]).is_empty());
+    /// assert!(!Batch::Single(()).is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Map the object(s) with `f`, keeping the [`Batch`] shape.
+    ///
+    /// ```rust
+    /// use cuprate_json_rpc::Batch;
+    ///
+    /// assert_eq!(Batch::Single(1).map(|i| i + 1), Batch::Single(2));
+    /// assert_eq!(Batch::Batch(vec![1, 2]).map(|i| i + 1), Batch::Batch(vec![2, 3]));
+    /// ```
+    pub fn map<U, F: FnMut(T) -> U>(self, mut f: F) -> Batch<U> {
+        match self {
+            Self::Batch(vec) => Batch::Batch(vec.into_iter().map(f).collect()),
+            Self::Single(t) => Batch::Single(f(t)),
+        }
+    }
+
+    /// Returns all objects in a [`Vec`].
+    ///
+    /// ```rust
+    /// use cuprate_json_rpc::Batch;
+    ///
+    /// assert_eq!(Batch::Single(1).into_vec(), vec![1]);
+    /// assert_eq!(Batch::Batch(vec![1, 2]).into_vec(), vec![1, 2]);
+    /// ```
+    pub fn into_vec(self) -> Vec<T> {
+        match self {
+            Self::Batch(vec) => vec,
+            Self::Single(t) => vec![t],
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Trait impl
+impl<T> From<T> for Batch<T> {
+    fn from(t: T) -> Self {
+        Self::Single(t)
+    }
+}
+
+impl<T> From<Vec<T>> for Batch<T> {
+    fn from(vec: Vec<T>) -> Self {
+        Self::Batch(vec)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- TESTS
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{id::Id, request::Request, response::Response, tests::Body};
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    /// A single request object deserializes as [`Batch::Single`].
+    #[test]
+    fn single_request() {
+        let json = json!({
+            "jsonrpc": "2.0",
+            "id": 0,
+            "method": "a",
+            "params": [0],
+        });
+
+        let batch = serde_json::from_value::<Batch<Request<Body<[u8; 1]>>>>(json).unwrap();
+
+        assert_eq!(
+            batch,
+            Batch::Single(Request::new_with_id(
+                Id::Num(0),
+                Body {
+                    method: "a".into(),
+                    params: [0],
+                }
+            ))
+        );
+    }
+
+    /// An array of request objects deserializes as [`Batch::Batch`], keeping the order.
+    #[test]
+    fn batch_request() {
+        let json = json!([
+            { "jsonrpc": "2.0", "id": 0, "method": "a", "params": [0] },
+            { "jsonrpc": "2.0", "id": "1", "method": "b", "params": [1] },
+            { "jsonrpc": "2.0", "method": "c", "params": [2] },
+        ]);
+
+        let batch = serde_json::from_value::<Batch<Request<Body<[u8; 1]>>>>(json).unwrap();
+
+        let expected = vec![
+            Request::new_with_id(
+                Id::Num(0),
+                Body {
+                    method: "a".into(),
+                    params: [0],
+                },
+            ),
+            Request::new_with_id(
+                Id::Str("1".into()),
+                Body {
+                    method: "b".into(),
+                    params: [1],
+                },
+            ),
+            Request::new(Body {
+                method: "c".into(),
+                params: [2],
+            }),
+        ];
+
+        assert_eq!(batch, Batch::Batch(expected));
+    }
+
+    /// An empty array deserializes as an empty [`Batch::Batch`].
+    #[test]
+    fn empty_batch() {
+        let batch = serde_json::from_str::<Batch<Request<Body<[u8; 1]>>>>("[]").unwrap();
+        assert!(batch.is_batch());
+        assert!(batch.is_empty());
+    }
+
+    /// Batch responses keep each element's ID and error.
+    #[test]
+    fn batch_response() {
+        let batch = Batch::Batch(vec![
+            Response::ok(Id::Num(0), "OK".to_string()),
+            Response::method_not_found(Id::Str("1".into())),
+            Response::invalid_request(Id::Null),
+        ]);
+
+        let json = json!([
+            { "jsonrpc": "2.0", "id": 0, "result": "OK" },
+            { "jsonrpc": "2.0", "id": "1", "error": { "code": -32601, "message": "Method not found" } },
+            { "jsonrpc": "2.0", "id": null, "error": { "code": -32600, "message": "Invalid Request" } },
+        ]);
+
+        assert_eq!(serde_json::to_value(&batch).unwrap(), json);
+        assert_eq!(
+            serde_json::from_value::<Batch<Response<String>>>(json).unwrap(),
+            batch
+        );
+    }
+}
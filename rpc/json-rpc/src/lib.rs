@@ -2,6 +2,9 @@
 
 pub mod error;
 
+mod batch;
+pub use batch::Batch;
+
 mod id;
 pub use id::Id;
 
@@ -122,6 +122,7 @@ impl From<ChainInfo> for crate::misc::ChainInfo {
             length: x.length,
             main_chain_parent_block: Hex(x.main_chain_parent_block),
             wide_difficulty: (x.difficulty, x.difficulty_top64).hex_prefix(),
+            reorg_held: false,
         }
     }
 }
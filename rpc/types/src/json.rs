@@ -13,10 +13,10 @@ use cuprate_types::rpc::{
 
 use crate::{
     base::{AccessResponseBase, ResponseBase},
-    macros::define_request_and_response,
+    macros::{define_request, define_request_and_response, define_response},
     misc::{
-        BlockHeader, ChainInfo, ConnectionInfo, Distribution, GetBan, HistogramEntry, SetBan, Span,
-        Status, SyncInfoPeer,
+        BlockHeader, ChainInfo, ConnectionInfo, DatabaseTableInfo, Distribution, GetBan,
+        HistogramEntry, SetBan, Span, Status, SyncInfoPeer,
     },
     rpc_call::RpcCallValue,
 };
@@ -372,6 +372,14 @@ define_request_and_response! {
         white_peerlist_size: u64,
         wide_cumulative_difficulty: String,
         wide_difficulty: String,
+        /// The amount of free bytes in the database file, these are reused before the file grows.
+        ///
+        /// Cuprate extension, this is `0` in restricted mode.
+        database_free_space: u64 = default::<u64>(), "default",
+        /// The statistics of each database table.
+        ///
+        /// Cuprate extension, this is empty in restricted mode.
+        database_tables: Vec<DatabaseTableInfo> = default::<Vec<DatabaseTableInfo>>(), "default",
     }
 }
 
@@ -521,12 +529,21 @@ define_request_and_response! {
 
     Request {
         grace_blocks: u64 = default::<u64>(), "default",
+        /// The amount of blocks a transaction should be included within.
+        ///
+        /// Cuprate extension, if this is not `0` `target_fee` is set.
+        target_blocks: u64 = default::<u64>(), "default",
     },
 
     AccessResponseBase {
         fee: u64,
         fees: Vec<u64>,
         quantization_mask: u64 = default_one::<u64>(), "default_one",
+        /// The fee per byte needed to be included within `target_blocks` blocks.
+        ///
+        /// Cuprate extension, this uses the fees of transactions in
+        /// the tx-pool and recent blocks, it is `0` if `target_blocks` is.
+        target_fee: u64 = default::<u64>(), "default",
     }
 }
 
@@ -726,6 +743,26 @@ define_request_and_response! {
     }
 }
 
+//---------------------------------------------------------------------------------------------------- Cuprate extensions
+// These methods do not exist in `monerod`.
+
+define_request! {
+    /// Approve a re-org that was held back because it was deeper than `--max-reorg-depth`.
+    ///
+    /// `alt_top_hash` must be the `alt_id` of the `json-full-reorg_alert`
+    /// that was published when the re-org was held back.
+    ApproveReorgRequest (restricted) {
+        alt_top_hash: Hex<32>,
+    }
+}
+
+define_response! {
+    /// The response to [`ApproveReorgRequest`].
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    #[derive(Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    ResponseBase => ApproveReorgResponse {}
+}
+
 //---------------------------------------------------------------------------------------------------- Request
 /// JSON-RPC requests.
 ///
@@ -772,6 +809,7 @@ pub enum JsonRpcRequest {
     FlushCache(FlushCacheRequest),
     AddAuxPow(AddAuxPowRequest),
     GetTxIdsLoose(GetTxIdsLooseRequest),
+    ApproveReorg(ApproveReorgRequest),
 }
 
 impl RpcCallValue for JsonRpcRequest {
@@ -808,6 +846,7 @@ impl RpcCallValue for JsonRpcRequest {
             Self::PruneBlockchain(x) => x.is_restricted(),
             Self::CalcPow(x) => x.is_restricted(),
             Self::FlushCache(x) => x.is_restricted(),
+            Self::ApproveReorg(x) => x.is_restricted(),
         }
     }
 
@@ -844,6 +883,7 @@ impl RpcCallValue for JsonRpcRequest {
             Self::PruneBlockchain(x) => x.is_empty(),
             Self::CalcPow(x) => x.is_empty(),
             Self::FlushCache(x) => x.is_empty(),
+            Self::ApproveReorg(x) => x.is_empty(),
         }
     }
 }
@@ -907,6 +947,7 @@ pub enum JsonRpcResponse {
     FlushCache(FlushCacheResponse),
     AddAuxPow(AddAuxPowResponse),
     GetTxIdsLoose(GetTxIdsLooseResponse),
+    ApproveReorg(ApproveReorgResponse),
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
@@ -1443,6 +1484,8 @@ mod test {
                 white_peerlist_size: 1000,
                 wide_cumulative_difficulty: "0x514bf349299d2eb".into(),
                 wide_difficulty: "0x415c05a7a2".into(),
+                database_free_space: 0,
+                database_tables: vec![],
             },
         );
     }
@@ -1665,6 +1708,7 @@ mod test {
                 fee: 20000,
                 fees: vec![20000, 80000, 320000, 4000000],
                 quantization_mask: 10000,
+                target_fee: 0,
             },
         );
     }
@@ -1691,6 +1735,7 @@ mod test {
                             "69b5075ea627d6ba06b1c30b7e023884eeaef5282cf58ec847dab838ddbcdd86"
                         )),
                         wide_difficulty: "0x4f5c1cb79e22635".into(),
+                        reorg_held: false,
                     },
                     ChainInfo {
                         block_hash: Hex(hex!(
@@ -1707,6 +1752,7 @@ mod test {
                             "fd522fcc4cefe5c8c0e5c5600981b3151772c285df3a4e38e5c4011cf466d2cb"
                         )),
                         wide_difficulty: "0x4ec469f8b9ee50d".into(),
+                        reorg_held: false,
                     },
                 ],
             },
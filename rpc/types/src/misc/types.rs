@@ -202,6 +202,11 @@ define_struct_and_impl_epee! {
         length: u64,
         main_chain_parent_block: Hex<32>,
         wide_difficulty: String,
+        /// If the re-org to this chain is held until it is manually approved.
+        ///
+        /// Cuprate extension, re-orgs deeper than the configured
+        /// maximum automatic re-org depth are held.
+        reorg_held: bool = default::<bool>(),
     }
 }
 
@@ -246,6 +251,7 @@ define_struct_and_impl_epee! {
     /// Used in:
     /// - [`crate::bin::GetOutsRequest`]
     /// - [`crate::other::GetOutsRequest`]
+    /// - [`crate::other::GetOutsResponse`]
     GetOutputsOut {
         amount: u64,
         index: u64,
@@ -326,6 +332,28 @@ define_struct_and_impl_epee! {
     }
 }
 
+define_struct_and_impl_epee! {
+    /// The statistics of a single database table.
+    ///
+    /// Cuprate extension, used in [`crate::json::GetInfoResponse`].
+    DatabaseTableInfo {
+        /// The name of the table.
+        name: String,
+        /// The amount of entries in the table.
+        entries: u64,
+        /// The depth of the table's B-tree.
+        depth: u32,
+        /// The amount of internal (non-leaf) pages.
+        branch_pages: u64,
+        /// The amount of leaf pages.
+        leaf_pages: u64,
+        /// The amount of overflow pages.
+        overflow_pages: u64,
+        /// The amount of bytes used by the table.
+        size_bytes: u64,
+    }
+}
+
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {}
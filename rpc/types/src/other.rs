@@ -85,6 +85,17 @@ define_request_and_response! {
     AccessResponseBase {
         /// These [`u8`]s are [`cuprate_types::rpc::KeyImageSpentStatus`].
         spent_status: Vec<u8>,
+        /// The hashes of the transactions that spent the key images.
+        ///
+        /// Cuprate extension, this is only set if the key image index is enabled.
+        /// The index of each entry corresponds with `spent_status`, key images that
+        /// are not (yet) in the index have a hash of all zeros.
+        spent_tx_hashes: Vec<Hex<32>> = default::<Vec<Hex<32>>>(), "default",
+        /// The heights of the blocks that spent the key images.
+        ///
+        /// Cuprate extension, this is set along with `spent_tx_hashes`,
+        /// key images that are not (yet) in the index have a height of `0`.
+        spent_heights: Vec<u64> = default::<Vec<u64>>(), "default",
     }
 }
 
@@ -115,6 +126,18 @@ define_request_and_response! {
         too_big: bool,
         too_few_outputs: bool,
         tx_extra_too_big: bool,
+        /// The tx extra field could not be parsed or has a tag the relay policy does not allow.
+        ///
+        /// Cuprate extension.
+        tx_extra_not_allowed: bool = default::<bool>(), "default",
+        /// The tx has more inputs than the relay policy allows.
+        ///
+        /// Cuprate extension.
+        too_many_inputs: bool = default::<bool>(), "default",
+        /// The tx has more outputs than the relay policy allows.
+        ///
+        /// Cuprate extension.
+        too_many_outputs: bool = default::<bool>(), "default",
     }
 }
 
@@ -393,10 +416,23 @@ define_request_and_response! {
     Request {
         outputs: Vec<GetOutputsOut>,
         get_txid: bool,
+        /// One-time output public keys to look up.
+        ///
+        /// Cuprate extension, this requires the output key index to be enabled.
+        /// The found outputs are returned in `outs` after the outputs in `outputs`.
+        output_keys: Vec<Hex<32>> = default::<Vec<Hex<32>>>(), "default",
     },
 
     ResponseBase {
         outs: Vec<OutKey>,
+        /// The amount and amount index of each found output in `output_keys`.
+        ///
+        /// Cuprate extension, the entries are in the same order as `output_keys`.
+        output_indices: Vec<GetOutputsOut> = default::<Vec<GetOutputsOut>>(), "default",
+        /// The keys in `output_keys` that are not in the output key index.
+        ///
+        /// Cuprate extension.
+        missed_output_keys: Vec<Hex<32>> = default::<Vec<Hex<32>>>(), "default",
     }
 }
 
@@ -760,6 +796,8 @@ mod test {
             IsKeyImageSpentResponse {
                 base: AccessResponseBase::OK,
                 spent_status: vec![1, 1],
+                spent_tx_hashes: vec![],
+                spent_heights: vec![],
             },
         );
     }
@@ -804,6 +842,9 @@ mod test {
                 too_few_outputs: false,
                 tx_extra_too_big: false,
                 nonzero_unlock_time: false,
+                tx_extra_not_allowed: false,
+                too_many_inputs: false,
+                too_many_outputs: false,
             },
         );
     }
@@ -1188,6 +1229,7 @@ mod test {
                     },
                 ],
                 get_txid: true,
+                output_keys: vec![],
             },
         );
     }
@@ -1232,6 +1274,8 @@ mod test {
                         unlocked: true,
                     },
                 ],
+                output_indices: vec![],
+                missed_output_keys: vec![],
             },
         );
     }
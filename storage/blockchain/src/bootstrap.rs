@@ -0,0 +1,458 @@
+//! `monerod` raw bootstrap files.
+//!
+//! This module reads and writes the `blockchain.raw` files created
+//! by `monero-blockchain-export` and read by `monero-blockchain-import`.
+//!
+//! # Format
+//! All integers are little-endian.
+//!
+//! | Bytes         | Contents |
+//! |---------------|----------|
+//! | 4             | [`BOOTSTRAP_MAGIC`] as a `u32`
+//! | [`HEADER_SIZE`] | The file header, see below
+//! | ...           | Chunks, a `u32` length followed by a [`BootstrapBlock`]
+//!
+//! The file header contains 2 length-prefixed objects, `file_info`
+//! (the format version and header size) and `blocks_info` (unused,
+//! always zeroed by `monerod`), and is padded with zeros.
+//!
+//! `monerod` writes 1 block per chunk, this is also the case here.
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::io::{self, Read, Write};
+
+use monero_serai::{
+    block::Block,
+    io::{read_varint, write_varint},
+    transaction::Transaction,
+};
+
+use cuprate_database::{DatabaseRo, RuntimeError};
+use cuprate_helper::{
+    cast::u32_to_usize,
+    map::{combine_low_high_bits_to_u128, split_u128_into_low_high_bits},
+};
+use cuprate_types::TransactionBlobs;
+
+use crate::{
+    ops::block::get_block_complete_entry_from_height, tables::TablesIter, types::BlockHeight,
+};
+
+//---------------------------------------------------------------------------------------------------- Constants
+/// The magic bytes at the start of a raw bootstrap file.
+pub const BOOTSTRAP_MAGIC: u32 = 0x2872_1586;
+
+/// The size of the header, after the [`BOOTSTRAP_MAGIC`].
+pub const HEADER_SIZE: u32 = 1024;
+
+/// The maximum size of a chunk, larger chunks are rejected.
+///
+/// This is the same limit `monerod` uses.
+pub const MAX_CHUNK_SIZE: u32 = 1_000_000;
+
+/// The bootstrap format version written in the header, `(major, minor)`.
+const FORMAT_VERSION: (u64, u64) = (0, 1);
+
+//---------------------------------------------------------------------------------------------------- BootstrapBlock
+/// A block in a raw bootstrap file.
+///
+/// This is `monerod`'s `bootstrap::block_package`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BootstrapBlock {
+    /// The block.
+    pub block: Block,
+    /// The block's transactions, excluding the miner transaction.
+    pub txs: Vec<Transaction>,
+    /// The block's weight.
+    pub block_weight: usize,
+    /// The cumulative difficulty of the chain up to and including this block.
+    pub cumulative_difficulty: u128,
+    /// The total amount of generated coins up to and including this block.
+    pub cumulative_generated_coins: u64,
+}
+
+impl BootstrapBlock {
+    /// Deserialize a [`BootstrapBlock`] from a chunk.
+    ///
+    /// # Errors
+    /// This returns an error if the chunk is not a valid block package,
+    /// or the transactions do not match [`Block::transactions`].
+    pub fn read(mut chunk: &[u8]) -> io::Result<Self> {
+        let chunk = &mut chunk;
+
+        let block = Block::read(chunk)?;
+        let txs = (0..read_varint::<_, usize>(chunk)?)
+            .map(|_| Transaction::read(chunk))
+            .collect::<io::Result<Vec<_>>>()?;
+        let block_weight = read_varint(chunk)?;
+        let cumulative_difficulty_high: u64 = read_varint(chunk)?;
+        let cumulative_difficulty_low: u64 = read_varint(chunk)?;
+        let cumulative_generated_coins = read_varint(chunk)?;
+
+        if !chunk.is_empty() {
+            return Err(invalid_data("trailing bytes after block package"));
+        }
+
+        if txs.len() != block.transactions.len()
+            || txs
+                .iter()
+                .zip(&block.transactions)
+                .any(|(tx, hash)| tx.hash() != *hash)
+        {
+            return Err(invalid_data("transactions do not match the block"));
+        }
+
+        Ok(Self {
+            block,
+            txs,
+            block_weight,
+            cumulative_difficulty: combine_low_high_bits_to_u128(
+                cumulative_difficulty_low,
+                cumulative_difficulty_high,
+            ),
+            cumulative_generated_coins,
+        })
+    }
+
+    /// Serialize this [`BootstrapBlock`] into a chunk.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut chunk = self.block.serialize();
+
+        write_varint(&self.txs.len(), &mut chunk).unwrap();
+        for tx in &self.txs {
+            tx.write(&mut chunk).unwrap();
+        }
+
+        write_package_info(
+            &mut chunk,
+            self.block_weight,
+            self.cumulative_difficulty,
+            self.cumulative_generated_coins,
+        );
+
+        chunk
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- BootstrapReader
+/// A reader of raw bootstrap files.
+pub struct BootstrapReader<R> {
+    /// The underlying reader, positioned at the start of a chunk.
+    reader: R,
+}
+
+impl<R: Read> BootstrapReader<R> {
+    /// Read the header of a raw bootstrap file.
+    ///
+    /// # Errors
+    /// This returns an error if the file does not start with a valid header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        if read_u32(&mut reader)? != BOOTSTRAP_MAGIC {
+            return Err(invalid_data("not a raw bootstrap file"));
+        }
+
+        let file_info_len = read_u32(&mut reader)?;
+        if file_info_len >= HEADER_SIZE {
+            return Err(invalid_data("invalid header"));
+        }
+
+        let mut file_info = vec![0; u32_to_usize(file_info_len)];
+        reader.read_exact(&mut file_info)?;
+        let file_info = &mut file_info.as_slice();
+        let _major_version: u64 = read_varint(file_info)?;
+        let _minor_version: u64 = read_varint(file_info)?;
+        let header_size: u64 = read_varint(file_info)?;
+
+        // Skip the rest of the header.
+        let Some(rest) = header_size.checked_sub(4 + u64::from(file_info_len)) else {
+            return Err(invalid_data("invalid header size"));
+        };
+        let skipped = io::copy(&mut (&mut reader).take(rest), &mut io::sink())?;
+        if skipped != rest {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(Self { reader })
+    }
+
+    /// Read the next chunk, returns [`None`] at the end of the file.
+    ///
+    /// The chunk can be deserialized with [`BootstrapBlock::read`].
+    ///
+    /// # Errors
+    /// This returns an error if the chunk could not be read or is larger than [`MAX_CHUNK_SIZE`].
+    pub fn next_chunk(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let Some(len) = self.next_chunk_len()? else {
+            return Ok(None);
+        };
+
+        let mut chunk = vec![0; u32_to_usize(len)];
+        self.reader.read_exact(&mut chunk)?;
+        Ok(Some(chunk))
+    }
+
+    /// Skip the next chunk, returns `false` at the end of the file.
+    ///
+    /// # Errors
+    /// This returns an error if the chunk could not be read or is larger than [`MAX_CHUNK_SIZE`].
+    pub fn skip_chunk(&mut self) -> io::Result<bool> {
+        let Some(len) = self.next_chunk_len()? else {
+            return Ok(false);
+        };
+
+        let skipped = io::copy(&mut (&mut self.reader).take(len.into()), &mut io::sink())?;
+        if skipped != u64::from(len) {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(true)
+    }
+
+    /// Read the length of the next chunk, returns [`None`] at the end of the file.
+    fn next_chunk_len(&mut self) -> io::Result<Option<u32>> {
+        let len = match read_u32(&mut self.reader) {
+            Ok(len) => len,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+
+        if len > MAX_CHUNK_SIZE {
+            return Err(invalid_data("chunk is too large"));
+        }
+
+        Ok(Some(len))
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- BootstrapWriter
+/// A writer of raw bootstrap files.
+pub struct BootstrapWriter<W> {
+    /// The underlying writer.
+    writer: W,
+}
+
+impl<W: Write> BootstrapWriter<W> {
+    /// Write the header of a new raw bootstrap file.
+    ///
+    /// # Errors
+    /// This returns any error from writing to `writer`.
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut file_info = Vec::new();
+        write_varint(&FORMAT_VERSION.0, &mut file_info)?;
+        write_varint(&FORMAT_VERSION.1, &mut file_info)?;
+        write_varint(&u64::from(HEADER_SIZE), &mut file_info)?;
+
+        // `monerod` never fills this in, `block_first`, `block_last` and `block_last_pos`.
+        let mut blocks_info = Vec::new();
+        for _ in 0..3 {
+            write_varint(&0_u64, &mut blocks_info)?;
+        }
+
+        let mut header = Vec::with_capacity(u32_to_usize(HEADER_SIZE));
+        for object in [file_info, blocks_info] {
+            header.extend_from_slice(&u32::try_from(object.len()).unwrap().to_le_bytes());
+            header.extend_from_slice(&object);
+        }
+        header.resize(u32_to_usize(HEADER_SIZE), 0);
+
+        writer.write_all(&BOOTSTRAP_MAGIC.to_le_bytes())?;
+        writer.write_all(&header)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Write a chunk, created by [`BootstrapBlock::serialize`] or [`block_chunk`].
+    ///
+    /// # Errors
+    /// This returns an error if the chunk is larger than [`MAX_CHUNK_SIZE`],
+    /// or any error from writing to the writer.
+    pub fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(chunk.len())
+            .ok()
+            .filter(|len| *len <= MAX_CHUNK_SIZE)
+            .ok_or_else(|| invalid_data("chunk is too large"))?;
+
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(chunk)
+    }
+
+    /// Flush and return the underlying writer.
+    ///
+    /// # Errors
+    /// This returns any error from flushing the writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Free functions
+/// Create the raw bootstrap chunk of the block at `block_height`.
+///
+/// This is equal to [`BootstrapBlock::serialize`], without deserializing
+/// the block and transactions from the database.
+///
+/// # Errors
+/// This returns [`RuntimeError::KeyNotFound`] if the block does not exist.
+pub fn block_chunk(
+    block_height: &BlockHeight,
+    tables: &impl TablesIter,
+) -> Result<Vec<u8>, RuntimeError> {
+    let entry = get_block_complete_entry_from_height(block_height, tables)?;
+    let block_info = tables.block_infos().get(block_height)?;
+
+    let TransactionBlobs::Normal(txs) = entry.txs else {
+        unreachable!("the database only returns full transactions");
+    };
+
+    let mut chunk = entry.block.to_vec();
+    write_varint(&txs.len(), &mut chunk).unwrap();
+    for tx in txs {
+        chunk.extend_from_slice(&tx);
+    }
+
+    write_package_info(
+        &mut chunk,
+        block_info.weight,
+        combine_low_high_bits_to_u128(
+            block_info.cumulative_difficulty_low,
+            block_info.cumulative_difficulty_high,
+        ),
+        block_info.cumulative_generated_coins,
+    );
+
+    Ok(chunk)
+}
+
+//---------------------------------------------------------------------------------------------------- Private
+/// Write the fields of a block package after the transactions.
+fn write_package_info(
+    chunk: &mut Vec<u8>,
+    block_weight: usize,
+    cumulative_difficulty: u128,
+    cumulative_generated_coins: u64,
+) {
+    // `monerod` serializes 128-bit difficulties as 2 varints, the high bits first.
+    let (cumulative_difficulty_low, cumulative_difficulty_high) =
+        split_u128_into_low_high_bits(cumulative_difficulty);
+
+    write_varint(&block_weight, chunk).unwrap();
+    write_varint(&cumulative_difficulty_high, chunk).unwrap();
+    write_varint(&cumulative_difficulty_low, chunk).unwrap();
+    write_varint(&cumulative_generated_coins, chunk).unwrap();
+}
+
+/// Read a little-endian `u32`.
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Create an [`io::ErrorKind::InvalidData`] error.
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use cuprate_database::{config::Backend, Env, EnvInner, TxRw};
+    use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
+
+    use crate::{
+        ops::block::add_block,
+        tables::OpenTables,
+        tests::{backend_tests, tmp_concrete_env},
+    };
+
+    use super::*;
+
+    /// The header is the size `monerod` expects and can be read back.
+    #[test]
+    fn header() {
+        let file = BootstrapWriter::new(Vec::new()).unwrap().finish().unwrap();
+        assert_eq!(file.len(), 4 + u32_to_usize(HEADER_SIZE));
+        assert_eq!(file[..4], BOOTSTRAP_MAGIC.to_le_bytes());
+
+        let mut reader = BootstrapReader::new(file.as_slice()).unwrap();
+        assert_eq!(reader.next_chunk().unwrap(), None);
+
+        // Wrong magic.
+        let mut file = file;
+        file[0] = 0;
+        assert!(BootstrapReader::new(file.as_slice()).is_err());
+    }
+
+    /// Blocks exported from the database can be read back.
+    fn export_read(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
+        let env_inner = env.env_inner();
+
+        let mut blocks = [
+            BLOCK_V1_TX2.clone(),
+            BLOCK_V9_TX3.clone(),
+            BLOCK_V16_TX0.clone(),
+        ];
+        for (height, block) in blocks.iter_mut().enumerate() {
+            block.height = height;
+        }
+
+        {
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+            for block in &blocks {
+                add_block(block, &mut tables).unwrap();
+            }
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let tables = env_inner.open_tables(&tx_ro).unwrap();
+
+        let mut writer = BootstrapWriter::new(Vec::new()).unwrap();
+        for block in &blocks {
+            writer
+                .write_chunk(&block_chunk(&block.height, &tables).unwrap())
+                .unwrap();
+        }
+        let file = writer.finish().unwrap();
+
+        let mut reader = BootstrapReader::new(file.as_slice()).unwrap();
+        let mut generated_coins = 0;
+        for block in &blocks {
+            generated_coins += block.generated_coins;
+
+            let chunk = reader.next_chunk().unwrap().unwrap();
+            let read = BootstrapBlock::read(&chunk).unwrap();
+
+            assert_eq!(read.block, block.block);
+            assert_eq!(
+                read.txs,
+                block.txs.iter().map(|tx| tx.tx.clone()).collect::<Vec<_>>()
+            );
+            assert_eq!(read.block_weight, block.weight);
+            assert_eq!(read.cumulative_difficulty, block.cumulative_difficulty);
+            assert_eq!(read.cumulative_generated_coins, generated_coins);
+
+            // Re-serializing is the same as exporting.
+            assert_eq!(read.serialize(), chunk);
+        }
+        assert_eq!(reader.next_chunk().unwrap(), None);
+
+        // Skipping chunks.
+        let mut reader = BootstrapReader::new(file.as_slice()).unwrap();
+        assert!(reader.skip_chunk().unwrap());
+        assert!(reader.skip_chunk().unwrap());
+        let chunk = reader.next_chunk().unwrap().unwrap();
+        assert_eq!(BootstrapBlock::read(&chunk).unwrap().block, blocks[2].block);
+        assert!(!reader.skip_chunk().unwrap());
+    }
+
+    backend_tests! {
+        export_read,
+    }
+}
@@ -46,7 +46,10 @@ use std::{borrow::Cow, path::PathBuf};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use cuprate_database::{config::SyncMode, resize::ResizeAlgorithm};
+use cuprate_database::{
+    config::{Backend, SyncMode},
+    resize::ResizeAlgorithm,
+};
 use cuprate_helper::{
     fs::{blockchain_path, CUPRATE_DATA_DIR},
     network::Network,
@@ -71,6 +74,12 @@ pub struct ConfigBuilder {
 
     /// [`Config::reader_threads`].
     reader_threads: Option<ReaderThreads>,
+
+    /// [`Config::key_image_index`].
+    key_image_index: bool,
+
+    /// [`Config::output_key_index`].
+    output_key_index: bool,
 }
 
 impl ConfigBuilder {
@@ -87,6 +96,8 @@ impl ConfigBuilder {
                 Network::Mainnet,
             ))),
             reader_threads: None,
+            key_image_index: false,
+            output_key_index: false,
         }
     }
 
@@ -112,8 +123,11 @@ impl ConfigBuilder {
             .build();
 
         Config {
+            network: self.network,
             db_config,
             reader_threads,
+            key_image_index: self.key_image_index,
+            output_key_index: self.output_key_index,
         }
     }
 
@@ -131,6 +145,13 @@ impl ConfigBuilder {
         self
     }
 
+    /// Calls [`cuprate_database::config::ConfigBuilder::backend`].
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.db_config = self.db_config.backend(backend);
+        self
+    }
+
     /// Calls [`cuprate_database::config::ConfigBuilder::sync_mode`].
     #[must_use]
     pub fn sync_mode(mut self, sync_mode: SyncMode) -> Self {
@@ -152,6 +173,20 @@ impl ConfigBuilder {
         self
     }
 
+    /// Enable or disable the optional key image index, see [`Config::key_image_index`].
+    #[must_use]
+    pub const fn key_image_index(mut self, key_image_index: bool) -> Self {
+        self.key_image_index = key_image_index;
+        self
+    }
+
+    /// Enable or disable the optional output key index, see [`Config::output_key_index`].
+    #[must_use]
+    pub const fn output_key_index(mut self, output_key_index: bool) -> Self {
+        self.output_key_index = output_key_index;
+        self
+    }
+
     /// Tune the [`ConfigBuilder`] for the highest performing,
     /// but also most resource-intensive & maybe risky settings.
     ///
@@ -187,6 +222,8 @@ impl Default for ConfigBuilder {
                 Network::default(),
             ))),
             reader_threads: Some(ReaderThreads::default()),
+            key_image_index: false,
+            output_key_index: false,
         }
     }
 }
@@ -202,11 +239,27 @@ impl Default for ConfigBuilder {
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Config {
+    /// The network this blockchain database is for.
+    pub network: Network,
+
     /// The database configuration.
     pub db_config: cuprate_database::config::Config,
 
     /// Database reader thread count.
     pub reader_threads: ReaderThreads,
+
+    /// Whether to keep the [`KeyImageSpends`](crate::tables::KeyImageSpends) index,
+    /// mapping key images to where they were spent.
+    ///
+    /// When this is first enabled, blocks already in the database need to be backfilled,
+    /// see [`crate::ops::key_image`]. Disabling this clears the index.
+    pub key_image_index: bool,
+
+    /// Whether to keep the [`OutputKeys`](crate::tables::OutputKeys) index,
+    /// mapping output public keys to their amount and amount index.
+    ///
+    /// This is backfilled and cleared the same way as [`Config::key_image_index`].
+    pub output_key_index: bool,
 }
 
 impl Config {
@@ -237,6 +290,9 @@ impl Config {
     /// assert_eq!(config.db_config.sync_mode, SyncMode::default());
     /// assert_eq!(config.db_config.resize_algorithm, ResizeAlgorithm::default());
     /// assert_eq!(config.reader_threads, ReaderThreads::default());
+    /// assert_eq!(config.network, Network::Mainnet);
+    /// assert!(!config.key_image_index);
+    /// assert!(!config.output_key_index);
     /// ```
     pub fn new() -> Self {
         ConfigBuilder::default().build()
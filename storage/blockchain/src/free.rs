@@ -1,9 +1,23 @@
 //! General free functions (related to the database).
 
 //---------------------------------------------------------------------------------------------------- Import
-use cuprate_database::{ConcreteEnv, Env, EnvInner, InitError, RuntimeError, TxRw};
+use cuprate_database::{
+    migration::{check_schema, migrate, Schema},
+    ConcreteEnv, DatabaseRo, DbResult, Env, EnvInner, InitError, RuntimeError, Table, TxRw,
+};
 
-use crate::{config::Config, tables::OpenTables};
+use crate::{
+    config::Config,
+    constants::DATABASE_VERSION,
+    migration::MIGRATIONS,
+    ops::{
+        blockchain::chain_height,
+        property::{
+            get_index_height, set_index_height, METADATA_KEY_IMAGE_INDEX, METADATA_OUTPUT_KEY_INDEX,
+        },
+    },
+    tables::{BlockHeights, BlockInfos, KeyImageSpends, Metadata, OpenTables, OutputKeys},
+};
 
 //---------------------------------------------------------------------------------------------------- Free functions
 /// Open the blockchain database using the passed [`Config`].
@@ -15,36 +29,104 @@ use crate::{config::Config, tables::OpenTables};
 /// All tables found in [`crate::tables`] will be
 /// ready for usage in the returned [`ConcreteEnv`].
 ///
+/// The database's network & schema version are checked and
+/// any needed migrations are run, see [`cuprate_database::migration`].
+///
+/// The optional indexes are enabled or cleared to match
+/// [`Config::key_image_index`] and [`Config::output_key_index`].
+///
 /// # Errors
 /// This will error if:
 /// - The database file could not be opened
 /// - A write transaction could not be opened
 /// - A table could not be created/opened
+/// - The database is for another network than [`Config::network`]
+/// - The database's schema version is not supported
+/// - A migration failed
 #[cold]
 #[inline(never)] // only called once
 pub fn open(config: Config) -> Result<ConcreteEnv, InitError> {
+    let key_image_index = config.key_image_index;
+    let output_key_index = config.output_key_index;
+
+    let env = open_keep_indexes(config)?;
+
+    set_index::<KeyImageSpends>(&env, METADATA_KEY_IMAGE_INDEX, key_image_index)
+        .and_then(|()| set_index::<OutputKeys>(&env, METADATA_OUTPUT_KEY_INDEX, output_key_index))
+        .map_err(runtime_to_init_error)?;
+
+    Ok(env)
+}
+
+/// Open the blockchain database using the passed [`Config`], leaving the optional indexes as they are.
+///
+/// This is the same as [`open`], except [`Config::key_image_index`] and
+/// [`Config::output_key_index`] are ignored and the indexes' state in the
+/// [`Metadata`] table is not written to.
+///
+/// This is for tools that work on an existing database, e.g.
+/// repairing it, which should not start or drop an index.
+///
+/// # Errors
+/// This will error if:
+/// - The database file could not be opened
+/// - A write transaction could not be opened
+/// - A table could not be created/opened
+/// - The database is for another network than [`Config::network`]
+/// - The database's schema version is not supported
+/// - A migration failed
+#[cold]
+#[inline(never)] // only called once
+pub fn open_keep_indexes(config: Config) -> Result<ConcreteEnv, InitError> {
+    let network = config.network.to_string();
+
+    let env = open_copy_destination(config)?;
+
+    // A database without any blocks has nothing to migrate.
+    let new_database = {
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().map_err(runtime_to_init_error)?;
+        env_inner
+            .open_db_ro::<BlockInfos>(&tx_ro)
+            .and_then(|table| table.is_empty())
+            .map_err(runtime_to_init_error)?
+    };
+
+    migrate(
+        &env,
+        &Schema {
+            version: DATABASE_VERSION,
+            network: &network,
+            migrations: MIGRATIONS,
+        },
+        new_database,
+    )?;
+
+    Ok(env)
+}
+
+/// Open the blockchain database using the passed [`Config`], to copy another blockchain database into.
+///
+/// Unlike [`open`], only the tables are created: the database is not stamped with
+/// a network or schema version, no migrations are run and the optional indexes are
+/// not touched. All of these are copied from the source database with the rest
+/// of the [`Metadata`] table.
+///
+/// This keeps every row in the database a row copied from the source, which
+/// [`cuprate_database::copy::copy_table`] relies on to resume an interrupted copy,
+/// so [`open`] must not be called on the database until the copy has finished.
+///
+/// # Errors
+/// This will error if:
+/// - The database file could not be opened
+/// - A write transaction could not be opened
+/// - A table could not be created/opened
+#[cold]
+#[inline(never)] // only called once
+pub fn open_copy_destination(config: Config) -> Result<ConcreteEnv, InitError> {
     // Attempt to open the database environment.
     let env = <ConcreteEnv as Env>::open(config.db_config)?;
 
-    /// Convert runtime errors to init errors.
-    ///
-    /// INVARIANT:
-    /// `cuprate_database`'s functions mostly return the former
-    /// so we must convert them. We have knowledge of which errors
-    /// makes sense in this functions context so we panic on
-    /// unexpected ones.
-    fn runtime_to_init_error(runtime: RuntimeError) -> InitError {
-        match runtime {
-            RuntimeError::Io(io_error) => io_error.into(),
-
-            // These errors shouldn't be happening here.
-            RuntimeError::KeyExists
-            | RuntimeError::KeyNotFound
-            | RuntimeError::ResizeNeeded
-            | RuntimeError::TableNotFound => unreachable!(),
-        }
-    }
-
     // INVARIANT: We must ensure that all tables are created,
     // `cuprate_database` has no way of knowing _which_ tables
     // we want since it is agnostic, so we are responsible for this.
@@ -61,8 +143,204 @@ pub fn open(config: Config) -> Result<ConcreteEnv, InitError> {
     Ok(env)
 }
 
+/// Convert runtime errors to init errors.
+///
+/// INVARIANT:
+/// `cuprate_database`'s functions mostly return the former
+/// so we must convert them. We have knowledge of which errors
+/// makes sense in the open functions' context so we panic on
+/// unexpected ones.
+fn runtime_to_init_error(runtime: RuntimeError) -> InitError {
+    match runtime {
+        RuntimeError::Io(io_error) => io_error.into(),
+
+        // These errors shouldn't be happening here.
+        RuntimeError::KeyExists
+        | RuntimeError::KeyNotFound
+        | RuntimeError::ResizeNeeded
+        | RuntimeError::TableNotFound => unreachable!(),
+    }
+}
+
+/// Open an existing blockchain database using the passed [`Config`], without writing to it.
+///
+/// Unlike [`open`], no tables are created, no migrations are run and
+/// the optional indexes are left as they are, so the database must
+/// already have been opened with [`open`] by this version of Cuprate.
+///
+/// The database is opened with [`cuprate_database::config::Config::read_only`],
+/// so the database file is never written to.
+///
+/// This is for offline tools that must not modify the database.
+///
+/// # Errors
+/// This will error if:
+/// - The database file does not exist or could not be opened
+/// - A table does not exist
+/// - The database is for another network than [`Config::network`]
+/// - The database's schema version is not [`DATABASE_VERSION`]
+#[cold]
+#[inline(never)] // only called once
+pub fn open_read_only(config: Config) -> Result<ConcreteEnv, InitError> {
+    let network = config.network.to_string();
+
+    // Don't create a new database if it doesn't exist.
+    if !config.db_config.db_file().try_exists()? {
+        return Err(std::io::Error::from(std::io::ErrorKind::NotFound).into());
+    }
+
+    let mut db_config = config.db_config;
+    db_config.read_only = true;
+    let env = <ConcreteEnv as Env>::open(db_config)?;
+
+    check_schema(
+        &env,
+        &Schema {
+            version: DATABASE_VERSION,
+            network: &network,
+            migrations: MIGRATIONS,
+        },
+    )?;
+
+    // All tables must already exist.
+    {
+        let env_inner = env.env_inner();
+        env_inner
+            .tx_ro()
+            .and_then(|tx_ro| env_inner.open_tables(&tx_ro).map(drop))
+            .map_err(|e| match e {
+                RuntimeError::Io(io_error) => io_error.into(),
+                RuntimeError::TableNotFound => InitError::Invalid,
+                e => InitError::Unknown(Box::new(e)),
+            })?;
+    }
+
+    Ok(env)
+}
+
+/// Enable or disable an optional index, if it is not already.
+///
+/// `T` is the index's table and `key` the [`Metadata`] key of its state,
+/// see [`crate::ops::property`].
+///
+/// Enabling the index marks all blocks currently in the database as needing to be backfilled,
+/// disabling it removes all entries.
+fn set_index<T: Table>(env: &ConcreteEnv, key: u8, enable: bool) -> DbResult<()> {
+    let env_inner = env.env_inner();
+    let mut tx_rw = env_inner.tx_rw()?;
+
+    let enabled = {
+        let table_metadata = env_inner.open_db_rw::<Metadata>(&tx_rw)?;
+        get_index_height(key, &table_metadata)?.is_some()
+    };
+
+    if enabled == enable {
+        return TxRw::abort(tx_rw);
+    }
+
+    {
+        let mut table_metadata = env_inner.open_db_rw::<Metadata>(&tx_rw)?;
+
+        let height = if enable {
+            Some(chain_height(
+                &env_inner.open_db_rw::<BlockHeights>(&tx_rw)?,
+            )?)
+        } else {
+            None
+        };
+
+        set_index_height(key, height, &mut table_metadata)?;
+    }
+
+    if !enable {
+        env_inner.clear_db::<T>(&mut tx_rw)?;
+    }
+
+    TxRw::commit(tx_rw)
+}
+
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {
-    // use super::*;
+    use std::num::NonZero;
+
+    use cuprate_database::config::Backend;
+    use cuprate_test_utils::data::BLOCK_V1_TX2;
+
+    use crate::{
+        config::ConfigBuilder,
+        ops::block::add_block,
+        tables::{copy_tables, verify_tables},
+        tests::backend_tests,
+    };
+
+    use super::*;
+
+    /// Returns the key image index state in `env`.
+    fn key_image_index_height(env: &ConcreteEnv) -> Option<usize> {
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let table_metadata = env_inner.open_db_ro::<Metadata>(&tx_ro).unwrap();
+        get_index_height(METADATA_KEY_IMAGE_INDEX, &table_metadata).unwrap()
+    }
+
+    /// Copying into a database opened with [`open_copy_destination`] copies the source's
+    /// whole [`Metadata`] table, including the index state with a backfill still pending.
+    fn copy_pending_index_backfill(backend: Backend) {
+        let other_backend = match backend {
+            Backend::Heed => Backend::Redb,
+            Backend::Redb => Backend::Heed,
+        };
+
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = |backend, key_image_index| {
+            ConfigBuilder::new()
+                .data_directory(tempdir.path().into())
+                .backend(backend)
+                .key_image_index(key_image_index)
+                .low_power()
+                .build()
+        };
+
+        let src = open(config(backend, false)).unwrap();
+        {
+            let env_inner = src.env_inner();
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+
+            let mut block = BLOCK_V1_TX2.clone();
+            block.height = 0;
+            add_block(&block, &mut tables).unwrap();
+
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        // Enable the index, the block added above is not backfilled.
+        set_index::<KeyImageSpends>(&src, METADATA_KEY_IMAGE_INDEX, true).unwrap();
+        assert_eq!(key_image_index_height(&src), Some(1));
+
+        // The index config of the destination is ignored and nothing is stamped before the copy.
+        let dst = open_copy_destination(config(other_backend, true)).unwrap();
+        {
+            let env_inner = dst.env_inner();
+            let tx_ro = env_inner.tx_ro().unwrap();
+            let table_metadata = env_inner.open_db_ro::<Metadata>(&tx_ro).unwrap();
+            assert!(table_metadata.is_empty().unwrap());
+        }
+
+        copy_tables(&src, &dst, NonZero::new(2).unwrap(), |_, _, _| {}).unwrap();
+        verify_tables(&src, &dst).unwrap();
+
+        assert_eq!(key_image_index_height(&dst), Some(1));
+
+        // The copy is a valid database.
+        drop(dst);
+        let dst = open_keep_indexes(config(other_backend, true)).unwrap();
+        assert_eq!(key_image_index_height(&dst), Some(1));
+    }
+
+    backend_tests! {
+        copy_pending_index_backfill,
+    }
 }
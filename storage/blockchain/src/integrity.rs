@@ -0,0 +1,787 @@
+//! Database integrity checks.
+//!
+//! [`check_integrity`] walks the main-chain tables and checks that they reference
+//! each other correctly, e.g. after a crash or a disk problem. Any problems found
+//! are returned in an [`IntegrityReport`].
+//!
+//! The checks are:
+//! - [`BlockHeights`] <-> [`BlockInfos`] (and the other block tables)
+//! - [`TxIds`] <-> [`TxHeights`] (and the other transaction tables)
+//! - [`TxOutputs`] <-> [`Outputs`] / [`RctOutputs`]
+//! - [`NumOutputs`] counts
+//! - the key images of every stored transaction are in [`KeyImages`]
+//! - cumulative difficulty increases with every block
+//!
+//! Problems with a known height can be repaired by popping blocks
+//! down to [`IntegrityReport::first_bad_height`] with [`pop_blocks`].
+//!
+//! [`BlockHeights`]: crate::tables::BlockHeights
+//! [`BlockInfos`]: crate::tables::BlockInfos
+//! [`TxIds`]: crate::tables::TxIds
+//! [`TxHeights`]: crate::tables::TxHeights
+//! [`TxOutputs`]: crate::tables::TxOutputs
+//! [`Outputs`]: crate::tables::Outputs
+//! [`RctOutputs`]: crate::tables::RctOutputs
+//! [`NumOutputs`]: crate::tables::NumOutputs
+//! [`KeyImages`]: crate::tables::KeyImages
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::collections::BTreeMap;
+
+use monero_serai::transaction::{Input, Transaction};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use cuprate_database::{
+    DatabaseIter, DatabaseRo, DatabaseRw, DbResult, Env, EnvInner, RuntimeError, Table, TxRw,
+};
+use cuprate_helper::{cast::usize_to_u64, map::combine_low_high_bits_to_u128};
+
+use crate::{
+    ops::{blockchain::chain_height, output::remove_output_keys},
+    tables::{OpenTables, Tables, TablesIter, TablesMut},
+    types::{Amount, BlockHeight, PreRctOutputId, TxId},
+};
+
+//---------------------------------------------------------------------------------------------------- Types
+/// The kind of check an [`IntegrityIssue`] was found by.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum IntegrityCheck {
+    /// The block tables do not match each other.
+    Blocks,
+    /// The transaction tables do not match each other or the blocks.
+    Transactions,
+    /// A transaction's outputs do not match the output tables.
+    Outputs,
+    /// The [`NumOutputs`](crate::tables::NumOutputs) counts do not match the stored outputs.
+    NumOutputs,
+    /// A key image of a stored transaction is missing.
+    KeyImages,
+    /// Cumulative difficulty does not increase with every block.
+    CumulativeDifficulty,
+}
+
+/// A problem found by [`check_integrity`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IntegrityIssue {
+    /// The check that found this issue.
+    pub check: IntegrityCheck,
+    /// The height of the block this issue is in, if known.
+    pub height: Option<BlockHeight>,
+    /// A description of the issue.
+    pub message: String,
+}
+
+/// The result of [`check_integrity`].
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IntegrityReport {
+    /// The chain height of the database.
+    pub chain_height: BlockHeight,
+    /// The amount of transactions checked.
+    pub txs_checked: u64,
+    /// The problems found, this is empty if the database is consistent.
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl IntegrityReport {
+    /// Returns `true` if no issues were found.
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The lowest block height with an issue.
+    ///
+    /// Popping blocks down to this height removes all issues with a known height.
+    pub fn first_bad_height(&self) -> Option<BlockHeight> {
+        self.issues.iter().filter_map(|issue| issue.height).min()
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Free functions
+/// Check that the main-chain tables are consistent with each other.
+///
+/// This reads every block, transaction and output so can take a long time.
+///
+/// # Errors
+/// This only returns an error if reading the database fails, missing
+/// entries and undeserializable data are returned as [`IntegrityIssue`]s.
+pub fn check_integrity(tables: &impl TablesIter) -> DbResult<IntegrityReport> {
+    let mut checker = Checker {
+        tables,
+        report: IntegrityReport {
+            chain_height: chain_height(tables.block_heights())?,
+            ..Default::default()
+        },
+    };
+
+    let expected_txs = checker.check_blocks()?;
+    checker.check_txs(expected_txs)?;
+    checker.check_num_outputs()?;
+
+    Ok(checker.report)
+}
+
+/// Pop blocks from the top of the chain until the chain height is `height`.
+///
+/// This is used to repair a database, popping all blocks
+/// from [`IntegrityReport::first_bad_height`] upwards.
+///
+/// Returns the amount of blocks popped.
+///
+/// Entries that are missing are skipped, so this also removes
+/// blocks that [`check_integrity`] found issues in.
+///
+/// # Errors
+/// This returns an error if writing to the database fails, no blocks are popped then.
+pub fn pop_blocks<E: Env>(env: &E, height: BlockHeight) -> DbResult<usize> {
+    loop {
+        match try_pop_blocks(env, height) {
+            Err(RuntimeError::ResizeNeeded) if E::MANUAL_RESIZE => {
+                env.resize_map(None);
+            }
+            result => return result,
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Checker
+/// The state of [`check_integrity`].
+struct Checker<'a, T> {
+    /// The tables being checked.
+    tables: &'a T,
+    /// The report being built.
+    report: IntegrityReport,
+}
+
+impl<T: TablesIter> Checker<'_, T> {
+    /// Add an issue to the report.
+    fn issue(&mut self, check: IntegrityCheck, height: Option<BlockHeight>, message: String) {
+        self.report.issues.push(IntegrityIssue {
+            check,
+            height,
+            message,
+        });
+    }
+
+    /// Check the block tables, cumulative difficulty and the heights of every block's transactions.
+    ///
+    /// Returns the amount of transactions the blocks contain.
+    fn check_blocks(&mut self) -> DbResult<u64> {
+        let tables = self.tables;
+        let mut expected_height = 0;
+        let mut expected_txs = 0;
+        let mut last_cumulative_difficulty = None;
+
+        let block_infos_len = tables.block_infos().len()?;
+        if block_infos_len != tables.block_heights().len()? {
+            self.issue(
+                IntegrityCheck::Blocks,
+                None,
+                format!(
+                    "BlockInfos has {block_infos_len} entries, BlockHeights has {}",
+                    tables.block_heights().len()?
+                ),
+            );
+        }
+
+        for res in tables.block_infos_iter().iter()? {
+            let (height, info) = res?;
+            let some_height = Some(height);
+
+            if height != expected_height {
+                self.issue(
+                    IntegrityCheck::Blocks,
+                    Some(expected_height),
+                    format!("BlockInfos has no entry for heights {expected_height}..{height}"),
+                );
+            }
+            expected_height = height + 1;
+
+            match get_opt(tables.block_heights(), &info.block_hash)? {
+                Some(h) if h == height => (),
+                Some(h) => self.issue(
+                    IntegrityCheck::Blocks,
+                    some_height,
+                    format!("the block hash is at height {h} in BlockHeights"),
+                ),
+                None => self.issue(
+                    IntegrityCheck::Blocks,
+                    some_height,
+                    "the block hash is missing from BlockHeights".to_string(),
+                ),
+            }
+
+            if !tables.block_header_blobs().contains(&height)? {
+                self.issue(
+                    IntegrityCheck::Blocks,
+                    some_height,
+                    "the block header is missing from BlockHeaderBlobs".to_string(),
+                );
+            }
+
+            let cumulative_difficulty = combine_low_high_bits_to_u128(
+                info.cumulative_difficulty_low,
+                info.cumulative_difficulty_high,
+            );
+            if last_cumulative_difficulty.is_some_and(|last| cumulative_difficulty <= last) {
+                self.issue(
+                    IntegrityCheck::CumulativeDifficulty,
+                    some_height,
+                    format!("cumulative difficulty {cumulative_difficulty} did not increase"),
+                );
+            }
+            last_cumulative_difficulty = Some(cumulative_difficulty);
+
+            // The block's transactions should be at this height.
+            self.check_tx_height(height, info.mining_tx_index, "miner transaction")?;
+
+            let Some(tx_hashes) = get_opt(tables.block_txs_hashes(), &height)? else {
+                self.issue(
+                    IntegrityCheck::Blocks,
+                    some_height,
+                    "the transaction hashes are missing from BlockTxsHashes".to_string(),
+                );
+                continue;
+            };
+
+            expected_txs += 1 + usize_to_u64(tx_hashes.0.len());
+
+            for tx_hash in &tx_hashes.0 {
+                match get_opt(tables.tx_ids(), tx_hash)? {
+                    Some(tx_id) => self.check_tx_height(height, tx_id, "transaction")?,
+                    None => self.issue(
+                        IntegrityCheck::Transactions,
+                        some_height,
+                        format!("transaction {} is missing from TxIds", hex(tx_hash)),
+                    ),
+                }
+            }
+        }
+
+        for res in tables.block_heights_iter().iter()? {
+            let (block_hash, height) = res?;
+
+            if get_opt(tables.block_infos(), &height)?.map(|info| info.block_hash)
+                != Some(block_hash)
+            {
+                self.issue(
+                    IntegrityCheck::Blocks,
+                    Some(height),
+                    format!(
+                        "BlockHeights entry {} has no matching BlockInfos entry",
+                        hex(&block_hash)
+                    ),
+                );
+            }
+        }
+
+        Ok(expected_txs)
+    }
+
+    /// Check the transaction with ID `tx_id` is at `height` in `TxHeights`.
+    fn check_tx_height(&mut self, height: BlockHeight, tx_id: TxId, what: &str) -> DbResult<()> {
+        match get_opt(self.tables.tx_heights(), &tx_id)? {
+            Some(h) if h == height => (),
+            Some(h) => self.issue(
+                IntegrityCheck::Transactions,
+                Some(height),
+                format!("{what} {tx_id} is at height {h} in TxHeights"),
+            ),
+            None => self.issue(
+                IntegrityCheck::Transactions,
+                Some(height),
+                format!("{what} {tx_id} is missing from TxHeights"),
+            ),
+        }
+
+        Ok(())
+    }
+
+    /// Check the transaction tables, outputs and key images of every transaction.
+    fn check_txs(&mut self, expected_txs: u64) -> DbResult<()> {
+        let tables = self.tables;
+
+        for (name, len) in [
+            ("TxIds", tables.tx_ids().len()?),
+            ("TxHeights", tables.tx_heights().len()?),
+            ("TxBlobs", tables.tx_blobs().len()?),
+            ("TxOutputs", tables.tx_outputs().len()?),
+        ] {
+            if len != expected_txs {
+                self.issue(
+                    IntegrityCheck::Transactions,
+                    None,
+                    format!(
+                        "{name} has {len} entries, the blocks contain {expected_txs} transactions"
+                    ),
+                );
+            }
+        }
+
+        for res in tables.tx_ids_iter().iter()? {
+            let (tx_hash, tx_id) = res?;
+
+            if !tables.tx_blobs().contains(&tx_id)? {
+                let height = get_opt(tables.tx_heights(), &tx_id)?;
+                self.issue(
+                    IntegrityCheck::Transactions,
+                    height,
+                    format!("transaction {} is missing from TxBlobs", hex(&tx_hash)),
+                );
+            }
+        }
+
+        let mut key_images = 0;
+        let mut outputs = 0;
+        let mut rct_outputs = 0;
+
+        for res in tables.tx_blobs_iter().iter()? {
+            let (tx_id, tx_blob) = res?;
+            self.report.txs_checked += 1;
+
+            let height = get_opt(tables.tx_heights(), &tx_id)?;
+
+            let Ok(tx) = Transaction::read(&mut tx_blob.0.as_slice()) else {
+                self.issue(
+                    IntegrityCheck::Transactions,
+                    height,
+                    format!("transaction {tx_id} could not be deserialized"),
+                );
+                continue;
+            };
+
+            if get_opt(tables.tx_ids(), &tx.hash())? != Some(tx_id) {
+                self.issue(
+                    IntegrityCheck::Transactions,
+                    height,
+                    format!("transaction {tx_id}'s hash does not map to it in TxIds"),
+                );
+            }
+
+            for input in &tx.prefix().inputs {
+                if let Input::ToKey { key_image, .. } = input {
+                    key_images += 1;
+
+                    if !tables.key_images().contains(key_image.as_bytes())? {
+                        self.issue(
+                            IntegrityCheck::KeyImages,
+                            height,
+                            format!(
+                                "key image {} of transaction {tx_id} is missing",
+                                hex(key_image.as_bytes())
+                            ),
+                        );
+                    }
+                }
+            }
+
+            let Some(amount_indices) = get_opt(tables.tx_outputs(), &tx_id)? else {
+                self.issue(
+                    IntegrityCheck::Outputs,
+                    height,
+                    format!("transaction {tx_id} is missing from TxOutputs"),
+                );
+                continue;
+            };
+
+            if amount_indices.0.len() != tx.prefix().outputs.len() {
+                self.issue(
+                    IntegrityCheck::Outputs,
+                    height,
+                    format!(
+                        "transaction {tx_id} has {} outputs, TxOutputs has {}",
+                        tx.prefix().outputs.len(),
+                        amount_indices.0.len()
+                    ),
+                );
+                continue;
+            }
+
+            for (output, amount_index) in tx.prefix().outputs.iter().zip(amount_indices.0) {
+                // See `ops::tx::add_tx` for which table outputs are in.
+                let stored = match &tx {
+                    Transaction::V1 { .. } => {
+                        outputs += 1;
+                        let id = PreRctOutputId {
+                            amount: output.amount.unwrap_or(0),
+                            amount_index,
+                        };
+                        get_opt(tables.outputs(), &id)?.map(|o| (o.key, o.tx_idx))
+                    }
+                    Transaction::V2 { .. } => {
+                        rct_outputs += 1;
+                        get_opt(tables.rct_outputs(), &amount_index)?.map(|o| (o.key, o.tx_idx))
+                    }
+                };
+
+                if stored != Some((output.key.0, tx_id)) {
+                    self.issue(
+                        IntegrityCheck::Outputs,
+                        height,
+                        format!(
+                            "output with amount index {amount_index} of transaction {tx_id} is missing or does not match"
+                        ),
+                    );
+                }
+            }
+        }
+
+        if tables.key_images().len()? != key_images {
+            self.issue(
+                IntegrityCheck::KeyImages,
+                None,
+                format!(
+                    "KeyImages has {} entries, the transactions contain {key_images} key images",
+                    tables.key_images().len()?
+                ),
+            );
+        }
+
+        for (name, len, expected) in [
+            ("Outputs", tables.outputs().len()?, outputs),
+            ("RctOutputs", tables.rct_outputs().len()?, rct_outputs),
+        ] {
+            if len != expected {
+                self.issue(
+                    IntegrityCheck::Outputs,
+                    None,
+                    format!(
+                        "{name} has {len} entries, the transactions contain {expected} outputs"
+                    ),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check the [`NumOutputs`](crate::tables::NumOutputs) counts match the pre-RCT outputs.
+    fn check_num_outputs(&mut self) -> DbResult<()> {
+        let tables = self.tables;
+
+        let mut counts = BTreeMap::<Amount, u64>::new();
+        for res in tables.outputs_iter().keys()? {
+            let id = res?;
+            *counts.entry(id.amount).or_default() += 1;
+        }
+
+        for res in tables.num_outputs_iter().iter()? {
+            let (amount, num_outputs) = res?;
+            let count = counts.remove(&amount).unwrap_or(0);
+
+            if count != num_outputs {
+                self.issue(
+                    IntegrityCheck::NumOutputs,
+                    None,
+                    format!("NumOutputs has {num_outputs} outputs with amount {amount}, Outputs has {count}"),
+                );
+            }
+        }
+
+        for (amount, count) in counts {
+            self.issue(
+                IntegrityCheck::NumOutputs,
+                None,
+                format!("NumOutputs has no entry for amount {amount}, Outputs has {count}"),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Private
+/// Pop blocks down to `height` in a single write
+/// transaction, aborting the transaction on errors.
+fn try_pop_blocks(env: &impl Env, height: BlockHeight) -> DbResult<usize> {
+    let env_inner = env.env_inner();
+
+    // A `BlockHeights` entry can only be found from its block's `BlockInfos`
+    // entry, which may be missing, so find the entries to remove first.
+    let block_heights = {
+        let tx_ro = env_inner.tx_ro()?;
+        let tables = env_inner.open_tables(&tx_ro)?;
+        tables
+            .block_heights_iter()
+            .iter()?
+            .filter(|res| res.as_ref().map_or(true, |(_, h)| *h >= height))
+            .collect::<DbResult<Vec<_>>>()?
+    };
+
+    let tx_rw = env_inner.tx_rw()?;
+
+    let result = {
+        let mut tables = env_inner.open_tables_mut(&tx_rw)?;
+
+        let top_height = match tables.block_infos().last() {
+            Ok((top_height, _)) => top_height + 1,
+            Err(RuntimeError::KeyNotFound) => 0,
+            Err(e) => return Err(e),
+        }
+        .max(block_heights.iter().map(|(_, h)| h + 1).max().unwrap_or(0));
+
+        (height..top_height)
+            .rev()
+            .try_for_each(|h| remove_block(h, &mut tables))
+            .and_then(|()| {
+                block_heights
+                    .iter()
+                    .try_for_each(|(block_hash, _)| tables.block_heights_mut().delete(block_hash))
+            })
+            .map(|()| top_height.saturating_sub(height))
+    };
+
+    match result {
+        Ok(count) => {
+            TxRw::commit(tx_rw)?;
+            Ok(count)
+        }
+        Err(e) => {
+            TxRw::abort(tx_rw)?;
+            Err(e)
+        }
+    }
+}
+
+/// Remove the block at `height` and its transactions.
+///
+/// Unlike [`pop_block`](crate::ops::block::pop_block) this skips missing
+/// entries, so blocks with the issues [`check_integrity`] finds can be removed.
+fn remove_block(height: BlockHeight, tables: &mut impl TablesMut) -> DbResult<()> {
+    let block_info = get_opt(tables.block_infos(), &height)?;
+    let tx_hashes = get_opt(tables.block_txs_hashes(), &height)?;
+
+    tables.block_infos_mut().delete(&height)?;
+    tables.block_header_blobs_mut().delete(&height)?;
+    tables.block_txs_hashes_mut().delete(&height)?;
+
+    // Remove the transactions in reverse, so outputs are removed from the top.
+    for tx_hash in tx_hashes
+        .iter()
+        .flat_map(|tx_hashes| tx_hashes.0.iter().rev())
+    {
+        if let Some(tx_id) = get_opt(tables.tx_ids(), tx_hash)? {
+            remove_tx(tx_id, tables)?;
+        }
+        tables.tx_ids_mut().delete(tx_hash)?;
+    }
+
+    if let Some(block_info) = block_info {
+        tables.block_heights_mut().delete(&block_info.block_hash)?;
+        remove_tx(block_info.mining_tx_index, tables)?;
+    }
+
+    Ok(())
+}
+
+/// Remove the transaction with ID `tx_id`, its key images and outputs.
+///
+/// Like [`remove_block`] this skips missing entries, outputs are only
+/// removed if they belong to this transaction.
+fn remove_tx(tx_id: TxId, tables: &mut impl TablesMut) -> DbResult<()> {
+    let tx_blob = get_opt(tables.tx_blobs(), &tx_id)?;
+    let amount_indices = get_opt(tables.tx_outputs(), &tx_id)?;
+
+    tables.tx_blobs_mut().delete(&tx_id)?;
+    tables.tx_heights_mut().delete(&tx_id)?;
+    tables.tx_outputs_mut().delete(&tx_id)?;
+    tables.tx_unlock_time_mut().delete(&tx_id)?;
+
+    // Without the transaction its key images and outputs can't be found.
+    let Some(Ok(tx)) = tx_blob.map(|tx_blob| Transaction::read(&mut tx_blob.0.as_slice())) else {
+        return Ok(());
+    };
+
+    tables.tx_ids_mut().delete(&tx.hash())?;
+
+    for input in &tx.prefix().inputs {
+        if let Input::ToKey { key_image, .. } = input {
+            tables.key_images_mut().delete(key_image.as_bytes())?;
+            tables.key_image_spends_mut().delete(key_image.as_bytes())?;
+        }
+    }
+
+    if let Some(amount_indices) = &amount_indices {
+        remove_output_keys(&tx, &amount_indices.0, tables.output_keys_mut())?;
+    }
+
+    for (i, output) in tx.prefix().outputs.iter().enumerate().rev() {
+        let amount_index = amount_indices
+            .as_ref()
+            .and_then(|amount_indices| amount_indices.0.get(i).copied());
+
+        // See `ops::tx::add_tx` for which table outputs are in.
+        // Without `TxOutputs` the output is assumed to be the top one,
+        // as blocks are removed from the top.
+        match &tx {
+            Transaction::V1 { .. } => {
+                let amount = output.amount.unwrap_or(0);
+                let num_outputs = get_opt(tables.num_outputs(), &amount)?.unwrap_or(0);
+                let id = PreRctOutputId {
+                    amount,
+                    amount_index: amount_index.unwrap_or(num_outputs.saturating_sub(1)),
+                };
+
+                if get_opt(tables.outputs(), &id)?.is_some_and(|o| o.tx_idx == tx_id) {
+                    tables.outputs_mut().delete(&id)?;
+                }
+
+                // Outputs above this one were removed with the blocks above.
+                if id.amount_index == 0 {
+                    tables.num_outputs_mut().delete(&amount)?;
+                } else if id.amount_index < num_outputs {
+                    tables.num_outputs_mut().put(&amount, &id.amount_index)?;
+                }
+            }
+            Transaction::V2 { .. } => {
+                let amount_index = match amount_index {
+                    Some(amount_index) => amount_index,
+                    None => tables.rct_outputs().len()?.saturating_sub(1),
+                };
+
+                if get_opt(tables.rct_outputs(), &amount_index)?.is_some_and(|o| o.tx_idx == tx_id)
+                {
+                    tables.rct_outputs_mut().delete(&amount_index)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// [`DatabaseRo::get`], returning [`None`] if the key does not exist.
+fn get_opt<T: Table>(table: &impl DatabaseRo<T>, key: &T::Key) -> DbResult<Option<T::Value>> {
+    match table.get(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(RuntimeError::KeyNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Hex-encode a hash for an [`IntegrityIssue`] message.
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use pretty_assertions::assert_eq;
+
+    use cuprate_database::config::Backend;
+    use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
+
+    use crate::{
+        ops::block::add_block,
+        tests::{backend_tests, tmp_concrete_env},
+    };
+
+    use super::*;
+
+    /// A database with 3 blocks.
+    fn env_with_blocks(backend: Backend) -> (impl Env, tempfile::TempDir) {
+        let (env, tmp) = tmp_concrete_env(backend);
+        let env_inner = env.env_inner();
+
+        let tx_rw = env_inner.tx_rw().unwrap();
+        let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+        for (height, block) in [&BLOCK_V1_TX2, &BLOCK_V9_TX3, &BLOCK_V16_TX0]
+            .into_iter()
+            .enumerate()
+        {
+            let mut block = (*block).clone();
+            block.height = height;
+            add_block(&block, &mut tables).unwrap();
+        }
+        drop(tables);
+        TxRw::commit(tx_rw).unwrap();
+        drop(env_inner);
+
+        (env, tmp)
+    }
+
+    /// A consistent database has no issues.
+    fn consistent(backend: Backend) {
+        let (env, _tmp) = env_with_blocks(backend);
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let tables = env_inner.open_tables(&tx_ro).unwrap();
+
+        let report = check_integrity(&tables).unwrap();
+        assert_eq!(report.issues, vec![]);
+        assert_eq!(report.chain_height, 3);
+        assert_eq!(report.txs_checked, (1 + 2) + (1 + 3) + 1);
+        assert!(report.is_ok());
+    }
+
+    /// Check the database has an issue found by `check`,
+    /// then repair it by popping blocks and check it again.
+    fn check_and_repair(env: &impl Env, check: IntegrityCheck) {
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let tables = env_inner.open_tables(&tx_ro).unwrap();
+        let report = check_integrity(&tables).unwrap();
+        drop(tables);
+        drop(tx_ro);
+
+        assert!(report.issues.iter().any(|issue| issue.check == check));
+
+        let first_bad_height = report.first_bad_height().unwrap();
+        assert!(first_bad_height < 3);
+
+        assert_eq!(
+            pop_blocks(env, first_bad_height).unwrap(),
+            3 - first_bad_height
+        );
+
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let tables = env_inner.open_tables(&tx_ro).unwrap();
+        let report = check_integrity(&tables).unwrap();
+        assert_eq!(report.issues, vec![]);
+        assert_eq!(report.chain_height, first_bad_height);
+    }
+
+    /// A missing key image is found and repaired by popping blocks.
+    fn missing_key_image(backend: Backend) {
+        let (env, _tmp) = env_with_blocks(backend);
+
+        {
+            let env_inner = env.env_inner();
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+            tables.key_images_mut().pop_last().unwrap();
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        check_and_repair(&env, IntegrityCheck::KeyImages);
+    }
+
+    /// A missing output and amount indices are found and repaired by popping blocks.
+    fn missing_outputs(backend: Backend) {
+        let (env, _tmp) = env_with_blocks(backend);
+
+        {
+            let env_inner = env.env_inner();
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+            tables.rct_outputs_mut().pop_last().unwrap();
+            tables.tx_outputs_mut().pop_last().unwrap();
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        check_and_repair(&env, IntegrityCheck::Outputs);
+    }
+
+    backend_tests! {
+        consistent,
+        missing_key_image,
+        missing_outputs,
+    }
+}
@@ -22,12 +22,17 @@ compile_error!("Cuprate is only compatible with 64-bit CPUs");
 
 mod constants;
 mod free;
+mod migration;
 
 pub use constants::DATABASE_VERSION;
 pub use cuprate_database;
-pub use free::open;
+pub use free::{open, open_copy_destination, open_keep_indexes, open_read_only};
 
+pub mod bootstrap;
 pub mod config;
+pub mod integrity;
+#[cfg(feature = "monerod")]
+pub mod monerod;
 pub mod ops;
 pub mod service;
 pub mod tables;
@@ -0,0 +1,19 @@
+//! Database migrations.
+//!
+//! Every change to the tables in [`crate::tables`] that is not
+//! backwards compatible must:
+//! 1. Increment [`DATABASE_VERSION`](crate::DATABASE_VERSION)
+//! 2. Add a [`Migration`] from the old version to [`MIGRATIONS`]
+//!
+//! [`crate::open`] will then upgrade existing databases in place,
+//! see [`cuprate_database::migration`] for more info.
+
+//---------------------------------------------------------------------------------------------------- Import
+use cuprate_database::migration::Migration;
+
+//---------------------------------------------------------------------------------------------------- Migrations
+/// All migrations of the blockchain database.
+///
+/// The migration from version `n` to `n + 1`
+/// must have a [`Migration::from_version`] of `n`.
+pub(crate) const MIGRATIONS: &[Migration] = &[];
@@ -0,0 +1,308 @@
+//! `monerod` LMDB databases.
+//!
+//! This module reads blocks directly from a `monerod` `lmdb/` data directory,
+//! so they can be added to a Cuprate database without downloading them again.
+//!
+//! Only the `blocks`, `txs_pruned` and `txs_prunable` tables are read,
+//! everything else can be derived from the blocks and transactions.
+//!
+//! `monerod` gives transactions an ID in the order they were added, the miner transaction
+//! of a block first, then the block's transactions in order. [`MonerodReader`] tracks
+//! this ID instead of looking up transaction hashes, as the hash tables use custom
+//! comparison functions in `monerod`.
+//!
+//! Pruned `monerod` databases cannot be read as they do not contain full transactions.
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::path::Path;
+
+use heed::{
+    byteorder::NativeEndian,
+    types::{Bytes, U64},
+    Database, EnvFlags, EnvOpenOptions,
+};
+use monero_serai::{block::Block, transaction::Transaction};
+
+use cuprate_helper::cast::usize_to_u64;
+
+//---------------------------------------------------------------------------------------------------- MonerodDbError
+/// An error reading a `monerod` database.
+#[derive(Debug, thiserror::Error)]
+pub enum MonerodDbError {
+    /// An error from LMDB.
+    #[error("LMDB error: {0}")]
+    Lmdb(#[from] heed::Error),
+
+    /// A table `monerod` creates is missing.
+    #[error("the `{0}` table is missing, this is not a monerod database")]
+    TableNotFound(&'static str),
+
+    /// A block could not be deserialized.
+    #[error("the block at height {0} is invalid")]
+    InvalidBlock(usize),
+
+    /// A transaction is missing or could not be deserialized.
+    #[error("the transaction with ID {0} is missing or invalid, the database may be pruned")]
+    InvalidTransaction(u64),
+
+    /// A transaction does not match the hash in its block.
+    #[error("the transactions of the block at height {0} do not match the block")]
+    TransactionMismatch(usize),
+}
+
+//---------------------------------------------------------------------------------------------------- MonerodReader
+/// A `monerod` LMDB table with `u64` keys.
+type MonerodTable = Database<U64<NativeEndian>, Bytes>;
+
+/// Reads blocks in order from a `monerod` LMDB database.
+pub struct MonerodReader {
+    /// The LMDB environment, opened read-only.
+    env: heed::Env,
+    /// `block height -> block blob`
+    blocks: MonerodTable,
+    /// `transaction ID -> pruned transaction blob`
+    txs_pruned: MonerodTable,
+    /// `transaction ID -> prunable transaction blob`
+    txs_prunable: MonerodTable,
+    /// The height of the next block to read.
+    height: usize,
+    /// The ID of the next block's miner transaction.
+    tx_id: u64,
+}
+
+impl MonerodReader {
+    /// Open the `monerod` LMDB database in `directory`, read-only.
+    ///
+    /// `directory` is the directory containing `data.mdb`, e.g. `~/.bitmonero/lmdb`.
+    ///
+    /// The first block read will be at `start_height`, to find the
+    /// transaction IDs of this block all blocks below it are read.
+    ///
+    /// # Errors
+    /// This returns an error if the database could not be opened,
+    /// is not a `monerod` database or a block below `start_height` is invalid.
+    pub fn open(directory: &Path, start_height: usize) -> Result<Self, MonerodDbError> {
+        let mut env_open_options = EnvOpenOptions::new();
+        env_open_options.max_dbs(32);
+
+        // SAFETY: the database is only read, `monerod` should not be running.
+        unsafe {
+            env_open_options.flags(EnvFlags::READ_ONLY | EnvFlags::NO_READ_AHEAD);
+        }
+
+        // SAFETY: LMDB uses a memory-map backed file.
+        // <https://docs.rs/heed/0.20.0/heed/struct.EnvOpenOptions.html#method.open>
+        let env = unsafe { env_open_options.open(directory)? };
+
+        // LMDB stores the `MDB_INTEGERKEY` flag `monerod`
+        // sets for these tables, so no flags are needed here.
+        let tx_ro = env.read_txn()?;
+        let open = |name: &'static str| -> Result<MonerodTable, MonerodDbError> {
+            env.open_database(&tx_ro, Some(name))?
+                .ok_or(MonerodDbError::TableNotFound(name))
+        };
+        let blocks = open("blocks")?;
+        let txs_pruned = open("txs_pruned")?;
+        let txs_prunable = open("txs_prunable")?;
+
+        // Count the transactions below `start_height`.
+        let mut tx_id = 0;
+        for (height, res) in blocks.iter(&tx_ro)?.take(start_height).enumerate() {
+            let (_, blob) = res?;
+            let block = read_block(blob, height)?;
+            tx_id += 1 + usize_to_u64(block.transactions.len());
+        }
+
+        drop(tx_ro);
+
+        Ok(Self {
+            env,
+            blocks,
+            txs_pruned,
+            txs_prunable,
+            height: start_height,
+            tx_id,
+        })
+    }
+
+    /// The amount of blocks in the `monerod` database.
+    ///
+    /// # Errors
+    /// This returns an error if LMDB returns an error.
+    pub fn chain_height(&self) -> Result<usize, MonerodDbError> {
+        let tx_ro = self.env.read_txn()?;
+
+        #[expect(clippy::cast_possible_truncation, reason = "we enforce 64-bit")]
+        Ok(self.blocks.len(&tx_ro)? as usize)
+    }
+
+    /// Read up to `len` blocks, continuing from the last block read.
+    ///
+    /// The returned transactions are in the same order as [`Block::transactions`],
+    /// the returned [`Vec`] is empty if there are no more blocks.
+    ///
+    /// # Errors
+    /// This returns an error if a block or transaction is invalid or missing.
+    pub fn next_blocks(
+        &mut self,
+        len: usize,
+    ) -> Result<Vec<(Block, Vec<Transaction>)>, MonerodDbError> {
+        let tx_ro = self.env.read_txn()?;
+
+        let start = usize_to_u64(self.height);
+        let mut blocks = Vec::with_capacity(len);
+
+        for res in self.blocks.range(&tx_ro, &(start..))?.take(len) {
+            let (_, blob) = res?;
+            let block = read_block(blob, self.height)?;
+
+            // Skip the miner transaction, it is in the block blob.
+            let mut tx_id = self.tx_id + 1;
+            let mut txs = Vec::with_capacity(block.transactions.len());
+
+            for tx_hash in &block.transactions {
+                let tx = self.read_tx(&tx_ro, tx_id)?;
+                if tx.hash() != *tx_hash {
+                    return Err(MonerodDbError::TransactionMismatch(self.height));
+                }
+
+                txs.push(tx);
+                tx_id += 1;
+            }
+
+            blocks.push((block, txs));
+            self.height += 1;
+            self.tx_id = tx_id;
+        }
+
+        Ok(blocks)
+    }
+
+    /// Read the full transaction with ID `tx_id`.
+    fn read_tx(&self, tx_ro: &heed::RoTxn<'_>, tx_id: u64) -> Result<Transaction, MonerodDbError> {
+        let invalid = || MonerodDbError::InvalidTransaction(tx_id);
+
+        // `monerod` splits every transaction blob into 2 parts.
+        let pruned = self.txs_pruned.get(tx_ro, &tx_id)?.ok_or_else(invalid)?;
+        let prunable = self.txs_prunable.get(tx_ro, &tx_id)?.ok_or_else(invalid)?;
+
+        let blob = [pruned, prunable].concat();
+        Transaction::read(&mut blob.as_slice()).map_err(|_| invalid())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Private
+/// Deserialize the block blob at `height`.
+fn read_block(mut blob: &[u8], height: usize) -> Result<Block, MonerodDbError> {
+    Block::read(&mut blob).map_err(|_| MonerodDbError::InvalidBlock(height))
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use heed::DatabaseFlags;
+    use pretty_assertions::assert_eq;
+
+    use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
+    use cuprate_types::VerifiedBlockInformation;
+
+    use super::*;
+
+    /// Create a database with the tables `monerod` uses for `blocks`.
+    fn monerod_db(blocks: &[&VerifiedBlockInformation]) -> tempfile::TempDir {
+        let tempdir = tempfile::tempdir().unwrap();
+
+        // SAFETY: the database is only used in this test.
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .max_dbs(32)
+                .map_size(10 * 1024 * 1024)
+                .open(tempdir.path())
+                .unwrap()
+        };
+
+        let mut tx_rw = env.write_txn().unwrap();
+        let mut create = |name: &str| -> MonerodTable {
+            env.database_options()
+                .types()
+                .name(name)
+                .flags(DatabaseFlags::INTEGER_KEY)
+                .create(&mut tx_rw)
+                .unwrap()
+        };
+        let (blocks_db, txs_pruned, txs_prunable) = (
+            create("blocks"),
+            create("txs_pruned"),
+            create("txs_prunable"),
+        );
+
+        let mut tx_id = 0;
+        for (height, block) in blocks.iter().enumerate() {
+            blocks_db
+                .put(&mut tx_rw, &usize_to_u64(height), &block.block_blob)
+                .unwrap();
+
+            // The miner transaction.
+            tx_id += 1;
+
+            for tx in &block.txs {
+                let (pruned, prunable) = tx.tx_blob.split_at(tx.tx_blob.len() / 2);
+                txs_pruned.put(&mut tx_rw, &tx_id, pruned).unwrap();
+                txs_prunable.put(&mut tx_rw, &tx_id, prunable).unwrap();
+                tx_id += 1;
+            }
+        }
+
+        tx_rw.commit().unwrap();
+        tempdir
+    }
+
+    /// Blocks and their transactions are read in order.
+    #[test]
+    fn read() {
+        let blocks = [&*BLOCK_V1_TX2, &*BLOCK_V9_TX3, &*BLOCK_V16_TX0];
+        let tempdir = monerod_db(&blocks);
+
+        let assert_blocks = |read: &[(Block, Vec<Transaction>)],
+                             expected: &[&VerifiedBlockInformation]| {
+            assert_eq!(read.len(), expected.len());
+            for ((block, txs), expected) in read.iter().zip(expected) {
+                assert_eq!(block, &expected.block);
+                assert_eq!(
+                    txs,
+                    &expected
+                        .txs
+                        .iter()
+                        .map(|tx| tx.tx.clone())
+                        .collect::<Vec<_>>()
+                );
+            }
+        };
+
+        let mut reader = MonerodReader::open(tempdir.path(), 0).unwrap();
+        assert_eq!(reader.chain_height().unwrap(), 3);
+        assert_blocks(&reader.next_blocks(2).unwrap(), &blocks[..2]);
+        assert_blocks(&reader.next_blocks(2).unwrap(), &blocks[2..]);
+        assert!(reader.next_blocks(2).unwrap().is_empty());
+
+        // Resuming finds the right transaction IDs.
+        drop(reader);
+        let mut reader = MonerodReader::open(tempdir.path(), 1).unwrap();
+        assert_blocks(&reader.next_blocks(3).unwrap(), &blocks[1..]);
+    }
+
+    /// Missing transactions, e.g. from pruning, are an error.
+    #[test]
+    fn missing_tx() {
+        let mut block = BLOCK_V9_TX3.clone();
+        block.txs.clear();
+        let tempdir = monerod_db(&[&*BLOCK_V1_TX2, &block]);
+
+        // Transaction IDs 0 to 2 are in the first block, 3 is the miner transaction.
+        let mut reader = MonerodReader::open(tempdir.path(), 1).unwrap();
+        assert!(matches!(
+            reader.next_blocks(1),
+            Err(MonerodDbError::InvalidTransaction(4))
+        ));
+    }
+}
@@ -1,7 +1,9 @@
+use std::collections::{HashMap, HashSet};
+
 use bytemuck::TransparentWrapper;
 use monero_serai::block::{Block, BlockHeader};
 
-use cuprate_database::{DatabaseRo, DatabaseRw, DbResult, StorableVec};
+use cuprate_database::{DatabaseIter, DatabaseRo, DatabaseRw, DbResult, RuntimeError, StorableVec};
 use cuprate_helper::map::{combine_low_high_bits_to_u128, split_u128_into_low_high_bits};
 use cuprate_types::{AltBlockInformation, Chain, ChainId, ExtendedBlockHeader, HardFork};
 
@@ -9,10 +11,13 @@ use crate::{
     ops::{
         alt_block::{add_alt_transaction_blob, get_alt_transaction, update_alt_chain_info},
         block::get_block_info,
+        blockchain::chain_height,
         macros::doc_error,
     },
-    tables::{Tables, TablesMut},
-    types::{AltBlockHeight, BlockHash, BlockHeight, CompactAltBlockInfo},
+    tables::{Tables, TablesIter, TablesMut},
+    types::{
+        AltBlockHeight, AltChainInfo, BlockHash, BlockHeight, CompactAltBlockInfo, RawChainId,
+    },
 };
 
 /// Flush all alt-block data from all the alt-block tables.
@@ -35,6 +40,156 @@ pub fn flush_alt_blocks<'a, E: cuprate_database::EnvInner<'a>>(
     env_inner.clear_db::<AltTransactionInfos>(tx_rw)
 }
 
+/// The alt-chains [`prune_alt_chains`] removes, returned from [`alt_chains_to_prune`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AltChainsToPrune {
+    /// The alt-chains to remove.
+    pub remove: Vec<RawChainId>,
+    /// The alt-chains to keep.
+    pub keep: Vec<RawChainId>,
+}
+
+/// Find the old alt-chains to remove from the alt-block tables.
+///
+/// Alt-chains are removed along with any chains that split from them, a chain is removed if:
+/// - its newest block is more than `max_depth` blocks below the top of the main-chain, or
+/// - more than `max_blocks` alt-blocks are stored, the chains with the oldest
+///   top blocks are removed first, the chain with the newest block is always kept.
+///
+/// If [`AltChainsToPrune::remove`] is empty nothing needs to be pruned.
+///
+#[doc = doc_error!()]
+pub fn alt_chains_to_prune(
+    max_depth: usize,
+    max_blocks: usize,
+    tables: &impl TablesIter,
+) -> DbResult<AltChainsToPrune> {
+    let chains = tables
+        .alt_chain_infos_iter()
+        .iter()?
+        .collect::<DbResult<HashMap<_, _>>>()?;
+
+    // Group the chains by the chain that split from the main-chain, as a chain
+    // can't be removed without the chains that split from it.
+    let mut trees = HashMap::<RawChainId, (BlockHeight, usize)>::new();
+    let mut chain_roots = HashMap::with_capacity(chains.len());
+    for (chain_id, chain_info) in &chains {
+        let root = alt_chain_root(*chain_id, &chains)?;
+        chain_roots.insert(*chain_id, root);
+
+        let (top_height, numb_blocks) = trees.entry(root).or_default();
+        *top_height = (*top_height).max(chain_info.chain_height - 1);
+        *numb_blocks += chain_info.chain_height - chain_info.common_ancestor_height - 1;
+    }
+
+    let mut trees = trees.into_iter().collect::<Vec<_>>();
+    trees.sort_unstable_by_key(|(_, (top_height, _))| std::cmp::Reverse(*top_height));
+
+    let main_chain_height = chain_height(tables.block_heights())?;
+
+    let mut total_blocks = 0;
+    let mut removed_trees = HashSet::new();
+    for (i, (root, (top_height, numb_blocks))) in trees.into_iter().enumerate() {
+        total_blocks += numb_blocks;
+
+        let too_old = top_height.saturating_add(max_depth) < main_chain_height;
+        let too_many = i != 0 && total_blocks > max_blocks;
+
+        if too_old || too_many {
+            removed_trees.insert(root);
+        }
+    }
+
+    let (remove, keep) = chains
+        .into_keys()
+        .partition(|chain_id| removed_trees.contains(&chain_roots[chain_id]));
+
+    Ok(AltChainsToPrune { remove, keep })
+}
+
+/// Remove the alt-chains in [`AltChainsToPrune::remove`] from the alt-block tables.
+///
+/// Transactions are only removed if no block in [`AltChainsToPrune::keep`] also has them.
+///
+/// Returns the amount of alt-blocks removed.
+///
+#[doc = doc_error!()]
+pub fn prune_alt_chains(
+    to_prune: &AltChainsToPrune,
+    tables: &mut impl TablesMut,
+) -> DbResult<usize> {
+    let mut removed_txs = HashSet::new();
+    let mut removed_blocks = 0;
+    for &chain_id in &to_prune.remove {
+        let chain_info = tables.alt_chain_infos_mut().take(&chain_id)?;
+
+        for height in (chain_info.common_ancestor_height + 1)..chain_info.chain_height {
+            let alt_block_height = AltBlockHeight { chain_id, height };
+
+            let block_info = tables.alt_blocks_info_mut().take(&alt_block_height)?;
+            tables
+                .alt_block_heights_mut()
+                .delete(&block_info.block_hash)?;
+
+            let block_blob = tables.alt_block_blobs_mut().take(&alt_block_height)?.0;
+            let block = Block::read(&mut block_blob.as_slice())?;
+            removed_txs.extend(block.transactions);
+
+            removed_blocks += 1;
+        }
+    }
+
+    // The same transaction can be in blocks on different chains, only remove
+    // transactions no kept block needs.
+    for &chain_id in &to_prune.keep {
+        if removed_txs.is_empty() {
+            break;
+        }
+
+        let chain_info = tables.alt_chain_infos().get(&chain_id)?;
+        for height in (chain_info.common_ancestor_height + 1)..chain_info.chain_height {
+            let block_blob = tables
+                .alt_block_blobs()
+                .get(&AltBlockHeight { chain_id, height })?
+                .0;
+            let block = Block::read(&mut block_blob.as_slice())?;
+
+            for tx_hash in &block.transactions {
+                removed_txs.remove(tx_hash);
+            }
+        }
+    }
+
+    for tx_hash in &removed_txs {
+        tables.alt_transaction_infos_mut().delete(tx_hash)?;
+        tables.alt_transaction_blobs_mut().delete(tx_hash)?;
+    }
+
+    Ok(removed_blocks)
+}
+
+/// Get the [`RawChainId`] of the chain that `chain_id` originally split from the main-chain from.
+///
+#[doc = doc_error!()]
+fn alt_chain_root(
+    mut chain_id: RawChainId,
+    chains: &HashMap<RawChainId, AltChainInfo>,
+) -> DbResult<RawChainId> {
+    // A chain can't have more ancestors than there are chains.
+    for _ in 0..chains.len() {
+        let chain_info = chains.get(&chain_id).ok_or(RuntimeError::KeyNotFound)?;
+
+        match chain_info.parent_chain.into() {
+            Chain::Main => return Ok(chain_id),
+            Chain::Alt(parent_id) => chain_id = parent_id.into(),
+        }
+    }
+
+    Err(RuntimeError::Io(std::io::Error::other(
+        "Loop detected in ChainIDs, invalid alt chain.",
+    )))
+}
+
 /// Add a [`AltBlockInformation`] to the database.
 ///
 /// This extracts all the data from the input block and
@@ -210,28 +365,29 @@ pub fn get_alt_block_extended_header_from_height(
 mod tests {
     use std::num::NonZero;
 
-    use cuprate_database::{Env, EnvInner, TxRw};
+    use cuprate_database::{config::Backend, DatabaseRo, Env, EnvInner, TxRw};
     use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
-    use cuprate_types::{Chain, ChainId};
+    use cuprate_types::{Chain, ChainId, VerifiedBlockInformation};
 
     use crate::{
         ops::{
             alt_block::{
-                add_alt_block, flush_alt_blocks, get_alt_block,
+                add_alt_block, alt_chains_to_prune, flush_alt_blocks, get_alt_block,
                 get_alt_block_extended_header_from_height, get_alt_block_hash,
-                get_alt_chain_history_ranges,
+                get_alt_chain_history_ranges, prune_alt_chains, AltChainsToPrune,
             },
             block::{add_block, pop_block},
         },
         tables::{OpenTables, Tables},
-        tests::{assert_all_tables_are_empty, map_verified_block_to_alt, tmp_concrete_env},
-        types::AltBlockHeight,
+        tests::{
+            assert_all_tables_are_empty, backend_tests, map_verified_block_to_alt, tmp_concrete_env,
+        },
+        types::{AltBlockHeight, RawChainId},
     };
 
     #[expect(clippy::range_plus_one)]
-    #[test]
-    fn all_alt_blocks() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_alt_blocks(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -331,4 +487,156 @@ mod tests {
 
         assert_all_tables_are_empty(&env);
     }
+
+    fn prune_alt_chains_removes_blocks(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
+        let env_inner = env.env_inner();
+        assert_all_tables_are_empty(&env);
+
+        let alt_block = |block: &VerifiedBlockInformation, chain_id, height, previous| {
+            let mut alt_block =
+                map_verified_block_to_alt(block.clone(), ChainId(NonZero::new(chain_id).unwrap()));
+            alt_block.height = height;
+            alt_block.block.header.previous = previous;
+            alt_block.block_blob = alt_block.block.serialize();
+            alt_block
+        };
+
+        // Chain 1 has 2 blocks, chain 2 shares its txs with chain 1
+        // and chain 3 has txs only it has.
+        let chain_1 = [
+            alt_block(&BLOCK_V9_TX3, 1, 1, BLOCK_V1_TX2.block_hash),
+            alt_block(&BLOCK_V16_TX0, 1, 2, BLOCK_V9_TX3.block_hash),
+        ];
+        let mut chain_2 = alt_block(&BLOCK_V9_TX3, 2, 1, BLOCK_V1_TX2.block_hash);
+        chain_2.block_hash = [2; 32];
+        let mut chain_3 = alt_block(&BLOCK_V1_TX2, 3, 1, BLOCK_V1_TX2.block_hash);
+        chain_3.block_hash = [3; 32];
+
+        {
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+
+            let mut initial_block = BLOCK_V1_TX2.clone();
+            initial_block.height = 0;
+            add_block(&initial_block, &mut tables).unwrap();
+
+            for block in chain_1.iter().chain([&chain_2, &chain_3]) {
+                add_alt_block(block, &mut tables).unwrap();
+            }
+
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        let find_prune = |max_depth, max_blocks| {
+            let tx_ro = env_inner.tx_ro().unwrap();
+            let tables = env_inner.open_tables(&tx_ro).unwrap();
+            let mut to_prune = alt_chains_to_prune(max_depth, max_blocks, &tables).unwrap();
+            to_prune.remove.sort_unstable();
+            to_prune.keep.sort_unstable();
+            to_prune
+        };
+
+        let prune = |to_prune: &AltChainsToPrune| {
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+            let removed_blocks = prune_alt_chains(to_prune, &mut tables).unwrap();
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+            removed_blocks
+        };
+
+        let chain_id = |id| RawChainId::from(ChainId(NonZero::new(id).unwrap()));
+
+        // Nothing is over the limits.
+        let to_prune = find_prune(usize::MAX, 4);
+        assert!(to_prune.remove.is_empty());
+        assert_eq!(to_prune.keep, [chain_id(1), chain_id(2), chain_id(3)]);
+
+        // Only chain 1 fits in the limit, it has the newest block.
+        let to_prune = find_prune(usize::MAX, 2);
+        assert_eq!(to_prune.remove, [chain_id(2), chain_id(3)]);
+        assert_eq!(to_prune.keep, [chain_id(1)]);
+        assert_eq!(prune(&to_prune), 2);
+
+        {
+            let tx_ro = env_inner.tx_ro().unwrap();
+            let tables = env_inner.open_tables(&tx_ro).unwrap();
+
+            assert_eq!(tables.alt_chain_infos().len().unwrap(), 1);
+            assert_eq!(tables.alt_blocks_info().len().unwrap(), 2);
+            assert_eq!(tables.alt_block_heights().len().unwrap(), 2);
+            assert_eq!(tables.alt_block_blobs().len().unwrap(), 2);
+            assert!(!tables.alt_block_heights().contains(&[2; 32]).unwrap());
+            assert!(!tables.alt_block_heights().contains(&[3; 32]).unwrap());
+
+            // The txs chain 1 shares with chain 2 are kept.
+            for tx in &BLOCK_V9_TX3.txs {
+                assert!(tables
+                    .alt_transaction_infos()
+                    .contains(&tx.tx_hash)
+                    .unwrap());
+                assert!(tables
+                    .alt_transaction_blobs()
+                    .contains(&tx.tx_hash)
+                    .unwrap());
+            }
+            for tx in &BLOCK_V1_TX2.txs {
+                assert!(!tables
+                    .alt_transaction_infos()
+                    .contains(&tx.tx_hash)
+                    .unwrap());
+            }
+
+            let block = get_alt_block(
+                &AltBlockHeight {
+                    chain_id: chain_id(1),
+                    height: 2,
+                },
+                &tables,
+            )
+            .unwrap();
+            assert_eq!(block.block_hash, BLOCK_V16_TX0.block_hash);
+        }
+
+        // Move the main-chain past chain 1.
+        {
+            let tx_rw = env_inner.tx_rw().unwrap();
+            let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+
+            for (height, block) in [&BLOCK_V9_TX3, &BLOCK_V16_TX0].into_iter().enumerate() {
+                let mut block = (**block).clone();
+                block.height = height + 1;
+                add_block(&block, &mut tables).unwrap();
+            }
+
+            drop(tables);
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        assert!(find_prune(1, usize::MAX).remove.is_empty());
+
+        let to_prune = find_prune(0, usize::MAX);
+        assert_eq!(to_prune.remove, [chain_id(1)]);
+        assert!(to_prune.keep.is_empty());
+        assert_eq!(prune(&to_prune), 2);
+
+        {
+            let tx_ro = env_inner.tx_ro().unwrap();
+            let tables = env_inner.open_tables(&tx_ro).unwrap();
+
+            assert!(tables.alt_chain_infos().is_empty().unwrap());
+            assert!(tables.alt_blocks_info().is_empty().unwrap());
+            assert!(tables.alt_block_heights().is_empty().unwrap());
+            assert!(tables.alt_block_blobs().is_empty().unwrap());
+            assert!(tables.alt_transaction_infos().is_empty().unwrap());
+            assert!(tables.alt_transaction_blobs().is_empty().unwrap());
+        }
+    }
+
+    backend_tests! {
+        all_alt_blocks,
+        prune_alt_chains_removes_blocks,
+    }
 }
@@ -1,11 +1,15 @@
 use std::cmp::{max, min};
 
-use cuprate_database::{DatabaseRo, DatabaseRw, DbResult, RuntimeError};
-use cuprate_types::{Chain, ChainId};
+use cuprate_database::{DatabaseIter, DatabaseRo, DatabaseRw, DbResult, RuntimeError};
+use cuprate_helper::cast::usize_to_u64;
+use cuprate_types::{rpc::ChainInfo, Chain, ChainId};
 
 use crate::{
-    ops::macros::{doc_add_alt_block_inner_invariant, doc_error},
-    tables::{AltChainInfos, TablesMut},
+    ops::{
+        block::get_block_info,
+        macros::{doc_add_alt_block_inner_invariant, doc_error},
+    },
+    tables::{AltChainInfos, Tables, TablesIter, TablesMut},
     types::{AltBlockHeight, AltChainInfo, BlockHash, BlockHeight},
 };
 
@@ -115,3 +119,69 @@ pub fn get_alt_chain_history_ranges(
 
     Ok(ranges)
 }
+
+/// Get a [`ChainInfo`] for every alt-chain in the database.
+///
+/// Each [`ChainInfo`] contains all the alt-blocks in the chain, including those stored under the
+/// chains it split from, [`ChainInfo::block_hashes`] starts at the top block.
+#[doc = doc_error!()]
+pub fn get_alt_chains(tables: &impl TablesIter) -> DbResult<Vec<ChainInfo>> {
+    tables
+        .alt_chain_infos_iter()
+        .iter()?
+        .map(|res| {
+            let (chain_id, chain_info) = res?;
+            get_alt_chain(chain_id.into(), &chain_info, tables)
+        })
+        .collect()
+}
+
+/// Get the [`ChainInfo`] of a single alt-chain.
+#[doc = doc_error!()]
+fn get_alt_chain(
+    chain_id: ChainId,
+    chain_info: &AltChainInfo,
+    tables: &impl Tables,
+) -> DbResult<ChainInfo> {
+    let top_block = tables.alt_blocks_info().get(&AltBlockHeight {
+        chain_id: chain_id.into(),
+        height: chain_info.chain_height - 1,
+    })?;
+
+    let ranges = get_alt_chain_history_ranges(
+        0..chain_info.chain_height,
+        chain_id,
+        tables.alt_chain_infos(),
+    )?;
+
+    let mut block_hashes = Vec::new();
+    for (chain, range) in ranges {
+        // The main-chain range is always last.
+        let Chain::Alt(chain_id) = chain else {
+            break;
+        };
+
+        for height in range.rev() {
+            let block_info = tables.alt_blocks_info().get(&AltBlockHeight {
+                chain_id: chain_id.into(),
+                height,
+            })?;
+
+            block_hashes.push(block_info.block_hash);
+        }
+    }
+
+    let split_height = chain_info.chain_height - block_hashes.len();
+    let main_chain_parent_block =
+        get_block_info(&(split_height - 1), tables.block_infos())?.block_hash;
+
+    Ok(ChainInfo {
+        block_hash: top_block.block_hash,
+        length: usize_to_u64(block_hashes.len()),
+        block_hashes,
+        difficulty_top64: top_block.cumulative_difficulty_high,
+        difficulty: top_block.cumulative_difficulty_low,
+        height: usize_to_u64(top_block.height),
+        main_chain_parent_block,
+    })
+}
@@ -51,8 +51,9 @@ mod chain;
 mod tx;
 
 pub use block::{
-    add_alt_block, flush_alt_blocks, get_alt_block, get_alt_block_extended_header_from_height,
-    get_alt_block_hash,
+    add_alt_block, alt_chains_to_prune, flush_alt_blocks, get_alt_block,
+    get_alt_block_extended_header_from_height, get_alt_block_hash, prune_alt_chains,
+    AltChainsToPrune,
 };
-pub use chain::{get_alt_chain_history_ranges, update_alt_chain_info};
+pub use chain::{get_alt_chain_history_ranges, get_alt_chains, update_alt_chain_info};
 pub use tx::{add_alt_transaction_blob, get_alt_transaction};
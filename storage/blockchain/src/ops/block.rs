@@ -396,13 +396,13 @@ pub fn block_exists(
 mod test {
     use pretty_assertions::assert_eq;
 
-    use cuprate_database::{Env, EnvInner, TxRw};
+    use cuprate_database::{config::Backend, Env, EnvInner, TxRw};
     use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
 
     use crate::{
         ops::tx::{get_tx, tx_exists},
         tables::OpenTables,
-        tests::{assert_all_tables_are_empty, tmp_concrete_env, AssertTableLen},
+        tests::{assert_all_tables_are_empty, backend_tests, tmp_concrete_env, AssertTableLen},
     };
 
     use super::*;
@@ -414,9 +414,8 @@ mod test {
     ///
     /// It simply tests if the proper tables are mutated, and if the data
     /// stored and retrieved is the same.
-    #[test]
-    fn all_block_functions() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_block_functions(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -561,10 +560,8 @@ mod test {
     }
 
     /// We should panic if: `block.height` > `u32::MAX`
-    #[test]
-    #[should_panic(expected = "block.height (4294967296) > u32::MAX")]
-    fn block_height_gt_u32_max() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn block_height_gt_u32_max(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -578,12 +575,8 @@ mod test {
     }
 
     /// We should panic if: `block.height` != the chain height
-    #[test]
-    #[should_panic(
-        expected = "assertion `left == right` failed: block.height (123) != chain_height (1)\n  left: 123\n right: 1"
-    )]
-    fn block_height_not_chain_height() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn block_height_not_chain_height(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -603,4 +596,14 @@ mod test {
         block.height = 123;
         add_block(&block, &mut tables).unwrap();
     }
+
+    backend_tests! {
+        all_block_functions,
+        #[should_panic(expected = "block.height (4294967296) > u32::MAX")]
+        block_height_gt_u32_max,
+        #[should_panic(
+            expected = "assertion `left == right` failed: block.height (123) != chain_height (1)\n  left: 123\n right: 1"
+        )]
+        block_height_not_chain_height,
+    }
 }
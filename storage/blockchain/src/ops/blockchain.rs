@@ -127,7 +127,7 @@ pub fn find_split_point(
 mod test {
     use pretty_assertions::assert_eq;
 
-    use cuprate_database::{Env, EnvInner, TxRw};
+    use cuprate_database::{config::Backend, Env, EnvInner, TxRw};
     use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
 
     use super::*;
@@ -135,7 +135,7 @@ mod test {
     use crate::{
         ops::block::add_block,
         tables::{OpenTables, Tables},
-        tests::{assert_all_tables_are_empty, tmp_concrete_env, AssertTableLen},
+        tests::{assert_all_tables_are_empty, backend_tests, tmp_concrete_env, AssertTableLen},
     };
 
     /// Tests all above functions.
@@ -145,9 +145,8 @@ mod test {
     ///
     /// It simply tests if the proper tables are mutated, and if the data
     /// stored and retrieved is the same.
-    #[test]
-    fn all_blockchain_functions() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_blockchain_functions(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -225,4 +224,8 @@ mod test {
             TxRw::commit(tx_rw).unwrap();
         }
     }
+
+    backend_tests! {
+        all_blockchain_functions,
+    }
 }
@@ -1,12 +1,27 @@
 //! Key image functions.
+//!
+//! # Key image index
+//! The [`KeyImageSpends`] table is an optional index of where key images were spent,
+//! it is enabled with [`Config::key_image_index`](crate::config::Config::key_image_index).
+//!
+//! New blocks are added to the index as they are added, blocks that existed before
+//! the index was enabled are added with [`backfill_key_image_spends`], from the top down.
+//! The progress of this is stored with [`set_key_image_index_height`].
 
 //---------------------------------------------------------------------------------------------------- Import
-use cuprate_database::{DatabaseRo, DatabaseRw, DbResult};
+use monero_serai::transaction::Input;
+
+use cuprate_database::{DatabaseRo, DatabaseRw, DbResult, RuntimeError};
 
 use crate::{
-    ops::macros::{doc_add_block_inner_invariant, doc_error},
-    tables::KeyImages,
-    types::KeyImage,
+    ops::{
+        blockchain::chain_height,
+        macros::{doc_add_block_inner_invariant, doc_error},
+        property::{get_key_image_index_height, set_key_image_index_height},
+        tx::get_tx_from_id,
+    },
+    tables::{KeyImageSpends, KeyImages, TablesMut},
+    types::{BlockHeight, KeyImage, KeyImageSpend},
 };
 
 //---------------------------------------------------------------------------------------------------- Key image functions
@@ -42,18 +57,109 @@ pub fn key_image_exists(
     table_key_images.contains(key_image)
 }
 
+//---------------------------------------------------------------------------------------------------- Key image index functions
+#[doc = doc_add_block_inner_invariant!()]
+#[doc = doc_error!()]
+#[inline]
+pub fn add_key_image_spend(
+    key_image: &KeyImage,
+    spend: &KeyImageSpend,
+    table_key_image_spends: &mut impl DatabaseRw<KeyImageSpends>,
+) -> DbResult<()> {
+    table_key_image_spends.put(key_image, spend)
+}
+
+/// This will return `Ok(())` if the key image is not in the index,
+/// e.g. because it has not been backfilled yet.
+///
+#[doc = doc_add_block_inner_invariant!()]
+#[doc = doc_error!()]
+#[inline]
+pub fn remove_key_image_spend(
+    key_image: &KeyImage,
+    table_key_image_spends: &mut impl DatabaseRw<KeyImageSpends>,
+) -> DbResult<()> {
+    table_key_image_spends.delete(key_image)
+}
+
+/// Retrieve where a key image was spent.
+///
+/// This returns [`None`] if the key image is not in the index, this does not mean it is
+/// unspent if the index is disabled or not fully backfilled, see [`get_key_image_index_height`].
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn get_key_image_spend(
+    key_image: &KeyImage,
+    table_key_image_spends: &impl DatabaseRo<KeyImageSpends>,
+) -> DbResult<Option<KeyImageSpend>> {
+    match table_key_image_spends.get(key_image) {
+        Ok(spend) => Ok(Some(spend)),
+        Err(RuntimeError::KeyNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Add up to `max_blocks` blocks that are not yet in the [`KeyImageSpends`] index to it.
+///
+/// Blocks are added from the top down, starting below the height
+/// returned from [`get_key_image_index_height`], which is then updated.
+///
+/// This returns the new height below which blocks still need to be added,
+/// `Some(0)` once the index is complete, or [`None`] if the index is disabled.
+///
+#[doc = doc_error!()]
+pub fn backfill_key_image_spends(
+    max_blocks: usize,
+    tables: &mut impl TablesMut,
+) -> DbResult<Option<BlockHeight>> {
+    let Some(index_height) = get_key_image_index_height(tables.metadata())? else {
+        return Ok(None);
+    };
+
+    // Blocks may have been popped since the height was set.
+    let end = index_height.min(chain_height(tables.block_heights())?);
+    let start = end.saturating_sub(max_blocks);
+
+    for height in start..end {
+        let tx_hashes = tables.block_txs_hashes().get(&height)?.0;
+
+        // The miner transaction is not included, it does not spend any key images.
+        for tx_hash in &tx_hashes {
+            let tx_id = tables.tx_ids().get(tx_hash)?;
+            let tx = get_tx_from_id(&tx_id, tables.tx_blobs())?;
+
+            for input in &tx.prefix().inputs {
+                if let Input::ToKey { key_image, .. } = input {
+                    add_key_image_spend(
+                        key_image.as_bytes(),
+                        &KeyImageSpend { tx_id, height },
+                        tables.key_image_spends_mut(),
+                    )?;
+                }
+            }
+        }
+    }
+
+    set_key_image_index_height(Some(start), tables.metadata_mut())?;
+    Ok(Some(start))
+}
+
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {
     use hex_literal::hex;
 
-    use cuprate_database::{Env, EnvInner, TxRw};
+    use cuprate_database::{config::Backend, Env, EnvInner, TxRw};
+    use cuprate_test_utils::data::{BLOCK_V1_TX2, BLOCK_V9_TX3};
+    use cuprate_types::VerifiedBlockInformation;
 
     use super::*;
 
     use crate::{
+        ops::block::{add_block, pop_block},
         tables::{OpenTables, Tables, TablesMut},
-        tests::{assert_all_tables_are_empty, tmp_concrete_env, AssertTableLen},
+        tests::{assert_all_tables_are_empty, backend_tests, tmp_concrete_env, AssertTableLen},
     };
 
     /// Tests all above key-image functions.
@@ -63,9 +169,8 @@ mod test {
     ///
     /// It simply tests if the proper tables are mutated, and if the data
     /// stored and retrieved is the same.
-    #[test]
-    fn all_key_image_functions() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_key_image_functions(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -125,4 +230,72 @@ mod test {
 
         assert_all_tables_are_empty(&env);
     }
+
+    /// Tests the key image index is kept up to date with
+    /// added/removed blocks and backfilled correctly.
+    fn key_image_index(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
+        let env_inner = env.env_inner();
+
+        let mut blocks = [BLOCK_V1_TX2.clone(), BLOCK_V9_TX3.clone()];
+        for (height, block) in blocks.iter_mut().enumerate() {
+            block.height = height;
+        }
+
+        /// Assert the key images of `block` are (not) in the index.
+        fn assert_indexed(block: &VerifiedBlockInformation, indexed: bool, tables: &impl Tables) {
+            for tx in &block.txs {
+                let tx_id = tables.tx_ids().get(&tx.tx_hash).unwrap();
+                for input in &tx.tx.prefix().inputs {
+                    let Input::ToKey { key_image, .. } = input else {
+                        continue;
+                    };
+
+                    let spend =
+                        get_key_image_spend(key_image.as_bytes(), tables.key_image_spends())
+                            .unwrap();
+                    let expected = indexed.then_some(KeyImageSpend {
+                        tx_id,
+                        height: block.height,
+                    });
+                    assert_eq!(spend, expected);
+                }
+            }
+        }
+
+        let tx_rw = env_inner.tx_rw().unwrap();
+        let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+
+        // The index is disabled.
+        add_block(&blocks[0], &mut tables).unwrap();
+        assert_eq!(backfill_key_image_spends(10, &mut tables).unwrap(), None);
+        assert_eq!(tables.key_image_spends().len().unwrap(), 0);
+
+        // Enable the index, new blocks are added to it.
+        set_key_image_index_height(Some(1), tables.metadata_mut()).unwrap();
+        add_block(&blocks[1], &mut tables).unwrap();
+        assert_indexed(&blocks[0], false, &tables);
+        assert_indexed(&blocks[1], true, &tables);
+
+        // Old blocks are backfilled.
+        assert_eq!(backfill_key_image_spends(10, &mut tables).unwrap(), Some(0));
+        assert_eq!(
+            get_key_image_index_height(tables.metadata()).unwrap(),
+            Some(0)
+        );
+        assert_indexed(&blocks[0], true, &tables);
+
+        // Popped blocks are removed.
+        pop_block(None, &mut tables).unwrap();
+        assert_indexed(&blocks[0], true, &tables);
+        assert_eq!(
+            tables.key_image_spends().len().unwrap(),
+            tables.key_images().len().unwrap()
+        );
+    }
+
+    backend_tests! {
+        all_key_image_functions,
+        key_image_index,
+    }
 }
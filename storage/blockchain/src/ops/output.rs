@@ -8,15 +8,26 @@ use cuprate_database::{
     DbResult, RuntimeError, {DatabaseRo, DatabaseRw},
 };
 use cuprate_helper::{cast::u32_to_usize, crypto::compute_zero_commitment};
-use cuprate_helper::{cast::u64_to_usize, map::u64_to_timelock};
+use cuprate_helper::{
+    cast::{u64_to_usize, usize_to_u64},
+    map::u64_to_timelock,
+};
 use cuprate_types::OutputOnChain;
 
 use crate::{
-    ops::macros::{doc_add_block_inner_invariant, doc_error},
+    ops::{
+        blockchain::chain_height,
+        macros::{doc_add_block_inner_invariant, doc_error},
+        property::{get_output_key_index_height, set_output_key_index_height},
+        tx::get_tx_from_id,
+    },
     tables::{
-        BlockInfos, BlockTxsHashes, Outputs, RctOutputs, Tables, TablesMut, TxBlobs, TxUnlockTime,
+        BlockInfos, BlockTxsHashes, OutputKeys, Outputs, RctOutputs, Tables, TablesMut, TxBlobs,
+        TxUnlockTime,
+    },
+    types::{
+        Amount, AmountIndex, BlockHeight, Output, OutputFlags, OutputKey, PreRctOutputId, RctOutput,
     },
-    types::{Amount, AmountIndex, Output, OutputFlags, PreRctOutputId, RctOutput},
 };
 
 //---------------------------------------------------------------------------------------------------- Pre-RCT Outputs
@@ -148,6 +159,130 @@ pub fn get_rct_num_outputs(table_rct_outputs: &impl DatabaseRo<RctOutputs>) -> D
     table_rct_outputs.len()
 }
 
+//---------------------------------------------------------------------------------------------------- Output key index
+/// Add the keys of all outputs in `tx` to the [`OutputKeys`] index.
+///
+/// `amount_indices` are the amount indices of `tx`'s outputs, as stored in
+/// [`TxOutputs`](crate::tables::TxOutputs).
+///
+/// If `overwrite` is `false` keys already in the index are left as is.
+///
+#[doc = doc_add_block_inner_invariant!()]
+#[doc = doc_error!()]
+#[inline]
+pub fn add_output_keys(
+    tx: &Transaction,
+    amount_indices: &[AmountIndex],
+    overwrite: bool,
+    table_output_keys: &mut impl DatabaseRw<OutputKeys>,
+) -> DbResult<()> {
+    for (output, amount_index) in tx.prefix().outputs.iter().zip(amount_indices) {
+        if !overwrite && table_output_keys.contains(&output.key.0)? {
+            continue;
+        }
+
+        table_output_keys.put(&output.key.0, &output_id(tx, output.amount, *amount_index))?;
+    }
+
+    Ok(())
+}
+
+/// Remove the keys of all outputs in `tx` from the [`OutputKeys`] index.
+///
+/// Keys that map to another output, or are not in the index, are left as is.
+///
+#[doc = doc_add_block_inner_invariant!()]
+#[doc = doc_error!()]
+#[inline]
+pub fn remove_output_keys(
+    tx: &Transaction,
+    amount_indices: &[AmountIndex],
+    table_output_keys: &mut impl DatabaseRw<OutputKeys>,
+) -> DbResult<()> {
+    for (output, amount_index) in tx.prefix().outputs.iter().zip(amount_indices) {
+        match table_output_keys.get(&output.key.0) {
+            Ok(id) if id == output_id(tx, output.amount, *amount_index) => {
+                table_output_keys.delete(&output.key.0)?;
+            }
+            Ok(_) | Err(RuntimeError::KeyNotFound) => (),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Retrieve the [`PreRctOutputId`] of the output with the key `output_key`.
+///
+/// This returns [`None`] if the key is not in the index, this does not mean there is
+/// no such output if the index is disabled or not fully backfilled, see
+/// [`get_output_key_index_height`].
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn get_output_by_key(
+    output_key: &OutputKey,
+    table_output_keys: &impl DatabaseRo<OutputKeys>,
+) -> DbResult<Option<PreRctOutputId>> {
+    match table_output_keys.get(output_key) {
+        Ok(id) => Ok(Some(id)),
+        Err(RuntimeError::KeyNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Add up to `max_blocks` blocks that are not yet in the [`OutputKeys`] index to it.
+///
+/// This works the same way as [`crate::ops::key_image::backfill_key_image_spends`],
+/// using [`get_output_key_index_height`].
+///
+#[doc = doc_error!()]
+pub fn backfill_output_keys(
+    max_blocks: usize,
+    tables: &mut impl TablesMut,
+) -> DbResult<Option<BlockHeight>> {
+    let Some(index_height) = get_output_key_index_height(tables.metadata())? else {
+        return Ok(None);
+    };
+
+    // Blocks may have been popped since the height was set.
+    let end = index_height.min(chain_height(tables.block_heights())?);
+    let start = end.saturating_sub(max_blocks);
+
+    // Newer outputs are already in the index, go from the newest output to
+    // the oldest without overwriting so the latest output with a key is kept.
+    for height in (start..end).rev() {
+        let mining_tx_index = tables.block_infos().get(&height)?.mining_tx_index;
+        let num_txs = usize_to_u64(tables.block_txs_hashes().get(&height)?.0.len());
+
+        for tx_id in (mining_tx_index..=mining_tx_index + num_txs).rev() {
+            let tx = get_tx_from_id(&tx_id, tables.tx_blobs())?;
+            let amount_indices = tables.tx_outputs().get(&tx_id)?;
+            add_output_keys(&tx, &amount_indices.0, false, tables.output_keys_mut())?;
+        }
+    }
+
+    set_output_key_index_height(Some(start), tables.metadata_mut())?;
+    Ok(Some(start))
+}
+
+/// The [`PreRctOutputId`] of an output of `tx`, pre-RCT outputs are only in v1 transactions.
+const fn output_id(
+    tx: &Transaction,
+    amount: Option<Amount>,
+    amount_index: AmountIndex,
+) -> PreRctOutputId {
+    let amount = match (tx, amount) {
+        (Transaction::V1 { .. }, Some(amount)) => amount,
+        _ => 0,
+    };
+
+    PreRctOutputId {
+        amount,
+        amount_index,
+    }
+}
+
 //---------------------------------------------------------------------------------------------------- Mapping functions
 /// Map an [`Output`] to a [`cuprate_types::OutputOnChain`].
 #[doc = doc_error!()]
@@ -301,11 +436,14 @@ mod test {
 
     use pretty_assertions::assert_eq;
 
-    use cuprate_database::{Env, EnvInner};
+    use cuprate_database::{config::Backend, Env, EnvInner};
+    use cuprate_test_utils::data::{BLOCK_V1_TX2, BLOCK_V9_TX3};
+    use cuprate_types::VerifiedBlockInformation;
 
     use crate::{
+        ops::block::{add_block, pop_block},
         tables::{OpenTables, Tables, TablesMut},
-        tests::{assert_all_tables_are_empty, tmp_concrete_env, AssertTableLen},
+        tests::{assert_all_tables_are_empty, backend_tests, tmp_concrete_env, AssertTableLen},
         types::OutputFlags,
     };
 
@@ -336,9 +474,8 @@ mod test {
     ///
     /// It simply tests if the proper tables are mutated, and if the data
     /// stored and retrieved is the same.
-    #[test]
-    fn all_output_functions() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_output_functions(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -422,4 +559,66 @@ mod test {
 
         assert_all_tables_are_empty(&env);
     }
+
+    /// Tests the output key index is kept up to date with
+    /// added/removed blocks and backfilled correctly.
+    fn output_key_index(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
+        let env_inner = env.env_inner();
+
+        let mut blocks = [BLOCK_V1_TX2.clone(), BLOCK_V9_TX3.clone()];
+        for (height, block) in blocks.iter_mut().enumerate() {
+            block.height = height;
+        }
+
+        /// Assert the output keys of `block` are (not) in the index.
+        fn assert_indexed(block: &VerifiedBlockInformation, indexed: bool, tables: &impl Tables) {
+            let txs = std::iter::once(&block.block.miner_transaction)
+                .chain(block.txs.iter().map(|tx| &tx.tx));
+
+            for tx in txs {
+                for output in &tx.prefix().outputs {
+                    let id = get_output_by_key(&output.key.0, tables.output_keys()).unwrap();
+                    assert_eq!(id.is_some(), indexed);
+
+                    if let Some(id) = id {
+                        let output_on_chain = id_to_output_on_chain(&id, false, tables).unwrap();
+                        assert_eq!(output_on_chain.key.0, output.key.0);
+                    }
+                }
+            }
+        }
+
+        let tx_rw = env_inner.tx_rw().unwrap();
+        let mut tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+
+        // The index is disabled.
+        add_block(&blocks[0], &mut tables).unwrap();
+        assert_eq!(backfill_output_keys(10, &mut tables).unwrap(), None);
+        assert_eq!(tables.output_keys().len().unwrap(), 0);
+
+        // Enable the index, new blocks are added to it.
+        set_output_key_index_height(Some(1), tables.metadata_mut()).unwrap();
+        add_block(&blocks[1], &mut tables).unwrap();
+        assert_indexed(&blocks[0], false, &tables);
+        assert_indexed(&blocks[1], true, &tables);
+
+        // Old blocks are backfilled.
+        assert_eq!(backfill_output_keys(10, &mut tables).unwrap(), Some(0));
+        assert_indexed(&blocks[0], true, &tables);
+
+        // Popped blocks are removed.
+        pop_block(None, &mut tables).unwrap();
+        assert_indexed(&blocks[0], true, &tables);
+        assert_indexed(&blocks[1], false, &tables);
+        assert_eq!(
+            tables.output_keys().len().unwrap(),
+            tables.outputs().len().unwrap() + tables.rct_outputs().len().unwrap()
+        );
+    }
+
+    backend_tests! {
+        all_output_functions,
+        output_key_index,
+    }
 }
@@ -1,12 +1,30 @@
 //! Database properties functions - version, pruning, etc.
 //!
-//! SOMEDAY: the database `properties` table is not yet implemented.
+//! SOMEDAY: the pruning seed is not yet stored in the database.
 
 //---------------------------------------------------------------------------------------------------- Import
-use cuprate_database::DbResult;
+use cuprate_database::{
+    migration::{read_u64, write_u64, METADATA_USER_KEYS_START, METADATA_VERSION},
+    DatabaseRo, DatabaseRw, DbResult, RuntimeError,
+};
+use cuprate_helper::cast::{u64_to_usize, usize_to_u64};
 use cuprate_pruning::PruningSeed;
 
-use crate::ops::macros::doc_error;
+use crate::{ops::macros::doc_error, tables::Metadata, types::BlockHeight};
+
+//---------------------------------------------------------------------------------------------------- Keys
+/// [`Metadata`] key for the state of the
+/// [`KeyImageSpends`](crate::tables::KeyImageSpends) index,
+/// stored as a little-endian [`u64`].
+///
+/// This only exists if the index is enabled, the value is the height
+/// below which blocks have not been indexed yet, `0` once all blocks are.
+pub const METADATA_KEY_IMAGE_INDEX: u8 = METADATA_USER_KEYS_START;
+
+/// [`Metadata`] key for the state of the
+/// [`OutputKeys`](crate::tables::OutputKeys) index,
+/// stored the same way as [`METADATA_KEY_IMAGE_INDEX`].
+pub const METADATA_OUTPUT_KEY_INDEX: u8 = METADATA_USER_KEYS_START + 1;
 
 //---------------------------------------------------------------------------------------------------- Free Functions
 /// SOMEDAY
@@ -21,21 +39,117 @@ use crate::ops::macros::doc_error;
 #[inline]
 pub const fn get_blockchain_pruning_seed() -> DbResult<PruningSeed> {
     // SOMEDAY: impl pruning.
-    // We need a DB properties table.
+    // This could be stored in the `Metadata` table.
     Ok(PruningSeed::NotPruned)
 }
 
-/// SOMEDAY
+/// Retrieve the schema version of the database.
+///
+/// This is the version stored in the database's
+/// [`Metadata`] table, which is [`DATABASE_VERSION`](crate::DATABASE_VERSION)
+/// once [`crate::open`] has finished any migrations.
 ///
 #[doc = doc_error!()]
 ///
 /// # Example
 /// ```rust
-/// # use cuprate_blockchain::{*, tables::*, ops::block::*, ops::tx::*};
-/// // SOMEDAY
+/// # use cuprate_blockchain::{
+/// #     config::ConfigBuilder,
+/// #     cuprate_database::{Env, EnvInner},
+/// #     ops::property::db_version,
+/// #     tables::{OpenTables, Tables},
+/// #     DATABASE_VERSION,
+/// # };
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let tmp_dir = tempfile::tempdir()?;
+/// # let config = ConfigBuilder::new().data_directory(tmp_dir.path().to_owned()).build();
+/// let env = cuprate_blockchain::open(config)?;
+/// let env_inner = env.env_inner();
+/// let tx_ro = env_inner.tx_ro()?;
+/// let tables = env_inner.open_tables(&tx_ro)?;
+///
+/// assert_eq!(db_version(tables.metadata())?, DATABASE_VERSION);
+/// # Ok(()) }
 /// ```
 #[inline]
-pub const fn db_version() -> DbResult<u64> {
-    // SOMEDAY: We need a DB properties table.
-    Ok(crate::constants::DATABASE_VERSION)
+pub fn db_version(table_metadata: &impl DatabaseRo<Metadata>) -> DbResult<u64> {
+    Ok(read_u64(&table_metadata.get(&METADATA_VERSION)?))
+}
+
+/// Retrieve the state of the [`KeyImageSpends`](crate::tables::KeyImageSpends) index.
+///
+/// This returns [`None`] if the index is disabled, otherwise it returns
+/// the height below which blocks still need to be backfilled into the index.
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn get_key_image_index_height(
+    table_metadata: &impl DatabaseRo<Metadata>,
+) -> DbResult<Option<BlockHeight>> {
+    get_index_height(METADATA_KEY_IMAGE_INDEX, table_metadata)
+}
+
+/// Set the state of the [`KeyImageSpends`](crate::tables::KeyImageSpends) index.
+///
+/// See [`get_key_image_index_height`], [`None`] marks the index as disabled.
+///
+/// This does not add or remove any entries of the index itself.
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn set_key_image_index_height(
+    height: Option<BlockHeight>,
+    table_metadata: &mut impl DatabaseRw<Metadata>,
+) -> DbResult<()> {
+    set_index_height(METADATA_KEY_IMAGE_INDEX, height, table_metadata)
+}
+
+/// Retrieve the state of the [`OutputKeys`](crate::tables::OutputKeys) index.
+///
+/// See [`get_key_image_index_height`].
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn get_output_key_index_height(
+    table_metadata: &impl DatabaseRo<Metadata>,
+) -> DbResult<Option<BlockHeight>> {
+    get_index_height(METADATA_OUTPUT_KEY_INDEX, table_metadata)
+}
+
+/// Set the state of the [`OutputKeys`](crate::tables::OutputKeys) index.
+///
+/// See [`set_key_image_index_height`].
+///
+#[doc = doc_error!()]
+#[inline]
+pub fn set_output_key_index_height(
+    height: Option<BlockHeight>,
+    table_metadata: &mut impl DatabaseRw<Metadata>,
+) -> DbResult<()> {
+    set_index_height(METADATA_OUTPUT_KEY_INDEX, height, table_metadata)
+}
+
+//---------------------------------------------------------------------------------------------------- Private
+/// Read the index state stored under the metadata `key`.
+pub(crate) fn get_index_height(
+    key: u8,
+    table_metadata: &impl DatabaseRo<Metadata>,
+) -> DbResult<Option<BlockHeight>> {
+    match table_metadata.get(&key) {
+        Ok(value) => Ok(Some(u64_to_usize(read_u64(&value)))),
+        Err(RuntimeError::KeyNotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Write the index state stored under the metadata `key`.
+pub(crate) fn set_index_height(
+    key: u8,
+    height: Option<BlockHeight>,
+    table_metadata: &mut impl DatabaseRw<Metadata>,
+) -> DbResult<()> {
+    match height {
+        Some(height) => table_metadata.put(&key, &write_u64(usize_to_u64(height))),
+        None => table_metadata.delete(&key),
+    }
 }
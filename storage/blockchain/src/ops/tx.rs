@@ -9,14 +9,18 @@ use cuprate_helper::crypto::compute_zero_commitment;
 
 use crate::{
     ops::{
-        key_image::{add_key_image, remove_key_image},
+        key_image::{add_key_image, add_key_image_spend, remove_key_image, remove_key_image_spend},
         macros::{doc_add_block_inner_invariant, doc_error},
         output::{
-            add_output, add_rct_output, get_rct_num_outputs, remove_output, remove_rct_output,
+            add_output, add_output_keys, add_rct_output, get_rct_num_outputs, remove_output,
+            remove_output_keys, remove_rct_output,
         },
+        property::{get_key_image_index_height, get_output_key_index_height},
     },
     tables::{TablesMut, TxBlobs, TxIds},
-    types::{BlockHeight, Output, OutputFlags, PreRctOutputId, RctOutput, TxHash, TxId},
+    types::{
+        BlockHeight, KeyImageSpend, Output, OutputFlags, PreRctOutputId, RctOutput, TxHash, TxId,
+    },
 };
 
 //---------------------------------------------------------------------------------------------------- Private
@@ -91,12 +95,26 @@ pub fn add_tx(
     // <https://github.com/monero-project/monero/blob/eac1b86bb2818ac552457380c9dd421fb8935e5b/src/blockchain_db/blockchain_db.cpp#L212-L216>
     let mut miner_tx = false;
 
+    // Is the optional key image index enabled?
+    let key_image_index = get_key_image_index_height(tables.metadata())?.is_some();
+
     // Key images.
     for inputs in &tx.prefix().inputs {
         match inputs {
             // Key images.
             Input::ToKey { key_image, .. } => {
                 add_key_image(key_image.as_bytes(), tables.key_images_mut())?;
+
+                if key_image_index {
+                    add_key_image_spend(
+                        key_image.as_bytes(),
+                        &KeyImageSpend {
+                            tx_id,
+                            height: *block_height,
+                        },
+                        tables.key_image_spends_mut(),
+                    )?;
+                }
             }
             // This is a miner transaction, set it for later use.
             Input::Gen(_) => miner_tx = true,
@@ -162,6 +180,12 @@ pub fn add_tx(
             .collect::<Result<Vec<_>, _>>()?,
     };
 
+    //------------------------------------------------------ Output keys
+    // Is the optional output key index enabled?
+    if get_output_key_index_height(tables.metadata())?.is_some() {
+        add_output_keys(tx, &amount_indices, true, tables.output_keys_mut())?;
+    }
+
     tables
         .tx_outputs_mut()
         .put(&tx_id, &StorableVec(amount_indices))?;
@@ -191,7 +215,7 @@ pub fn remove_tx(tx_hash: &TxHash, tables: &mut impl TablesMut) -> DbResult<(TxI
     let tx_id = tables.tx_ids_mut().take(tx_hash)?;
     let tx_blob = tables.tx_blobs_mut().take(&tx_id)?;
     tables.tx_heights_mut().delete(&tx_id)?;
-    tables.tx_outputs_mut().delete(&tx_id)?;
+    let amount_indices = tables.tx_outputs_mut().take(&tx_id)?;
 
     //------------------------------------------------------ Pruning
     // SOMEDAY: implement pruning after `monero-serai` does.
@@ -215,17 +239,27 @@ pub fn remove_tx(tx_hash: &TxHash, tables: &mut impl TablesMut) -> DbResult<(TxI
     //------------------------------------------------------ Key Images
     // Is this a miner transaction?
     let mut miner_tx = false;
+    let key_image_index = get_key_image_index_height(tables.metadata())?.is_some();
     for inputs in &tx.prefix().inputs {
         match inputs {
             // Key images.
             Input::ToKey { key_image, .. } => {
                 remove_key_image(key_image.as_bytes(), tables.key_images_mut())?;
+
+                if key_image_index {
+                    remove_key_image_spend(key_image.as_bytes(), tables.key_image_spends_mut())?;
+                }
             }
             // This is a miner transaction, set it for later use.
             Input::Gen(_) => miner_tx = true,
         }
     } // for each input
 
+    //------------------------------------------------------ Output keys
+    if get_output_key_index_height(tables.metadata())?.is_some() {
+        remove_output_keys(&tx, &amount_indices.0, tables.output_keys_mut())?;
+    }
+
     //------------------------------------------------------ Outputs
     // Remove each output in the transaction.
     for output in &tx.prefix().outputs {
@@ -312,18 +346,17 @@ mod test {
 
     use pretty_assertions::assert_eq;
 
-    use cuprate_database::{Env, EnvInner, TxRw};
+    use cuprate_database::{config::Backend, Env, EnvInner, TxRw};
     use cuprate_test_utils::data::{TX_V1_SIG0, TX_V1_SIG2, TX_V2_RCT3};
 
     use crate::{
         tables::{OpenTables, Tables},
-        tests::{assert_all_tables_are_empty, tmp_concrete_env, AssertTableLen},
+        tests::{assert_all_tables_are_empty, backend_tests, tmp_concrete_env, AssertTableLen},
     };
 
     /// Tests all above tx functions when only inputting `Transaction` data (no Block).
-    #[test]
-    fn all_tx_functions() {
-        let (env, _tmp) = tmp_concrete_env();
+    fn all_tx_functions(backend: Backend) {
+        let (env, _tmp) = tmp_concrete_env(backend);
         let env_inner = env.env_inner();
         assert_all_tables_are_empty(&env);
 
@@ -418,4 +451,8 @@ mod test {
 
         assert_all_tables_are_empty(&env);
     }
+
+    backend_tests! {
+        all_tx_functions,
+    }
 }
@@ -24,21 +24,24 @@ use rayon::{
 };
 use thread_local::ThreadLocal;
 
-use cuprate_database::{ConcreteEnv, DatabaseRo, DbResult, Env, EnvInner, RuntimeError};
+use cuprate_database::{
+    stats::EnvStats, ConcreteEnv, DatabaseRo, DbResult, Env, EnvInner, RuntimeError,
+};
 use cuprate_database_service::{init_thread_pool, DatabaseReadService, ReaderThreads};
-use cuprate_helper::map::combine_low_high_bits_to_u128;
+use cuprate_helper::{cast::u64_to_usize, fs::free_disk_space, map::combine_low_high_bits_to_u128};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse},
     output_cache::OutputCache,
     rpc::OutputHistogramInput,
-    Chain, ChainId, ExtendedBlockHeader, OutputDistributionInput, TxsInBlock,
+    Chain, ChainId, DatabaseStats, ExtendedBlockHeader, KeyImageSpend, OutputDistributionInput,
+    TableStats, TxsInBlock,
 };
 
 use crate::{
     ops::{
         alt_block::{
             get_alt_block, get_alt_block_extended_header_from_height, get_alt_block_hash,
-            get_alt_chain_history_ranges,
+            get_alt_chain_history_ranges, get_alt_chains,
         },
         block::{
             block_exists, get_block_blob_with_tx_indexes, get_block_complete_entry,
@@ -46,19 +49,21 @@ use crate::{
             get_block_height, get_block_info,
         },
         blockchain::{cumulative_generated_coins, find_split_point, top_block_height},
-        key_image::key_image_exists,
-        output::id_to_output_on_chain,
+        key_image::{get_key_image_spend, key_image_exists},
+        output::{get_output_by_key, id_to_output_on_chain},
+        property::get_key_image_index_height,
     },
     service::{
         free::{compact_history_genesis_not_included, compact_history_index_to_height_offset},
         types::{BlockchainReadHandle, ResponseResult},
     },
     tables::{
-        AltBlockHeights, BlockHeights, BlockInfos, OpenTables, RctOutputs, Tables, TablesIter,
-        TxIds, TxOutputs,
+        table_stats, AltBlockHeights, AltChainInfos, BlockHeights, BlockInfos, OpenTables,
+        OutputKeys, RctOutputs, Tables, TablesIter, TxIds, TxOutputs,
     },
     types::{
-        AltBlockHeight, Amount, AmountIndex, BlockHash, BlockHeight, KeyImage, PreRctOutputId,
+        AltBlockHeight, Amount, AmountIndex, BlockHash, BlockHeight, KeyImage, OutputKey,
+        PreRctOutputId,
     },
 };
 
@@ -130,6 +135,8 @@ fn map_request(
         R::NumberOutputsWithAmount(vec) => number_outputs_with_amount(env, vec),
         R::KeyImagesSpent(set) => key_images_spent(env, set),
         R::KeyImagesSpentVec(set) => key_images_spent_vec(env, set),
+        R::KeyImageSpends(key_images) => key_image_spends(env, key_images),
+        R::OutputByKey(output_key) => output_by_key(env, &output_key),
         R::CompactChainHistory => compact_chain_history(env),
         R::NextChainEntry(block_hashes, amount) => next_chain_entry(env, &block_hashes, amount),
         R::FindFirstUnknown(block_ids) => find_first_unknown(env, &block_ids),
@@ -142,6 +149,7 @@ fn map_request(
         R::BlockByHash(hash) => block_by_hash(env, hash),
         R::TotalTxCount => total_tx_count(env),
         R::DatabaseSize => database_size(env),
+        R::DatabaseStats => database_stats(env),
         R::OutputHistogram(input) => output_histogram(env, input),
         R::CoinbaseTxSum { height, count } => coinbase_tx_sum(env, height, count),
         R::AltChains => alt_chains(env),
@@ -673,6 +681,55 @@ fn key_images_spent_vec(env: &ConcreteEnv, key_images: Vec<KeyImage>) -> Respons
     ))
 }
 
+/// [`BlockchainReadRequest::KeyImageSpends`]
+fn key_image_spends(env: &ConcreteEnv, key_images: Vec<KeyImage>) -> ResponseResult {
+    // Single-threaded, no `ThreadLocal` required.
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let tables = env_inner.open_tables(&tx_ro)?;
+
+    if get_key_image_index_height(tables.metadata())?.is_none() {
+        return Ok(BlockchainResponse::KeyImageSpends(None));
+    }
+
+    let key_image_spend = |key_image: KeyImage| -> DbResult<Option<KeyImageSpend>> {
+        let Some(spend) = get_key_image_spend(&key_image, tables.key_image_spends())? else {
+            return Ok(None);
+        };
+
+        // The transaction's position in its block, after the miner transaction.
+        let mining_tx_index = get_block_info(&spend.height, tables.block_infos())?.mining_tx_index;
+        let tx_index = u64_to_usize(spend.tx_id - mining_tx_index - 1);
+        let tx_hash = tables.block_txs_hashes().get(&spend.height)?.0[tx_index];
+
+        Ok(Some(KeyImageSpend {
+            tx_hash,
+            block_height: spend.height,
+        }))
+    };
+
+    Ok(BlockchainResponse::KeyImageSpends(Some(
+        key_images
+            .into_iter()
+            .map(key_image_spend)
+            .collect::<DbResult<_>>()?,
+    )))
+}
+
+/// [`BlockchainReadRequest::OutputByKey`]
+fn output_by_key(env: &ConcreteEnv, output_key: &OutputKey) -> ResponseResult {
+    // Single-threaded, no `ThreadLocal` required.
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let table_output_keys = env_inner.open_db_ro::<OutputKeys>(&tx_ro)?;
+
+    let output = get_output_by_key(output_key, &table_output_keys)?;
+
+    Ok(BlockchainResponse::OutputByKey(
+        output.map(|id| (id.amount, id.amount_index)),
+    ))
+}
+
 /// [`BlockchainReadRequest::CompactChainHistory`]
 fn compact_chain_history(env: &ConcreteEnv) -> ResponseResult {
     let env_inner = env.env_inner();
@@ -904,11 +961,41 @@ fn total_tx_count(env: &ConcreteEnv) -> ResponseResult {
 /// [`BlockchainReadRequest::DatabaseSize`]
 fn database_size(env: &ConcreteEnv) -> ResponseResult {
     Ok(BlockchainResponse::DatabaseSize {
-        database_size: todo!(),
-        free_space: todo!(),
+        database_size: env.disk_size_bytes()?,
+        free_space: free_disk_space(env.config().db_directory())?,
     })
 }
 
+/// [`BlockchainReadRequest::DatabaseStats`]
+fn database_stats(env: &ConcreteEnv) -> ResponseResult {
+    let EnvStats {
+        map_size,
+        used_bytes,
+        free_bytes,
+    } = env.stats()?;
+
+    let tables = table_stats(env)?
+        .into_iter()
+        .map(|stats| TableStats {
+            table: stats.table,
+            entries: stats.entries,
+            depth: stats.depth,
+            branch_pages: stats.branch_pages,
+            leaf_pages: stats.leaf_pages,
+            overflow_pages: stats.overflow_pages,
+            size_bytes: stats.size_bytes,
+        })
+        .collect();
+
+    Ok(BlockchainResponse::DatabaseStats(DatabaseStats {
+        disk_size: env.disk_size_bytes()?,
+        map_size,
+        used_bytes,
+        free_bytes,
+        tables,
+    }))
+}
+
 /// [`BlockchainReadRequest::OutputHistogram`]
 fn output_histogram(env: &ConcreteEnv, input: OutputHistogramInput) -> ResponseResult {
     Ok(BlockchainResponse::OutputHistogram(todo!()))
@@ -921,12 +1008,22 @@ fn coinbase_tx_sum(env: &ConcreteEnv, height: usize, count: u64) -> ResponseResu
 
 /// [`BlockchainReadRequest::AltChains`]
 fn alt_chains(env: &ConcreteEnv) -> ResponseResult {
-    Ok(BlockchainResponse::AltChains(todo!()))
+    // Single-threaded, no `ThreadLocal` required.
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let tables = env_inner.open_tables(&tx_ro)?;
+
+    Ok(BlockchainResponse::AltChains(get_alt_chains(&tables)?))
 }
 
 /// [`BlockchainReadRequest::AltChainCount`]
 fn alt_chain_count(env: &ConcreteEnv) -> ResponseResult {
-    Ok(BlockchainResponse::AltChainCount(todo!()))
+    // Single-threaded, no `ThreadLocal` required.
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let len = env_inner.open_db_ro::<AltChainInfos>(&tx_ro)?.len()?;
+
+    Ok(BlockchainResponse::AltChainCount(u64_to_usize(len)))
 }
 
 /// [`BlockchainReadRequest::Transactions`]
@@ -13,7 +13,9 @@ use pretty_assertions::assert_eq;
 use rand::Rng;
 use tower::{Service, ServiceExt};
 
-use cuprate_database::{ConcreteEnv, DatabaseIter, DatabaseRo, Env, EnvInner, RuntimeError};
+use cuprate_database::{
+    config::Backend, ConcreteEnv, DatabaseIter, DatabaseRo, Env, EnvInner, RuntimeError,
+};
 use cuprate_test_utils::data::{BLOCK_V16_TX0, BLOCK_V1_TX2, BLOCK_V9_TX3};
 use cuprate_types::{
     blockchain::{BlockchainReadRequest, BlockchainResponse, BlockchainWriteRequest},
@@ -29,13 +31,15 @@ use crate::{
     },
     service::{init, BlockchainReadHandle, BlockchainWriteHandle},
     tables::{OpenTables, Tables, TablesIter},
-    tests::{map_verified_block_to_alt, AssertTableLen},
+    tests::{async_backend_tests, map_verified_block_to_alt, AssertTableLen},
     types::{Amount, AmountIndex, PreRctOutputId},
 };
 
 //---------------------------------------------------------------------------------------------------- Helper functions
-/// Initialize the `service`.
-fn init_service() -> (
+/// Initialize the `service` with `backend`.
+fn init_service(
+    backend: Backend,
+) -> (
     BlockchainReadHandle,
     BlockchainWriteHandle,
     Arc<ConcreteEnv>,
@@ -44,6 +48,7 @@ fn init_service() -> (
     let tempdir = tempfile::tempdir().unwrap();
     let config = ConfigBuilder::new()
         .data_directory(tempdir.path().into())
+        .backend(backend)
         .low_power()
         .build();
     let (reader, writer, env) = init(config).unwrap();
@@ -61,6 +66,8 @@ fn init_service() -> (
     reason = "INVARIANT: tests are using a single threaded runtime"
 )]
 async fn test_template(
+    // Which database backend to use?
+    backend: Backend,
     // Which block(s) to add?
     blocks: &[&VerifiedBlockInformation],
     // Total amount of generated coins after the block(s) have been added.
@@ -69,7 +76,7 @@ async fn test_template(
     assert_table_len: AssertTableLen,
 ) {
     //----------------------------------------------------------------------- Write requests
-    let (reader, mut writer, env, _tempdir) = init_service();
+    let (reader, mut writer, env, _tempdir) = init_service(backend);
 
     let env_inner = env.env_inner();
     let tx_ro = env_inner.tx_ro().unwrap();
@@ -329,15 +336,14 @@ async fn test_template(
 /// Simply `init()` the service and then drop it.
 ///
 /// If this test fails, something is very wrong.
-#[test]
-fn init_drop() {
-    let (_reader, _writer, _env, _tempdir) = init_service();
+async fn init_drop(backend: Backend) {
+    let (_reader, _writer, _env, _tempdir) = init_service(backend);
 }
 
 /// Assert write/read correctness of [`block_v1_tx2`].
-#[tokio::test]
-async fn v1_tx2() {
+async fn v1_tx2(backend: Backend) {
     test_template(
+        backend,
         &[&*BLOCK_V1_TX2],
         14_535_350_982_449,
         AssertTableLen {
@@ -362,9 +368,9 @@ async fn v1_tx2() {
 }
 
 /// Assert write/read correctness of [`block_v9_tx3`].
-#[tokio::test]
-async fn v9_tx3() {
+async fn v9_tx3(backend: Backend) {
     test_template(
+        backend,
         &[&*BLOCK_V9_TX3],
         3_403_774_022_163,
         AssertTableLen {
@@ -389,9 +395,9 @@ async fn v9_tx3() {
 }
 
 /// Assert write/read correctness of [`block_v16_tx0`].
-#[tokio::test]
-async fn v16_tx0() {
+async fn v16_tx0(backend: Backend) {
     test_template(
+        backend,
         &[&*BLOCK_V16_TX0],
         600_000_000_000,
         AssertTableLen {
@@ -416,9 +422,8 @@ async fn v16_tx0() {
 }
 
 /// Tests the alt-chain requests and responses.
-#[tokio::test]
-async fn alt_chain_requests() {
-    let (reader, mut writer, _, _tempdir) = init_service();
+async fn alt_chain_requests(backend: Backend) {
+    let (reader, mut writer, _, _tempdir) = init_service(backend);
 
     // Set up the test by adding blocks to the main-chain.
     for (i, mut block) in [BLOCK_V9_TX3.clone(), BLOCK_V16_TX0.clone()]
@@ -475,6 +480,39 @@ async fn alt_chain_requests() {
         assert_eq!(got_block.txs, alt_block.txs);
     }
 
+    // Get the alt-chain infos.
+    let request = BlockchainReadRequest::AltChains;
+    let response = reader.clone().oneshot(request).await.unwrap();
+
+    let BlockchainResponse::AltChains(chains) = response else {
+        panic!("Wrong response type was returned");
+    };
+
+    let top_chain = chains.iter().find(|chain| chain.height == 3).unwrap();
+    assert_eq!(top_chain.length, 3);
+    assert_eq!(top_chain.block_hash, alt_blocks[2].block_hash);
+    assert_eq!(
+        top_chain.block_hashes,
+        alt_blocks
+            .iter()
+            .rev()
+            .map(|block| block.block_hash)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(top_chain.main_chain_parent_block, BLOCK_V9_TX3.block_hash);
+
+    let request = BlockchainReadRequest::AltChainCount;
+    let response = reader.clone().oneshot(request).await.unwrap();
+    assert_eq!(response, BlockchainResponse::AltChainCount(chains.len()));
+
+    // The chain with the newest block is always kept.
+    let request = BlockchainWriteRequest::PruneAltChains {
+        max_depth: 0,
+        max_blocks: 0,
+    };
+    let response = writer.ready().await.unwrap().call(request).await.unwrap();
+    assert_eq!(response, BlockchainResponse::PruneAltChains(0));
+
     // Flush all alt blocks.
     let request = BlockchainWriteRequest::FlushAltBlocks;
     let response = writer.ready().await.unwrap().call(request).await.unwrap();
@@ -503,3 +541,11 @@ async fn alt_chain_requests() {
     let response = reader.clone().oneshot(request).await.unwrap();
     assert!(matches!(response, BlockchainResponse::ChainHeight(2, _)));
 }
+
+async_backend_tests! {
+    init_drop,
+    v1_tx2,
+    v9_tx3,
+    v16_tx0,
+    alt_chain_requests,
+}
@@ -46,6 +46,16 @@ fn handle_blockchain_request(
             reverse_reorg(env, *old_main_chain_id)
         }
         BlockchainWriteRequest::FlushAltBlocks => flush_alt_blocks(env),
+        BlockchainWriteRequest::PruneAltChains {
+            max_depth,
+            max_blocks,
+        } => prune_alt_chains(env, *max_depth, *max_blocks),
+        BlockchainWriteRequest::BackfillKeyImageSpends(max_blocks) => {
+            backfill_key_image_spends(env, *max_blocks)
+        }
+        BlockchainWriteRequest::BackfillOutputKeys(max_blocks) => {
+            backfill_output_keys(env, *max_blocks)
+        }
     }
 }
 
@@ -237,3 +247,85 @@ fn flush_alt_blocks(env: &ConcreteEnv) -> ResponseResult {
         }
     }
 }
+
+/// [`BlockchainWriteRequest::PruneAltChains`].
+#[inline]
+fn prune_alt_chains(env: &ConcreteEnv, max_depth: usize, max_blocks: usize) -> ResponseResult {
+    let env_inner = env.env_inner();
+
+    // Find the chains to remove in a read transaction first, so we don't
+    // need a write transaction when the limits are not exceeded.
+    let to_prune = {
+        let tx_ro = env_inner.tx_ro()?;
+        let tables = env_inner.open_tables(&tx_ro)?;
+        crate::ops::alt_block::alt_chains_to_prune(max_depth, max_blocks, &tables)?
+    };
+
+    if to_prune.remove.is_empty() {
+        return Ok(BlockchainResponse::PruneAltChains(0));
+    }
+
+    let tx_rw = env_inner.tx_rw()?;
+
+    let result = {
+        let mut tables_mut = env_inner.open_tables_mut(&tx_rw)?;
+        crate::ops::alt_block::prune_alt_chains(&to_prune, &mut tables_mut)
+    };
+
+    match result {
+        Ok(removed_blocks) => {
+            TxRw::commit(tx_rw)?;
+            Ok(BlockchainResponse::PruneAltChains(removed_blocks))
+        }
+        Err(e) => {
+            TxRw::abort(tx_rw).expect(TX_RW_ABORT_FAIL);
+            Err(e)
+        }
+    }
+}
+
+/// [`BlockchainWriteRequest::BackfillKeyImageSpends`].
+#[inline]
+fn backfill_key_image_spends(env: &ConcreteEnv, max_blocks: usize) -> ResponseResult {
+    let env_inner = env.env_inner();
+    let tx_rw = env_inner.tx_rw()?;
+
+    let result = {
+        let mut tables_mut = env_inner.open_tables_mut(&tx_rw)?;
+        crate::ops::key_image::backfill_key_image_spends(max_blocks, &mut tables_mut)
+    };
+
+    match result {
+        Ok(height) => {
+            TxRw::commit(tx_rw)?;
+            Ok(BlockchainResponse::BackfillKeyImageSpends(height))
+        }
+        Err(e) => {
+            TxRw::abort(tx_rw).expect(TX_RW_ABORT_FAIL);
+            Err(e)
+        }
+    }
+}
+
+/// [`BlockchainWriteRequest::BackfillOutputKeys`].
+#[inline]
+fn backfill_output_keys(env: &ConcreteEnv, max_blocks: usize) -> ResponseResult {
+    let env_inner = env.env_inner();
+    let tx_rw = env_inner.tx_rw()?;
+
+    let result = {
+        let mut tables_mut = env_inner.open_tables_mut(&tx_rw)?;
+        crate::ops::output::backfill_output_keys(max_blocks, &mut tables_mut)
+    };
+
+    match result {
+        Ok(height) => {
+            TxRw::commit(tx_rw)?;
+            Ok(BlockchainResponse::BackfillOutputKeys(height))
+        }
+        Err(e) => {
+            TxRw::abort(tx_rw).expect(TX_RW_ABORT_FAIL);
+            Err(e)
+        }
+    }
+}
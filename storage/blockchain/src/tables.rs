@@ -16,11 +16,13 @@
 //! accessing _all_ tables defined here at once.
 
 //---------------------------------------------------------------------------------------------------- Import
+use cuprate_database::StorableVec;
+
 use crate::types::{
     AltBlockHeight, AltChainInfo, AltTransactionInfo, Amount, AmountIndex, AmountIndices,
     BlockBlob, BlockHash, BlockHeaderBlob, BlockHeight, BlockInfo, BlockTxHashes,
-    CompactAltBlockInfo, KeyImage, Output, PreRctOutputId, PrunableBlob, PrunableHash, PrunedBlob,
-    RawChainId, RctOutput, TxBlob, TxHash, TxId, UnlockTime,
+    CompactAltBlockInfo, KeyImage, KeyImageSpend, Output, OutputKey, PreRctOutputId, PrunableBlob,
+    PrunableHash, PrunedBlob, RawChainId, RctOutput, TxBlob, TxHash, TxId, UnlockTime,
 };
 
 //---------------------------------------------------------------------------------------------------- Tables
@@ -94,12 +96,6 @@ cuprate_database::define_tables! {
     9 => PrunableHashes,
     TxId => PrunableHash,
 
-    // SOMEDAY: impl a properties table:
-    // - db version
-    // - pruning seed
-    // Properties,
-    // StorableString => StorableVec,
-
     /// RCT output data.
     10 => RctOutputs,
     AmountIndex => RctOutput,
@@ -170,6 +166,39 @@ cuprate_database::define_tables! {
     /// Contains information on all alt transactions, even if they are in the main-chain.
     21 => AltTransactionInfos,
     TxHash => AltTransactionInfo,
+
+    /// Key image spends.
+    ///
+    /// Maps key images to the transaction and block they were spent in.
+    ///
+    /// This is an optional index, it is only filled if
+    /// [`Config::key_image_index`](crate::config::Config::key_image_index)
+    /// is enabled, see [`crate::ops::key_image`].
+    22 => KeyImageSpends,
+    KeyImage => KeyImageSpend,
+
+    /// Database metadata.
+    ///
+    /// This is the same table as [`cuprate_database::migration::Metadata`],
+    /// which holds the schema version and network, `cuprate_blockchain`'s
+    /// own keys are the `METADATA_*` constants in [`crate::ops::property`].
+    // SOMEDAY: store the pruning seed here.
+    23 => Metadata,
+    u8 => StorableVec<u8>,
+
+    /// Output keys.
+    ///
+    /// Maps output public keys to the [`PreRctOutputId`] of the output,
+    /// RCT outputs have an [`PreRctOutputId::amount`] of `0`.
+    ///
+    /// Output keys are not guaranteed to be unique, if multiple outputs
+    /// have the same key only the latest output is stored.
+    ///
+    /// This is an optional index, it is only filled if
+    /// [`Config::output_key_index`](crate::config::Config::output_key_index)
+    /// is enabled, see [`crate::ops::output`].
+    24 => OutputKeys,
+    OutputKey => PreRctOutputId,
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
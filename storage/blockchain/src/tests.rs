@@ -9,12 +9,12 @@ use std::fmt::Debug;
 
 use pretty_assertions::assert_eq;
 
-use cuprate_database::{DatabaseRo, Env, EnvInner};
+use cuprate_database::{config::Backend, DatabaseRo, Env, EnvInner, TxRw};
 use cuprate_types::{AltBlockInformation, ChainId, VerifiedBlockInformation};
 
 use crate::{
     config::ConfigBuilder,
-    tables::{OpenTables, Tables},
+    tables::{Metadata, OpenTables, Tables},
 };
 
 //---------------------------------------------------------------------------------------------------- Struct
@@ -67,14 +67,72 @@ impl AssertTableLen {
     }
 }
 
+//---------------------------------------------------------------------------------------------------- Macros
+/// Generate a `#[test]` module for each [`Backend`],
+/// calling each `fn test(backend: Backend)` with that backend.
+///
+/// Attributes before a test, e.g. `#[should_panic]`, are added to each generated `#[test]`.
+macro_rules! backend_tests {
+    ($( $(#[$attr:meta])* $test:ident ),* $(,)?) => {
+        mod heed {
+            $(
+                #[test]
+                $(#[$attr])*
+                fn $test() {
+                    super::$test(cuprate_database::config::Backend::Heed);
+                }
+            )*
+        }
+
+        mod redb {
+            $(
+                #[test]
+                $(#[$attr])*
+                fn $test() {
+                    super::$test(cuprate_database::config::Backend::Redb);
+                }
+            )*
+        }
+    };
+}
+pub(crate) use backend_tests;
+
+/// Same as [`backend_tests`], but for `async fn test(backend: Backend)`s, which are run with `#[tokio::test]`.
+macro_rules! async_backend_tests {
+    ($( $(#[$attr:meta])* $test:ident ),* $(,)?) => {
+        mod heed {
+            $(
+                #[tokio::test]
+                $(#[$attr])*
+                async fn $test() {
+                    super::$test(cuprate_database::config::Backend::Heed).await;
+                }
+            )*
+        }
+
+        mod redb {
+            $(
+                #[tokio::test]
+                $(#[$attr])*
+                async fn $test() {
+                    super::$test(cuprate_database::config::Backend::Redb).await;
+                }
+            )*
+        }
+    };
+}
+pub(crate) use async_backend_tests;
+
 //---------------------------------------------------------------------------------------------------- fn
-/// Create an `Env` in a temporarily directory.
+/// Create an `Env` with `backend` in a temporarily directory.
 /// The directory is automatically removed after the `TempDir` is dropped.
 ///
-pub(crate) fn tmp_concrete_env() -> (impl Env, tempfile::TempDir) {
+/// Tests using this should be run with each [`Backend`], see [`backend_tests`].
+pub(crate) fn tmp_concrete_env(backend: Backend) -> (impl Env, tempfile::TempDir) {
     let tempdir = tempfile::tempdir().unwrap();
     let config = ConfigBuilder::new()
         .data_directory(tempdir.path().into())
+        .backend(backend)
         .low_power()
         .build();
     let env = crate::open(config).unwrap();
@@ -83,12 +141,20 @@ pub(crate) fn tmp_concrete_env() -> (impl Env, tempfile::TempDir) {
 }
 
 /// Assert all the tables in the environment are empty.
+///
+/// The [`Metadata`] table is ignored, as [`crate::open`] always fills it.
 pub(crate) fn assert_all_tables_are_empty(env: &impl Env) {
     let env_inner = env.env_inner();
-    let tx_ro = env_inner.tx_ro().unwrap();
-    let tables = env_inner.open_tables(&tx_ro).unwrap();
-    assert!(tables.all_tables_empty().unwrap());
-    assert_eq!(crate::ops::tx::get_num_tx(tables.tx_ids()).unwrap(), 0);
+    let mut tx_rw = env_inner.tx_rw().unwrap();
+    env_inner.clear_db::<Metadata>(&mut tx_rw).unwrap();
+
+    {
+        let tables = env_inner.open_tables_mut(&tx_rw).unwrap();
+        assert!(tables.all_tables_empty().unwrap());
+        assert_eq!(crate::ops::tx::get_num_tx(tables.tx_ids()).unwrap(), 0);
+    }
+
+    TxRw::abort(tx_rw).unwrap();
 }
 
 pub(crate) fn map_verified_block_to_alt(
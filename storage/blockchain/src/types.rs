@@ -81,6 +81,9 @@ pub type BlockHeight = usize;
 /// A key image.
 pub type KeyImage = [u8; 32];
 
+/// An output's one-time public key.
+pub type OutputKey = [u8; 32];
+
 /// Pruned serialized bytes.
 pub type PrunedBlob = StorableVec<u8>;
 
@@ -588,6 +591,42 @@ pub struct AltTransactionInfo {
     pub tx_hash: [u8; 32],
 }
 
+//---------------------------------------------------------------------------------------------------- KeyImageSpend
+/// Where a key image was spent.
+///
+/// This is the value of the optional [`KeyImageSpends`](crate::tables::KeyImageSpends) index.
+///
+/// ```rust
+/// # use std::borrow::*;
+/// # use cuprate_blockchain::{*, types::*};
+/// use cuprate_database::Storable;
+///
+/// // Assert Storable is correct.
+/// let a = KeyImageSpend {
+///     tx_id: 123,
+///     height: 321,
+/// };
+/// let b = Storable::as_bytes(&a);
+/// let c: KeyImageSpend = Storable::from_bytes(b);
+/// assert_eq!(a, c);
+/// ```
+///
+/// # Size & Alignment
+/// ```rust
+/// # use cuprate_blockchain::types::*;
+/// assert_eq!(size_of::<KeyImageSpend>(), 16);
+/// assert_eq!(align_of::<KeyImageSpend>(), 8);
+/// ```
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd, Eq, Ord, Hash, Pod, Zeroable)]
+#[repr(C)]
+pub struct KeyImageSpend {
+    /// The [`TxId`] of the transaction that spent the key image.
+    pub tx_id: TxId,
+    /// The height of the block containing the transaction.
+    pub height: BlockHeight,
+}
+
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
 mod test {
@@ -0,0 +1,338 @@
+//! Runtime dispatch between the database backends.
+//!
+//! [`ConcreteEnv`] holds the [`Env`] of the [`Backend`] selected in its [`Config`].
+//!
+//! All types created from it (the [`EnvInner`], transactions, tables and iterators)
+//! are a [`Dispatch`] of the equivalent `heed` and `redb` types, which forward
+//! all trait functions to the backend that is actually in use.
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::{cell::RefCell, num::NonZeroUsize, sync::RwLockReadGuard};
+
+use crate::{
+    backend::{heed::HeedEnv, redb::RedbEnv},
+    config::{Backend, Config, SyncMode},
+    database::{DatabaseIter, DatabaseRo, DatabaseRw},
+    env::{Env, EnvInner},
+    error::{DbResult, InitError},
+    resize::ResizeAlgorithm,
+    stats::{EnvStats, TableStats},
+    table::Table,
+    transaction::{TxRo, TxRw},
+};
+
+//---------------------------------------------------------------------------------------------------- Dispatch
+/// A value belonging to one of the database backends.
+///
+/// This is the type of everything created from a [`ConcreteEnv`], e.g.
+/// [`Env::env_inner`], [`EnvInner::tx_rw`] and [`EnvInner::open_db_ro`].
+///
+/// It implements the same traits as the backend types it wraps.
+/// Like any other backend type, it should not be relied upon directly,
+/// use the traits instead, see the crate documentation on concrete types.
+///
+/// # Invariant
+/// Types from different backends cannot be mixed, e.g. a `heed` transaction
+/// being passed to a `redb` [`EnvInner`]. This is impossible when
+/// everything comes from a single [`ConcreteEnv`], doing so will panic.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Dispatch<H, R> {
+    /// The `heed` type.
+    Heed(H),
+    /// The `redb` type.
+    Redb(R),
+}
+
+/// Evaluate the same expression with the inner value of whichever [`Dispatch`] variant is active.
+macro_rules! dispatch {
+    ($dispatch:expr, $inner:ident => $expr:expr) => {
+        match $dispatch {
+            Dispatch::Heed($inner) => $expr,
+            Dispatch::Redb($inner) => $expr,
+        }
+    };
+}
+
+/// Like `dispatch!`, but the output is wrapped back into the same [`Dispatch`] variant.
+macro_rules! dispatch_wrap {
+    ($dispatch:expr, $inner:ident => $expr:expr) => {
+        match $dispatch {
+            Dispatch::Heed($inner) => Dispatch::Heed($expr),
+            Dispatch::Redb($inner) => Dispatch::Redb($expr),
+        }
+    };
+}
+
+/// Panic, as types from different backends were mixed.
+///
+/// See the [`Dispatch`] invariant.
+#[cold]
+#[inline(never)]
+#[track_caller]
+fn mixed_backends() -> ! {
+    panic!("database types from different backends were mixed");
+}
+
+//---------------------------------------------------------------------------------------------------- ConcreteEnv
+/// A strongly typed, concrete database environment.
+///
+/// The backend used is [`Config::backend`],
+/// it is selected at runtime in [`Env::open`].
+pub struct ConcreteEnv(Dispatch<HeedEnv, RedbEnv>);
+
+impl ConcreteEnv {
+    /// Returns [`Env::MANUAL_RESIZE`] of the backend in use.
+    ///
+    /// [`ConcreteEnv::MANUAL_RESIZE`] is `true` if _any_ backend needs manual
+    /// resizes, this is for code that should only run if the active one does.
+    pub const fn manual_resize(&self) -> bool {
+        match self.0 {
+            Dispatch::Heed(_) => HeedEnv::MANUAL_RESIZE,
+            Dispatch::Redb(_) => RedbEnv::MANUAL_RESIZE,
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Env Impl
+impl Env for ConcreteEnv {
+    /// This is `true` if _any_ backend needs manual resizes,
+    /// so [`crate::RuntimeError::ResizeNeeded`] must always be handled.
+    ///
+    /// Note that [`Env::resize_map`] and [`Env::current_map_size`]
+    /// will still panic when called with a backend that does not,
+    /// see [`ConcreteEnv::manual_resize`] for the active backend.
+    const MANUAL_RESIZE: bool = HeedEnv::MANUAL_RESIZE || RedbEnv::MANUAL_RESIZE;
+    /// This is `true` only if _all_ backends sync per transaction.
+    const SYNCS_PER_TX: bool = HeedEnv::SYNCS_PER_TX && RedbEnv::SYNCS_PER_TX;
+    type EnvInner<'env> =
+        Dispatch<RwLockReadGuard<'env, heed::Env>, (&'env redb::Database, redb::Durability)>;
+    type TxRo<'tx> = Dispatch<heed::RoTxn<'tx>, redb::ReadTransaction>;
+    type TxRw<'tx> = Dispatch<RefCell<heed::RwTxn<'tx>>, redb::WriteTransaction>;
+
+    #[cold]
+    #[inline(never)] // called once.
+    fn open(config: Config) -> Result<Self, InitError> {
+        Ok(Self(match config.backend() {
+            Backend::Heed => Dispatch::Heed(HeedEnv::open(config)?),
+            Backend::Redb => Dispatch::Redb(RedbEnv::open(config)?),
+        }))
+    }
+
+    fn config(&self) -> &Config {
+        dispatch!(&self.0, env => env.config())
+    }
+
+    fn sync(&self) -> DbResult<()> {
+        dispatch!(&self.0, env => env.sync())
+    }
+
+    fn sync_mode(&self) -> SyncMode {
+        dispatch!(&self.0, env => env.sync_mode())
+    }
+
+    fn switch_to_safe_sync(&self) -> DbResult<()> {
+        dispatch!(&self.0, env => env.switch_to_safe_sync())
+    }
+
+    fn resize_map(&self, resize_algorithm: Option<ResizeAlgorithm>) -> NonZeroUsize {
+        dispatch!(&self.0, env => env.resize_map(resize_algorithm))
+    }
+
+    #[inline]
+    fn current_map_size(&self) -> usize {
+        dispatch!(&self.0, env => env.current_map_size())
+    }
+
+    #[inline]
+    fn env_inner(&self) -> Self::EnvInner<'_> {
+        dispatch_wrap!(&self.0, env => env.env_inner())
+    }
+
+    fn stats(&self) -> DbResult<EnvStats> {
+        dispatch!(&self.0, env => env.stats())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- EnvInner Impl
+impl<'env, H, R> EnvInner<'env> for Dispatch<H, R>
+where
+    H: EnvInner<'env>,
+    R: EnvInner<'env>,
+{
+    type Ro<'tx> = Dispatch<H::Ro<'tx>, R::Ro<'tx>>;
+    type Rw<'tx> = Dispatch<H::Rw<'tx>, R::Rw<'tx>>;
+
+    #[inline]
+    fn tx_ro(&self) -> DbResult<Self::Ro<'_>> {
+        Ok(dispatch_wrap!(self, env => env.tx_ro()?))
+    }
+
+    #[inline]
+    fn tx_rw(&self) -> DbResult<Self::Rw<'_>> {
+        Ok(dispatch_wrap!(self, env => env.tx_rw()?))
+    }
+
+    #[inline]
+    fn open_db_ro<T: Table>(
+        &self,
+        tx_ro: &Self::Ro<'_>,
+    ) -> DbResult<impl DatabaseRo<T> + DatabaseIter<T>> {
+        Ok(match (self, tx_ro) {
+            (Self::Heed(env), Dispatch::Heed(tx_ro)) => Dispatch::Heed(env.open_db_ro::<T>(tx_ro)?),
+            (Self::Redb(env), Dispatch::Redb(tx_ro)) => Dispatch::Redb(env.open_db_ro::<T>(tx_ro)?),
+            _ => mixed_backends(),
+        })
+    }
+
+    #[inline]
+    fn open_db_rw<T: Table>(&self, tx_rw: &Self::Rw<'_>) -> DbResult<impl DatabaseRw<T>> {
+        Ok(match (self, tx_rw) {
+            (Self::Heed(env), Dispatch::Heed(tx_rw)) => Dispatch::Heed(env.open_db_rw::<T>(tx_rw)?),
+            (Self::Redb(env), Dispatch::Redb(tx_rw)) => Dispatch::Redb(env.open_db_rw::<T>(tx_rw)?),
+            _ => mixed_backends(),
+        })
+    }
+
+    fn create_db<T: Table>(&self, tx_rw: &Self::Rw<'_>) -> DbResult<()> {
+        match (self, tx_rw) {
+            (Self::Heed(env), Dispatch::Heed(tx_rw)) => env.create_db::<T>(tx_rw),
+            (Self::Redb(env), Dispatch::Redb(tx_rw)) => env.create_db::<T>(tx_rw),
+            _ => mixed_backends(),
+        }
+    }
+
+    #[inline]
+    fn clear_db<T: Table>(&self, tx_rw: &mut Self::Rw<'_>) -> DbResult<()> {
+        match (self, tx_rw) {
+            (Self::Heed(env), Dispatch::Heed(tx_rw)) => env.clear_db::<T>(tx_rw),
+            (Self::Redb(env), Dispatch::Redb(tx_rw)) => env.clear_db::<T>(tx_rw),
+            _ => mixed_backends(),
+        }
+    }
+
+    fn table_stats<T: Table>(&self, tx_ro: &Self::Ro<'_>) -> DbResult<TableStats> {
+        match (self, tx_ro) {
+            (Self::Heed(env), Dispatch::Heed(tx_ro)) => env.table_stats::<T>(tx_ro),
+            (Self::Redb(env), Dispatch::Redb(tx_ro)) => env.table_stats::<T>(tx_ro),
+            _ => mixed_backends(),
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- TxRo/TxRw Impl
+impl<'tx, H: TxRo<'tx>, R: TxRo<'tx>> TxRo<'tx> for Dispatch<H, R> {
+    fn commit(self) -> DbResult<()> {
+        dispatch!(self, tx_ro => TxRo::commit(tx_ro))
+    }
+}
+
+impl<'tx, H: TxRw<'tx>, R: TxRw<'tx>> TxRw<'tx> for Dispatch<H, R> {
+    fn commit(self) -> DbResult<()> {
+        dispatch!(self, tx_rw => TxRw::commit(tx_rw))
+    }
+
+    fn abort(self) -> DbResult<()> {
+        dispatch!(self, tx_rw => TxRw::abort(tx_rw))
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- DatabaseIter Impl
+impl<T: Table, H: DatabaseIter<T>, R: DatabaseIter<T>> DatabaseIter<T> for Dispatch<H, R> {
+    #[inline]
+    fn iter(&self) -> DbResult<impl Iterator<Item = DbResult<(T::Key, T::Value)>> + '_> {
+        Ok(dispatch_wrap!(self, table => table.iter()?))
+    }
+
+    #[inline]
+    fn keys(&self) -> DbResult<impl Iterator<Item = DbResult<T::Key>> + '_> {
+        Ok(dispatch_wrap!(self, table => table.keys()?))
+    }
+
+    #[inline]
+    fn values(&self) -> DbResult<impl Iterator<Item = DbResult<T::Value>> + '_> {
+        Ok(dispatch_wrap!(self, table => table.values()?))
+    }
+}
+
+impl<H: Iterator, R: Iterator<Item = H::Item>> Iterator for Dispatch<H, R> {
+    type Item = H::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        dispatch!(self, iter => iter.next())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        dispatch!(self, iter => iter.size_hint())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- DatabaseRo Impl
+// SAFETY: `Dispatch` holds the backend's table by value,
+// so it is only `Send` if the backend's table is.
+unsafe impl<T: Table, H: DatabaseRo<T>, R: DatabaseRo<T>> DatabaseRo<T> for Dispatch<H, R> {
+    #[inline]
+    fn get(&self, key: &T::Key) -> DbResult<T::Value> {
+        dispatch!(self, table => table.get(key))
+    }
+
+    #[inline]
+    fn contains(&self, key: &T::Key) -> DbResult<bool> {
+        dispatch!(self, table => table.contains(key))
+    }
+
+    #[inline]
+    fn len(&self) -> DbResult<u64> {
+        dispatch!(self, table => table.len())
+    }
+
+    #[inline]
+    fn first(&self) -> DbResult<(T::Key, T::Value)> {
+        dispatch!(self, table => table.first())
+    }
+
+    #[inline]
+    fn last(&self) -> DbResult<(T::Key, T::Value)> {
+        dispatch!(self, table => table.last())
+    }
+
+    #[inline]
+    fn is_empty(&self) -> DbResult<bool> {
+        dispatch!(self, table => table.is_empty())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- DatabaseRw Impl
+impl<T: Table, H: DatabaseRw<T>, R: DatabaseRw<T>> DatabaseRw<T> for Dispatch<H, R> {
+    #[inline]
+    fn put(&mut self, key: &T::Key, value: &T::Value) -> DbResult<()> {
+        dispatch!(self, table => table.put(key, value))
+    }
+
+    #[inline]
+    fn delete(&mut self, key: &T::Key) -> DbResult<()> {
+        dispatch!(self, table => table.delete(key))
+    }
+
+    #[inline]
+    fn take(&mut self, key: &T::Key) -> DbResult<T::Value> {
+        dispatch!(self, table => table.take(key))
+    }
+
+    #[inline]
+    fn pop_first(&mut self) -> DbResult<(T::Key, T::Value)> {
+        dispatch!(self, table => table.pop_first())
+    }
+
+    #[inline]
+    fn pop_last(&mut self) -> DbResult<(T::Key, T::Value)> {
+        dispatch!(self, table => table.pop_last())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    // use super::*;
+}
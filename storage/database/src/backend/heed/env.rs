@@ -4,10 +4,13 @@
 use std::{
     cell::RefCell,
     num::NonZeroUsize,
-    sync::{RwLock, RwLockReadGuard},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        RwLock, RwLockReadGuard,
+    },
 };
 
-use heed::{DatabaseFlags, EnvFlags, EnvOpenOptions};
+use heed::{DatabaseFlags, EnvFlags, EnvOpenOptions, FlagSetMode};
 use tracing::{debug, warn};
 
 use crate::{
@@ -22,12 +25,13 @@ use crate::{
     error::{DbResult, InitError, RuntimeError},
     key::{Key, KeyCompare},
     resize::ResizeAlgorithm,
+    stats::{EnvStats, TableStats},
     table::Table,
 };
 
-//---------------------------------------------------------------------------------------------------- ConcreteEnv
+//---------------------------------------------------------------------------------------------------- HeedEnv
 /// A strongly typed, concrete database environment, backed by `heed`.
-pub struct ConcreteEnv {
+pub(crate) struct HeedEnv {
     /// The actual database environment.
     ///
     /// # Why `RwLock`?
@@ -57,11 +61,15 @@ pub struct ConcreteEnv {
     /// The configuration we were opened with
     /// (and in current use).
     pub(super) config: Config,
+
+    /// Has a [`SyncMode::FastThenSafe`] environment
+    /// been switched to [`SyncMode::Safe`]?
+    switched_to_safe: AtomicBool,
 }
 
-impl Drop for ConcreteEnv {
+impl Drop for HeedEnv {
     fn drop(&mut self) {
-        // INVARIANT: drop(ConcreteEnv) must sync.
+        // INVARIANT: drop(HeedEnv) must sync.
         //
         // SOMEDAY:
         // "if the environment has the MDB_NOSYNC flag set the flushes will be omitted,
@@ -86,7 +94,7 @@ impl Drop for ConcreteEnv {
 }
 
 //---------------------------------------------------------------------------------------------------- Env Impl
-impl Env for ConcreteEnv {
+impl Env for HeedEnv {
     const MANUAL_RESIZE: bool = true;
     const SYNCS_PER_TX: bool = false;
     type EnvInner<'env> = RwLockReadGuard<'env, heed::Env>;
@@ -119,11 +127,21 @@ impl Env for ConcreteEnv {
         // Map our `Config` sync mode to the LMDB environment flags.
         //
         // <https://github.com/monero-project/monero/blob/059028a30a8ae9752338a7897329fe8012a310d5/src/blockchain_db/lmdb/db_lmdb.cpp#L1324>
-        let flags = match config.sync_mode {
-            SyncMode::Safe => EnvFlags::empty(),
-            // TODO: impl `FastThenSafe`
-            SyncMode::FastThenSafe | SyncMode::Fast => {
-                EnvFlags::NO_SYNC | EnvFlags::WRITE_MAP | EnvFlags::MAP_ASYNC
+        //
+        // `FastThenSafe` starts with the same flags as `Fast`, `MDB_NOSYNC` and
+        // `MDB_MAPASYNC` are cleared later in `Env::switch_to_safe_sync`.
+        // `MDB_WRITEMAP` cannot be changed after opening, although it stays
+        // durable as long as the other 2 flags are not set.
+        //
+        // A read-only environment is never written to, so the sync mode doesn't matter.
+        let flags = if config.read_only {
+            EnvFlags::READ_ONLY
+        } else {
+            match config.sync_mode {
+                SyncMode::Safe => EnvFlags::empty(),
+                SyncMode::FastThenSafe | SyncMode::Fast => {
+                    EnvFlags::NO_SYNC | EnvFlags::WRITE_MAP | EnvFlags::MAP_ASYNC
+                }
             }
         };
 
@@ -186,7 +204,9 @@ impl Env for ConcreteEnv {
         });
 
         // Create the database directory if it doesn't exist.
-        std::fs::create_dir_all(config.db_directory())?;
+        if !config.read_only {
+            std::fs::create_dir_all(config.db_directory())?;
+        }
         // Open the environment in the user's PATH.
         // SAFETY: LMDB uses a memory-map backed file.
         // <https://docs.rs/heed/0.20.0/heed/struct.EnvOpenOptions.html#method.open>
@@ -195,6 +215,7 @@ impl Env for ConcreteEnv {
         Ok(Self {
             env: RwLock::new(env),
             config,
+            switched_to_safe: AtomicBool::new(false),
         })
     }
 
@@ -203,9 +224,58 @@ impl Env for ConcreteEnv {
     }
 
     fn sync(&self) -> DbResult<()> {
+        // LMDB refuses to sync read-only environments.
+        if self.config.read_only {
+            return Ok(());
+        }
+
         Ok(self.env.read().unwrap().force_sync()?)
     }
 
+    fn sync_mode(&self) -> SyncMode {
+        if self.switched_to_safe.load(Ordering::Acquire) {
+            SyncMode::Safe
+        } else {
+            self.config.sync_mode
+        }
+    }
+
+    fn switch_to_safe_sync(&self) -> DbResult<()> {
+        if self.sync_mode() != SyncMode::FastThenSafe {
+            return Ok(());
+        }
+
+        // Take the write lock, so no transactions
+        // are active while the flags are changed.
+        let env = self.env.write().unwrap();
+
+        // Another thread may have switched while we waited on the lock.
+        if self.switched_to_safe.load(Ordering::Acquire) {
+            return Ok(());
+        }
+
+        // SAFETY:
+        // `MDB_NOSYNC` and `MDB_MAPASYNC` are allowed to be changed after
+        // the environment is opened, and we have exclusive access to it.
+        //
+        // <http://www.lmdb.tech/doc/group__mdb.html#ga83f66cf02bfd42119451e9468dc58445>
+        unsafe {
+            env.set_flags(
+                EnvFlags::NO_SYNC | EnvFlags::MAP_ASYNC,
+                FlagSetMode::Disable,
+            )?;
+        }
+
+        // Flush everything written while in fast mode.
+        env.force_sync()?;
+
+        self.switched_to_safe.store(true, Ordering::Release);
+        drop(env);
+
+        debug!("LMDB switched to safe sync mode");
+        Ok(())
+    }
+
     fn resize_map(&self, resize_algorithm: Option<ResizeAlgorithm>) -> NonZeroUsize {
         let resize_algorithm = resize_algorithm.unwrap_or_else(|| self.config().resize_algorithm);
 
@@ -240,6 +310,21 @@ impl Env for ConcreteEnv {
     fn env_inner(&self) -> Self::EnvInner<'_> {
         self.env.read().unwrap()
     }
+
+    fn stats(&self) -> DbResult<EnvStats> {
+        let env = self.env.read().unwrap();
+
+        // Pages freed by old transactions are kept in the file
+        // and reused, so the file is bigger than the used pages.
+        let used_bytes = env.non_free_pages_size()?;
+        let disk_size_bytes = env.real_disk_size()?;
+
+        Ok(EnvStats {
+            map_size: Some(env.info().map_size as u64),
+            used_bytes,
+            free_bytes: disk_size_bytes.saturating_sub(used_bytes),
+        })
+    }
 }
 
 //---------------------------------------------------------------------------------------------------- EnvInner Impl
@@ -337,6 +422,26 @@ where
 
         Ok(())
     }
+
+    fn table_stats<T: Table>(&self, tx_ro: &Self::Ro<'_>) -> DbResult<TableStats> {
+        let db: HeedDb<T::Key, T::Value> = self
+            .open_database(tx_ro, Some(T::NAME))?
+            .ok_or(RuntimeError::TableNotFound)?;
+
+        // <http://www.lmdb.tech/doc/group__mdb.html#gae6c1069febe94299769dbdd032fadef6>
+        let stat = db.stat(tx_ro)?;
+        let pages = stat.branch_pages + stat.leaf_pages + stat.overflow_pages;
+
+        Ok(TableStats {
+            table: T::NAME,
+            entries: stat.entries as u64,
+            depth: stat.depth,
+            branch_pages: stat.branch_pages as u64,
+            leaf_pages: stat.leaf_pages as u64,
+            overflow_pages: stat.overflow_pages as u64,
+            size_bytes: pages as u64 * u64::from(stat.page_size),
+        })
+    }
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
@@ -1,7 +1,7 @@
 //! Database backend implementation backed by `heed`.
 
 mod env;
-pub use env::ConcreteEnv;
+pub(super) use env::HeedEnv;
 
 mod database;
 mod error;
@@ -1,18 +1,13 @@
 //! Database backends.
+//!
+//! All backends are always compiled, [`ConcreteEnv`]
+//! selects one at runtime with [`Config::backend`](crate::config::Config::backend).
 
-cfg_if::cfg_if! {
-    // If both backends are enabled, fallback to `heed`.
-    // This is useful when using `--all-features`.
-    if #[cfg(all(feature = "redb", not(feature = "heed")))] {
-        use heed as _;
+mod dispatch;
+pub use dispatch::{ConcreteEnv, Dispatch};
 
-        mod redb;
-        pub use redb::ConcreteEnv;
-    } else {
-        mod heed;
-        pub use heed::ConcreteEnv;
-    }
-}
+mod heed;
+mod redb;
 
 #[cfg(test)]
 mod tests;
@@ -1,19 +1,24 @@
 //! Implementation of `trait Env` for `redb`.
 
 //---------------------------------------------------------------------------------------------------- Import
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use redb::ReadableTableMetadata;
+
 use crate::{
-    backend::redb::storable::StorableRedb,
+    backend::redb::{read_only::ReadOnlyBackend, storable::StorableRedb, types::RedbTableRo},
     config::{Config, SyncMode},
     database::{DatabaseIter, DatabaseRo, DatabaseRw},
     env::{Env, EnvInner},
     error::{DbResult, InitError, RuntimeError},
+    stats::{EnvStats, TableStats},
     table::Table,
     TxRw,
 };
 
-//---------------------------------------------------------------------------------------------------- ConcreteEnv
+//---------------------------------------------------------------------------------------------------- RedbEnv
 /// A strongly typed, concrete database environment, backed by `redb`.
-pub struct ConcreteEnv {
+pub(crate) struct RedbEnv {
     /// The actual database environment.
     env: redb::Database,
 
@@ -21,15 +26,18 @@ pub struct ConcreteEnv {
     /// (and in current use).
     config: Config,
 
-    /// A cached, redb version of `cuprate_database::config::SyncMode`.
-    /// `redb` needs the sync mode to be set _per_ TX, so we
-    /// will continue to use this value every `Env::tx_rw`.
-    durability: redb::Durability,
+    /// Has a [`SyncMode::FastThenSafe`] environment
+    /// been switched to [`SyncMode::Safe`]?
+    ///
+    /// `redb` needs the sync mode to be set _per_ TX, so
+    /// [`Env::env_inner`] maps [`Env::sync_mode`] to a
+    /// [`redb::Durability`] every time it is called.
+    switched_to_safe: AtomicBool,
 }
 
-impl Drop for ConcreteEnv {
+impl Drop for RedbEnv {
     fn drop(&mut self) {
-        // INVARIANT: drop(ConcreteEnv) must sync.
+        // INVARIANT: drop(RedbEnv) must sync.
         if let Err(e) = self.sync() {
             tracing::warn!("Env sync error: {e}");
         }
@@ -37,7 +45,7 @@ impl Drop for ConcreteEnv {
 }
 
 //---------------------------------------------------------------------------------------------------- Env Impl
-impl Env for ConcreteEnv {
+impl Env for RedbEnv {
     const MANUAL_RESIZE: bool = false;
     const SYNCS_PER_TX: bool = false;
     type EnvInner<'env> = (&'env redb::Database, redb::Durability);
@@ -47,21 +55,14 @@ impl Env for ConcreteEnv {
     #[cold]
     #[inline(never)] // called once.
     fn open(config: Config) -> Result<Self, InitError> {
-        // SOMEDAY: dynamic syncs are not implemented.
-        let durability = match config.sync_mode {
-            SyncMode::Safe => redb::Durability::Immediate,
-            // TODO: impl `FastThenSafe`
-            SyncMode::FastThenSafe | SyncMode::Fast => redb::Durability::Eventual,
-        };
-
         let env_builder = redb::Builder::new();
 
         // FIXME: we can set cache sizes with:
         // env_builder.set_cache(bytes);
 
-        // Use the in-memory backend if the feature is enabled.
-        let mut env = if cfg!(feature = "redb-memory") {
-            env_builder.create_with_backend(redb::backends::InMemoryBackend::new())?
+        let mut env = if config.read_only {
+            // Open the existing database file without ever writing to it.
+            env_builder.create_with_backend(ReadOnlyBackend::open(&config)?)?
         } else {
             // Create the database directory if it doesn't exist.
             std::fs::create_dir_all(config.db_directory())?;
@@ -88,7 +89,7 @@ impl Env for ConcreteEnv {
         Ok(Self {
             env,
             config,
-            durability,
+            switched_to_safe: AtomicBool::new(false),
         })
     }
 
@@ -97,6 +98,11 @@ impl Env for ConcreteEnv {
     }
 
     fn sync(&self) -> DbResult<()> {
+        // Nothing is written to disk in read-only mode.
+        if self.config.read_only {
+            return Ok(());
+        }
+
         // `redb`'s syncs are tied with write transactions,
         // so just create one, don't do anything and commit.
         let mut tx_rw = self.env.begin_write()?;
@@ -105,8 +111,56 @@ impl Env for ConcreteEnv {
         TxRw::commit(tx_rw)
     }
 
+    fn sync_mode(&self) -> SyncMode {
+        if self.switched_to_safe.load(Ordering::Acquire) {
+            SyncMode::Safe
+        } else {
+            self.config.sync_mode
+        }
+    }
+
+    fn switch_to_safe_sync(&self) -> DbResult<()> {
+        if self.sync_mode() != SyncMode::FastThenSafe {
+            return Ok(());
+        }
+
+        // Flush everything written while in fast mode,
+        // new write transactions will use `Durability::Immediate`.
+        self.sync()?;
+        self.switched_to_safe.store(true, Ordering::Release);
+
+        tracing::debug!("redb switched to safe sync mode");
+        Ok(())
+    }
+
     fn env_inner(&self) -> Self::EnvInner<'_> {
-        (&self.env, self.durability)
+        let durability = match self.sync_mode() {
+            SyncMode::Safe => redb::Durability::Immediate,
+            SyncMode::FastThenSafe | SyncMode::Fast => redb::Durability::Eventual,
+        };
+
+        (&self.env, durability)
+    }
+
+    fn stats(&self) -> DbResult<EnvStats> {
+        // `redb` only has database stats on write transactions, which would
+        // wait for any writer, so the stats of each table are summed instead.
+        // This does not count free pages not owned by any table.
+        let tx_ro = self.env.begin_read()?;
+
+        let mut used_bytes = 0;
+        let mut free_bytes = 0;
+        for table in tx_ro.list_tables()? {
+            let stats = tx_ro.open_untyped_table(table)?.stats()?;
+            used_bytes += stats.stored_bytes() + stats.metadata_bytes();
+            free_bytes += stats.fragmented_bytes();
+        }
+
+        Ok(EnvStats {
+            map_size: None,
+            used_bytes,
+            free_bytes,
+        })
     }
 }
 
@@ -191,6 +245,23 @@ where
 
         Ok(())
     }
+
+    fn table_stats<T: Table>(&self, tx_ro: &Self::Ro<'_>) -> DbResult<TableStats> {
+        let table: RedbTableRo<T::Key, T::Value> =
+            tx_ro.open_table(redb::TableDefinition::new(T::NAME))?;
+        let stats = table.stats()?;
+
+        Ok(TableStats {
+            table: T::NAME,
+            entries: table.len()?,
+            depth: stats.tree_height(),
+            branch_pages: stats.branch_pages(),
+            leaf_pages: stats.leaf_pages(),
+            // `redb` stores large values in leaf pages.
+            overflow_pages: 0,
+            size_bytes: stats.stored_bytes() + stats.metadata_bytes() + stats.fragmented_bytes(),
+        })
+    }
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
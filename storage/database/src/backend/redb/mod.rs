@@ -1,9 +1,10 @@
-//! Database backend implementation backed by `sanakirja`.
+//! Database backend implementation backed by `redb`.
 
 mod env;
-pub use env::ConcreteEnv;
+pub(super) use env::RedbEnv;
 mod database;
 mod error;
+mod read_only;
 mod storable;
 mod transaction;
 mod types;
@@ -0,0 +1,227 @@
+//! A read-only `redb` storage backend.
+//!
+//! `redb` has no read-only mode, opening a database always writes to the file
+//! (e.g. to mark it as in use), so [`ReadOnlyBackend`] keeps all writes in memory
+//! and the database file itself is never modified.
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    sync::Mutex,
+};
+
+use redb::StorageBackend;
+
+use crate::config::Config;
+
+//---------------------------------------------------------------------------------------------------- Constants
+/// The length of the in-memory pages that hold written data.
+const PAGE_LEN: usize = 4096;
+
+/// [`PAGE_LEN`] as a [`u64`].
+const PAGE_SIZE: u64 = PAGE_LEN as u64;
+
+//---------------------------------------------------------------------------------------------------- ReadOnlyBackend
+/// A [`StorageBackend`] that reads from a file but keeps all writes in memory.
+///
+/// Note that the file is not locked, the database must not be written
+/// to by another process while it is open with this backend.
+#[derive(Debug)]
+pub(super) struct ReadOnlyBackend {
+    /// The database file, opened read-only.
+    file: Mutex<File>,
+
+    /// The length of the database file.
+    file_len: u64,
+
+    /// The in-memory state.
+    overlay: Mutex<Overlay>,
+}
+
+/// The writes of a [`ReadOnlyBackend`].
+#[derive(Debug)]
+struct Overlay {
+    /// The length of the database, including writes.
+    len: u64,
+
+    /// Pages that were written to, keyed by their page index.
+    pages: HashMap<u64, Box<[u8]>>,
+}
+
+impl ReadOnlyBackend {
+    /// Open the database file of `config` in read-only mode.
+    ///
+    /// # Errors
+    /// This returns an error if the file could not be opened.
+    pub(super) fn open(config: &Config) -> Result<Self, std::io::Error> {
+        let file = File::open(config.db_file())?;
+        let file_len = file.metadata()?.len();
+
+        Ok(Self {
+            file: Mutex::new(file),
+            file_len,
+            overlay: Mutex::new(Overlay {
+                len: file_len,
+                pages: HashMap::new(),
+            }),
+        })
+    }
+
+    /// Read the page at `page_index` from the file, pages past the end of the file are zeroed.
+    fn read_file_page(&self, page_index: u64) -> Result<Box<[u8]>, std::io::Error> {
+        let mut page = vec![0; PAGE_LEN].into_boxed_slice();
+
+        let offset = page_index * PAGE_SIZE;
+        if offset < self.file_len {
+            #[expect(clippy::cast_possible_truncation, reason = "len <= PAGE_SIZE")]
+            let len = (self.file_len - offset).min(PAGE_SIZE) as usize;
+
+            let mut file = self.file.lock().unwrap();
+            file.seek(SeekFrom::Start(offset))?;
+            file.read_exact(&mut page[..len])?;
+        }
+
+        Ok(page)
+    }
+}
+
+impl StorageBackend for ReadOnlyBackend {
+    fn len(&self) -> Result<u64, std::io::Error> {
+        Ok(self.overlay.lock().unwrap().len)
+    }
+
+    fn read(&self, offset: u64, len: usize) -> Result<Vec<u8>, std::io::Error> {
+        let overlay = self.overlay.lock().unwrap();
+
+        let mut buf = Vec::with_capacity(len);
+        let end = offset + len as u64;
+        let mut position = offset;
+
+        while position < end {
+            let page_index = position / PAGE_SIZE;
+            #[expect(clippy::cast_possible_truncation, reason = "< PAGE_SIZE")]
+            let start = (position % PAGE_SIZE) as usize;
+            #[expect(clippy::cast_possible_truncation, reason = "<= PAGE_SIZE")]
+            let take = (end - position).min(PAGE_SIZE - start as u64) as usize;
+
+            match overlay.pages.get(&page_index) {
+                Some(page) => buf.extend_from_slice(&page[start..start + take]),
+                None => {
+                    buf.extend_from_slice(&self.read_file_page(page_index)?[start..start + take])
+                }
+            }
+
+            position += take as u64;
+        }
+
+        Ok(buf)
+    }
+
+    fn set_len(&self, len: u64) -> Result<(), std::io::Error> {
+        let mut overlay = self.overlay.lock().unwrap();
+
+        // Zero anything past the new length, so growing again reads zeros like a real file.
+        if len < overlay.len {
+            overlay
+                .pages
+                .retain(|page_index, _| page_index * PAGE_SIZE < len);
+
+            let page_index = len / PAGE_SIZE;
+            #[expect(clippy::cast_possible_truncation, reason = "< PAGE_SIZE")]
+            let start = (len % PAGE_SIZE) as usize;
+            if start != 0 {
+                let mut page = match overlay.pages.remove(&page_index) {
+                    Some(page) => page,
+                    None => self.read_file_page(page_index)?,
+                };
+                page[start..].fill(0);
+                overlay.pages.insert(page_index, page);
+            }
+
+            // Pages of the file past the new length must read as zeros.
+            let file_pages = self.file_len.div_ceil(PAGE_SIZE);
+            for page_index in len.div_ceil(PAGE_SIZE)..file_pages {
+                overlay
+                    .pages
+                    .insert(page_index, vec![0; PAGE_LEN].into_boxed_slice());
+            }
+        }
+
+        overlay.len = len;
+        Ok(())
+    }
+
+    fn sync_data(&self, _: bool) -> Result<(), std::io::Error> {
+        // Nothing is written to disk.
+        Ok(())
+    }
+
+    fn write(&self, offset: u64, data: &[u8]) -> Result<(), std::io::Error> {
+        let mut overlay = self.overlay.lock().unwrap();
+
+        let mut position = offset;
+        let mut data = data;
+
+        while !data.is_empty() {
+            let page_index = position / PAGE_SIZE;
+            #[expect(clippy::cast_possible_truncation, reason = "< PAGE_SIZE")]
+            let start = (position % PAGE_SIZE) as usize;
+            let take = data.len().min(PAGE_LEN - start);
+
+            if !overlay.pages.contains_key(&page_index) {
+                let page = self.read_file_page(page_index)?;
+                overlay.pages.insert(page_index, page);
+            }
+
+            let page = overlay.pages.get_mut(&page_index).unwrap();
+            page[start..start + take].copy_from_slice(&data[..take]);
+
+            data = &data[take..];
+            position += take as u64;
+        }
+
+        overlay.len = overlay.len.max(position);
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use crate::config::ConfigBuilder;
+
+    /// Writes are readable but never reach the file.
+    #[test]
+    fn writes_stay_in_memory() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = ConfigBuilder::new(tempdir.path().to_path_buf().into()).build();
+
+        let data = (0..2500_u32)
+            .flat_map(u32::to_le_bytes)
+            .collect::<Vec<u8>>();
+        std::fs::write(config.db_file(), &data).unwrap();
+
+        let backend = ReadOnlyBackend::open(&config).unwrap();
+        assert_eq!(backend.len().unwrap(), 10_000);
+        assert_eq!(backend.read(100, 5000).unwrap(), data[100..5100]);
+
+        backend.write(4000, &[1; 200]).unwrap();
+        backend.write(12_000, &[2; 10]).unwrap();
+        assert_eq!(backend.len().unwrap(), 12_010);
+        assert_eq!(backend.read(4000, 200).unwrap(), [1; 200]);
+        assert_eq!(backend.read(3990, 10).unwrap(), data[3990..4000]);
+        assert_eq!(backend.read(10_000, 2000).unwrap(), [0; 2000]);
+        assert_eq!(backend.read(12_000, 10).unwrap(), [2; 10]);
+
+        backend.set_len(5000).unwrap();
+        backend.set_len(10_000).unwrap();
+        assert_eq!(backend.read(5000, 5000).unwrap(), [0; 5000]);
+        assert_eq!(backend.read(4000, 200).unwrap(), [1; 200]);
+
+        assert_eq!(std::fs::read(config.db_file()).unwrap(), data);
+    }
+}
@@ -3,38 +3,68 @@
 //! These tests are fully trait-based, meaning there
 //! is no reference to `backend/`-specific types.
 //!
-//! As such, which backend is tested is
-//! dependant on the feature flags used.
-//!
-//! | Feature flag  | Tested backend |
-//! |---------------|----------------|
-//! | Only `redb`   | `redb`
-//! | Anything else | `heed`
-//!
-//! `redb`, and it only must be enabled for it to be tested.
+//! Each test takes the [`Backend`] to test, and is
+//! run once for every backend by `backend_tests!`.
 
 //---------------------------------------------------------------------------------------------------- Import
+use std::borrow::Cow;
+
 use crate::{
+    config::{Backend, ConfigBuilder, SyncMode},
     database::{DatabaseIter, DatabaseRo, DatabaseRw},
     env::{Env, EnvInner},
     error::RuntimeError,
     resize::ResizeAlgorithm,
-    tests::{tmp_concrete_env, TestTable},
+    table::Table,
+    tests::{tmp_concrete_env_with, TestTable},
     transaction::{TxRo, TxRw},
     ConcreteEnv,
 };
 
 //---------------------------------------------------------------------------------------------------- Tests
 /// Simply call [`Env::open`]. If this fails, something is really wrong.
-#[test]
-fn open() {
-    tmp_concrete_env();
+fn open(backend: Backend) {
+    tmp_concrete_env_with(backend);
+}
+
+/// Test [`Env::switch_to_safe_sync`] changes the sync mode
+/// of [`SyncMode::FastThenSafe`] databases only.
+fn switch_to_safe_sync(backend: Backend) {
+    for (sync_mode, expected) in [
+        (SyncMode::FastThenSafe, SyncMode::Safe),
+        (SyncMode::Fast, SyncMode::Fast),
+        (SyncMode::Safe, SyncMode::Safe),
+    ] {
+        let tempdir = tempfile::tempdir().unwrap();
+        let config = ConfigBuilder::new(Cow::Owned(tempdir.path().into()))
+            .low_power()
+            .sync_mode(sync_mode)
+            .backend(backend)
+            .build();
+        let env = ConcreteEnv::open(config).unwrap();
+
+        assert_eq!(env.sync_mode(), sync_mode);
+
+        // Switching is idempotent.
+        env.switch_to_safe_sync().unwrap();
+        env.switch_to_safe_sync().unwrap();
+        assert_eq!(env.sync_mode(), expected);
+
+        // Writes still work after switching.
+        let env_inner = env.env_inner();
+        let tx_rw = env_inner.tx_rw().unwrap();
+        env_inner.create_db::<TestTable>(&tx_rw).unwrap();
+        {
+            let mut table = env_inner.open_db_rw::<TestTable>(&tx_rw).unwrap();
+            table.put(&0, &0).unwrap();
+        }
+        TxRw::commit(tx_rw).unwrap();
+    }
 }
 
 /// Create database transactions, but don't write any data.
-#[test]
-fn tx() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn tx(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
 
     TxRo::commit(env_inner.tx_ro().unwrap()).unwrap();
@@ -43,9 +73,8 @@ fn tx() {
 }
 
 /// Test [`Env::open`] and creating/opening tables.
-#[test]
-fn open_db() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn open_db(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
 
     // Create table.
@@ -68,9 +97,8 @@ fn open_db() {
 }
 
 /// Assert that opening a read-only table before creating errors.
-#[test]
-fn open_ro_uncreated_table() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn open_ro_uncreated_table(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
     let tx_ro = env_inner.tx_ro().unwrap();
 
@@ -80,9 +108,8 @@ fn open_ro_uncreated_table() {
 }
 
 /// Assert that opening a read/write table before creating is OK.
-#[test]
-fn open_rw_uncreated_table() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn open_rw_uncreated_table(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
     let tx_rw = env_inner.tx_rw().unwrap();
 
@@ -91,9 +118,8 @@ fn open_rw_uncreated_table() {
 }
 
 /// Assert that opening a read-only table after creating is OK.
-#[test]
-fn open_ro_created_table() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn open_ro_created_table(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
 
     // Assert uncreated table errors.
@@ -116,14 +142,11 @@ fn open_ro_created_table() {
 }
 
 /// Test `Env` resizes.
+///
+/// This test is only valid for backends that need to resize manually.
 #[test]
 fn resize() {
-    // This test is only valid for `Env`'s that need to resize manually.
-    if !ConcreteEnv::MANUAL_RESIZE {
-        return;
-    }
-
-    let (env, _tempdir) = tmp_concrete_env();
+    let (env, _tempdir) = tmp_concrete_env_with(Backend::Heed);
 
     // Resize by the OS page size.
     let page_size = *crate::resize::PAGE_SIZE;
@@ -135,32 +158,25 @@ fn resize() {
     assert_eq!(new_size, old_size + page_size.get());
 }
 
-/// Test that `Env`'s that don't manually resize.
+/// Test that backends that don't manually resize panic on resize functions.
 #[test]
 #[should_panic = "unreachable"]
 fn non_manual_resize_1() {
-    if ConcreteEnv::MANUAL_RESIZE {
-        unreachable!();
-    }
-    let (env, _tempdir) = tmp_concrete_env();
+    let (env, _tempdir) = tmp_concrete_env_with(Backend::Redb);
     env.resize_map(None);
 }
 
 #[test]
 #[should_panic = "unreachable"]
 fn non_manual_resize_2() {
-    if ConcreteEnv::MANUAL_RESIZE {
-        unreachable!();
-    }
-    let (env, _tempdir) = tmp_concrete_env();
+    let (env, _tempdir) = tmp_concrete_env_with(Backend::Redb);
     env.current_map_size();
 }
 
 /// Tests that [`EnvInner::clear_db`] will return
 /// [`RuntimeError::TableNotFound`] if the table doesn't exist.
-#[test]
-fn clear_db_table_not_found() {
-    let (env, _tmpdir) = tmp_concrete_env();
+fn clear_db_table_not_found(backend: Backend) {
+    let (env, _tmpdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
     let mut tx_rw = env_inner.tx_rw().unwrap();
     let err = env_inner.clear_db::<TestTable>(&mut tx_rw).unwrap_err();
@@ -171,9 +187,8 @@ fn clear_db_table_not_found() {
 }
 
 /// Test all `DatabaseR{o,w}` operations.
-#[test]
-fn db_read_write() {
-    let (env, _tempdir) = tmp_concrete_env();
+fn db_read_write(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
     let tx_rw = env_inner.tx_rw().unwrap();
     let mut table = env_inner.open_db_rw::<TestTable>(&tx_rw).unwrap();
@@ -333,9 +348,8 @@ fn db_read_write() {
 /// an ordered B-Tree fashion, i.e. `min_value -> max_value`.
 ///
 /// And that it is true for integers, e.g. `0` -> `10`.
-#[test]
-fn tables_are_sorted() {
-    let (env, _tmp) = tmp_concrete_env();
+fn tables_are_sorted(backend: Backend) {
+    let (env, _tmp) = tmp_concrete_env_with(backend);
     let env_inner = env.env_inner();
 
     /// Range of keys to insert, `{0, 1, 2 ... 256}`.
@@ -396,3 +410,101 @@ fn tables_are_sorted() {
         table.delete(&key).unwrap();
     }
 }
+
+/// Test [`Env::stats`] and [`EnvInner::table_stats`] report the table contents.
+fn stats(backend: Backend) {
+    let (env, _tempdir) = tmp_concrete_env_with(backend);
+    let env_inner = env.env_inner();
+
+    /// How many `(key, value)` pairs will be inserted.
+    const N: u32 = 1_000;
+
+    {
+        let tx_rw = env_inner.tx_rw().unwrap();
+        env_inner.create_db::<TestTable>(&tx_rw).unwrap();
+        TxRw::commit(tx_rw).unwrap();
+    }
+
+    // An empty table.
+    {
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let stats = env_inner.table_stats::<TestTable>(&tx_ro).unwrap();
+        assert_eq!(stats.table, TestTable::NAME);
+        assert_eq!(stats.entries, 0);
+        assert_eq!(stats.overflow_pages, 0);
+    }
+
+    {
+        let tx_rw = env_inner.tx_rw().unwrap();
+        let mut table = env_inner.open_db_rw::<TestTable>(&tx_rw).unwrap();
+        for key in 0..N {
+            table.put(&key, &u64::from(key)).unwrap();
+        }
+        drop(table);
+        TxRw::commit(tx_rw).unwrap();
+    }
+
+    {
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let stats = env_inner.table_stats::<TestTable>(&tx_ro).unwrap();
+        assert_eq!(stats.entries, u64::from(N));
+        assert!(stats.depth >= 1);
+        assert!(stats.leaf_pages >= 1);
+        assert!(stats.size_bytes > 0);
+    }
+
+    // Reading the stats must not wait for an open write transaction.
+    {
+        let tx_rw = env_inner.tx_rw().unwrap();
+        assert!(env.stats().unwrap().used_bytes > 0);
+        TxRw::abort(tx_rw).unwrap();
+    }
+
+    drop(env_inner);
+    let env_stats = env.stats().unwrap();
+    assert!(env_stats.used_bytes > 0);
+    assert_eq!(env_stats.map_size.is_some(), backend == Backend::Heed);
+}
+
+//---------------------------------------------------------------------------------------------------- Backend tests
+/// Generate a `#[test]` module for each [`Backend`],
+/// calling each `fn test(backend: Backend)` with that backend.
+macro_rules! backend_tests {
+    ($($test:ident),* $(,)?) => {
+        mod heed {
+            use super::Backend;
+
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(Backend::Heed);
+                }
+            )*
+        }
+
+        mod redb {
+            use super::Backend;
+
+            $(
+                #[test]
+                fn $test() {
+                    super::$test(Backend::Redb);
+                }
+            )*
+        }
+    };
+}
+
+backend_tests! {
+    open,
+    switch_to_safe_sync,
+    tx,
+    open_db,
+    open_ro_uncreated_table,
+    open_rw_uncreated_table,
+    open_ro_created_table,
+    clear_db_table_not_found,
+    db_read_write,
+    tables_are_sorted,
+    stats,
+}
@@ -1,31 +1,73 @@
-//! SOMEDAY
+//! Database [`Backend`].
 
 //---------------------------------------------------------------------------------------------------- Import
-use std::{
-    borrow::Cow,
-    num::NonZeroUsize,
-    path::{Path, PathBuf},
-};
-
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use cuprate_helper::fs::database_dir;
-
-use crate::{
-    config::{ReaderThreads, SyncMode},
-    constants::DATABASE_DATA_FILENAME,
-    resize::ResizeAlgorithm,
-};
-
 //---------------------------------------------------------------------------------------------------- Backend
-/// SOMEDAY: allow runtime hot-swappable backends.
+/// The database backend used by [`ConcreteEnv`](crate::ConcreteEnv).
+///
+/// All backends are compiled in, the backend
+/// is selected at runtime with [`Config::backend`](crate::config::Config::backend).
+///
+/// Note that backends use different file formats, a database
+/// created with one backend cannot be opened with another.
 #[derive(Copy, Clone, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Backend {
     #[default]
-    /// SOMEDAY
+    /// The [`heed`](https://github.com/meilisearch/heed) (LMDB) backend.
     Heed,
-    /// SOMEDAY
+    /// The [`redb`](https://github.com/cberner/redb) backend.
     Redb,
 }
+
+impl Backend {
+    /// Static string of the `crate` used as the database backend.
+    ///
+    /// ```rust
+    /// use cuprate_database::config::Backend;
+    ///
+    /// assert_eq!(Backend::Heed.name(), "heed");
+    /// assert_eq!(Backend::Redb.name(), "redb");
+    /// ```
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Heed => "heed",
+            Self::Redb => "redb",
+        }
+    }
+
+    /// The database data filename of this backend.
+    ///
+    /// Used in [`Config::db_file`](crate::config::Config::db_file).
+    ///
+    /// ```rust
+    /// use cuprate_database::config::Backend;
+    ///
+    /// assert_eq!(Backend::Heed.data_filename(), "data.mdb");
+    /// assert_eq!(Backend::Redb.data_filename(), "data.redb");
+    /// ```
+    pub const fn data_filename(self) -> &'static str {
+        match self {
+            Self::Heed => "data.mdb",
+            Self::Redb => "data.redb",
+        }
+    }
+
+    /// The database lock filename of this backend.
+    ///
+    /// ```rust
+    /// use cuprate_database::config::Backend;
+    ///
+    /// assert_eq!(Backend::Heed.lock_filename(), Some("lock.mdb"));
+    /// // `redb` doesn't use a file lock.
+    /// assert_eq!(Backend::Redb.lock_filename(), None);
+    /// ```
+    pub const fn lock_filename(self) -> Option<&'static str> {
+        match self {
+            Self::Heed => Some("lock.mdb"),
+            Self::Redb => None,
+        }
+    }
+}
@@ -6,7 +6,10 @@ use std::{borrow::Cow, num::NonZeroUsize, path::Path};
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-use crate::{config::SyncMode, constants::DATABASE_DATA_FILENAME, resize::ResizeAlgorithm};
+use crate::{
+    config::{Backend, SyncMode},
+    resize::ResizeAlgorithm,
+};
 
 //---------------------------------------------------------------------------------------------------- Constants
 /// Default value for [`Config::reader_threads`].
@@ -27,6 +30,9 @@ pub struct ConfigBuilder {
     /// [`Config::db_directory`].
     db_directory: Cow<'static, Path>,
 
+    /// [`Config::backend`].
+    backend: Option<Backend>,
+
     /// [`Config::sync_mode`].
     sync_mode: Option<SyncMode>,
 
@@ -35,6 +41,9 @@ pub struct ConfigBuilder {
 
     /// [`Config::resize_algorithm`].
     resize_algorithm: Option<ResizeAlgorithm>,
+
+    /// [`Config::read_only`].
+    read_only: bool,
 }
 
 impl ConfigBuilder {
@@ -45,9 +54,11 @@ impl ConfigBuilder {
     pub const fn new(db_directory: Cow<'static, Path>) -> Self {
         Self {
             db_directory,
+            backend: None,
             sync_mode: None,
             reader_threads: Some(READER_THREADS_DEFAULT),
             resize_algorithm: None,
+            read_only: false,
         }
     }
 
@@ -57,19 +68,23 @@ impl ConfigBuilder {
     /// - [`READER_THREADS_DEFAULT`] is used for [`Config::reader_threads`]
     /// - [`Default::default`] is used for all other values (except the `db_directory`)
     pub fn build(self) -> Config {
+        let backend = self.backend.unwrap_or_default();
+
         // Add the database filename to the directory.
         let db_file = {
             let mut db_file = self.db_directory.to_path_buf();
-            db_file.push(DATABASE_DATA_FILENAME);
+            db_file.push(backend.data_filename());
             Cow::Owned(db_file)
         };
 
         Config {
             db_directory: self.db_directory,
             db_file,
+            backend,
             sync_mode: self.sync_mode.unwrap_or_default(),
             reader_threads: self.reader_threads.unwrap_or(READER_THREADS_DEFAULT),
             resize_algorithm: self.resize_algorithm.unwrap_or_default(),
+            read_only: self.read_only,
         }
     }
 
@@ -102,6 +117,13 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set a custom [`Backend`].
+    #[must_use]
+    pub const fn backend(mut self, backend: Backend) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
     /// Set a custom [`SyncMode`].
     #[must_use]
     pub const fn sync_mode(mut self, sync_mode: SyncMode) -> Self {
@@ -122,6 +144,13 @@ impl ConfigBuilder {
         self.resize_algorithm = Some(resize_algorithm);
         self
     }
+
+    /// Set [`Config::read_only`].
+    #[must_use]
+    pub const fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
 }
 
 //---------------------------------------------------------------------------------------------------- Config
@@ -146,9 +175,16 @@ pub struct Config {
     pub(crate) db_directory: Cow<'static, Path>,
     /// The actual database data file.
     ///
-    /// This is private, and created from the above `db_directory`.
+    /// This is private, and created from the above `db_directory`
+    /// and the [`Backend::data_filename`] of `backend`.
     pub(crate) db_file: Cow<'static, Path>,
 
+    /// The database backend.
+    ///
+    /// This is private as [`Config::db_file`] depends on it,
+    /// use [`ConfigBuilder::backend`] to set it.
+    pub(crate) backend: Backend,
+
     /// Disk synchronization mode.
     pub sync_mode: SyncMode,
 
@@ -168,6 +204,15 @@ pub struct Config {
     /// custom algorithms can be used as well with
     /// [`Env::resize_map`](crate::Env::resize_map).
     pub resize_algorithm: ResizeAlgorithm,
+
+    /// Open the database in read-only mode.
+    ///
+    /// The database file must already exist and is never written to:
+    /// - `heed` opens the environment with `MDB_RDONLY`, write transactions will error
+    /// - `redb` keeps all writes in memory, they are lost when the [`Env`](crate::Env) is dropped
+    ///
+    /// By default, this value is `false`.
+    pub read_only: bool,
 }
 
 impl Config {
@@ -187,9 +232,11 @@ impl Config {
     /// assert_eq!(*config.db_directory(), db_directory);
     /// assert!(config.db_file().starts_with(db_directory));
     /// assert!(config.db_file().ends_with(DATABASE_DATA_FILENAME));
+    /// assert_eq!(config.backend(), Backend::default());
     /// assert_eq!(config.sync_mode, SyncMode::default());
     /// assert_eq!(config.reader_threads, READER_THREADS_DEFAULT);
     /// assert_eq!(config.resize_algorithm, ResizeAlgorithm::default());
+    /// assert!(!config.read_only);
     /// ```
     pub fn new(db_directory: Cow<'static, Path>) -> Self {
         ConfigBuilder::new(db_directory).build()
@@ -204,4 +251,9 @@ impl Config {
     pub const fn db_file(&self) -> &Cow<'_, Path> {
         &self.db_file
     }
+
+    /// Return the database [`Backend`].
+    pub const fn backend(&self) -> Backend {
+        self.backend
+    }
 }
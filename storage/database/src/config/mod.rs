@@ -37,5 +37,8 @@
 mod config;
 pub use config::{Config, ConfigBuilder, READER_THREADS_DEFAULT};
 
+mod backend;
+pub use backend::Backend;
+
 mod sync_mode;
 pub use sync_mode::SyncMode;
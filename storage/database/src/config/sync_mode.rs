@@ -25,27 +25,18 @@ pub enum SyncMode {
     /// Use [`SyncMode::Fast`] until fully synced,
     /// then use [`SyncMode::Safe`].
     ///
-    /// # TODO
-    /// This is not implemented internally and has the same behavior as [`SyncMode::Fast`].
+    /// The database starts with the same behavior as [`SyncMode::Fast`],
+    /// it is switched to [`SyncMode::Safe`] at runtime with
+    /// [`Env::switch_to_safe_sync`](crate::Env::switch_to_safe_sync),
+    /// e.g. when the node reaches the network's chain tip.
+    ///
+    /// This maps to:
+    /// - [`SyncMode::Fast`]'s LMDB flags, with `MDB_NOSYNC | MDB_MAPASYNC` cleared on switching
+    /// - [`SyncMode::Fast`]'s `redb` durability, then [`SyncMode::Safe`]'s on switching
     //
-    // # SOMEDAY: how to implement this?
     // ref: <https://github.com/monero-project/monero/issues/1463>
     // monerod-solution: <https://github.com/monero-project/monero/pull/1506>
     // cuprate-issue: <https://github.com/Cuprate/cuprate/issues/78>
-    //
-    // We could:
-    // ```rust,ignore
-    // if current_db_block <= top_block.saturating_sub(N) {
-    //     // don't sync()
-    // } else {
-    //     // sync()
-    // }
-    // ```
-    // where N is some threshold we pick that is _close_ enough
-    // to being synced where we want to start being safer.
-    //
-    // Essentially, when we are in a certain % range of being finished,
-    // switch to safe mode, until then, go fast.
     FastThenSafe,
 
     /// Fully sync to disk per transaction.
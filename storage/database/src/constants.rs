@@ -1,7 +1,7 @@
 //! General constants used throughout `cuprate-blockchain`.
 
 //---------------------------------------------------------------------------------------------------- Import
-use cfg_if::cfg_if;
+use crate::config::Backend;
 
 //---------------------------------------------------------------------------------------------------- Error Messages
 /// Corrupt database error message.
@@ -12,8 +12,9 @@ use cfg_if::cfg_if;
 /// This is meant to be user-friendly.
 pub const DATABASE_CORRUPT_MSG: &str = r"`cuprated` has encountered a fatal error. The database may be corrupted.
 
-If `cuprated` continues to crash with the current database,
-you may have to delete the database file and re-sync from scratch.
+If `cuprated` continues to crash with the current database, try checking and repairing
+it with `cuprated --check-database --repair-database`, otherwise you may have to
+delete the database file and re-sync from scratch.
 
 See <https://user.cuprate.org/resources/disk.html>
 for more information on where database files are.
@@ -21,55 +22,36 @@ for more information on where database files are.
 If this happens frequently, consider using the `Safe` sync mode.";
 
 //---------------------------------------------------------------------------------------------------- Misc
-/// Static string of the `crate` being used as the database backend.
+/// Static string of the `crate` being used as the default database backend.
 ///
-/// | Backend | Value |
-/// |---------|-------|
-/// | `heed`  | `"heed"`
-/// | `redb`  | `"redb"`
-pub const DATABASE_BACKEND: &str = {
-    cfg_if! {
-        if #[cfg(all(feature = "redb", not(feature = "heed")))] {
-            "redb"
-        } else {
-            "heed"
-        }
-    }
-};
+/// This is [`Backend::name`] of the default [`Backend`],
+/// the backend actually in use is [`Config::backend`](crate::config::Config::backend).
+///
+/// ```rust
+/// # use cuprate_database::{*, config::*};
+/// assert_eq!(DATABASE_BACKEND, Backend::default().name());
+/// ```
+pub const DATABASE_BACKEND: &str = Backend::Heed.name();
 
-/// Cuprate's database filename.
+/// Cuprate's database filename, when using the default database backend.
 ///
-/// Used in [`Config::db_file`](crate::config::Config::db_file).
+/// See [`Backend::data_filename`].
 ///
-/// | Backend | Value |
-/// |---------|-------|
-/// | `heed`  | `"data.mdb"`
-/// | `redb`  | `"data.redb"`
-pub const DATABASE_DATA_FILENAME: &str = {
-    cfg_if! {
-        if #[cfg(all(feature = "redb", not(feature = "heed")))] {
-            "data.redb"
-        } else {
-            "data.mdb"
-        }
-    }
-};
+/// ```rust
+/// # use cuprate_database::{*, config::*};
+/// assert_eq!(DATABASE_DATA_FILENAME, Backend::default().data_filename());
+/// ```
+pub const DATABASE_DATA_FILENAME: &str = Backend::Heed.data_filename();
 
-/// Cuprate's database lock filename.
+/// Cuprate's database lock filename, when using the default database backend.
+///
+/// See [`Backend::lock_filename`].
 ///
-/// | Backend | Value |
-/// |---------|-------|
-/// | `heed`  | `Some("lock.mdb")`
-/// | `redb`  | `None` (redb doesn't use a file lock)
-pub const DATABASE_LOCK_FILENAME: Option<&str> = {
-    cfg_if! {
-        if #[cfg(all(feature = "redb", not(feature = "heed")))] {
-            None
-        } else {
-            Some("lock.mdb")
-        }
-    }
-};
+/// ```rust
+/// # use cuprate_database::{*, config::*};
+/// assert_eq!(DATABASE_LOCK_FILENAME, Backend::default().lock_filename());
+/// ```
+pub const DATABASE_LOCK_FILENAME: Option<&str> = Backend::Heed.lock_filename();
 
 //---------------------------------------------------------------------------------------------------- Tests
 #[cfg(test)]
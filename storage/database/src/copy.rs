@@ -0,0 +1,394 @@
+//! Copying tables between databases.
+//!
+//! This is used to convert a database from one [`Backend`](crate::config::Backend)
+//! to another, by copying every table into a new database with [`copy_table`]
+//! and then checking the copy with [`verify_table`].
+//!
+//! # Resuming
+//! Rows are copied in key order in batches, each batch is committed
+//! in its own write transaction. If the copy is interrupted, calling
+//! [`copy_table`] again will continue after the last committed batch.
+//!
+//! This relies on the destination table only containing rows from
+//! a previous copy of the same source table, so nothing (e.g. a schema
+//! stamp) must be written to the destination before the copy.
+//! [`verify_table`] will catch the case where it was.
+//!
+//! # Example
+//! ```rust
+//! use std::num::NonZeroUsize;
+//!
+//! use cuprate_database::{
+//!     config::{Backend, ConfigBuilder},
+//!     copy::{copy_table, verify_table},
+//!     ConcreteEnv, DatabaseRw, Env, EnvInner, Table, TxRw,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! struct Numbers;
+//! impl Table for Numbers {
+//!     const NAME: &'static str = "numbers";
+//!     type Key = u64;
+//!     type Value = u64;
+//! }
+//!
+//! // Open a `heed` and a `redb` database.
+//! let tmp_dir = tempfile::tempdir()?;
+//! let open = |backend| {
+//!     let config = ConfigBuilder::new(tmp_dir.path().to_path_buf().into())
+//!         .backend(backend)
+//!         .build();
+//!     let env = ConcreteEnv::open(config)?;
+//!
+//!     let env_inner = env.env_inner();
+//!     let tx_rw = env_inner.tx_rw()?;
+//!     env_inner.create_db::<Numbers>(&tx_rw)?;
+//!     TxRw::commit(tx_rw)?;
+//!     drop(env_inner);
+//!
+//!     Ok::<_, Box<dyn std::error::Error>>(env)
+//! };
+//! let heed = open(Backend::Heed)?;
+//! let redb = open(Backend::Redb)?;
+//!
+//! // Write some data to the `heed` database.
+//! {
+//!     let env_inner = heed.env_inner();
+//!     let tx_rw = env_inner.tx_rw()?;
+//!     let mut table = env_inner.open_db_rw::<Numbers>(&tx_rw)?;
+//!     for i in 0..100 {
+//!         table.put(&i, &(i * 2))?;
+//!     }
+//!     drop(table);
+//!     TxRw::commit(tx_rw)?;
+//! }
+//!
+//! // Copy it to the `redb` database.
+//! let batch_size = NonZeroUsize::new(16).unwrap();
+//! let copy = copy_table::<Numbers>(&heed, &redb, batch_size, |_, _| {})?;
+//! assert_eq!(copy.rows, 100);
+//! assert_eq!(copy.resumed_from, 0);
+//!
+//! // Check the copy.
+//! verify_table::<Numbers>(&heed, &redb)?;
+//! # Ok(()) }
+//! ```
+
+//---------------------------------------------------------------------------------------------------- Import
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    num::NonZeroUsize,
+};
+
+use crate::{
+    database::{DatabaseIter, DatabaseRo, DatabaseRw},
+    env::{Env, EnvInner},
+    error::{CopyError, DbResult, RuntimeError},
+    storable::Storable,
+    table::Table,
+    transaction::{TxRo, TxRw},
+};
+
+//---------------------------------------------------------------------------------------------------- TableCopy
+/// The result of a successful [`copy_table`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TableCopy {
+    /// The name of the copied table.
+    pub table: &'static str,
+    /// The total amount of rows in the table.
+    pub rows: u64,
+    /// The amount of rows that were already copied before
+    /// this call, i.e. a previous copy was resumed if this is non-zero.
+    pub resumed_from: u64,
+}
+
+//---------------------------------------------------------------------------------------------------- Free functions
+/// Copy all rows of table `T` from `src` into `dst`.
+///
+/// Both databases must have already created `T`.
+///
+/// Rows are written to `dst` in batches of `batch_size` rows,
+/// `progress` is called after each committed batch with
+/// the amount of copied rows and the total amount of rows.
+///
+/// If `dst` already contains rows of `T`, they are assumed to be from
+/// an interrupted copy and the copy will continue after them.
+///
+/// # Errors
+/// This returns [`CopyError::RowCount`] if `dst` contains more rows than `src`.
+///
+/// Any [`RuntimeError`] from either database is returned as
+/// [`CopyError::Runtime`], except for [`RuntimeError::ResizeNeeded`]
+/// which is handled by resizing `dst`.
+pub fn copy_table<T: Table>(
+    src: &impl Env,
+    dst: &impl Env,
+    batch_size: NonZeroUsize,
+    mut progress: impl FnMut(u64, u64),
+) -> Result<TableCopy, CopyError> {
+    let resumed_from = table_len::<T>(dst)?;
+
+    let src_inner = src.env_inner();
+    let tx_ro = src_inner.tx_ro()?;
+    let table = src_inner.open_db_ro::<T>(&tx_ro)?;
+    let rows = table.len()?;
+
+    if resumed_from > rows {
+        return Err(CopyError::RowCount {
+            table: T::NAME,
+            source_rows: rows,
+            destination_rows: resumed_from,
+        });
+    }
+
+    // Skip the rows of the previous copy, rows
+    // are always iterated in the same key order.
+    let mut iter = table.iter()?;
+    for _ in 0..resumed_from {
+        iter.next().transpose()?;
+    }
+
+    let mut copied = resumed_from;
+    progress(copied, rows);
+
+    loop {
+        let batch = iter
+            .by_ref()
+            .take(batch_size.get())
+            .collect::<DbResult<Vec<_>>>()?;
+
+        if batch.is_empty() {
+            break;
+        }
+
+        write_batch::<T>(dst, &batch)?;
+
+        copied += u64::try_from(batch.len()).unwrap();
+        progress(copied, rows);
+    }
+
+    drop(iter);
+    drop(table);
+    TxRo::commit(tx_ro)?;
+
+    Ok(TableCopy {
+        table: T::NAME,
+        rows,
+        resumed_from,
+    })
+}
+
+/// Verify that table `T` in `dst` is an exact copy of `T` in `src`.
+///
+/// This compares the amount of rows and the [`table_checksum`] of both tables.
+///
+/// # Errors
+/// - [`CopyError::RowCount`] if the amount of rows differ
+/// - [`CopyError::Checksum`] if the rows differ
+/// - [`CopyError::Runtime`] on database errors
+pub fn verify_table<T: Table>(src: &impl Env, dst: &impl Env) -> Result<(), CopyError> {
+    let (source_rows, src_checksum) = len_and_checksum::<T>(src)?;
+    let (destination_rows, dst_checksum) = len_and_checksum::<T>(dst)?;
+
+    if source_rows != destination_rows {
+        return Err(CopyError::RowCount {
+            table: T::NAME,
+            source_rows,
+            destination_rows,
+        });
+    }
+
+    if src_checksum != dst_checksum {
+        return Err(CopyError::Checksum { table: T::NAME });
+    }
+
+    Ok(())
+}
+
+/// Calculate a checksum of all rows in a table.
+///
+/// This hashes the bytes of all keys and values in key order, so
+/// the same rows will produce the same checksum on any [`Backend`](crate::config::Backend).
+///
+/// The checksum is only meant to be compared within the same program,
+/// it should not be stored as it may change between Rust versions.
+///
+/// # Errors
+/// This returns any [`RuntimeError`] that occurs while iterating.
+pub fn table_checksum<T: Table>(table: &impl DatabaseIter<T>) -> DbResult<u64> {
+    let mut hasher = DefaultHasher::new();
+
+    for row in table.iter()? {
+        let (key, value) = row?;
+        key.as_bytes().hash(&mut hasher);
+        value.as_bytes().hash(&mut hasher);
+    }
+
+    Ok(hasher.finish())
+}
+
+//---------------------------------------------------------------------------------------------------- Private
+/// Returns the amount of rows in table `T`.
+fn table_len<T: Table>(env: &impl Env) -> DbResult<u64> {
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let len = env_inner.open_db_ro::<T>(&tx_ro)?.len()?;
+    TxRo::commit(tx_ro)?;
+    Ok(len)
+}
+
+/// Returns the amount of rows in, and the [`table_checksum`] of table `T`.
+fn len_and_checksum<T: Table>(env: &impl Env) -> DbResult<(u64, u64)> {
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro()?;
+    let table = env_inner.open_db_ro::<T>(&tx_ro)?;
+    let len_and_checksum = (table.len()?, table_checksum(&table)?);
+    drop(table);
+    TxRo::commit(tx_ro)?;
+    Ok(len_and_checksum)
+}
+
+/// Write `batch` into table `T` in a single write transaction.
+///
+/// If `env` must be resized, it is resized and the batch is retried.
+fn write_batch<T: Table, E: Env>(env: &E, batch: &[(T::Key, T::Value)]) -> DbResult<()> {
+    loop {
+        match try_write_batch::<T>(env, batch) {
+            Err(RuntimeError::ResizeNeeded) if E::MANUAL_RESIZE => {
+                env.resize_map(None);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Write `batch` into table `T` in a single write
+/// transaction, aborting the transaction on errors.
+fn try_write_batch<T: Table>(env: &impl Env, batch: &[(T::Key, T::Value)]) -> DbResult<()> {
+    let env_inner = env.env_inner();
+    let tx_rw = env_inner.tx_rw()?;
+
+    let result = {
+        let mut table = env_inner.open_db_rw::<T>(&tx_rw)?;
+        batch
+            .iter()
+            .try_for_each(|(key, value)| table.put(key, value))
+    };
+
+    match result {
+        Ok(()) => TxRw::commit(tx_rw),
+        Err(e) => {
+            TxRw::abort(tx_rw)?;
+            Err(e)
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::Backend,
+        tests::{tmp_concrete_env_with, TestTable},
+        ConcreteEnv,
+    };
+
+    /// Create `TestTable` in `env` and write `rows` into it.
+    fn create_and_fill(env: &ConcreteEnv, rows: impl IntoIterator<Item = (u32, u64)>) {
+        let env_inner = env.env_inner();
+        let tx_rw = env_inner.tx_rw().unwrap();
+        env_inner.create_db::<TestTable>(&tx_rw).unwrap();
+        {
+            let mut table = env_inner.open_db_rw::<TestTable>(&tx_rw).unwrap();
+            for (key, value) in rows {
+                table.put(&key, &value).unwrap();
+            }
+        }
+        TxRw::commit(tx_rw).unwrap();
+    }
+
+    /// Copy between both backends, in both directions.
+    #[test]
+    fn copy() {
+        for (from, to) in [
+            (Backend::Heed, Backend::Redb),
+            (Backend::Redb, Backend::Heed),
+        ] {
+            let (src, _tempdir) = tmp_concrete_env_with(from);
+            let (dst, _tempdir) = tmp_concrete_env_with(to);
+            create_and_fill(&src, (0..1000).map(|i| (i, u64::from(i) * 3)));
+            create_and_fill(&dst, []);
+
+            let mut calls = 0;
+            let batch_size = NonZeroUsize::new(100).unwrap();
+            let copy = copy_table::<TestTable>(&src, &dst, batch_size, |copied, rows| {
+                assert_eq!(copied, calls * 100);
+                assert_eq!(rows, 1000);
+                calls += 1;
+            })
+            .unwrap();
+
+            // 1 initial call + 10 batches.
+            assert_eq!(calls, 11);
+            assert_eq!(
+                copy,
+                TableCopy {
+                    table: TestTable::NAME,
+                    rows: 1000,
+                    resumed_from: 0,
+                }
+            );
+            verify_table::<TestTable>(&src, &dst).unwrap();
+        }
+    }
+
+    /// An interrupted copy is resumed.
+    #[test]
+    fn resume() {
+        let (src, _tempdir) = tmp_concrete_env_with(Backend::Heed);
+        let (dst, _tempdir) = tmp_concrete_env_with(Backend::Redb);
+        create_and_fill(&src, (0..100).map(|i| (i, u64::from(i))));
+        create_and_fill(&dst, (0..42).map(|i| (i, u64::from(i))));
+
+        let batch_size = NonZeroUsize::new(10).unwrap();
+        let copy = copy_table::<TestTable>(&src, &dst, batch_size, |_, _| {}).unwrap();
+        assert_eq!(copy.resumed_from, 42);
+        assert_eq!(copy.rows, 100);
+        verify_table::<TestTable>(&src, &dst).unwrap();
+
+        // Copying a finished copy does nothing.
+        let copy = copy_table::<TestTable>(&src, &dst, batch_size, |_, _| {}).unwrap();
+        assert_eq!(copy.resumed_from, 100);
+        verify_table::<TestTable>(&src, &dst).unwrap();
+    }
+
+    /// Unrelated data in the destination is detected.
+    #[test]
+    fn mismatch() {
+        let (src, _tempdir) = tmp_concrete_env_with(Backend::Heed);
+        let (dst, _tempdir) = tmp_concrete_env_with(Backend::Redb);
+        create_and_fill(&src, (0..10).map(|i| (i, u64::from(i))));
+        create_and_fill(&dst, [(0, 123)]);
+
+        let batch_size = NonZeroUsize::new(10).unwrap();
+        copy_table::<TestTable>(&src, &dst, batch_size, |_, _| {}).unwrap();
+        assert!(matches!(
+            verify_table::<TestTable>(&src, &dst),
+            Err(CopyError::Checksum {
+                table: TestTable::NAME
+            })
+        ));
+
+        // More rows in the destination than the source.
+        create_and_fill(&dst, (10..20).map(|i| (i, u64::from(i))));
+        assert!(matches!(
+            copy_table::<TestTable>(&src, &dst, batch_size, |_, _| {}),
+            Err(CopyError::RowCount {
+                source_rows: 10,
+                destination_rows: 20,
+                ..
+            })
+        ));
+    }
+}
@@ -4,10 +4,11 @@
 use std::num::NonZeroUsize;
 
 use crate::{
-    config::Config,
+    config::{Config, SyncMode},
     database::{DatabaseIter, DatabaseRo, DatabaseRw},
     error::{DbResult, InitError},
     resize::ResizeAlgorithm,
+    stats::{EnvStats, TableStats},
     table::Table,
     transaction::{TxRo, TxRw},
 };
@@ -109,6 +110,27 @@ pub trait Env: Sized {
     /// If there is a synchronization error, this should return an error.
     fn sync(&self) -> DbResult<()>;
 
+    /// Return the [`SyncMode`] the database is _currently_ using.
+    ///
+    /// This is the [`Config::sync_mode`] the database was opened with, except for
+    /// [`SyncMode::FastThenSafe`], which returns [`SyncMode::Safe`] after
+    /// [`Env::switch_to_safe_sync`] has been called.
+    fn sync_mode(&self) -> SyncMode;
+
+    /// Switch a [`SyncMode::FastThenSafe`] database to [`SyncMode::Safe`].
+    ///
+    /// This changes the durability of the database at runtime, without re-opening it.
+    /// All write transactions committed after this function returns will be fully synced.
+    ///
+    /// This also fully syncs any data written while in fast mode.
+    ///
+    /// This is a no-op if the database was not opened with
+    /// [`SyncMode::FastThenSafe`] or has already been switched.
+    ///
+    /// # Errors
+    /// If changing the sync mode or syncing fails, this should return an error.
+    fn switch_to_safe_sync(&self) -> DbResult<()>;
+
     /// Resize the database's memory map to a
     /// new (bigger) size using a [`ResizeAlgorithm`].
     ///
@@ -147,6 +169,14 @@ pub trait Env: Sized {
     /// [`Env::resize_map`]) will take a _write_ lock.
     fn env_inner(&self) -> Self::EnvInner<'_>;
 
+    /// Return the [`EnvStats`] of the whole database.
+    ///
+    /// See [`EnvInner::table_stats`] for the statistics of a single table.
+    ///
+    /// # Errors
+    /// This will error if the backend fails to read its statistics.
+    fn stats(&self) -> DbResult<EnvStats>;
+
     //------------------------------------------------ Provided
     /// Return the amount of actual of bytes the database is taking up on disk.
     ///
@@ -324,4 +354,13 @@ pub trait EnvInner<'env> {
     /// If the specified table is not created upon before this function is called,
     /// this will return [`crate::RuntimeError::TableNotFound`].
     fn clear_db<T: Table>(&self, tx_rw: &mut Self::Rw<'_>) -> DbResult<()>;
+
+    /// Return the [`TableStats`] of a database table.
+    ///
+    /// # Errors
+    /// This will return [`crate::RuntimeError::Io`] on normal errors.
+    ///
+    /// If the specified table is not created upon before this function is called,
+    /// this will return [`crate::RuntimeError::TableNotFound`].
+    fn table_stats<T: Table>(&self, tx_ro: &Self::Ro<'_>) -> DbResult<TableStats>;
 }
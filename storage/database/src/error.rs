@@ -35,6 +35,38 @@ pub enum InitError {
     #[error("database file is valid, but version is incorrect")]
     InvalidVersion,
 
+    /// The database was created for a different network
+    /// than the one it is being opened for.
+    ///
+    /// See [`crate::migration`].
+    #[error("database was created for {found}, but is being opened for {expected}")]
+    NetworkMismatch {
+        /// The network stored in the database.
+        found: String,
+        /// The network the database is being opened for.
+        expected: String,
+    },
+
+    /// The database schema version is newer than the latest supported version,
+    /// i.e. the database was created by a newer version of Cuprate.
+    ///
+    /// See [`crate::migration`].
+    #[error(
+        "database schema version {found} is newer than the latest supported version {supported}"
+    )]
+    UnsupportedVersion {
+        /// The schema version stored in the database.
+        found: u64,
+        /// The latest supported schema version.
+        supported: u64,
+    },
+
+    /// There is no migration from this database schema version.
+    ///
+    /// See [`crate::migration`].
+    #[error("no migration exists from database schema version {0}")]
+    MissingMigration(u64),
+
     /// I/O error.
     #[error("database I/O error: {0}")]
     Io(#[from] std::io::Error),
@@ -100,3 +132,35 @@ pub enum RuntimeError {
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+//---------------------------------------------------------------------------------------------------- CopyError
+/// Errors that occur when copying tables between
+/// databases, see [`crate::copy`].
+#[derive(thiserror::Error, Debug)]
+pub enum CopyError {
+    /// The copied table has a different amount of rows than the source table.
+    ///
+    /// This can happen if the destination database already
+    /// contained unrelated data, or the source database was
+    /// modified in between copying.
+    #[error("table `{table}` has {source_rows} rows, but its copy has {destination_rows}")]
+    RowCount {
+        /// The name of the table.
+        table: &'static str,
+        /// The amount of rows in the source table.
+        source_rows: u64,
+        /// The amount of rows in the destination table.
+        destination_rows: u64,
+    },
+
+    /// The copied table's rows do not match the source table's rows.
+    #[error("table `{table}` checksum does not match its copy")]
+    Checksum {
+        /// The name of the table.
+        table: &'static str,
+    },
+
+    /// A database error.
+    #[error("database error: {0}")]
+    Runtime(#[from] RuntimeError),
+}
@@ -42,15 +42,18 @@ mod tables;
 mod transaction;
 
 pub mod config;
+pub mod copy;
+pub mod migration;
 pub mod resize;
+pub mod stats;
 
-pub use backend::ConcreteEnv;
+pub use backend::{ConcreteEnv, Dispatch};
 pub use constants::{
     DATABASE_BACKEND, DATABASE_CORRUPT_MSG, DATABASE_DATA_FILENAME, DATABASE_LOCK_FILENAME,
 };
 pub use database::{DatabaseIter, DatabaseRo, DatabaseRw};
 pub use env::{Env, EnvInner};
-pub use error::{DbResult, InitError, RuntimeError};
+pub use error::{CopyError, DbResult, InitError, RuntimeError};
 pub use key::{Key, KeyCompare};
 pub use storable::{Storable, StorableBytes, StorableStr, StorableVec};
 pub use table::Table;
@@ -63,13 +66,3 @@ pub(crate) mod tests;
 // Used inside public facing macros.
 #[doc(hidden)]
 pub use paste;
-
-//----------------------------------------------------------------------------------------------------
-// HACK: needed to satisfy the `unused_crate_dependencies` lint.
-cfg_if::cfg_if! {
-    if #[cfg(feature = "redb")]  {
-        use redb as _;
-    } else {
-        use heed as _;
-    }
-}
@@ -0,0 +1,670 @@
+//! Database schema metadata & migrations.
+//!
+//! Databases built on `cuprate_database` store their schema version
+//! and network inside the [`Metadata`] table, this allows:
+//! - refusing to open databases for another network
+//! - refusing to open databases created by a newer version of Cuprate
+//! - upgrading older databases in place with [`Migration`]s
+//!
+//! The main entrypoint is [`migrate`], which should be
+//! called right after the database's tables are created.
+//!
+//! # Resuming
+//! Each [`Migration`] is split into steps, each step
+//! runs in its own write transaction along with the
+//! migration's progress, so a migration interrupted
+//! by a crash/shutdown will resume from its last
+//! committed step the next time the database is opened.
+//!
+//! # Example
+//! ```rust
+//! use cuprate_database::{
+//!     config::ConfigBuilder,
+//!     migration::{
+//!         migrate, ConcreteEnvInner, ConcreteTxRw, Migration, MigrationStep, Schema,
+//!     },
+//!     ConcreteEnv, DbResult, Env,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let tmp_dir = tempfile::tempdir()?;
+//! let config = ConfigBuilder::new(tmp_dir.path().to_path_buf().into()).build();
+//! let env = ConcreteEnv::open(config)?;
+//!
+//! /// A migration step that does nothing.
+//! fn no_op(
+//!     _env_inner: &ConcreteEnvInner<'_>,
+//!     _tx_rw: &mut ConcreteTxRw<'_, '_>,
+//!     _cursor: u64,
+//! ) -> DbResult<MigrationStep> {
+//!     Ok(MigrationStep::Done)
+//! }
+//!
+//! /// Our migrations, from version 0 to 1.
+//! const MIGRATIONS: &[Migration] = &[Migration {
+//!     from_version: 0,
+//!     description: "no-op",
+//!     step: no_op,
+//! }];
+//!
+//! let schema = Schema {
+//!     version: 1,
+//!     network: "mainnet",
+//!     migrations: MIGRATIONS,
+//! };
+//!
+//! // A new database starts at the latest version.
+//! migrate(&env, &schema, true)?;
+//!
+//! // Opening it for another network is an error.
+//! let testnet = Schema { network: "testnet", ..schema };
+//! assert!(migrate(&env, &testnet, false).is_err());
+//! # Ok(()) }
+//! ```
+
+//---------------------------------------------------------------------------------------------------- Import
+use crate::{
+    database::{DatabaseRo, DatabaseRw},
+    env::{Env, EnvInner},
+    error::{DbResult, InitError, RuntimeError},
+    storable::StorableVec,
+    table::Table,
+    transaction::{TxRo, TxRw},
+    ConcreteEnv,
+};
+
+//---------------------------------------------------------------------------------------------------- Metadata
+/// The database metadata table.
+///
+/// This is a small key-value table, keys are one of:
+/// - [`METADATA_VERSION`]
+/// - [`METADATA_NETWORK`]
+/// - [`METADATA_MIGRATION_CURSOR`]
+/// - the database user's own keys, starting at [`METADATA_USER_KEYS_START`]
+///
+/// It is created by [`migrate`], it does not need to
+/// be part of the database user's own tables.
+pub struct Metadata;
+
+impl Table for Metadata {
+    const NAME: &'static str = "metadata";
+    type Key = u8;
+    type Value = StorableVec<u8>;
+}
+
+/// [`Metadata`] key for the schema version, stored as a little-endian [`u64`].
+pub const METADATA_VERSION: u8 = 0;
+
+/// [`Metadata`] key for the network the database is for, stored as a UTF-8 string.
+pub const METADATA_NETWORK: u8 = 1;
+
+/// [`Metadata`] key for the cursor of the currently running
+/// [`Migration`], stored as a little-endian [`u64`].
+///
+/// This only exists while a migration is in progress.
+pub const METADATA_MIGRATION_CURSOR: u8 = 2;
+
+/// The first [`Metadata`] key free for the database user,
+/// all keys below this are reserved for this module.
+pub const METADATA_USER_KEYS_START: u8 = 16;
+
+//---------------------------------------------------------------------------------------------------- Migration
+/// The [`EnvInner`] of the [`ConcreteEnv`].
+pub type ConcreteEnvInner<'env> = <ConcreteEnv as Env>::EnvInner<'env>;
+
+/// The write transaction of the [`ConcreteEnv`].
+pub type ConcreteTxRw<'env, 'tx> = <ConcreteEnvInner<'env> as EnvInner<'env>>::Rw<'tx>;
+
+/// The outcome of a single [`Migration::step`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MigrationStep {
+    /// The migration is not finished, the next
+    /// step should be called with this cursor.
+    Continue(u64),
+
+    /// The migration is finished.
+    Done,
+}
+
+/// A database migration, upgrading the schema from
+/// [`Migration::from_version`] to `from_version + 1`.
+#[derive(Copy, Clone, Debug)]
+pub struct Migration {
+    /// The schema version this migration upgrades from.
+    pub from_version: u64,
+
+    /// A short human-readable description of this migration, used in logs.
+    pub description: &'static str,
+
+    /// A single step of the migration.
+    ///
+    /// This is called repeatedly with the cursor returned by the
+    /// last step (starting at `0`) until [`MigrationStep::Done`] is returned.
+    ///
+    /// Each step is run inside its own write transaction, which is
+    /// only committed along with the new cursor, so a step must
+    /// only write to the database with the passed transaction.
+    ///
+    /// Steps should be small enough to fit in memory, e.g. a few thousand rows.
+    pub step: for<'env, 'tx> fn(
+        &ConcreteEnvInner<'env>,
+        &mut ConcreteTxRw<'env, 'tx>,
+        u64,
+    ) -> DbResult<MigrationStep>,
+}
+
+/// The expected schema of a database, passed to [`migrate`].
+#[derive(Copy, Clone, Debug)]
+pub struct Schema<'a> {
+    /// The latest schema version.
+    pub version: u64,
+
+    /// The network the database is for, e.g. `"mainnet"`.
+    pub network: &'a str,
+
+    /// All migrations, which must upgrade a database
+    /// from any older schema version to [`Schema::version`].
+    ///
+    /// The order does not matter.
+    pub migrations: &'a [Migration],
+}
+
+//---------------------------------------------------------------------------------------------------- Free functions
+/// Check the database's [`Metadata`] and run any needed [`Migration`]s.
+///
+/// This will:
+/// 1. Create the [`Metadata`] table if needed
+/// 2. Write the schema version & network if they are missing
+/// 3. Check the network & schema version are valid
+/// 4. Run migrations until the database is at [`Schema::version`]
+///
+/// Databases without a stored schema version were created before
+/// the [`Metadata`] table existed, they are assumed to be at version `0`
+/// unless `new_database` is `true`, in which case they are assumed to be
+/// at the latest version, as their tables were just created.
+///
+/// # Errors
+/// This will error if:
+/// - The database is for another network
+/// - The database's schema version is newer than [`Schema::version`]
+/// - There is no [`Migration`] for the database's schema version
+/// - A database error occurred, including inside a [`Migration`]
+#[cold]
+#[inline(never)] // only called once per database
+pub fn migrate(
+    env: &ConcreteEnv,
+    schema: &Schema<'_>,
+    new_database: bool,
+) -> Result<(), InitError> {
+    let (network, mut version) =
+        init_metadata(env, schema, new_database).map_err(runtime_to_init_error)?;
+
+    if network != schema.network {
+        return Err(InitError::NetworkMismatch {
+            found: network,
+            expected: schema.network.to_string(),
+        });
+    }
+
+    if version > schema.version {
+        return Err(InitError::UnsupportedVersion {
+            found: version,
+            supported: schema.version,
+        });
+    }
+
+    while version < schema.version {
+        let Some(migration) = schema
+            .migrations
+            .iter()
+            .find(|migration| migration.from_version == version)
+        else {
+            return Err(InitError::MissingMigration(version));
+        };
+
+        tracing::info!(
+            "Migrating database from schema version {version} to {}: {}",
+            version + 1,
+            migration.description
+        );
+
+        loop {
+            match migration_step(env, migration) {
+                Ok(MigrationStep::Continue(_)) => (),
+                Ok(MigrationStep::Done) => break,
+                Err(RuntimeError::ResizeNeeded) if ConcreteEnv::MANUAL_RESIZE => {
+                    env.resize_map(None);
+                }
+                Err(e) => return Err(runtime_to_init_error(e)),
+            }
+        }
+
+        version += 1;
+        tracing::info!("Migrated database to schema version {version}");
+    }
+
+    Ok(())
+}
+
+/// Check the database's [`Metadata`] matches `schema`, without writing to the database.
+///
+/// Unlike [`migrate`] nothing is created or migrated, this is for opening
+/// a database that must not be modified, e.g. by offline tools.
+///
+/// # Errors
+/// This will error if:
+/// - The database has no [`Metadata`]
+/// - The database is for another network
+/// - The database's schema version is not [`Schema::version`]
+/// - A database error occurred
+#[cold]
+#[inline(never)] // only called once per database
+pub fn check_schema(env: &ConcreteEnv, schema: &Schema<'_>) -> Result<(), InitError> {
+    let env_inner = env.env_inner();
+    let tx_ro = env_inner.tx_ro().map_err(runtime_to_init_error)?;
+
+    let (network, version) = {
+        let table = match env_inner.open_db_ro::<Metadata>(&tx_ro) {
+            Ok(table) => table,
+            Err(RuntimeError::TableNotFound) => return Err(InitError::Invalid),
+            Err(e) => return Err(runtime_to_init_error(e)),
+        };
+
+        let network = match table.get(&METADATA_NETWORK) {
+            Ok(network) => String::from_utf8_lossy(&network.0).into_owned(),
+            Err(RuntimeError::KeyNotFound) => return Err(InitError::Invalid),
+            Err(e) => return Err(runtime_to_init_error(e)),
+        };
+
+        let version = match schema_version(&table) {
+            Ok(version) => version,
+            Err(RuntimeError::KeyNotFound) => return Err(InitError::Invalid),
+            Err(e) => return Err(runtime_to_init_error(e)),
+        };
+
+        (network, version)
+    };
+
+    TxRo::commit(tx_ro).map_err(runtime_to_init_error)?;
+
+    if network != schema.network {
+        return Err(InitError::NetworkMismatch {
+            found: network,
+            expected: schema.network.to_string(),
+        });
+    }
+
+    if version > schema.version {
+        return Err(InitError::UnsupportedVersion {
+            found: version,
+            supported: schema.version,
+        });
+    }
+
+    if version < schema.version {
+        return Err(InitError::InvalidVersion);
+    }
+
+    Ok(())
+}
+
+/// Returns the schema version stored in the [`Metadata`] table.
+///
+/// # Errors
+/// This returns [`RuntimeError::KeyNotFound`] if
+/// [`migrate`] was never called on this database.
+pub fn schema_version(table_metadata: &impl DatabaseRo<Metadata>) -> DbResult<u64> {
+    Ok(read_u64(&table_metadata.get(&METADATA_VERSION)?))
+}
+
+/// Returns the network & schema version stored in the [`Metadata`] table.
+///
+/// Missing values are written first, unless the stored network does not
+/// match [`Schema::network`], in which case nothing is written.
+fn init_metadata(
+    env: &ConcreteEnv,
+    schema: &Schema<'_>,
+    new_database: bool,
+) -> DbResult<(String, u64)> {
+    let env_inner = env.env_inner();
+    let tx_rw = env_inner.tx_rw()?;
+    env_inner.create_db::<Metadata>(&tx_rw)?;
+
+    let (network, version) = {
+        let mut table = env_inner.open_db_rw::<Metadata>(&tx_rw)?;
+
+        let network = match table.get(&METADATA_NETWORK) {
+            Ok(network) => String::from_utf8_lossy(&network.0).into_owned(),
+            Err(RuntimeError::KeyNotFound) => {
+                table.put(
+                    &METADATA_NETWORK,
+                    &StorableVec(schema.network.as_bytes().to_vec()),
+                )?;
+                schema.network.to_string()
+            }
+            Err(e) => return Err(e),
+        };
+
+        let version = match table.get(&METADATA_VERSION) {
+            Ok(version) => read_u64(&version),
+            Err(RuntimeError::KeyNotFound) => {
+                let version = if new_database { schema.version } else { 0 };
+                table.put(&METADATA_VERSION, &write_u64(version))?;
+                version
+            }
+            Err(e) => return Err(e),
+        };
+
+        (network, version)
+    };
+
+    if network == schema.network {
+        TxRw::commit(tx_rw)?;
+    } else {
+        TxRw::abort(tx_rw)?;
+    }
+
+    Ok((network, version))
+}
+
+/// Run a single step of `migration`, committing its progress.
+fn migration_step(env: &ConcreteEnv, migration: &Migration) -> DbResult<MigrationStep> {
+    let env_inner = env.env_inner();
+    let mut tx_rw = env_inner.tx_rw()?;
+
+    let cursor = {
+        let table = env_inner.open_db_rw::<Metadata>(&tx_rw)?;
+        match table.get(&METADATA_MIGRATION_CURSOR) {
+            Ok(cursor) => read_u64(&cursor),
+            Err(RuntimeError::KeyNotFound) => 0,
+            Err(e) => return Err(e),
+        }
+    };
+
+    let step = match (migration.step)(&env_inner, &mut tx_rw, cursor) {
+        Ok(step) => step,
+        Err(e) => {
+            TxRw::abort(tx_rw)?;
+            return Err(e);
+        }
+    };
+
+    {
+        let mut table = env_inner.open_db_rw::<Metadata>(&tx_rw)?;
+        match step {
+            MigrationStep::Continue(cursor) => {
+                table.put(&METADATA_MIGRATION_CURSOR, &write_u64(cursor))?;
+            }
+            MigrationStep::Done => {
+                table.delete(&METADATA_MIGRATION_CURSOR)?;
+                table.put(&METADATA_VERSION, &write_u64(migration.from_version + 1))?;
+            }
+        }
+    }
+
+    TxRw::commit(tx_rw)?;
+    Ok(step)
+}
+
+/// Convert runtime errors that occurred while migrating to init errors.
+fn runtime_to_init_error(runtime: RuntimeError) -> InitError {
+    match runtime {
+        RuntimeError::Io(io_error) => io_error.into(),
+        e => InitError::Unknown(Box::new(e)),
+    }
+}
+
+/// Decode a [`Metadata`] integer value, stored as a little-endian [`u64`].
+///
+/// Missing trailing bytes are treated as `0`, extra bytes are ignored.
+pub fn read_u64(value: &StorableVec<u8>) -> u64 {
+    let mut bytes = [0; 8];
+    let len = value.0.len().min(8);
+    bytes[..len].copy_from_slice(&value.0[..len]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Encode a [`Metadata`] integer value as a little-endian [`u64`].
+pub fn write_u64(value: u64) -> StorableVec<u8> {
+    StorableVec(value.to_le_bytes().to_vec())
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tests::{tmp_concrete_env, TestTable};
+
+    /// Adds `1` to every value of [`TestTable`], one row per step.
+    fn increment_step(
+        env_inner: &ConcreteEnvInner<'_>,
+        tx_rw: &mut ConcreteTxRw<'_, '_>,
+        cursor: u64,
+    ) -> DbResult<MigrationStep> {
+        let mut table = env_inner.open_db_rw::<TestTable>(tx_rw)?;
+        let key = u32::try_from(cursor).unwrap();
+
+        match table.get(&key) {
+            Ok(value) => {
+                table.put(&key, &(value + 1))?;
+                Ok(MigrationStep::Continue(cursor + 1))
+            }
+            Err(RuntimeError::KeyNotFound) => Ok(MigrationStep::Done),
+            Err(e) => Err(e),
+        }
+    }
+
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            from_version: 1,
+            description: "increment again",
+            step: increment_step,
+        },
+        Migration {
+            from_version: 0,
+            description: "increment",
+            step: increment_step,
+        },
+    ];
+
+    const SCHEMA: Schema<'static> = Schema {
+        version: 2,
+        network: "mainnet",
+        migrations: MIGRATIONS,
+    };
+
+    /// Returns the stored schema version.
+    fn version(env: &ConcreteEnv) -> u64 {
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let table = env_inner.open_db_ro::<Metadata>(&tx_ro).unwrap();
+        schema_version(&table).unwrap()
+    }
+
+    /// Write `0..len` into [`TestTable`].
+    fn fill(env: &ConcreteEnv, len: u32) {
+        let env_inner = env.env_inner();
+        let tx_rw = env_inner.tx_rw().unwrap();
+        env_inner.create_db::<TestTable>(&tx_rw).unwrap();
+        {
+            let mut table = env_inner.open_db_rw::<TestTable>(&tx_rw).unwrap();
+            for i in 0..len {
+                table.put(&i, &u64::from(i)).unwrap();
+            }
+        }
+        TxRw::commit(tx_rw).unwrap();
+    }
+
+    /// New databases start at the latest version and don't run migrations.
+    #[test]
+    fn new_database() {
+        let (env, _tempdir) = tmp_concrete_env();
+        fill(&env, 4);
+
+        migrate(&env, &SCHEMA, true).unwrap();
+        assert_eq!(version(&env), 2);
+
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let table = env_inner.open_db_ro::<TestTable>(&tx_ro).unwrap();
+        assert_eq!(table.get(&3).unwrap(), 3);
+    }
+
+    /// Old databases run all migrations in order.
+    #[test]
+    fn old_database() {
+        let (env, _tempdir) = tmp_concrete_env();
+        fill(&env, 4);
+
+        migrate(&env, &SCHEMA, false).unwrap();
+        assert_eq!(version(&env), 2);
+
+        {
+            let env_inner = env.env_inner();
+            let tx_ro = env_inner.tx_ro().unwrap();
+            let table = env_inner.open_db_ro::<TestTable>(&tx_ro).unwrap();
+            for i in 0..4 {
+                assert_eq!(table.get(&i).unwrap(), u64::from(i) + 2);
+            }
+
+            let metadata = env_inner.open_db_ro::<Metadata>(&tx_ro).unwrap();
+            assert!(!metadata.contains(&METADATA_MIGRATION_CURSOR).unwrap());
+        }
+
+        // Migrating again is a no-op.
+        migrate(&env, &SCHEMA, false).unwrap();
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let table = env_inner.open_db_ro::<TestTable>(&tx_ro).unwrap();
+        assert_eq!(table.get(&0).unwrap(), 2);
+    }
+
+    /// An interrupted migration resumes from its stored cursor.
+    #[test]
+    fn resume() {
+        let (env, _tempdir) = tmp_concrete_env();
+        fill(&env, 4);
+
+        // Pretend the first 2 rows were migrated before a crash.
+        {
+            let env_inner = env.env_inner();
+            let tx_rw = env_inner.tx_rw().unwrap();
+            env_inner.create_db::<Metadata>(&tx_rw).unwrap();
+            {
+                let mut table = env_inner.open_db_rw::<Metadata>(&tx_rw).unwrap();
+                table.put(&METADATA_VERSION, &write_u64(1)).unwrap();
+                table
+                    .put(&METADATA_NETWORK, &StorableVec(b"mainnet".to_vec()))
+                    .unwrap();
+                table
+                    .put(&METADATA_MIGRATION_CURSOR, &write_u64(2))
+                    .unwrap();
+            }
+            TxRw::commit(tx_rw).unwrap();
+        }
+
+        migrate(&env, &SCHEMA, false).unwrap();
+        assert_eq!(version(&env), 2);
+
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().unwrap();
+        let table = env_inner.open_db_ro::<TestTable>(&tx_ro).unwrap();
+        assert_eq!(table.get(&0).unwrap(), 0);
+        assert_eq!(table.get(&1).unwrap(), 1);
+        assert_eq!(table.get(&2).unwrap(), 3);
+        assert_eq!(table.get(&3).unwrap(), 4);
+    }
+
+    /// Databases for other networks are refused.
+    #[test]
+    fn network_mismatch() {
+        let (env, _tempdir) = tmp_concrete_env();
+        migrate(&env, &SCHEMA, true).unwrap();
+
+        let testnet = Schema {
+            network: "testnet",
+            ..SCHEMA
+        };
+
+        let error = migrate(&env, &testnet, true).unwrap_err();
+        assert!(
+            matches!(
+                &error,
+                InitError::NetworkMismatch { found, expected }
+                    if found == "mainnet" && expected == "testnet"
+            ),
+            "{error:?}"
+        );
+    }
+
+    /// Databases from a newer version are refused,
+    /// as are versions with no migration.
+    #[test]
+    fn invalid_version() {
+        let (env, _tempdir) = tmp_concrete_env();
+        migrate(&env, &SCHEMA, true).unwrap();
+
+        let old = Schema {
+            version: 1,
+            ..SCHEMA
+        };
+        assert!(matches!(
+            migrate(&env, &old, false),
+            Err(InitError::UnsupportedVersion {
+                found: 2,
+                supported: 1
+            })
+        ));
+
+        let newer = Schema {
+            version: 3,
+            ..SCHEMA
+        };
+        assert!(matches!(
+            migrate(&env, &newer, false),
+            Err(InitError::MissingMigration(2))
+        ));
+    }
+
+    /// Checking the schema never writes to the database.
+    #[test]
+    fn check() {
+        let (env, _tempdir) = tmp_concrete_env();
+        fill(&env, 4);
+        assert!(matches!(
+            check_schema(&env, &SCHEMA),
+            Err(InitError::Invalid)
+        ));
+
+        migrate(&env, &SCHEMA, true).unwrap();
+        check_schema(&env, &SCHEMA).unwrap();
+
+        let testnet = Schema {
+            network: "testnet",
+            ..SCHEMA
+        };
+        assert!(matches!(
+            check_schema(&env, &testnet),
+            Err(InitError::NetworkMismatch { .. })
+        ));
+
+        let old = Schema {
+            version: 1,
+            ..SCHEMA
+        };
+        assert!(matches!(
+            check_schema(&env, &old),
+            Err(InitError::UnsupportedVersion { .. })
+        ));
+
+        let newer = Schema {
+            version: 3,
+            ..SCHEMA
+        };
+        assert!(matches!(
+            check_schema(&env, &newer),
+            Err(InitError::InvalidVersion)
+        ));
+        assert_eq!(version(&env), 2);
+    }
+}
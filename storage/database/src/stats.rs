@@ -0,0 +1,44 @@
+//! Database statistics.
+//!
+//! [`Env::stats`](crate::Env::stats) reports the size of the whole database and
+//! [`EnvInner::table_stats`](crate::EnvInner::table_stats) reports the size of a single table.
+//!
+//! The numbers come from the backend, `mdb_stat` for `heed` and
+//! `redb`'s table/database stats. A backend not having an equivalent
+//! value reports `0`, e.g. `redb` has no overflow pages.
+
+//---------------------------------------------------------------------------------------------------- TableStats
+/// Statistics of a single database table.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TableStats {
+    /// The name of the table.
+    pub table: &'static str,
+    /// The amount of `(key, value)` entries in the table.
+    pub entries: u64,
+    /// The depth (height) of the table's B-tree.
+    pub depth: u32,
+    /// The amount of internal (non-leaf) pages.
+    pub branch_pages: u64,
+    /// The amount of leaf pages.
+    pub leaf_pages: u64,
+    /// The amount of overflow pages, used for values that do not fit in a single page.
+    pub overflow_pages: u64,
+    /// The amount of bytes used by the table, including all of its pages.
+    pub size_bytes: u64,
+}
+
+//---------------------------------------------------------------------------------------------------- EnvStats
+/// Statistics of a whole database environment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EnvStats {
+    /// The size of the memory map in bytes.
+    ///
+    /// This is [`None`] if the backend in use has no memory map to resize, i.e. it is
+    /// [`Some`] for [`Backend::Heed`](crate::config::Backend::Heed) and
+    /// [`None`] for [`Backend::Redb`](crate::config::Backend::Redb).
+    pub map_size: Option<u64>,
+    /// The amount of bytes in use by the database.
+    pub used_bytes: u64,
+    /// The amount of bytes in the database file that are free and will be reused by new data.
+    pub free_bytes: u64,
+}
@@ -120,6 +120,8 @@
 /// 1. Creates a `pub trait TablesMut` trait (in scope)
 /// 1. Blanket implements a `(tuples, containing, all, open, database, tables, ...)` for the above traits
 /// 1. Creates a `pub trait OpenTables` trait (in scope)
+/// 1. Creates `pub fn copy_tables()` and `pub fn verify_tables()` (in scope)
+/// 1. Creates `pub fn table_stats()` (in scope)
 ///
 /// All table types are zero-sized structs that implement the `Table` trait.
 ///
@@ -419,6 +421,63 @@ macro_rules! define_tables {
                 }
             }
         }
+
+        /// Copy all tables from `src` into `dst`.
+        ///
+        /// This calls [`cuprate_database::copy::copy_table`] on all database tables in order,
+        /// `progress` is called with the table name, the amount of copied rows and the total
+        /// amount of rows of the table being copied.
+        ///
+        /// All tables must already be created in both databases.
+        ///
+        /// # Errors
+        /// This returns the first error returned by [`cuprate_database::copy::copy_table`].
+        pub fn copy_tables(
+            src: &impl $crate::Env,
+            dst: &impl $crate::Env,
+            batch_size: std::num::NonZeroUsize,
+            mut progress: impl FnMut(&'static str, u64, u64),
+        ) -> Result<Vec<$crate::copy::TableCopy>, $crate::CopyError> {
+            Ok(vec![$(
+                $crate::copy::copy_table::<[<$table:camel>]>(src, dst, batch_size, |copied, rows| {
+                    progress(<[<$table:camel>] as $crate::Table>::NAME, copied, rows);
+                })?,
+            )*])
+        }
+
+        /// Verify all tables in `dst` are an exact copy of the tables in `src`.
+        ///
+        /// This calls [`cuprate_database::copy::verify_table`] on all database tables.
+        ///
+        /// # Errors
+        /// This returns the first error returned by [`cuprate_database::copy::verify_table`].
+        pub fn verify_tables(
+            src: &impl $crate::Env,
+            dst: &impl $crate::Env,
+        ) -> Result<(), $crate::CopyError> {
+            $(
+                $crate::copy::verify_table::<[<$table:camel>]>(src, dst)?;
+            )*
+
+            Ok(())
+        }
+
+        /// Return the [`cuprate_database::stats::TableStats`] of all tables in `env`, in order.
+        ///
+        /// All tables are read in the same read-only transaction.
+        ///
+        /// # Errors
+        /// This returns the first error returned by [`cuprate_database::EnvInner::table_stats`].
+        pub fn table_stats(
+            env: &impl $crate::Env,
+        ) -> $crate::DbResult<Vec<$crate::stats::TableStats>> {
+            let env_inner = env.env_inner();
+            let tx_ro = $crate::EnvInner::tx_ro(&env_inner)?;
+
+            Ok(vec![$(
+                $crate::EnvInner::table_stats::<[<$table:camel>]>(&env_inner, &tx_ro)?,
+            )*])
+        }
     }};
 }
 
@@ -7,7 +7,11 @@
 //---------------------------------------------------------------------------------------------------- Import
 use std::borrow::Cow;
 
-use crate::{config::ConfigBuilder, table::Table, ConcreteEnv, Env};
+use crate::{
+    config::{Backend, ConfigBuilder},
+    table::Table,
+    ConcreteEnv, Env,
+};
 
 //---------------------------------------------------------------------------------------------------- struct
 /// A test table.
@@ -23,11 +27,19 @@ impl Table for TestTable {
 /// Create an `Env` in a temporarily directory.
 /// The directory is automatically removed after the `TempDir` is dropped.
 ///
+/// This uses the default [`Backend`], see [`tmp_concrete_env_with`].
+///
 /// FIXME: changing this to `-> impl Env` causes lifetime errors...
 pub(crate) fn tmp_concrete_env() -> (ConcreteEnv, tempfile::TempDir) {
+    tmp_concrete_env_with(Backend::default())
+}
+
+/// Same as [`tmp_concrete_env`], but with a specific [`Backend`].
+pub(crate) fn tmp_concrete_env_with(backend: Backend) -> (ConcreteEnv, tempfile::TempDir) {
     let tempdir = tempfile::tempdir().unwrap();
     let config = ConfigBuilder::new(Cow::Owned(tempdir.path().into()))
         .low_power()
+        .backend(backend)
         .build();
     let env = ConcreteEnv::open(config).unwrap();
 
@@ -95,6 +95,14 @@ fn database_writer<Req, Res>(
     Req: Send + 'static,
     Res: Debug + Send + 'static,
 {
+    // Whether the backend in use needs manual resizes.
+    let manual_resize = env.manual_resize();
+
+    // How many times should we retry handling the request on resize errors?
+    //
+    // This is 1 on automatically resizing databases, meaning there is only 1 iteration.
+    let request_retry_limit = if manual_resize { 3 } else { 1 };
+
     // 1. Hang on request channel
     // 2. Map request to some database function
     // 3. Execute that function, get the result
@@ -112,11 +120,6 @@ fn database_writer<Req, Res>(
             return;
         };
 
-        /// How many times should we retry handling the request on resize errors?
-        ///
-        /// This is 1 on automatically resizing databases, meaning there is only 1 iteration.
-        const REQUEST_RETRY_LIMIT: usize = if ConcreteEnv::MANUAL_RESIZE { 3 } else { 1 };
-
         // Map [`Request`]'s to specific database functions.
         //
         // Both will:
@@ -129,18 +132,18 @@ fn database_writer<Req, Res>(
         // FIXME: there's probably a more elegant way
         // to represent this retry logic with recursive
         // functions instead of a loop.
-        'retry: for retry in 0..REQUEST_RETRY_LIMIT {
+        'retry: for retry in 0..request_retry_limit {
             // FIXME: will there be more than 1 write request?
             // this won't have to be an enum.
             let response = inner_handler(env, &request);
 
             // If the database needs to resize, do so.
-            if ConcreteEnv::MANUAL_RESIZE && matches!(response, Err(RuntimeError::ResizeNeeded)) {
+            if manual_resize && matches!(response, Err(RuntimeError::ResizeNeeded)) {
                 // If this is the last iteration of the outer `for` loop and we
                 // encounter a resize error _again_, it means something is wrong.
                 assert_ne!(
-                    retry, REQUEST_RETRY_LIMIT,
-                    "database resize failed maximum of {REQUEST_RETRY_LIMIT} times"
+                    retry, request_retry_limit,
+                    "database resize failed maximum of {request_retry_limit} times"
                 );
 
                 // Resize the map, and retry the request handling loop.
@@ -168,7 +171,7 @@ fn database_writer<Req, Res>(
 
             // Automatically resizing databases should not be returning a resize error.
             #[cfg(debug_assertions)]
-            if !ConcreteEnv::MANUAL_RESIZE {
+            if !manual_resize {
                 assert!(
                     !matches!(response, Err(RuntimeError::ResizeNeeded)),
                     "auto-resizing database returned a ResizeNeeded error"
@@ -5,7 +5,7 @@ use std::{borrow::Cow, path::PathBuf};
 use serde::{Deserialize, Serialize};
 
 use cuprate_database::{
-    config::{Config as DbConfig, SyncMode},
+    config::{Backend, Config as DbConfig, SyncMode},
     resize::ResizeAlgorithm,
 };
 use cuprate_database_service::ReaderThreads;
@@ -83,6 +83,7 @@ impl ConfigBuilder {
             .build();
 
         Config {
+            network: self.network,
             db_config,
             reader_threads,
             max_txpool_weight,
@@ -110,6 +111,13 @@ impl ConfigBuilder {
         self
     }
 
+    /// Calls [`cuprate_database::config::ConfigBuilder::backend`].
+    #[must_use]
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.db_config = self.db_config.backend(backend);
+        self
+    }
+
     /// Calls [`cuprate_database::config::ConfigBuilder::sync_mode`].
     #[must_use]
     pub fn sync_mode(mut self, sync_mode: SyncMode) -> Self {
@@ -181,6 +189,9 @@ impl Default for ConfigBuilder {
 /// For construction, either use [`ConfigBuilder`] or [`Config::default`].
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Config {
+    /// The network this database is for.
+    pub network: Network,
+
     /// The database configuration.
     pub db_config: DbConfig,
 
@@ -221,6 +232,7 @@ impl Config {
     /// assert_eq!(config.db_config.sync_mode, SyncMode::default());
     /// assert_eq!(config.db_config.resize_algorithm, ResizeAlgorithm::default());
     /// assert_eq!(config.reader_threads, ReaderThreads::default());
+    /// assert_eq!(config.network, Network::Mainnet);
     /// ```
     pub fn new() -> Self {
         ConfigBuilder::new().build()
@@ -1,9 +1,17 @@
 //! General free functions (related to the tx-pool database).
 
 //---------------------------------------------------------------------------------------------------- Import
-use cuprate_database::{ConcreteEnv, Env, EnvInner, InitError, RuntimeError, TxRw};
+use cuprate_database::{
+    migration::{migrate, Schema},
+    ConcreteEnv, DatabaseRo, Env, EnvInner, InitError, RuntimeError, TxRw,
+};
 
-use crate::{config::Config, tables::OpenTables, types::TransactionBlobHash};
+use crate::{
+    config::Config,
+    migration::{DATABASE_VERSION, MIGRATIONS},
+    tables::{OpenTables, TransactionBlobs},
+    types::TransactionBlobHash,
+};
 
 //---------------------------------------------------------------------------------------------------- Free functions
 /// Open the txpool database using the passed [`Config`].
@@ -15,35 +23,67 @@ use crate::{config::Config, tables::OpenTables, types::TransactionBlobHash};
 /// All tables found in [`crate::tables`] will be
 /// ready for usage in the returned [`ConcreteEnv`].
 ///
+/// The database's network & schema version are checked and
+/// any needed migrations are run, see [`cuprate_database::migration`].
+///
 /// # Errors
 /// This will error if:
 /// - The database file could not be opened
 /// - A write transaction could not be opened
 /// - A table could not be created/opened
+/// - The database is for another network than [`Config::network`]
+/// - The database's schema version is not supported
+/// - A migration failed
 #[cold]
 #[inline(never)] // only called once
 pub fn open(config: Config) -> Result<ConcreteEnv, InitError> {
-    // Attempt to open the database environment.
-    let env = <ConcreteEnv as Env>::open(config.db_config)?;
+    let network = config.network.to_string();
 
-    /// Convert runtime errors to init errors.
-    ///
-    /// INVARIANT:
-    /// [`cuprate_database`]'s functions mostly return the former
-    /// so we must convert them. We have knowledge of which errors
-    /// makes sense in this functions context so we panic on
-    /// unexpected ones.
-    fn runtime_to_init_error(runtime: RuntimeError) -> InitError {
-        match runtime {
-            RuntimeError::Io(io_error) => io_error.into(),
+    let env = open_copy_destination(config)?;
 
-            // These errors shouldn't be happening here.
-            RuntimeError::KeyExists
-            | RuntimeError::KeyNotFound
-            | RuntimeError::ResizeNeeded
-            | RuntimeError::TableNotFound => unreachable!(),
-        }
-    }
+    // An empty tx-pool has nothing to migrate.
+    let new_database = {
+        let env_inner = env.env_inner();
+        let tx_ro = env_inner.tx_ro().map_err(runtime_to_init_error)?;
+        env_inner
+            .open_db_ro::<TransactionBlobs>(&tx_ro)
+            .and_then(|table| table.is_empty())
+            .map_err(runtime_to_init_error)?
+    };
+
+    migrate(
+        &env,
+        &Schema {
+            version: DATABASE_VERSION,
+            network: &network,
+            migrations: MIGRATIONS,
+        },
+        new_database,
+    )?;
+
+    Ok(env)
+}
+
+/// Open the txpool database using the passed [`Config`], to copy another txpool database into.
+///
+/// Unlike [`open`], only the tables are created: the database is not stamped with
+/// a network or schema version and no migrations are run, these are copied from
+/// the source database with the rest of its tables.
+///
+/// This keeps every row in the database a row copied from the source, which
+/// [`cuprate_database::copy::copy_table`] relies on to resume an interrupted copy,
+/// so [`open`] must not be called on the database until the copy has finished.
+///
+/// # Errors
+/// This will error if:
+/// - The database file could not be opened
+/// - A write transaction could not be opened
+/// - A table could not be created/opened
+#[cold]
+#[inline(never)] // only called once
+pub fn open_copy_destination(config: Config) -> Result<ConcreteEnv, InitError> {
+    // Attempt to open the database environment.
+    let env = <ConcreteEnv as Env>::open(config.db_config)?;
 
     // INVARIANT: We must ensure that all tables are created,
     // `cuprate_database` has no way of knowing _which_ tables
@@ -61,6 +101,25 @@ pub fn open(config: Config) -> Result<ConcreteEnv, InitError> {
     Ok(env)
 }
 
+/// Convert runtime errors to init errors.
+///
+/// INVARIANT:
+/// [`cuprate_database`]'s functions mostly return the former
+/// so we must convert them. We have knowledge of which errors
+/// makes sense in the open functions' context so we panic on
+/// unexpected ones.
+fn runtime_to_init_error(runtime: RuntimeError) -> InitError {
+    match runtime {
+        RuntimeError::Io(io_error) => io_error.into(),
+
+        // These errors shouldn't be happening here.
+        RuntimeError::KeyExists
+        | RuntimeError::KeyNotFound
+        | RuntimeError::ResizeNeeded
+        | RuntimeError::TableNotFound => unreachable!(),
+    }
+}
+
 /// Calculate the transaction blob hash.
 ///
 /// This value is supposed to be quick to compute just based of the tx-blob without needing to parse the tx.
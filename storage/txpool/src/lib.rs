@@ -9,6 +9,7 @@ use tower as _;
 
 pub mod config;
 mod free;
+mod migration;
 pub mod ops;
 pub mod service;
 pub mod tables;
@@ -16,7 +17,8 @@ mod tx;
 pub mod types;
 
 pub use config::Config;
-pub use free::{open, transaction_blob_hash};
+pub use free::{open, open_copy_destination, transaction_blob_hash};
+pub use migration::DATABASE_VERSION;
 pub use tx::TxEntry;
 
 //re-exports
@@ -24,8 +26,5 @@ pub use cuprate_database;
 
 #[cfg(test)]
 mod test {
-    use cuprate_test_utils as _;
     use hex_literal as _;
-    use tempfile as _;
-    use tokio as _;
 }
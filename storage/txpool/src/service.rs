@@ -94,6 +94,7 @@
 //! let request = TxpoolWriteRequest::AddTransaction {
 //!     tx: Box::new(tx.try_into().unwrap()),
 //!     state_stem: false,
+//!     chain_top_hash: [0; 32],
 //! };
 //!
 //! // Send the request.
@@ -128,5 +129,8 @@ mod read;
 mod types;
 mod write;
 
+#[cfg(test)]
+mod tests;
+
 pub use free::{init, init_with_pool};
 pub use types::{TxpoolReadHandle, TxpoolWriteHandle};
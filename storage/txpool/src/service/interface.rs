@@ -40,6 +40,8 @@ pub enum TxpoolReadRequest {
     TxsForBlock(Vec<TransactionHash>),
 
     /// Get information on all transactions in the pool.
+    ///
+    /// Transactions in the stem pool are not included.
     Backlog,
 
     /// Get the number of transactions in the pool.
@@ -168,6 +170,11 @@ pub enum TxpoolWriteRequest {
         ///
         /// [`true`] if this tx is in the stem state.
         state_stem: bool,
+        /// The hash of the top block of the chain the tx was verified against.
+        ///
+        /// If this does not match the top block hash given in the last [`TxpoolWriteRequest::NewBlock`]
+        /// the tx will not be added and [`TxpoolWriteResponse::ChainTipChanged`] will be returned.
+        chain_top_hash: [u8; 32],
     },
 
     /// Remove a transaction with the given hash from the pool.
@@ -178,7 +185,13 @@ pub enum TxpoolWriteRequest {
     Promote(TransactionHash),
 
     /// Tell the tx-pool about a new block.
+    ///
+    /// This can also be used to tell the tx-pool about a batch of new blocks, in which case
+    /// `top_hash` should be the hash of the last block and `spent_key_images` should contain
+    /// the spent key images from every block.
     NewBlock {
+        /// The hash of the new top block of the chain.
+        top_hash: [u8; 32],
         /// The spent key images in the new block.
         spent_key_images: Vec<KeyImage>,
     },
@@ -198,4 +211,10 @@ pub enum TxpoolWriteResponse {
     ///
     /// If the inner value is [`Some`] the tx was not added to the pool as it double spends a tx with the given hash.
     AddTransaction(Option<TransactionHash>),
+
+    /// Response to [`TxpoolWriteRequest::AddTransaction`].
+    ///
+    /// The tx was not added to the pool as it was verified against a chain tip that is no longer
+    /// the top of the chain. The tx must be verified again against the new chain tip.
+    ChainTipChanged,
 }
@@ -13,8 +13,11 @@ use std::{
 
 use rayon::ThreadPool;
 
-use cuprate_database::{ConcreteEnv, DatabaseRo, DbResult, Env, EnvInner, RuntimeError};
+use cuprate_database::{
+    ConcreteEnv, DatabaseIter, DatabaseRo, DbResult, Env, EnvInner, RuntimeError,
+};
 use cuprate_database_service::{init_thread_pool, DatabaseReadService, ReaderThreads};
+use cuprate_helper::cast::usize_to_u64;
 
 use crate::{
     ops::{get_transaction_verification_data, in_stem_pool},
@@ -23,7 +26,8 @@ use crate::{
         types::{ReadResponseResult, TxpoolReadHandle},
     },
     tables::{KnownBlobHashes, OpenTables, TransactionBlobs, TransactionInfos},
-    types::{TransactionBlobHash, TransactionHash},
+    types::{TransactionBlobHash, TransactionHash, TxStateFlags},
+    TxEntry,
 };
 
 // TODO: update the docs here
@@ -229,7 +233,28 @@ fn txs_for_block(env: &ConcreteEnv, txs: Vec<TransactionHash>) -> ReadResponseRe
 /// [`TxpoolReadRequest::Backlog`].
 #[inline]
 fn backlog(env: &ConcreteEnv) -> ReadResponseResult {
-    Ok(TxpoolReadResponse::Backlog(todo!()))
+    let inner_env = env.env_inner();
+    let tx_ro = inner_env.tx_ro()?;
+
+    let tx_infos_table = inner_env.open_db_ro::<TransactionInfos>(&tx_ro)?;
+
+    let backlog = tx_infos_table
+        .iter()?
+        .filter_map(|res| match res {
+            // Txs in the stem pool are private, they should not be included.
+            Ok((_, tx_info)) if tx_info.flags.contains(TxStateFlags::STATE_STEM) => None,
+            Ok((id, tx_info)) => Some(Ok(TxEntry {
+                id,
+                weight: usize_to_u64(tx_info.weight),
+                fee: tx_info.fee,
+                // TODO: the time a tx was received is not stored in the database.
+                time_in_pool: std::time::Duration::ZERO,
+            })),
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<DbResult<_>>()?;
+
+    Ok(TxpoolReadResponse::Backlog(backlog))
 }
 
 /// [`TxpoolReadRequest::Size`].
@@ -0,0 +1,207 @@
+//! `crate::service` tests.
+//!
+//! This module contains general tests for the `service` implementation.
+
+//---------------------------------------------------------------------------------------------------- Use
+use monero_serai::transaction::Input;
+use tower::{Service, ServiceExt};
+
+use cuprate_test_utils::data::{TX_V1_SIG0, TX_V1_SIG2};
+use cuprate_types::TransactionVerificationData;
+
+use cuprate_database::config::Backend;
+
+use crate::{
+    config::ConfigBuilder,
+    service::{
+        init,
+        interface::{
+            TxpoolReadRequest, TxpoolReadResponse, TxpoolWriteRequest, TxpoolWriteResponse,
+        },
+        TxpoolReadHandle, TxpoolWriteHandle,
+    },
+};
+
+//---------------------------------------------------------------------------------------------------- Helper functions
+/// Initialize the `service`.
+fn init_service(backend: Backend) -> (TxpoolReadHandle, TxpoolWriteHandle, tempfile::TempDir) {
+    let tempdir = tempfile::tempdir().unwrap();
+    let config = ConfigBuilder::new()
+        .data_directory(tempdir.path().into())
+        .backend(backend)
+        .build();
+    let (reader, writer, _) = init(config).unwrap();
+    (reader, writer, tempdir)
+}
+
+/// Send a [`TxpoolWriteRequest`] and return the response.
+async fn write(
+    write_handle: &mut TxpoolWriteHandle,
+    request: TxpoolWriteRequest,
+) -> TxpoolWriteResponse {
+    write_handle
+        .ready()
+        .await
+        .unwrap()
+        .call(request)
+        .await
+        .unwrap()
+}
+
+/// Returns [`true`] if the tx with the given hash is in the pool.
+async fn in_pool(read_handle: &mut TxpoolReadHandle, tx_hash: [u8; 32]) -> bool {
+    read_handle
+        .ready()
+        .await
+        .unwrap()
+        .call(TxpoolReadRequest::TxBlob(tx_hash))
+        .await
+        .is_ok()
+}
+
+/// Returns the key images spent in a tx.
+fn key_images(tx: &TransactionVerificationData) -> Vec<[u8; 32]> {
+    tx.tx
+        .prefix()
+        .inputs
+        .iter()
+        .map(|input| match input {
+            Input::ToKey { key_image, .. } => key_image.0,
+            Input::Gen(_) => unreachable!(),
+        })
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------- Tests
+/// A tx verified against an old chain tip must not be added after the tx-pool was told about a new block.
+///
+/// This is the interleaving from <https://github.com/Cuprate/cuprate/issues/314>:
+/// 1. a tx is verified against chain tip `A`
+/// 2. a block `B` spending the tx's key images is added
+/// 3. the tx is added to the pool
+async fn tx_verified_against_old_chain_tip_is_rejected(backend: Backend) {
+    let (mut read_handle, mut write_handle, _tempdir) = init_service(backend);
+
+    let tip_a = [1; 32];
+    let tip_b = [2; 32];
+
+    let tx: TransactionVerificationData = TX_V1_SIG2.clone().try_into().unwrap();
+
+    write(
+        &mut write_handle,
+        TxpoolWriteRequest::NewBlock {
+            top_hash: tip_a,
+            spent_key_images: vec![],
+        },
+    )
+    .await;
+
+    // 2. The block spending the key images is added before the tx reaches the pool.
+    write(
+        &mut write_handle,
+        TxpoolWriteRequest::NewBlock {
+            top_hash: tip_b,
+            spent_key_images: key_images(&tx),
+        },
+    )
+    .await;
+
+    // 3. The tx verified against `A` is rejected.
+    let response = write(
+        &mut write_handle,
+        TxpoolWriteRequest::AddTransaction {
+            tx: Box::new(tx.clone()),
+            state_stem: false,
+            chain_top_hash: tip_a,
+        },
+    )
+    .await;
+
+    assert_eq!(response, TxpoolWriteResponse::ChainTipChanged);
+    assert!(!in_pool(&mut read_handle, tx.tx_hash).await);
+}
+
+/// A tx added before the tx-pool is told about a block spending its key images must be removed by that block.
+async fn tx_added_before_new_block_is_removed(backend: Backend) {
+    let (mut read_handle, mut write_handle, _tempdir) = init_service(backend);
+
+    let tip_a = [1; 32];
+    let tip_b = [2; 32];
+
+    let tx: TransactionVerificationData = TX_V1_SIG2.clone().try_into().unwrap();
+    let other_tx: TransactionVerificationData = TX_V1_SIG0.clone().try_into().unwrap();
+
+    for tx in [&tx, &other_tx] {
+        let response = write(
+            &mut write_handle,
+            TxpoolWriteRequest::AddTransaction {
+                tx: Box::new(tx.clone()),
+                state_stem: false,
+                chain_top_hash: tip_a,
+            },
+        )
+        .await;
+
+        assert_eq!(response, TxpoolWriteResponse::AddTransaction(None));
+    }
+
+    write(
+        &mut write_handle,
+        TxpoolWriteRequest::NewBlock {
+            top_hash: tip_b,
+            spent_key_images: key_images(&tx),
+        },
+    )
+    .await;
+
+    assert!(!in_pool(&mut read_handle, tx.tx_hash).await);
+    assert!(in_pool(&mut read_handle, other_tx.tx_hash).await);
+
+    // A tx verified against the new chain tip is added.
+    write(
+        &mut write_handle,
+        TxpoolWriteRequest::RemoveTransaction(other_tx.tx_hash),
+    )
+    .await;
+
+    let response = write(
+        &mut write_handle,
+        TxpoolWriteRequest::AddTransaction {
+            tx: Box::new(other_tx.clone()),
+            state_stem: false,
+            chain_top_hash: tip_b,
+        },
+    )
+    .await;
+
+    assert_eq!(response, TxpoolWriteResponse::AddTransaction(None));
+    assert!(in_pool(&mut read_handle, other_tx.tx_hash).await);
+}
+
+/// Generate a `#[tokio::test]` per [`Backend`] for each async test function.
+macro_rules! backend_tests {
+    ($( $test:ident ),* $(,)?) => {
+        mod heed {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(super::Backend::Heed).await;
+                }
+            )*
+        }
+
+        mod redb {
+            $(
+                #[tokio::test]
+                async fn $test() {
+                    super::$test(super::Backend::Redb).await;
+                }
+            )*
+        }
+    };
+}
+
+backend_tests! {
+    tx_verified_against_old_chain_tip_is_rejected,
+    tx_added_before_new_block_is_removed,
+}
@@ -1,4 +1,4 @@
-use std::sync::Arc;
+use std::{cell::Cell, sync::Arc};
 
 use cuprate_database::{
     ConcreteEnv, DatabaseRo, DatabaseRw, DbResult, Env, EnvInner, RuntimeError, TxRw,
@@ -19,7 +19,15 @@ use crate::{
 //---------------------------------------------------------------------------------------------------- init_write_service
 /// Initialize the txpool write service from a [`ConcreteEnv`].
 pub(super) fn init_write_service(env: Arc<ConcreteEnv>) -> TxpoolWriteHandle {
-    DatabaseWriteHandle::init(env, handle_txpool_request)
+    // The top block hash given in the last [`TxpoolWriteRequest::NewBlock`].
+    //
+    // This is only accessed from the single writer thread, so all reads/writes of this
+    // happen in the same order as the requests are handled.
+    let chain_top_hash = Cell::new(None);
+
+    DatabaseWriteHandle::init(env, move |env, req| {
+        handle_txpool_request(env, req, &chain_top_hash)
+    })
 }
 
 //---------------------------------------------------------------------------------------------------- handle_txpool_request
@@ -27,14 +35,35 @@ pub(super) fn init_write_service(env: Arc<ConcreteEnv>) -> TxpoolWriteHandle {
 fn handle_txpool_request(
     env: &ConcreteEnv,
     req: &TxpoolWriteRequest,
+    chain_top_hash: &Cell<Option<[u8; 32]>>,
 ) -> DbResult<TxpoolWriteResponse> {
     match req {
-        TxpoolWriteRequest::AddTransaction { tx, state_stem } => {
+        TxpoolWriteRequest::AddTransaction {
+            tx,
+            state_stem,
+            chain_top_hash: tx_chain_top_hash,
+        } => {
+            // If we have been told about a block the tx was not verified against, the tx could
+            // spend key images that are now spent in the chain: <https://github.com/Cuprate/cuprate/issues/314>.
+            if chain_top_hash
+                .get()
+                .is_some_and(|top_hash| top_hash != *tx_chain_top_hash)
+            {
+                return Ok(TxpoolWriteResponse::ChainTipChanged);
+            }
+
             add_transaction(env, tx, *state_stem)
         }
         TxpoolWriteRequest::RemoveTransaction(tx_hash) => remove_transaction(env, tx_hash),
         TxpoolWriteRequest::Promote(tx_hash) => promote(env, tx_hash),
-        TxpoolWriteRequest::NewBlock { spent_key_images } => new_block(env, spent_key_images),
+        TxpoolWriteRequest::NewBlock {
+            top_hash,
+            spent_key_images,
+        } => {
+            let res = new_block(env, spent_key_images)?;
+            chain_top_hash.set(Some(*top_hash));
+            Ok(res)
+        }
     }
 }
 
@@ -18,7 +18,8 @@ use crate::{
         OutputHistogramInput,
     },
     types::{Chain, ExtendedBlockHeader, OutputOnChain, TxsInBlock, VerifiedBlockInformation},
-    AltBlockInformation, BlockCompleteEntry, ChainId, OutputDistributionInput, TxInBlockchain,
+    AltBlockInformation, BlockCompleteEntry, ChainId, DatabaseStats, KeyImageSpend,
+    OutputDistributionInput, TxInBlockchain,
 };
 
 //---------------------------------------------------------------------------------------------------- ReadRequest
@@ -131,6 +132,16 @@ pub enum BlockchainReadRequest {
     /// The response will be in the same order as the request.
     KeyImagesSpentVec(Vec<[u8; 32]>),
 
+    /// Request where key images were spent, using the optional key image index.
+    ///
+    /// The response will be in the same order as the request.
+    KeyImageSpends(Vec<[u8; 32]>),
+
+    /// Request the amount and amount index of an output, using the optional output key index.
+    ///
+    /// The input is the output's one-time public key.
+    OutputByKey([u8; 32]),
+
     /// A request for the compact chain history.
     CompactChainHistory,
 
@@ -177,6 +188,9 @@ pub enum BlockchainReadRequest {
     /// Get the current size of the database.
     DatabaseSize,
 
+    /// Get the statistics of the database and each of its tables.
+    DatabaseStats,
+
     /// Get an output histogram.
     ///
     /// TODO: document fields after impl.
@@ -247,6 +261,25 @@ pub enum BlockchainWriteRequest {
 
     /// A request to flush all alternative blocks.
     FlushAltBlocks,
+
+    /// A request to remove old alternative chains.
+    ///
+    /// Alt-chains are removed along with any chains that split from them, a chain is removed if:
+    /// - its newest block is more than `max_depth` blocks below the top of the main chain, or
+    /// - more than `max_blocks` alt-blocks are stored, the chains with the oldest
+    ///   top blocks are removed first, the chain with the newest block is always kept.
+    ///
+    /// If no chain needs to be removed the database is not written to.
+    PruneAltChains { max_depth: usize, max_blocks: usize },
+
+    /// A request to add blocks that were in the database before
+    /// the key image index was enabled to the index.
+    ///
+    /// Input is the maximum amount of blocks to add.
+    BackfillKeyImageSpends(usize),
+
+    /// Same as [`BlockchainWriteRequest::BackfillKeyImageSpends`] but for the output key index.
+    BackfillOutputKeys(usize),
 }
 
 //---------------------------------------------------------------------------------------------------- Response
@@ -345,6 +378,26 @@ pub enum BlockchainResponse {
     /// `true` means that the key image was spent.
     KeyImagesSpentVec(Vec<bool>),
 
+    /// Response to [`BlockchainReadRequest::KeyImageSpends`].
+    ///
+    /// The inner value is [`None`] if the key image index is disabled,
+    /// otherwise it is a `Vec` the same length as the input.
+    ///
+    /// The index of each entry corresponds with the request.
+    /// [`None`] means the key image was not found in the index, the key image may still be
+    /// spent in a block that has not been added to the index yet, see
+    /// [`BlockchainWriteRequest::BackfillKeyImageSpends`].
+    KeyImageSpends(Option<Vec<Option<KeyImageSpend>>>),
+
+    /// Response to [`BlockchainReadRequest::OutputByKey`].
+    ///
+    /// The inner value is the `(amount, amount index)` of the output, the amount is `0`
+    /// for RCT outputs.
+    ///
+    /// This will be [`None`] if the output was not found in the index, which includes
+    /// when the index is disabled or the output is in a block not yet added to it.
+    OutputByKey(Option<(u64, u64)>),
+
     /// Response to [`BlockchainReadRequest::CompactChainHistory`].
     CompactChainHistory {
         /// A list of blocks IDs in our chain, starting with the most recent block, all the way to the genesis block.
@@ -404,9 +457,14 @@ pub enum BlockchainResponse {
         database_size: u64,
         /// The amount of free bytes there are
         /// the disk where the database is located.
+        ///
+        /// Free space inside the database file is in [`DatabaseStats::free_bytes`].
         free_space: u64,
     },
 
+    /// Response to [`BlockchainReadRequest::DatabaseStats`].
+    DatabaseStats(DatabaseStats),
+
     /// Response to [`BlockchainReadRequest::OutputDistribution`].
     OutputDistribution(Vec<OutputDistributionData>),
 
@@ -450,6 +508,22 @@ pub enum BlockchainResponse {
     ///
     /// The inner value is the alt-chain ID for the old main chain blocks.
     PopBlocks(ChainId),
+
+    /// Response to [`BlockchainWriteRequest::PruneAltChains`].
+    ///
+    /// The inner value is the amount of alt-blocks removed.
+    PruneAltChains(usize),
+
+    /// Response to [`BlockchainWriteRequest::BackfillKeyImageSpends`].
+    ///
+    /// The inner value is [`None`] if the key image index is disabled, otherwise
+    /// it is the height below which blocks still need to be added, `0` once all are.
+    BackfillKeyImageSpends(Option<usize>),
+
+    /// Response to [`BlockchainWriteRequest::BackfillOutputKeys`].
+    ///
+    /// Same as [`BlockchainResponse::BackfillKeyImageSpends`] but for the output key index.
+    BackfillOutputKeys(Option<usize>),
 }
 
 //---------------------------------------------------------------------------------------------------- Tests
@@ -170,6 +170,44 @@ pub struct BlockTemplate {
     pub next_seed_hash: [u8; 32],
 }
 
+/// Statistics of the whole database.
+///
+/// Used in [`crate::blockchain::BlockchainResponse::DatabaseStats`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatabaseStats {
+    /// The size of the database file in bytes.
+    pub disk_size: u64,
+    /// The size of the memory map in bytes, [`None`] if the backend does not use one.
+    pub map_size: Option<u64>,
+    /// The amount of bytes in use by the database.
+    pub used_bytes: u64,
+    /// The amount of bytes in the database file that are free and will be reused.
+    pub free_bytes: u64,
+    /// The statistics of each table.
+    pub tables: Vec<TableStats>,
+}
+
+/// Statistics of a single database table.
+///
+/// Used in [`DatabaseStats`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TableStats {
+    /// The name of the table.
+    pub table: &'static str,
+    /// The amount of entries in the table.
+    pub entries: u64,
+    /// The depth of the table's B-tree.
+    pub depth: u32,
+    /// The amount of internal (non-leaf) pages.
+    pub branch_pages: u64,
+    /// The amount of leaf pages.
+    pub leaf_pages: u64,
+    /// The amount of overflow pages.
+    pub overflow_pages: u64,
+    /// The amount of bytes used by the table.
+    pub size_bytes: u64,
+}
+
 /// TODO
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TxInBlockchain {
@@ -184,6 +222,17 @@ pub struct TxInBlockchain {
     pub prunable_hash: [u8; 32],
 }
 
+/// Where a key image was spent.
+///
+/// Used in [`crate::blockchain::BlockchainResponse::KeyImageSpends`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct KeyImageSpend {
+    /// The hash of the transaction that spent the key image.
+    pub tx_hash: [u8; 32],
+    /// The height of the block containing the transaction.
+    pub block_height: usize,
+}
+
 /// TODO
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TxInPool {
@@ -206,6 +255,11 @@ bitflags::bitflags! {
         const TOO_BIG             = 1 << 7;
         const TOO_FEW_OUTPUTS     = 1 << 8;
         const TX_EXTRA_TOO_BIG    = 1 << 9;
+
+        // Cuprate extensions, relay policy rules `monerod` does not have.
+        const TX_EXTRA_NOT_ALLOWED = 1 << 10;
+        const TOO_MANY_INPUTS      = 1 << 11;
+        const TOO_MANY_OUTPUTS     = 1 << 12;
     }
 }
 
@@ -5,6 +5,7 @@
 //! * `json-full-chain_main` (`Vec<ChainMain>`)
 //! * `json-minimal-chain_main` (`ChainMainMin`)
 //! * `json-full-miner_data` (`MinerData`)
+//! * `json-full-reorg_alert` (`ReorgAlert`)
 
 use serde::{Deserialize, Serialize};
 
@@ -112,6 +113,34 @@ pub struct MinerData {
     pub tx_backlog: Vec<TxBacklog>,
 }
 
+/// ZMQ `json-full-reorg_alert` subscriber messages contain a single
+/// `ReorgAlert` object. This is a Cuprate extension, it is published when a
+/// competing chain with more work would re-org more blocks than the configured
+/// maximum, the re-org is held until it is manually approved.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReorgAlert {
+    /// height of the last block shared by the main chain and the competing chain
+    pub fork_height: u64,
+    /// block ID of the last block shared by the main chain and the competing chain
+    pub fork_id: Hex<32>,
+    /// number of main chain blocks the re-org would remove
+    pub depth: u64,
+    /// height of the top block of the main chain
+    pub main_height: u64,
+    /// block ID of the top block of the main chain
+    pub main_id: Hex<32>,
+    /// cumulative difficulty of the main chain
+    #[serde(with = "hex_wide_difficulty")]
+    pub main_cumulative_difficulty: u128,
+    /// height of the top block of the competing chain
+    pub alt_height: u64,
+    /// block ID of the top block of the competing chain
+    pub alt_id: Hex<32>,
+    /// cumulative difficulty of the competing chain
+    #[serde(with = "hex_wide_difficulty")]
+    pub alt_cumulative_difficulty: u128,
+}
+
 /// Holds a single input for the `TxPoolAdd` `inputs` array.
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct PoolInput {
@@ -302,6 +331,29 @@ mod hex_difficulty {
     }
 }
 
+mod hex_wide_difficulty {
+    //! Serializes the u128 cumulative difficulty fields of `ReorgAlert` in the
+    //! same format as `hex_difficulty`.
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[expect(clippy::trivially_copy_pass_by_ref)]
+    pub(super) fn serialize<S>(difficulty: &u128, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&format!("0x{difficulty:x}"))
+    }
+
+    pub(super) fn deserialize<'de, D>(deserializer: D) -> Result<u128, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let s = s.strip_prefix("0x").unwrap_or(&s);
+        u128::from_str_radix(s, 16).map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use assert_json_diff::assert_json_eq;
@@ -645,4 +697,27 @@ mod tests {
         let json2 = serde_json::to_value(&miner_data).unwrap();
         assert_json_eq!(json1, json2);
     }
+
+    #[test]
+    fn test_reorg_alert_json() {
+        let json1 = json!({
+          "fork_height": 3242700,
+          "fork_id": "ce3731311b7e4c1e58a2fe902dbb5c60bb2c0decc163d5397fa52a260d7f09c1",
+          "depth": 64,
+          "main_height": 3242764,
+          "main_id": "dc53c24683dca14586fb2909b9aa4a44adb524e010d438e2491e7d8cc1c80831",
+          "main_cumulative_difficulty": "0x17f4e8a3b2c6d91e05",
+          "alt_height": 3242765,
+          "alt_id": "ee1238b884e64f7e438223aa8d42d0efc15e7640f1a432448fbad116dc72f1b2",
+          "alt_cumulative_difficulty": "0x17f4e8a3f5d2a07c11"
+        });
+
+        let reorg_alert: ReorgAlert = serde_json::from_value(json1.clone()).unwrap();
+        assert_eq!(
+            reorg_alert.alt_cumulative_difficulty,
+            0x17f4_e8a3_f5d2_a07c_11
+        );
+        let json2 = serde_json::to_value(&reorg_alert).unwrap();
+        assert_json_eq!(json1, json2);
+    }
 }